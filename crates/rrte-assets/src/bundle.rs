@@ -0,0 +1,176 @@
+//! Binary bundles of meshes, materials and a scene graph
+
+use crate::{MaterialAsset, MeshAsset, SceneAsset};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// File extension of binary asset bundles
+pub const BUNDLE_EXTENSION: &str = "rrtebundle";
+
+/// Header identifying a serialized asset bundle
+const BUNDLE_MAGIC: &[u8; 8] = b"RRTEBDL1";
+
+/// Meshes, materials and a scene graph packed into a single binary file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetBundle {
+    /// Meshes by name
+    pub meshes: BTreeMap<String, MeshAsset>,
+    /// Materials by name
+    pub materials: BTreeMap<String, MaterialAsset>,
+    /// Scene graph referencing the meshes and materials, if any
+    pub scene: Option<SceneAsset>,
+}
+
+impl AssetBundle {
+    /// Create an empty bundle
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a mesh under `name`, replacing any mesh with the same name
+    pub fn add_mesh(&mut self, name: impl Into<String>, mesh: MeshAsset) {
+        self.meshes.insert(name.into(), mesh);
+    }
+
+    /// Add a material under its own name, replacing any material with the same name
+    pub fn add_material(&mut self, material: MaterialAsset) {
+        self.materials.insert(material.name.clone(), material);
+    }
+
+    /// Set the scene graph referencing the bundled meshes and materials
+    pub fn set_scene(&mut self, scene: SceneAsset) {
+        self.scene = Some(scene);
+    }
+
+    /// Encode the bundle as bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = BUNDLE_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    /// Decode a bundle written by [`AssetBundle::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let payload = bytes
+            .strip_prefix(BUNDLE_MAGIC.as_slice())
+            .ok_or_else(|| anyhow!("Not an asset bundle"))?;
+        Ok(bincode::deserialize(payload)?)
+    }
+
+    /// Write the bundle to a `.rrtebundle` file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Read a bundle from a `.rrtebundle` file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AssetManager, AssetMetadata, SceneCamera, SceneEntity, Vertex};
+    use rrte_math::{Color, Transform, Vec2, Vec3};
+    use std::time::SystemTime;
+
+    fn metadata(asset_type: &str) -> AssetMetadata {
+        AssetMetadata {
+            path: String::new(),
+            asset_type: asset_type.to_string(),
+            size: 0,
+            created: SystemTime::UNIX_EPOCH,
+            modified: SystemTime::UNIX_EPOCH,
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn material(name: &str, albedo: Color) -> MaterialAsset {
+        MaterialAsset {
+            name: name.to_string(),
+            albedo,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            emission: Color::BLACK,
+            ior: 1.5,
+            albedo_texture: None,
+            normal_texture: None,
+            metallic_texture: None,
+            roughness_texture: None,
+            metadata: metadata("Material"),
+        }
+    }
+
+    fn bundle() -> AssetBundle {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: Vec3::new(x, y, 0.0),
+            normal: Vec3::Z,
+            uv: Vec2::new(x, y),
+            color: Color::WHITE,
+        };
+        let mut bundle = AssetBundle::new();
+        bundle.add_mesh(
+            "triangle",
+            MeshAsset {
+                vertices: vec![vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(0.0, 1.0)],
+                indices: vec![0, 1, 2],
+                metadata: metadata("Mesh"),
+            },
+        );
+        bundle.add_material(material("red", Color::RED));
+        bundle.add_material(material("blue", Color::BLUE));
+        bundle.set_scene(SceneAsset {
+            name: "level".to_string(),
+            entities: vec![SceneEntity {
+                name: "floor".to_string(),
+                transform: Transform::identity(),
+                mesh: Some("triangle".to_string()),
+                material: Some("red".to_string()),
+            }],
+            lights: Vec::new(),
+            camera: SceneCamera { transform: Transform::identity(), fov: 1.0, near: 0.1, far: 100.0 },
+            metadata: metadata("Scene"),
+        });
+        bundle
+    }
+
+    #[test]
+    fn saved_bundle_loads_every_sub_asset_by_name() {
+        let name = format!("rrte-bundle-{}.{BUNDLE_EXTENSION}", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        bundle().save(&path).unwrap();
+        let mut manager = AssetManager::new();
+        let handles = manager.load_bundle(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(handles.unwrap().len(), 4);
+
+        let mesh = manager.get_by_name("triangle").unwrap();
+        let mesh = mesh.as_any().downcast_ref::<MeshAsset>().unwrap();
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert_eq!(mesh.vertices[1].position, Vec3::new(1.0, 0.0, 0.0));
+
+        for (name, albedo) in [("red", Color::RED), ("blue", Color::BLUE)] {
+            let material = manager.get_by_name(name).unwrap();
+            let material = material.as_any().downcast_ref::<MaterialAsset>().unwrap();
+            assert_eq!(material.name, name);
+            assert_eq!(material.albedo, albedo);
+        }
+
+        let scene = manager.get_by_name("level").unwrap();
+        let scene = scene.as_any().downcast_ref::<SceneAsset>().unwrap();
+        assert_eq!(scene.entities[0].mesh.as_deref(), Some("triangle"));
+    }
+
+    #[test]
+    fn bytes_without_the_header_are_rejected() {
+        let bytes = bundle().to_bytes().unwrap();
+        assert!(AssetBundle::from_bytes(&bytes).is_ok());
+        assert!(AssetBundle::from_bytes(&bytes[8..]).is_err());
+    }
+}
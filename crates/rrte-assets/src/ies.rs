@@ -0,0 +1,213 @@
+//! IES photometric profiles for light fixtures
+
+use crate::Asset;
+use anyhow::{anyhow, Result};
+use rrte_math::Vec3;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fs;
+use std::path::Path;
+
+/// File extension of IES photometric profiles
+pub const IES_EXTENSION: &str = "ies";
+
+/// Candela distribution of a light fixture, read from an IES LM-63 (`.ies`) file.
+///
+/// Angles follow type C photometry: the vertical angle is 0° straight down
+/// (-Y) and 180° straight up, and the horizontal angle turns around the Y axis
+/// from +X (0°) towards +Z (90°). Profiles covering only 0°, 0-90° or 0-180°
+/// horizontally are mirrored to cover the full circle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IesProfile {
+    /// Vertical angles in degrees, ascending
+    pub vertical_angles: Vec<f32>,
+    /// Horizontal angles in degrees, ascending
+    pub horizontal_angles: Vec<f32>,
+    /// Candela for every horizontal angle, each holding one value per vertical angle
+    pub candela: Vec<f32>,
+}
+
+impl IesProfile {
+    /// Parse the contents of an `.ies` file
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        let tilt = lines
+            .by_ref()
+            .find_map(|line| line.trim().strip_prefix("TILT="))
+            .ok_or_else(|| anyhow!("IES profile has no TILT line"))?
+            .trim()
+            .to_owned();
+        if tilt != "NONE" && tilt != "INCLUDE" {
+            return Err(anyhow!("IES profiles with a separate TILT file ({}) are not supported", tilt));
+        }
+
+        let mut values = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<f32>().map_err(|_| anyhow!("Invalid number in IES profile: {}", token)));
+        let mut next = || values.next().unwrap_or_else(|| Err(anyhow!("IES profile ended early")));
+
+        if tilt == "INCLUDE" {
+            // Lamp-to-luminaire geometry, then tilt angles and their multipliers
+            next()?;
+            let tilt_count = next()? as usize;
+            for _ in 0..2 * tilt_count {
+                next()?;
+            }
+        }
+
+        let _lamp_count = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        let photometric_type = next()?;
+        // Units and luminous opening width, length and height, then ballast
+        // factor, ballast-lamp factor and input watts
+        for _ in 0..4 {
+            next()?;
+        }
+        let ballast_factor = next()?;
+        next()?;
+        next()?;
+        if photometric_type != 1.0 {
+            return Err(anyhow!("Only type C IES profiles are supported, got type {}", photometric_type));
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(anyhow!("IES profile has no angles"));
+        }
+
+        let vertical_angles = (0..vertical_count).map(|_| next()).collect::<Result<Vec<_>>>()?;
+        let horizontal_angles = (0..horizontal_count).map(|_| next()).collect::<Result<Vec<_>>>()?;
+        let candela = (0..vertical_count * horizontal_count)
+            .map(|_| next().map(|value| value * multiplier * ballast_factor))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+        })
+    }
+
+    /// Read a profile from an `.ies` file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Get the largest candela value in the profile
+    pub fn peak_candela(&self) -> f32 {
+        self.candela.iter().copied().fold(0.0, f32::max)
+    }
+
+    /// Get the candela at a vertical and horizontal angle in degrees,
+    /// interpolating between the listed angles
+    pub fn candela_at(&self, vertical: f32, horizontal: f32) -> f32 {
+        let horizontal = horizontal.rem_euclid(360.0);
+        let last_horizontal = self.horizontal_angles.last().copied().unwrap_or(0.0);
+        let horizontal = if last_horizontal <= 0.0 {
+            0.0
+        } else if last_horizontal <= 90.0 {
+            let half = if horizontal > 180.0 { 360.0 - horizontal } else { horizontal };
+            if half > 90.0 { 180.0 - half } else { half }
+        } else if last_horizontal <= 180.0 && horizontal > 180.0 {
+            360.0 - horizontal
+        } else {
+            horizontal
+        };
+
+        let Some((v0, v1, vt)) = bracket(&self.vertical_angles, vertical) else {
+            return 0.0;
+        };
+        let (h0, h1, ht) = bracket(&self.horizontal_angles, horizontal).unwrap_or((0, 0, 0.0));
+
+        let vertical_count = self.vertical_angles.len();
+        let value = |h: usize, v: usize| self.candela[h * vertical_count + v];
+        let at_h0 = value(h0, v0) + (value(h0, v1) - value(h0, v0)) * vt;
+        let at_h1 = value(h1, v0) + (value(h1, v1) - value(h1, v0)) * vt;
+        at_h0 + (at_h1 - at_h0) * ht
+    }
+
+    /// Get the intensity in a direction away from the light, in the profile's
+    /// own frame, relative to the peak: 1 at the brightest angle, 0 where the
+    /// fixture emits nothing
+    pub fn relative_intensity(&self, direction: Vec3) -> f32 {
+        let peak = self.peak_candela();
+        let direction = direction.normalize_or_zero();
+        if peak <= 0.0 || direction == Vec3::ZERO {
+            return 0.0;
+        }
+
+        let vertical = (-direction.y).clamp(-1.0, 1.0).acos().to_degrees();
+        let horizontal = direction.z.atan2(direction.x).to_degrees();
+        self.candela_at(vertical, horizontal) / peak
+    }
+}
+
+/// Find the two entries of ascending `angles` around `angle` and how far it
+/// lies between them, or `None` if it is outside their range
+fn bracket(angles: &[f32], angle: f32) -> Option<(usize, usize, f32)> {
+    let (&first, &last) = (angles.first()?, angles.last()?);
+    if angle < first || angle > last {
+        return None;
+    }
+
+    let upper = angles.partition_point(|&a| a < angle).max(1).min(angles.len() - 1);
+    let lower = upper.saturating_sub(1);
+    let span = angles[upper] - angles[lower];
+    let t = if span > 0.0 { (angle - angles[lower]) / span } else { 0.0 };
+    Some((lower, upper, t))
+}
+
+impl Asset for IesProfile {
+    fn type_name(&self) -> &'static str {
+        "IesProfile"
+    }
+
+    fn serialize(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A rotationally symmetric downlight: brightest straight down, dark sideways
+    const DOWNLIGHT: &str = "IESNA:LM-63-2002
+[TEST] symmetric downlight
+TILT=NONE
+1 1000 1 3 1 1 2 0 0 0
+1 1 100
+0 45 90
+0
+1000 500 0
+";
+
+    #[test]
+    fn symmetric_profile_peaks_along_its_beam() {
+        let profile = IesProfile::parse(DOWNLIGHT).unwrap();
+        assert_eq!(profile.vertical_angles, [0.0, 45.0, 90.0]);
+        assert!((profile.peak_candela() - 1000.0).abs() < 1e-3);
+
+        let down = profile.relative_intensity(Vec3::NEG_Y);
+        let diagonal = profile.relative_intensity(Vec3::new(1.0, -1.0, 0.0));
+        let edge = profile.relative_intensity(Vec3::X);
+        assert!((down - 1.0).abs() < 1e-5, "{down}");
+        assert!((diagonal - 0.5).abs() < 1e-3, "{diagonal}");
+        assert!(edge.abs() < 1e-3, "{edge}");
+        // Symmetric around the beam, and nothing above the horizon
+        assert!((profile.relative_intensity(Vec3::new(0.0, -1.0, -1.0)) - diagonal).abs() < 1e-3);
+        assert!(profile.relative_intensity(Vec3::Y) == 0.0);
+    }
+
+    #[test]
+    fn tilt_files_and_truncated_profiles_are_rejected() {
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("TILT=NONE", "TILT=lamp.tlt")).is_err());
+        assert!(IesProfile::parse(DOWNLIGHT.trim_end().trim_end_matches("1000 500 0")).is_err());
+    }
+}
@@ -2,8 +2,12 @@ pub mod asset;
 pub mod loader;
 pub mod manager;
 pub mod handle;
+pub mod bundle;
+pub mod ies;
 
 pub use asset::*;
 pub use loader::*;
 pub use manager::*;
 pub use handle::*;
+pub use bundle::*;
+pub use ies::*;
@@ -1,4 +1,4 @@
-use crate::{Asset, UntypedHandle, LoaderRegistry};
+use crate::{Asset, AssetBundle, UntypedHandle, LoaderRegistry};
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
@@ -9,6 +9,7 @@ pub struct AssetManager {
     assets: Arc<RwLock<HashMap<UntypedHandle, Arc<dyn Asset>>>>,
     loader_registry: LoaderRegistry,
     next_handle: UntypedHandle,
+    names: HashMap<String, UntypedHandle>,
 }
 
 impl AssetManager {    pub fn new() -> Self {
@@ -16,16 +17,58 @@ impl AssetManager {    pub fn new() -> Self {
             assets: Arc::new(RwLock::new(HashMap::new())),
             loader_registry: LoaderRegistry::new(),
             next_handle: UntypedHandle::new(0),
+            names: HashMap::new(),
         }
     }    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<UntypedHandle> {
         let asset = self.loader_registry.load_asset(path.as_ref())?;
+        Ok(self.insert(Arc::from(asset)))
+    }
+
+    /// Register an already loaded asset
+    pub fn insert(&mut self, asset: Arc<dyn Asset>) -> UntypedHandle {
         let handle = self.next_handle;
         self.next_handle = UntypedHandle::new(self.next_handle.id() + 1);
 
         let mut assets = self.assets.write().unwrap();
-        assets.insert(handle, Arc::from(asset));
+        assets.insert(handle, asset);
+
+        handle
+    }
+
+    /// Register an already loaded asset under `name`, replacing the name's previous binding
+    pub fn insert_named(&mut self, name: impl Into<String>, asset: Arc<dyn Asset>) -> UntypedHandle {
+        let handle = self.insert(asset);
+        self.names.insert(name.into(), handle);
+        handle
+    }
+
+    /// Load a `.rrtebundle` file and register every mesh, material and scene it contains.
+    ///
+    /// Sub-assets are named after their bundle keys (meshes and materials) or
+    /// their own name (the scene). Returns the handles of all registered assets.
+    pub fn load_bundle<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<UntypedHandle>> {
+        let bundle = AssetBundle::load(path)?;
+        let mut handles = Vec::with_capacity(bundle.meshes.len() + bundle.materials.len() + 1);
+        for (name, mesh) in bundle.meshes {
+            handles.push(self.insert_named(name, Arc::new(mesh)));
+        }
+        for (name, material) in bundle.materials {
+            handles.push(self.insert_named(name, Arc::new(material)));
+        }
+        if let Some(scene) = bundle.scene {
+            handles.push(self.insert_named(scene.name.clone(), Arc::new(scene)));
+        }
+        Ok(handles)
+    }
+
+    /// Get the handle registered under `name`
+    pub fn handle_by_name(&self, name: &str) -> Option<UntypedHandle> {
+        self.names.get(name).copied()
+    }
 
-        Ok(handle)
+    /// Get the asset registered under `name`
+    pub fn get_by_name(&self, name: &str) -> Option<Arc<dyn Asset>> {
+        self.handle_by_name(name).and_then(|handle| self.get(handle))
     }    pub fn get(&self, handle: UntypedHandle) -> Option<Arc<dyn Asset>> {
         let assets = self.assets.read().unwrap();
         assets.get(&handle).cloned()
@@ -34,6 +77,7 @@ impl AssetManager {    pub fn new() -> Self {
     pub fn unload(&mut self, handle: UntypedHandle) {
         let mut assets = self.assets.write().unwrap();
         assets.remove(&handle);
+        self.names.retain(|_, named| *named != handle);
     }
 
     pub fn register_loader<T: Asset + 'static>(&mut self, loader: Box<dyn crate::AssetLoader<T>>) {
@@ -0,0 +1,164 @@
+//! Named actions and axes mapped onto raw input
+
+use crate::Input;
+use std::collections::HashMap;
+
+/// Keys and gamepad axis driving a -1..1 input axis
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AxisBinding {
+    /// Keys pushing the axis toward -1
+    pub negative_keys: Vec<String>,
+    /// Keys pushing the axis toward +1
+    pub positive_keys: Vec<String>,
+    /// Gamepad axis that overrides the keys while it is outside the dead zone
+    pub gamepad_axis: Option<String>,
+    /// Gamepad values with a smaller magnitude are treated as centered
+    pub dead_zone: f32,
+}
+
+impl AxisBinding {
+    /// Create a binding from one key in each direction
+    pub fn keys(negative: impl Into<String>, positive: impl Into<String>) -> Self {
+        Self {
+            negative_keys: vec![negative.into()],
+            positive_keys: vec![positive.into()],
+            gamepad_axis: None,
+            dead_zone: 0.1,
+        }
+    }
+
+    /// Let a gamepad axis override the keys
+    pub fn with_gamepad_axis(mut self, axis: impl Into<String>) -> Self {
+        self.gamepad_axis = Some(axis.into());
+        self
+    }
+
+    /// Get the axis value for the current input state.
+    ///
+    /// A gamepad axis outside the dead zone wins. Otherwise the keys give -1 or
+    /// +1, and 0 when neither or both directions are held.
+    pub fn value(&self, input: &Input) -> f32 {
+        if let Some(axis) = &self.gamepad_axis {
+            let value = input.gamepad_axis(axis);
+            if value.abs() > self.dead_zone {
+                return value;
+            }
+        }
+
+        let held = |keys: &[String]| keys.iter().any(|key| input.is_key_pressed(key));
+        match (held(&self.negative_keys), held(&self.positive_keys)) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Named actions and axes mapped onto raw input, so gameplay and camera code
+/// can ask for "jump" or "move_x" instead of specific keys
+#[derive(Debug, Clone, Default)]
+pub struct ActionMap {
+    actions: HashMap<String, Vec<String>>,
+    axes: HashMap<String, AxisBinding>,
+}
+
+impl ActionMap {
+    /// Create an empty action map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key that triggers an action; an action can have several keys
+    pub fn bind_action(&mut self, action: impl Into<String>, key: impl Into<String>) {
+        self.actions.entry(action.into()).or_default().push(key.into());
+    }
+
+    /// Set the binding of an axis, replacing any previous one
+    pub fn bind_axis(&mut self, axis: impl Into<String>, binding: AxisBinding) {
+        self.axes.insert(axis.into(), binding);
+    }
+
+    /// Remove an action or axis with this name
+    pub fn unbind(&mut self, name: &str) {
+        self.actions.remove(name);
+        self.axes.remove(name);
+    }
+
+    /// Get the binding of an axis
+    pub fn axis_binding(&self, axis: &str) -> Option<&AxisBinding> {
+        self.axes.get(axis)
+    }
+
+    /// Check if any key bound to an action is pressed
+    pub fn is_action_pressed(&self, input: &Input, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|keys| keys.iter().any(|key| input.is_key_pressed(key)))
+    }
+
+    /// Check if any key bound to an action was just pressed this frame
+    pub fn is_action_just_pressed(&self, input: &Input, action: &str) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|keys| keys.iter().any(|key| input.is_key_just_pressed(key)))
+    }
+
+    /// Get the value of an axis in `-1.0..=1.0`, or `0.0` if it is not bound;
+    /// see [`AxisBinding::value`]
+    pub fn axis(&self, input: &Input, axis: &str) -> f32 {
+        self.axes.get(axis).map_or(0.0, |binding| binding.value(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeyModifiers, SystemEvent};
+
+    fn key(input: &mut Input, key: &str, pressed: bool) {
+        input.handle_event(&if pressed {
+            SystemEvent::key_press(key, KeyModifiers::default())
+        } else {
+            SystemEvent::key_release(key, KeyModifiers::default())
+        });
+    }
+
+    #[test]
+    fn keys_drive_the_axis_and_a_gamepad_stick_takes_precedence() {
+        let mut actions = ActionMap::new();
+        actions.bind_axis("move_x", AxisBinding::keys("A", "D").with_gamepad_axis("LeftStickX"));
+        let mut input = Input::new();
+        assert_eq!(actions.axis(&input, "move_x"), 0.0);
+
+        key(&mut input, "A", true);
+        assert_eq!(actions.axis(&input, "move_x"), -1.0);
+        key(&mut input, "D", true);
+        assert_eq!(actions.axis(&input, "move_x"), 0.0);
+        key(&mut input, "A", false);
+        assert_eq!(actions.axis(&input, "move_x"), 1.0);
+
+        // The stick wins while D is held, until it returns inside the dead zone
+        input.handle_event(&SystemEvent::gamepad_axis("LeftStickX", -0.6));
+        assert_eq!(actions.axis(&input, "move_x"), -0.6);
+        input.handle_event(&SystemEvent::gamepad_axis("LeftStickX", 0.05));
+        assert_eq!(actions.axis(&input, "move_x"), 1.0);
+        assert_eq!(actions.axis(&input, "unbound"), 0.0);
+    }
+
+    #[test]
+    fn any_bound_key_triggers_an_action() {
+        let mut actions = ActionMap::new();
+        actions.bind_action("jump", "Space");
+        actions.bind_action("jump", "W");
+        let mut input = Input::new();
+
+        key(&mut input, "W", true);
+        assert!(actions.is_action_pressed(&input, "jump"));
+        assert!(actions.is_action_just_pressed(&input, "jump"));
+        input.update();
+        assert!(!actions.is_action_just_pressed(&input, "jump"));
+
+        actions.unbind("jump");
+        assert!(!actions.is_action_pressed(&input, "jump"));
+    }
+}
@@ -0,0 +1,77 @@
+//! Frame timing benchmarks
+
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// Frame timings from [`crate::Engine::run_benchmark`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BenchmarkResult {
+    /// Render time of every frame, in order
+    pub frame_times: Vec<Duration>,
+}
+
+impl BenchmarkResult {
+    /// Get the number of frames rendered
+    pub fn frame_count(&self) -> usize {
+        self.frame_times.len()
+    }
+
+    /// Get the summed render time of all frames
+    pub fn total(&self) -> Duration {
+        self.frame_times.iter().sum()
+    }
+
+    /// Get the mean frame time, or zero if no frames were rendered
+    pub fn average(&self) -> Duration {
+        match u32::try_from(self.frame_times.len()) {
+            Ok(count) if count > 0 => self.total() / count,
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Get the fastest frame time
+    pub fn min(&self) -> Option<Duration> {
+        self.frame_times.iter().min().copied()
+    }
+
+    /// Get the slowest frame time
+    pub fn max(&self) -> Option<Duration> {
+        self.frame_times.iter().max().copied()
+    }
+
+    /// Format the timings as CSV with a `frame,milliseconds` header and one row per frame
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("frame,milliseconds\n");
+        for (frame, time) in self.frame_times.iter().enumerate() {
+            // Writing to a String cannot fail
+            let _ = writeln!(csv, "{},{:.3}", frame, time.as_secs_f64() * 1000.0);
+        }
+        csv
+    }
+
+    /// Write the timings to a CSV file, see [`BenchmarkResult::to_csv`]
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_csv())
+            .with_context(|| format!("Failed to write benchmark timings to {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_and_csv_cover_every_frame() {
+        let result = BenchmarkResult {
+            frame_times: [4, 2, 6].map(Duration::from_millis).to_vec(),
+        };
+        assert_eq!(result.average(), Duration::from_millis(4));
+        assert_eq!(result.min(), Some(Duration::from_millis(2)));
+        assert_eq!(result.max(), Some(Duration::from_millis(6)));
+        assert_eq!(result.to_csv(), "frame,milliseconds\n0,4.000\n1,2.000\n2,6.000\n");
+        assert_eq!(BenchmarkResult::default().average(), Duration::ZERO);
+    }
+}
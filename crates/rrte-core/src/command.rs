@@ -0,0 +1,96 @@
+//! Scene mutations queued from other threads
+
+use rrte_math::AnimationTrack;
+use rrte_renderer::{light::PointLight, primitives::Sphere, Light, Material, SceneObject};
+use rrte_scene::{ObjectId, Scene};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
+
+/// A deferred scene mutation, sent from any thread through a [`CommandSender`]
+pub enum SceneCommand {
+    /// [`Scene::add_object`]
+    AddObject(Arc<dyn SceneObject>),
+    /// [`Scene::add_sphere`]
+    AddSphere(Arc<Sphere>),
+    /// [`Scene::remove_object`]
+    RemoveObject(usize),
+    /// [`Scene::add_material`]
+    AddMaterial(Arc<dyn Material>),
+    /// [`Scene::add_light`]
+    AddLight(Arc<dyn Light>),
+    /// [`Scene::add_point_light`]
+    AddPointLight(Arc<PointLight>),
+    /// [`Scene::remove_light`]
+    RemoveLight(usize),
+    /// [`Scene::set_object_animation`]
+    SetObjectAnimation(ObjectId, AnimationTrack),
+    /// [`Scene::clear`]
+    Clear,
+    /// Any other mutation
+    Custom(Box<dyn FnOnce(&mut Scene) + Send>),
+}
+
+impl SceneCommand {
+    /// Apply the mutation to a scene
+    pub fn apply(self, scene: &mut Scene) {
+        match self {
+            Self::AddObject(object) => scene.add_object(object),
+            Self::AddSphere(sphere) => scene.add_sphere(sphere),
+            Self::RemoveObject(index) => {
+                scene.remove_object(index);
+            }
+            Self::AddMaterial(material) => scene.add_material(material),
+            Self::AddLight(light) => scene.add_light(light),
+            Self::AddPointLight(light) => scene.add_point_light(light),
+            Self::RemoveLight(index) => {
+                scene.remove_light(index);
+            }
+            Self::SetObjectAnimation(id, track) => scene.set_object_animation(id, track),
+            Self::Clear => scene.clear(),
+            Self::Custom(mutation) => mutation(scene),
+        }
+    }
+}
+
+impl std::fmt::Debug for SceneCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AddObject(object) => f.debug_tuple("AddObject").field(object).finish(),
+            Self::AddSphere(sphere) => f.debug_tuple("AddSphere").field(sphere).finish(),
+            Self::RemoveObject(index) => f.debug_tuple("RemoveObject").field(index).finish(),
+            Self::AddMaterial(material) => f.debug_tuple("AddMaterial").field(material).finish(),
+            Self::AddLight(light) => f.debug_tuple("AddLight").field(light).finish(),
+            Self::AddPointLight(light) => f.debug_tuple("AddPointLight").field(light).finish(),
+            Self::RemoveLight(index) => f.debug_tuple("RemoveLight").field(index).finish(),
+            Self::SetObjectAnimation(id, track) => f.debug_tuple("SetObjectAnimation").field(id).field(track).finish(),
+            Self::Clear => f.debug_tuple("Clear").finish(),
+            Self::Custom(_) => f.debug_tuple("Custom").finish(),
+        }
+    }
+}
+
+/// Cloneable, thread-safe handle for queueing [`SceneCommand`]s on an engine
+#[derive(Debug, Clone)]
+pub struct CommandSender {
+    sender: Sender<SceneCommand>,
+}
+
+impl CommandSender {
+    /// Queue a command; fails if the engine has been dropped
+    pub fn send(&self, command: SceneCommand) -> anyhow::Result<()> {
+        self.sender
+            .send(command)
+            .map_err(|_| anyhow::anyhow!("Engine command queue is closed"))
+    }
+
+    /// Queue an arbitrary scene mutation
+    pub fn send_fn(&self, mutation: impl FnOnce(&mut Scene) + Send + 'static) -> anyhow::Result<()> {
+        self.send(SceneCommand::Custom(Box::new(mutation)))
+    }
+}
+
+/// Create a connected command sender and receiver
+pub(crate) fn command_channel() -> (CommandSender, Receiver<SceneCommand>) {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    (CommandSender { sender }, receiver)
+}
@@ -0,0 +1,126 @@
+//! Constraints applied to transforms after animation
+
+use rrte_math::{Transform, Vec3, AABB};
+
+/// A rule applied to a transform after animation, e.g. for camera rigs
+pub trait TransformConstraint: Send + Sync + std::fmt::Debug {
+    /// Adjust `transform` in place to satisfy the constraint
+    fn apply(&self, transform: &mut Transform);
+}
+
+/// Apply constraints in order; later constraints win where they conflict
+pub fn apply_constraints(transform: &mut Transform, constraints: &[Box<dyn TransformConstraint>]) {
+    for constraint in constraints {
+        constraint.apply(transform);
+    }
+}
+
+/// Keeps the transform's `forward()` pointing at a target
+#[derive(Debug, Clone, Copy)]
+pub struct LookAtConstraint {
+    /// World-space point to face
+    pub target: Vec3,
+    /// Preferred up direction
+    pub up: Vec3,
+}
+
+impl LookAtConstraint {
+    /// Create a look-at constraint with +Y as up
+    pub fn new(target: Vec3) -> Self {
+        Self { target, up: Vec3::Y }
+    }
+}
+
+impl TransformConstraint for LookAtConstraint {
+    fn apply(&self, transform: &mut Transform) {
+        // There is no direction to face when the transform sits on the target
+        if transform.position.distance_squared(self.target) < 1e-12 {
+            return;
+        }
+        transform.rotation = Transform::billboard(transform.position, self.target, self.up).rotation;
+    }
+}
+
+/// Keeps the transform's position inside a box
+#[derive(Debug, Clone, Copy)]
+pub struct PositionClampConstraint {
+    /// Allowed region
+    pub bounds: AABB,
+}
+
+impl PositionClampConstraint {
+    /// Create a position clamp constraint
+    pub fn new(bounds: AABB) -> Self {
+        Self { bounds }
+    }
+}
+
+impl TransformConstraint for PositionClampConstraint {
+    fn apply(&self, transform: &mut Transform) {
+        transform.position = self.bounds.closest_point(transform.position);
+    }
+}
+
+/// Keeps the transform's position at a fixed distance from a point
+#[derive(Debug, Clone, Copy)]
+pub struct DistanceConstraint {
+    /// Point to orbit
+    pub center: Vec3,
+    /// Distance to maintain from `center`
+    pub radius: f32,
+}
+
+impl DistanceConstraint {
+    /// Create a distance constraint
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+}
+
+impl TransformConstraint for DistanceConstraint {
+    fn apply(&self, transform: &mut Transform) {
+        // Without a direction to push along, move out along +Z
+        let offset = transform.position - self.center;
+        let direction = offset.try_normalize().unwrap_or(Vec3::Z);
+        transform.position = self.center + direction * self.radius;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn look_at_constraint_turns_the_transform_toward_its_target() {
+        let mut transform = Transform::from_position(Vec3::new(3.0, 1.0, -2.0));
+        let target = Vec3::new(-1.0, 4.0, 5.0);
+        LookAtConstraint::new(target).apply(&mut transform);
+
+        let expected = (target - transform.position).normalize();
+        assert!(transform.forward().distance(expected) < 1e-5, "{:?}", transform.forward());
+    }
+
+    #[test]
+    fn position_clamp_keeps_the_position_inside_the_bounds() {
+        let clamp = PositionClampConstraint::new(AABB::new(Vec3::splat(-1.0), Vec3::splat(1.0)));
+
+        let mut outside = Transform::from_position(Vec3::new(5.0, 0.5, -3.0));
+        clamp.apply(&mut outside);
+        assert_eq!(outside.position, Vec3::new(1.0, 0.5, -1.0));
+
+        let mut inside = Transform::from_position(Vec3::new(0.2, -0.4, 0.9));
+        clamp.apply(&mut inside);
+        assert_eq!(inside.position, Vec3::new(0.2, -0.4, 0.9));
+    }
+
+    #[test]
+    fn later_constraints_win() {
+        let constraints: Vec<Box<dyn TransformConstraint>> = vec![
+            Box::new(DistanceConstraint::new(Vec3::ZERO, 10.0)),
+            Box::new(PositionClampConstraint::new(AABB::new(Vec3::splat(-2.0), Vec3::splat(2.0)))),
+        ];
+        let mut transform = Transform::from_position(Vec3::new(0.0, 0.0, 1.0));
+        apply_constraints(&mut transform, &constraints);
+        assert_eq!(transform.position, Vec3::new(0.0, 0.0, 2.0));
+    }
+}
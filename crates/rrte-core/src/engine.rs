@@ -1,12 +1,16 @@
-use crate::{Time, Events, Input};
+use crate::{Time, Events, EventBus, Input, CommandSender, SceneCommand, BenchmarkResult};
+use crate::command::command_channel;
+use rrte_math::{Transform, Vec2, Vec3};
 use rrte_renderer::{
-    Raytracer, RaytracerConfig, Camera as RendererCamera, GpuRenderer, GpuRendererConfig,
+    Bvh, Raytracer, RaytracerConfig, Camera as RendererCamera, GpuRenderer, GpuRendererConfig, choose_surface_format,
+    Viewport, ViewportRect,
 };
 
 use anyhow::Result;
 use log::{info, warn, error};
 use std::time::Instant;
 use std::sync::Arc;
+use std::sync::mpsc::Receiver;
 use winit::window::Window;
 use wgpu;
 
@@ -14,10 +18,45 @@ use wgpu;
 pub enum RendererMode {
     Cpu,
     Gpu,
+    /// GPU rendering without a window or surface; frames stay in the output
+    /// texture and are read back with [`Engine::capture_frame`]
+    GpuHeadless,
+}
+
+/// Pick an adapter, compatible with `surface` if given, and open a device on it
+async fn request_gpu_device(
+    instance: &wgpu::Instance,
+    surface: Option<&wgpu::Surface<'static>>,
+) -> Result<(wgpu::Adapter, Arc<wgpu::Device>, Arc<wgpu::Queue>)> {
+    // Adapter
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: surface,
+            force_fallback_adapter: false,
+        })
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Failed to find a suitable GPU adapter."))?;
+    info!("Selected GPU: {}", adapter.get_info().name);
+
+    // Device and Queue
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: wgpu::Features::empty(), // Add features as needed
+                required_limits: wgpu::Limits::default(),
+                label: Some("RRTE Device"),
+            },
+            None, // Trace path
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create logical device and queue: {}", e))?;
+
+    Ok((adapter, Arc::new(device), Arc::new(queue)))
 }
 
 /// Engine configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct EngineConfig {
     pub renderer_mode: RendererMode,
     pub renderer_config: RaytracerConfig,
@@ -25,6 +64,9 @@ pub struct EngineConfig {
     pub target_fps: f32,
     pub enable_vsync: bool,
     pub log_level: log::LevelFilter,
+    /// Master seed for the CPU renderer's sampling. When set, it replaces
+    /// `renderer_config.seed` and makes repeated renders of a scene identical.
+    pub seed: Option<u64>,
 }
 
 impl Default for EngineConfig {
@@ -36,7 +78,96 @@ impl Default for EngineConfig {
             target_fps: 60.0,
             enable_vsync: true,
             log_level: log::LevelFilter::Info,
+            seed: None,
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Start building a configuration from the defaults
+    pub fn builder() -> EngineConfigBuilder {
+        EngineConfigBuilder::default()
+    }
+
+    /// Check that the configuration can start an engine
+    pub fn validate(&self) -> Result<()> {
+        self.renderer_config.validate()?;
+        if self.target_fps.is_nan() || self.target_fps <= 0.0 {
+            return Err(anyhow::anyhow!("Target FPS must be positive, got {}", self.target_fps));
         }
+        // A GPU width or height of 0 inherits the CPU renderer's, which was validated above
+        if self.renderer_mode != RendererMode::Cpu {
+            let gpu = &self.gpu_renderer_config;
+            if gpu.samples == 0 {
+                return Err(anyhow::anyhow!("GPU renderer needs at least one sample per pixel"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the CPU raytracer configuration, seeded with the master seed if one is set
+    fn raytracer_config(&self) -> RaytracerConfig {
+        RaytracerConfig {
+            seed: self.seed.or(self.renderer_config.seed),
+            ..self.renderer_config.clone()
+        }
+    }
+}
+
+/// Fluent builder for [`EngineConfig`], validated by [`EngineConfigBuilder::build`]
+#[derive(Debug, Clone, Default)]
+pub struct EngineConfigBuilder {
+    config: EngineConfig,
+}
+
+impl EngineConfigBuilder {
+    /// Set which renderer draws the scene
+    pub fn renderer_mode(mut self, mode: RendererMode) -> Self {
+        self.config.renderer_mode = mode;
+        self
+    }
+
+    /// Set the configuration of the CPU raytracer
+    pub fn renderer_config(mut self, config: RaytracerConfig) -> Self {
+        self.config.renderer_config = config;
+        self
+    }
+
+    /// Set the configuration of the GPU renderer; a width or height of 0 uses
+    /// the CPU renderer configuration's
+    pub fn gpu_renderer_config(mut self, config: GpuRendererConfig) -> Self {
+        self.config.gpu_renderer_config = config;
+        self
+    }
+
+    /// Set the frame rate the main loop aims for
+    pub fn target_fps(mut self, fps: f32) -> Self {
+        self.config.target_fps = fps;
+        self
+    }
+
+    /// Set whether presentation waits for vertical sync
+    pub fn enable_vsync(mut self, enable: bool) -> Self {
+        self.config.enable_vsync = enable;
+        self
+    }
+
+    /// Set the most verbose log level that is printed
+    pub fn log_level(mut self, level: log::LevelFilter) -> Self {
+        self.config.log_level = level;
+        self
+    }
+
+    /// Set the master seed for the CPU renderer's sampling
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Finish the configuration, failing if [`EngineConfig::validate`] does
+    pub fn build(self) -> Result<EngineConfig> {
+        self.config.validate()?;
+        Ok(self.config)
     }
 }
 
@@ -78,8 +209,15 @@ pub struct Engine {
     scene: rrte_scene::Scene,
     camera: RendererCamera,
     events: Events,
+    event_bus: EventBus,
     input: Input,
     frame_buffer: Vec<u8>,
+    viewports: Vec<(String, Viewport)>,
+    command_sender: CommandSender,
+    command_receiver: Receiver<SceneCommand>,
+    auto_present: bool,
+    /// Camera transform the GPU renderer's accumulated samples were rendered from
+    accumulation_transform: Option<Transform>,
 }
 
 impl Engine {
@@ -102,6 +240,7 @@ impl Engine {
 
         let buffer_size = (config.renderer_config.width * config.renderer_config.height * 4) as usize;
         let frame_buffer = vec![0u8; buffer_size];
+        let (command_sender, command_receiver) = command_channel();
 
         Ok(Self {
             config,
@@ -111,8 +250,14 @@ impl Engine {
             scene,
             camera,
             events,
+            event_bus: EventBus::new(),
             input,
             frame_buffer,
+            viewports: Vec::new(),
+            command_sender,
+            command_receiver,
+            auto_present: true,
+            accumulation_transform: None,
         })
     }
 
@@ -127,21 +272,13 @@ impl Engine {
 
         match self.config.renderer_mode {
             RendererMode::Cpu => {
-                let cpu_renderer = Raytracer::new(self.config.renderer_config.clone());
+                let cpu_renderer = Raytracer::new(self.config.raytracer_config());
                 self.renderer = ActiveRenderer::Cpu(cpu_renderer);
                 info!("CPU Renderer initialized.");
             }
             RendererMode::Gpu => {
                 let window_arc = window.ok_or_else(|| anyhow::anyhow!("Window handle required for GPU renderer initialization"))?;
-                
-                let mut gpu_config = self.config.gpu_renderer_config.clone();
-                // Ensure GPU config dimensions match the main config if not already set
-                // (These might have been set by update_resolution before renderer init)
-                if gpu_config.width == 0 { gpu_config.width = self.config.renderer_config.width; }
-                if gpu_config.height == 0 { gpu_config.height = self.config.renderer_config.height; }
-                if gpu_config.width == 0 || gpu_config.height == 0 {
-                    return Err(anyhow::anyhow!("GPU renderer dimensions are zero."));
-                }
+                let mut gpu_config = self.gpu_config_for_init()?;
 
                 // WGPU Instance
                 let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -158,42 +295,15 @@ impl Engine {
                 )}.map_err(|e| anyhow::anyhow!("Failed to create wgpu surface: {}", e))?;
                 let surface_arc = Arc::new(surface);
 
-                // Adapter
-                let adapter = instance
-                    .request_adapter(&wgpu::RequestAdapterOptions {
-                        power_preference: wgpu::PowerPreference::HighPerformance,
-                        compatible_surface: Some(&surface_arc),
-                        force_fallback_adapter: false,
-                    })
-                    .await
-                    .ok_or_else(|| anyhow::anyhow!("Failed to find a suitable GPU adapter."))?;
-                info!("Selected GPU: {}", adapter.get_info().name);
-
-                // Device and Queue
-                let (device, queue) = adapter
-                    .request_device(
-                        &wgpu::DeviceDescriptor {
-                            required_features: wgpu::Features::empty(), // Add features as needed
-                            required_limits: wgpu::Limits::default(),
-                            label: Some("RRTE Device"),
-                        },
-                        None, // Trace path
-                    )
-                    .await
-                    .map_err(|e| anyhow::anyhow!("Failed to create logical device and queue: {}", e))?;
-                
-                let device_arc = Arc::new(device);
-                let queue_arc = Arc::new(queue);
+                let (adapter, device_arc, queue_arc) = request_gpu_device(&instance, Some(&surface_arc)).await?;
 
                 // Surface Configuration
                 let surface_caps = surface_arc.get_capabilities(&adapter);
-                // Shader code in GpuRenderer uses Bgra8UnormSrgb or similar, ensure it matches.
-                // GpuRendererConfig also has a format, use that.
-                let surface_format = gpu_config.format; 
-                if !surface_caps.formats.contains(&surface_format) {
-                    warn!("Preferred surface format {:?} not supported. Falling back to first supported format: {:?}", 
-                           surface_format, surface_caps.formats[0]);
-                    gpu_config.format = surface_caps.formats[0];
+                let surface_format = choose_surface_format(gpu_config.format, &surface_caps.formats);
+                if surface_format != gpu_config.format {
+                    warn!("Preferred surface format {:?} not supported. Falling back to {:?}",
+                           gpu_config.format, surface_format);
+                    gpu_config.format = surface_format;
                 }
 
                 // Use actual window size instead of config size for surface configuration
@@ -222,19 +332,54 @@ impl Engine {
                     device_arc, 
                     queue_arc, 
                     surface_config, 
-                    surface_arc, 
+                    Some(surface_arc), 
                     Some(window_arc.clone())
                 ).await?;
                 
                 self.renderer = ActiveRenderer::Gpu(gpu_renderer_instance);
                 
                 // Update camera aspect ratio to match actual window size
-                if let rrte_renderer::camera::ProjectionType::Perspective { aspect_ratio, .. } = &mut self.camera.projection {
-                    *aspect_ratio = window_size.width as f32 / window_size.height as f32;
-                }
+                self.camera.set_aspect_ratio(window_size.width as f32 / window_size.height as f32);
                 
                 info!("GPU Renderer initialized.");
             }
+            RendererMode::GpuHeadless => {
+                let gpu_config = self.gpu_config_for_init()?;
+                self.config.gpu_renderer_config = gpu_config.clone();
+
+                let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+                    backends: wgpu::Backends::all(),
+                    dx12_shader_compiler: Default::default(),
+                    flags: wgpu::InstanceFlags::default(),
+                    gles_minor_version: wgpu::Gles3MinorVersion::default(),
+                });
+                let (_adapter, device_arc, queue_arc) = request_gpu_device(&instance, None).await?;
+
+                // Never passed to a surface; only describes the frame size and format
+                let surface_config = wgpu::SurfaceConfiguration {
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    format: gpu_config.format,
+                    width: gpu_config.width,
+                    height: gpu_config.height,
+                    present_mode: gpu_config.present_mode,
+                    alpha_mode: wgpu::CompositeAlphaMode::Auto,
+                    view_formats: vec![],
+                    desired_maximum_frame_latency: 2,
+                };
+
+                let gpu_renderer_instance = GpuRenderer::new(
+                    &gpu_config,
+                    device_arc,
+                    queue_arc,
+                    surface_config,
+                    None,
+                    None,
+                ).await?;
+                self.renderer = ActiveRenderer::Gpu(gpu_renderer_instance);
+                self.camera.set_aspect_ratio(gpu_config.width as f32 / gpu_config.height as f32);
+
+                info!("Headless GPU Renderer initialized.");
+            }
         }
         self.state = EngineState::Running;
         self.time.start();
@@ -242,14 +387,42 @@ impl Engine {
         Ok(())
     }
 
+    /// GPU renderer config with its size, background and output curve filled
+    /// in from the CPU config
+    fn gpu_config_for_init(&self) -> Result<GpuRendererConfig> {
+        let mut gpu_config = self.config.gpu_renderer_config.clone();
+        // Ensure GPU config dimensions match the main config if not already set
+        // (These might have been set by update_resolution before renderer init)
+        if gpu_config.width == 0 { gpu_config.width = self.config.renderer_config.width; }
+        if gpu_config.height == 0 { gpu_config.height = self.config.renderer_config.height; }
+        // Rays that miss and bright colors should look the same in both renderer modes
+        gpu_config.background = self.config.renderer_config.background();
+        gpu_config.tonemap = self.config.renderer_config.tonemap;
+        gpu_config.exposure = self.config.renderer_config.exposure;
+        if gpu_config.width == 0 || gpu_config.height == 0 {
+            return Err(anyhow::anyhow!("GPU renderer dimensions are zero."));
+        }
+        Ok(gpu_config)
+    }
+
+    /// Advance input by one frame, timed by the engine clock.
+    ///
+    /// Call once per frame after updating [`Engine::time_mut`]. Double-clicks and
+    /// held durations are measured against the synced time, so loops should use
+    /// this rather than updating [`Engine::input_mut`] directly.
+    pub fn update_input(&mut self) {
+        self.input.sync_time(&self.time);
+        self.input.update();
+    }
+
     /// Main engine run loop (conceptual, actual loop is in main.rs)
     /// This method is kept for potential non-windowed/headless operation or future refactor.
     pub fn run_headless_loop(&mut self) -> Result<()> {
-        if !matches!(self.config.renderer_mode, RendererMode::Cpu) {
-            error!("Headless loop is only supported for CPU renderer.");
-            return Err(anyhow::anyhow!("Headless loop not supported for GPU renderer"));
+        if self.config.renderer_mode == RendererMode::Gpu {
+            error!("Headless loop is only supported for the CPU and headless GPU renderers.");
+            return Err(anyhow::anyhow!("Headless loop not supported for windowed GPU renderer"));
         }
-        info!("Starting engine headless loop (CPU only)...");
+        info!("Starting engine headless loop...");
         
         let target_frame_duration = std::time::Duration::from_secs_f32(1.0 / self.config.target_fps);
 
@@ -258,8 +431,8 @@ impl Engine {
             
             self.time.update();
             self.events.poll();
-            self.input.update();
-            // self.scene.update(self.time.delta_time()); // Commented out: Scene::update not yet on rrte_scene::Scene
+            self.update_input();
+            self.scene.update(self.time.delta_time());
             
             if let Err(e) = self.render_frame() {
                 error!("Render error in headless loop: {}", e);
@@ -274,35 +447,156 @@ impl Engine {
         Ok(())
     }
 
+    /// Render a reproducible sequence of frames.
+    ///
+    /// Time advances by exactly `dt` seconds per frame instead of following the
+    /// wall clock, and the scene is updated before each frame is rendered. Returns
+    /// every frame as RGBA8 (see [`Engine::capture_frame`]), e.g. for comparing
+    /// against golden images.
+    pub fn render_sequence(&mut self, frames: u32, dt: f32) -> Result<Vec<Vec<u8>>> {
+        if matches!(self.renderer, ActiveRenderer::None) {
+            return Err(anyhow::anyhow!("Render sequences require an initialized renderer"));
+        }
+
+        let step = std::time::Duration::from_secs_f32(dt.max(0.0));
+        let mut sequence = Vec::with_capacity(frames as usize);
+        for _ in 0..frames {
+            self.time.step(step);
+            self.apply_scene_commands();
+            self.scene.update(self.time.delta_time());
+            self.render_frame()?;
+            sequence.push(self.capture_frame()?);
+        }
+        Ok(sequence)
+    }
+
+    /// Build a scene and time rendering it headlessly for `frames` frames.
+    ///
+    /// The scene is cleared before `scene_builder` fills it and places the
+    /// camera. Like [`Engine::render_sequence`], time advances by a fixed
+    /// `1 / target_fps` per frame; only [`Engine::render_frame`] is timed. A CPU
+    /// renderer is created if none was initialized; other renderers are rejected.
+    pub fn run_benchmark(
+        &mut self,
+        scene_builder: impl FnOnce(&mut rrte_scene::Scene, &mut RendererCamera),
+        frames: u32,
+    ) -> Result<BenchmarkResult> {
+        if matches!(self.renderer, ActiveRenderer::None) && self.config.renderer_mode == RendererMode::Cpu {
+            self.renderer = ActiveRenderer::Cpu(Raytracer::new(self.config.raytracer_config()));
+        }
+        if !matches!(self.renderer, ActiveRenderer::Cpu(_)) {
+            return Err(anyhow::anyhow!("Benchmarks require the CPU renderer"));
+        }
+
+        self.scene.clear();
+        scene_builder(&mut self.scene, &mut self.camera);
+
+        let step = std::time::Duration::from_secs_f32(1.0 / self.config.target_fps.max(1.0));
+        let mut result = BenchmarkResult::default();
+        for _ in 0..frames {
+            self.time.step(step);
+            self.scene.update(self.time.delta_time());
+            let frame_start = Instant::now();
+            self.render_frame()?;
+            result.frame_times.push(frame_start.elapsed());
+        }
+        Ok(result)
+    }
+
+    /// Get a handle for queueing scene mutations from other threads.
+    ///
+    /// Queued commands are applied in order at the start of the next
+    /// [`Engine::render_frame`] (or frame of [`Engine::render_sequence`]).
+    pub fn command_sender(&self) -> CommandSender {
+        self.command_sender.clone()
+    }
+
+    /// Apply every queued [`SceneCommand`] to the scene now, returning how many ran
+    pub fn apply_scene_commands(&mut self) -> usize {
+        let mut applied = 0;
+        while let Ok(command) = self.command_receiver.try_recv() {
+            command.apply(&mut self.scene);
+            applied += 1;
+        }
+        applied
+    }
+
     /// Render a frame.
     /// For CPU, it renders to an internal buffer.
     /// For GPU, it renders directly to the screen/surface.
     pub fn render_frame(&mut self) -> Result<()> {
+        self.apply_scene_commands();
+        self.scene.set_lod_camera(self.camera.transform.position);
+
         match &mut self.renderer {
             ActiveRenderer::Cpu(raytracer) => {
-                // Convert Vec<Arc<Sphere>> to Vec<Arc<dyn SceneObject>> for the CPU raytracer
-                let scene_objects: Vec<Arc<dyn rrte_renderer::primitives::SceneObject>> = 
-                    self.scene.objects().iter().map(|s| s.clone() as Arc<dyn rrte_renderer::primitives::SceneObject>).collect();
+                // Only the dynamic objects are rebuilt into the hierarchy every frame
+                self.scene.update_bvh();
+                let scene_bvh = self.scene.bvh().hierarchy();
+                let scene_objects = self.scene.get_objects();
                 
-                // Convert Vec<Arc<PointLight>> to Vec<Arc<dyn Light>> for the CPU raytracer
-                let scene_lights: Vec<Arc<dyn rrte_renderer::light::Light>> = 
-                    self.scene.lights().iter().map(|l| l.clone() as Arc<dyn rrte_renderer::light::Light>).collect();
+                // Every kind of light, not just the point lights the GPU renderer takes
+                let mut scene_lights = self.scene.get_lights().to_vec();
+                for instanced_light in self.scene.instanced_lights() {
+                    scene_lights.extend(instanced_light.instances().map(|l| Arc::new(l) as Arc<dyn rrte_renderer::light::Light>));
+                }
                 
-                // TODO: The Scene struct should also store directional lights if needed by CPU raytracer.
-                // For now, passing an empty vec for directional lights.
-                self.frame_buffer = raytracer.render(&scene_objects, &scene_lights, &Vec::new(), &self.camera);
+                if self.config.renderer_config.caustics {
+                    raytracer.update_photon_map(scene_objects, &scene_lights);
+                }
+                if self.viewports.is_empty() {
+                    self.frame_buffer = raytracer.render_with_bvh(scene_bvh, scene_objects, &scene_lights, &Vec::new(), &self.camera);
+                } else {
+                    Self::composite_viewports(
+                        &mut self.frame_buffer,
+                        &self.config.raytracer_config(),
+                        &self.viewports,
+                        scene_bvh,
+                        scene_objects,
+                        &scene_lights,
+                    );
+                }
             }
             ActiveRenderer::Gpu(gpu_renderer) => {
-                let output_surface_texture = gpu_renderer.get_current_texture()?;
-                
-                // GpuRenderer::render now takes spheres and lights directly to avoid cyclic dependency
-                gpu_renderer.render(
-                    &output_surface_texture.texture, // This is the swap chain texture
-                    self.scene.legacy_spheres(), // Pass legacy spheres for GPU compatibility
-                    self.scene.legacy_lights(), // Pass legacy lights for GPU compatibility
-                    &self.camera
-                )?;
-                output_surface_texture.present();
+                // Only upload lights whose range reaches into the view frustum
+                let visible_lights = self.scene.visible_point_lights(&self.camera);
+                gpu_renderer.set_instanced_lights(self.scene.instanced_lights());
+                gpu_renderer.set_scene_version(self.scene.scene_version());
+
+                // Samples from another viewpoint would smear into the average
+                if self.accumulation_transform.as_ref() != Some(&self.camera.transform) {
+                    gpu_renderer.reset_accumulation();
+                    self.accumulation_transform = Some(self.camera.transform.clone());
+                }
+
+                if self.viewports.is_empty() {
+                    // GpuRenderer takes spheres and lights directly to avoid cyclic dependency
+                    gpu_renderer.render_to_texture(
+                        self.scene.legacy_spheres(), // Pass legacy spheres for GPU compatibility
+                        self.scene.get_objects(), // Cubes and triangles are gathered from the objects
+                        &visible_lights,
+                        &self.camera
+                    )?;
+                    if self.auto_present {
+                        gpu_renderer.present()?;
+                    }
+                } else {
+                    let Some(output_surface_texture) = gpu_renderer.acquire_frame()? else {
+                        // Surface was lost/outdated and has been reconfigured; skip this frame
+                        return Ok(());
+                    };
+
+                    // Every viewport is blitted into its own rect of the swap chain texture
+                    let viewports: Vec<Viewport> = self.viewports.iter().map(|(_, viewport)| viewport.clone()).collect();
+                    gpu_renderer.render_viewports(
+                        &output_surface_texture.texture,
+                        self.scene.legacy_spheres(),
+                        self.scene.get_objects(),
+                        &visible_lights,
+                        &viewports,
+                    )?;
+                    output_surface_texture.present();
+                }
             }
             ActiveRenderer::None => {
                 return Err(anyhow::anyhow!("Renderer not initialized before render_frame call."));
@@ -311,6 +605,124 @@ impl Engine {
         Ok(())
     }
 
+    /// Choose whether [`Engine::render_frame`] presents GPU frames immediately.
+    ///
+    /// When disabled, frames stay in the renderer's output texture until
+    /// [`Engine::present_frame`] is called, so they can be captured first or
+    /// presented on a separate schedule. Frames with viewports are always
+    /// presented immediately. Enabled by default.
+    pub fn set_auto_present(&mut self, auto_present: bool) {
+        self.auto_present = auto_present;
+    }
+
+    /// Check whether GPU frames are presented as soon as they are rendered
+    pub fn auto_present(&self) -> bool {
+        self.auto_present
+    }
+
+    /// Present the last GPU frame, returning `false` if nothing was presented
+    /// because the surface was not ready or the CPU renderer is active
+    pub fn present_frame(&mut self) -> Result<bool> {
+        match &self.renderer {
+            ActiveRenderer::Gpu(gpu_renderer) => gpu_renderer.present(),
+            _ => Ok(false),
+        }
+    }
+
+    /// Read back the last GPU frame at `width` x `height` as RGBA8, e.g. to
+    /// capture it before presenting. Returns `None` for the CPU renderer, whose
+    /// frames are available from [`Engine::get_frame_buffer`].
+    pub fn read_gpu_frame(&self, width: u32, height: u32) -> Result<Option<Vec<u8>>> {
+        match &self.renderer {
+            ActiveRenderer::Gpu(gpu_renderer) => gpu_renderer.read_thumbnail(width, height).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the last rendered frame at full resolution as sRGB-encoded RGBA8,
+    /// with either renderer, e.g. for screenshots or comparing against a
+    /// reference image
+    pub fn capture_frame(&self) -> Result<Vec<u8>> {
+        match &self.renderer {
+            ActiveRenderer::Cpu(_) => Ok(self.frame_buffer.clone()),
+            ActiveRenderer::Gpu(gpu_renderer) => gpu_renderer.read_pixels(),
+            ActiveRenderer::None => Err(anyhow::anyhow!("Renderer not initialized before capture_frame call.")),
+        }
+    }
+
+    /// Render every viewport with the CPU raytracer and copy it into its rect of `frame_buffer`.
+    ///
+    /// Pixels not covered by any viewport are cleared to transparent black.
+    fn composite_viewports(
+        frame_buffer: &mut [u8],
+        config: &RaytracerConfig,
+        viewports: &[(String, Viewport)],
+        bvh: &Bvh,
+        objects: &[Arc<dyn rrte_renderer::primitives::SceneObject>],
+        lights: &[Arc<dyn rrte_renderer::light::Light>],
+    ) {
+        frame_buffer.fill(0);
+        let frame_width = config.width as usize;
+
+        for (_, viewport) in viewports {
+            let Some(rect) = viewport.rect.clipped(config.width, config.height) else {
+                continue;
+            };
+            let viewport_raytracer = Raytracer::new(RaytracerConfig {
+                width: rect.width,
+                height: rect.height,
+                ..config.clone()
+            });
+            let pixels = viewport_raytracer.render_with_bvh(bvh, objects, lights, &Vec::new(), &viewport.camera);
+
+            let row_bytes = rect.width as usize * 4;
+            for (row, source) in pixels.chunks_exact(row_bytes).enumerate() {
+                let start = ((rect.y as usize + row) * frame_width + rect.x as usize) * 4;
+                frame_buffer[start..start + row_bytes].copy_from_slice(source);
+            }
+        }
+    }
+
+    /// Add a named viewport rendering `camera` into `rect` of the frame.
+    ///
+    /// While any viewports exist, [`Engine::render_frame`] renders them instead of
+    /// the main camera, in the order they were added. Adding a viewport under an
+    /// existing name replaces it in place.
+    pub fn add_viewport(&mut self, name: impl Into<String>, camera: RendererCamera, rect: ViewportRect) {
+        let name = name.into();
+        let viewport = Viewport::new(camera, rect);
+        match self.viewports.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, slot)) => *slot = viewport,
+            None => self.viewports.push((name, viewport)),
+        }
+    }
+
+    /// Remove a viewport by name, returning it if it existed
+    pub fn remove_viewport(&mut self, name: &str) -> Option<Viewport> {
+        let index = self.viewports.iter().position(|(existing, _)| existing == name)?;
+        Some(self.viewports.remove(index).1)
+    }
+
+    /// Get a viewport by name
+    pub fn viewport(&self, name: &str) -> Option<&Viewport> {
+        self.viewports.iter().find(|(existing, _)| existing == name).map(|(_, viewport)| viewport)
+    }
+
+    /// Get a mutable viewport by name
+    pub fn viewport_mut(&mut self, name: &str) -> Option<&mut Viewport> {
+        self.viewports.iter_mut().find(|(existing, _)| existing == name).map(|(_, viewport)| viewport)
+    }
+
+    /// Get all viewports with their names, in render order
+    pub fn viewports(&self) -> &[(String, Viewport)] {
+        &self.viewports
+    }
+
+    /// Remove all viewports, returning to rendering the main camera full-frame
+    pub fn clear_viewports(&mut self) {
+        self.viewports.clear();
+    }
+
     /// Updates the engine and renderer resolution.
     pub fn update_resolution(&mut self, width: u32, height: u32) {
         if width == 0 || height == 0 {
@@ -323,7 +735,7 @@ impl Engine {
             ActiveRenderer::Cpu(raytracer) => {
                 self.config.renderer_config.width = width;
                 self.config.renderer_config.height = height;
-                raytracer.update_config(self.config.renderer_config.clone());
+                raytracer.update_config(self.config.raytracer_config());
                 let buffer_size = (width * height * 4) as usize;
                 self.frame_buffer.resize(buffer_size, 0u8);
             }
@@ -333,6 +745,13 @@ impl Engine {
                 }
                 self.config.gpu_renderer_config.width = width;
                 self.config.gpu_renderer_config.height = height;
+                // Pick up background and tone mapping changes made to the CPU config since init
+                let background = self.config.renderer_config.background();
+                gpu_renderer.set_background(background);
+                self.config.gpu_renderer_config.background = background;
+                let tonemap = self.config.renderer_config.tonemap;
+                gpu_renderer.set_tonemap(tonemap);
+                self.config.gpu_renderer_config.tonemap = tonemap;
             }
             ActiveRenderer::None => {
                 warn!("update_resolution called before renderer initialization. Storing in CPU config for now.");
@@ -343,16 +762,14 @@ impl Engine {
             }
         }
         
-        if let rrte_renderer::camera::ProjectionType::Perspective { aspect_ratio, .. } = &mut self.camera.projection {
-            *aspect_ratio = width as f32 / height as f32;
-        }
+        self.camera.set_aspect_ratio(width as f32 / height as f32);
     }
 
     /// Get the current frame buffer (only Some for CPU renderer)
     pub fn get_frame_buffer(&self) -> Option<&[u8]> {
         match self.config.renderer_mode {
             RendererMode::Cpu => Some(&self.frame_buffer),
-            RendererMode::Gpu => None,
+            RendererMode::Gpu | RendererMode::GpuHeadless => None,
         }
     }
     
@@ -399,6 +816,40 @@ impl Engine {
         Ok(())
     }
     
+    /// Move the camera back along its view direction so the scene's bounding
+    /// sphere fills the view. Returns `false` if the scene has no bounded objects.
+    pub fn frame_scene(&mut self) -> bool {
+        match self.scene.bounding_sphere() {
+            Some((center, radius)) => {
+                self.camera.frame_sphere(center, radius);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drag an object along a world-space axis through its position, as the
+    /// handle of a translate gizmo follows the cursor from `screen_from` to `screen_to`.
+    ///
+    /// Screen points are normalized as for [`RendererCamera::generate_ray`].
+    /// Each is projected onto the axis at the point closest to its camera ray,
+    /// and the object moves by the difference. Returns the world-space offset
+    /// applied, or `None` if the object is missing or cannot be changed (see
+    /// [`rrte_scene::Scene::set_object_transform`]) or a ray runs parallel to the axis.
+    pub fn drag_object_axis(&mut self, id: rrte_scene::ObjectId, axis: Vec3, screen_from: Vec2, screen_to: Vec2) -> Option<Vec3> {
+        let axis = axis.try_normalize()?;
+        let mut transform = self.scene.get_object(id)?.transform().clone();
+        let project = |screen: Vec2| {
+            self.camera
+                .generate_ray(screen.x, screen.y)
+                .closest_point_on_line(transform.position, axis)
+        };
+        let offset = (project(screen_to)? - project(screen_from)?) * axis;
+
+        transform.position += offset;
+        self.scene.set_object_transform(id, transform).then_some(offset)
+    }
+
     pub fn config(&self) -> &EngineConfig { &self.config }
     pub fn config_mut(&mut self) -> &mut EngineConfig { &mut self.config }
     pub fn state(&self) -> &EngineState { &self.state }
@@ -410,6 +861,10 @@ impl Engine {
     pub fn time_mut(&mut self) -> &mut Time { &mut self.time }
     pub fn input(&self) -> &Input { &self.input }
     pub fn input_mut(&mut self) -> &mut Input { &mut self.input }
+    /// Get the bus that system and custom events are published on
+    pub fn event_bus(&self) -> &EventBus { &self.event_bus }
+    /// Get the event bus for subscribing and publishing
+    pub fn event_bus_mut(&mut self) -> &mut EventBus { &mut self.event_bus }
 }
 
 impl Drop for Engine {
@@ -419,3 +874,244 @@ impl Drop for Engine {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rrte_math::Color;
+    use crate::{KeyModifiers, MouseButton, SystemEvent};
+    use rrte_renderer::{LambertianMaterial, PointLight, SceneObject, Sphere};
+
+    /// An engine with an initialized CPU renderer drawing a lit diffuse sphere
+    fn cpu_engine(width: u32, height: u32, seed: u64) -> Engine {
+        let config = EngineConfig::builder()
+            .renderer_config(RaytracerConfig {
+                width,
+                height,
+                samples_per_pixel: 2,
+                ..RaytracerConfig::default()
+            })
+            .seed(seed)
+            .log_level(log::LevelFilter::Warn)
+            .build()
+            .unwrap();
+        let mut engine = Engine::new(config).unwrap();
+        pollster::block_on(engine.initialize_renderer(None)).unwrap();
+
+        let mut sphere = Sphere::new(Vec3::new(0.0, 0.0, -4.0), 1.0);
+        sphere.set_material(LambertianMaterial::new(Color::new(0.8, 0.3, 0.2, 1.0)));
+        engine.scene_mut().add_sphere(Arc::new(sphere));
+        let light = PointLight::new(Vec3::new(2.0, 3.0, 0.0), Color::WHITE, 10.0);
+        engine.scene_mut().add_point_light(Arc::new(light));
+        engine
+    }
+
+    #[test]
+    fn render_sequence_is_reproducible_with_the_same_seed() {
+        let first = cpu_engine(16, 12, 42).render_sequence(3, 1.0 / 30.0).unwrap();
+        let mut engine = cpu_engine(16, 12, 42);
+        let second = engine.render_sequence(3, 1.0 / 30.0).unwrap();
+
+        assert_eq!(first.len(), 3);
+        assert!(first.iter().all(|frame| frame.len() == 16 * 12 * 4));
+        assert_eq!(first, second);
+        assert!((engine.time().elapsed_time() - 0.1).abs() < 1e-4);
+    }
+
+    #[test]
+    fn render_sequence_needs_a_renderer() {
+        let mut engine = Engine::new(EngineConfig::default()).unwrap();
+        assert!(engine.render_sequence(1, 0.1).is_err());
+    }
+
+    #[test]
+    fn two_viewports_are_rendered_into_their_own_rects() {
+        let mut engine = cpu_engine(32, 16, 1);
+        let camera = engine.camera().clone();
+        engine.add_viewport("left", camera.clone(), ViewportRect::new(0, 0, 12, 16));
+        engine.add_viewport("right", camera, ViewportRect::new(16, 0, 12, 16));
+        engine.render_frame().unwrap();
+        let frame = engine.capture_frame().unwrap();
+
+        let column_has_content = |x: usize| (0..16).any(|y| frame[(y * 32 + x) * 4 + 3] != 0);
+        let covered: Vec<bool> = (0..32).map(column_has_content).collect();
+        let expected: Vec<bool> = (0..32).map(|x| x < 12 || (16..28).contains(&x)).collect();
+        assert_eq!(covered, expected);
+
+        // Both viewports see the sphere in the middle of their rect
+        let center = |x: usize| &frame[(8 * 32 + x) * 4..(8 * 32 + x) * 4 + 4];
+        assert_eq!(center(6), center(22));
+        assert_ne!(center(6), center(0));
+    }
+
+    #[test]
+    fn commands_sent_from_another_thread_apply_on_the_next_frame() {
+        let mut engine = cpu_engine(8, 8, 1);
+        let sender = engine.command_sender();
+        std::thread::spawn(move || {
+            let sphere = Sphere::new(Vec3::new(1.0, 0.0, -4.0), 0.5);
+            sender.send(SceneCommand::AddSphere(Arc::new(sphere))).unwrap();
+            sender.send_fn(|scene| scene.config_mut().name = "Edited".to_string()).unwrap();
+        })
+        .join()
+        .unwrap();
+
+        // Nothing changes until the engine drains the queue
+        assert_eq!(engine.scene().objects().len(), 1);
+        engine.render_frame().unwrap();
+        assert_eq!(engine.scene().objects().len(), 2);
+        assert_eq!(engine.scene().config().name, "Edited");
+        assert_eq!(engine.apply_scene_commands(), 0);
+    }
+
+    #[test]
+    fn cpu_frames_reuse_the_static_hierarchy_and_draw_every_object_kind() {
+        let mut engine = cpu_engine(16, 12, 1);
+        engine.render_frame().unwrap();
+        let without_cube = engine.capture_frame().unwrap();
+
+        let mut cube = rrte_renderer::Cube::new(Vec3::new(-1.5, 0.0, -4.0), Vec3::splat(0.8));
+        cube.set_material(LambertianMaterial::new(Color::new(0.2, 0.8, 0.3, 1.0)));
+        engine.scene_mut().add_dynamic_object(Arc::new(cube));
+        engine.render_frame().unwrap();
+        engine.render_frame().unwrap();
+
+        // The moving cube is rebuilt every frame, the sphere's hierarchy only once
+        let bvh = engine.scene().bvh();
+        assert_eq!((bvh.static_builds(), bvh.dynamic_builds()), (1, 3));
+        assert_ne!(engine.capture_frame().unwrap(), without_cube);
+    }
+
+    #[test]
+    fn engines_with_the_same_seed_render_glass_and_area_lights_byte_identically() {
+        let render_with_panel = |seed: u64, panel: bool| {
+            let mut engine = cpu_engine(16, 12, seed);
+            let mut glass = Sphere::new(Vec3::new(0.8, 0.2, -3.0), 0.6);
+            glass.set_material(rrte_renderer::DielectricMaterial::new(1.5));
+            engine.scene_mut().add_sphere(Arc::new(glass));
+            if panel {
+                // Soft shadows sample a random point on the panel for every shading sample
+                let light = rrte_renderer::AreaLight::new(Vec3::new(0.0, 3.0, -3.0), Vec3::X, Vec3::Z, Color::WHITE, 4.0);
+                engine.scene_mut().add_light(Arc::new(light));
+            }
+            engine.render_frame().unwrap();
+            engine.capture_frame().unwrap()
+        };
+        let render = |seed: u64| render_with_panel(seed, true);
+        assert_ne!(render(9), render_with_panel(9, false), "the area light reaches the render");
+
+        assert_eq!(render(9), render(9));
+        assert_ne!(render(9), render(10));
+    }
+
+    #[test]
+    fn five_frame_benchmark_times_every_frame() {
+        let mut engine = cpu_engine(16, 12, 1);
+        let result = engine
+            .run_benchmark(
+                |scene, camera| {
+                    scene.add_sphere(Arc::new(Sphere::new(Vec3::new(0.0, 0.0, -4.0), 1.0)));
+                    camera.transform.position = Vec3::ZERO;
+                },
+                5,
+            )
+            .unwrap();
+
+        assert_eq!(result.frame_count(), 5);
+        assert!(result.average() > std::time::Duration::ZERO);
+        assert_eq!(result.to_csv().lines().count(), 6);
+        // The builder's scene replaced the one the engine started with
+        assert_eq!(engine.scene().objects().len(), 1);
+        assert!(engine.scene().lights().is_empty());
+    }
+
+    #[test]
+    fn dragging_along_x_moves_the_object_by_the_dragged_world_distance() {
+        let mut engine = cpu_engine(16, 12, 1);
+        let mut handle = Sphere::new(Vec3::ZERO, 0.5);
+        handle.set_transform(Transform::from_position(Vec3::new(0.0, 0.0, -4.0)));
+        engine.scene_mut().add_object(Arc::new(handle));
+        let id = rrte_scene::ObjectId::new(engine.scene().object_count() - 1);
+
+        let camera = engine.camera_mut();
+        camera.transform.position = Vec3::new(1.0, 2.0, 2.0);
+        camera.look_at(Vec3::new(0.0, 0.0, -4.0), Vec3::Y);
+        let from = engine.camera().project_to_screen(Vec3::new(0.0, 0.0, -4.0)).unwrap();
+        let to = engine.camera().project_to_screen(Vec3::new(1.5, 0.0, -4.0)).unwrap();
+
+        let offset = engine.drag_object_axis(id, Vec3::X, from, to).unwrap();
+        assert!(offset.abs_diff_eq(Vec3::new(1.5, 0.0, 0.0), 1e-3), "{offset}");
+        let position = engine.scene().get_object(id).unwrap().transform().position;
+        assert!(position.abs_diff_eq(Vec3::new(1.5, 0.0, -4.0), 1e-3), "{position}");
+
+        // Dragging back returns it to where it started
+        engine.drag_object_axis(id, Vec3::X, to, from).unwrap();
+        let position = engine.scene().get_object(id).unwrap().transform().position;
+        assert!(position.abs_diff_eq(Vec3::new(0.0, 0.0, -4.0), 1e-3), "{position}");
+    }
+
+    #[test]
+    fn builder_matches_the_struct_literal_and_rejects_invalid_settings() {
+        let renderer_config = RaytracerConfig::builder().width(320).height(240).build().unwrap();
+        let built = EngineConfig::builder()
+            .renderer_config(renderer_config.clone())
+            .target_fps(30.0)
+            .enable_vsync(false)
+            .seed(9)
+            .build()
+            .unwrap();
+        let literal = EngineConfig {
+            renderer_config,
+            target_fps: 30.0,
+            enable_vsync: false,
+            seed: Some(9),
+            ..EngineConfig::default()
+        };
+        assert_eq!(built, literal);
+
+        assert!(EngineConfig::builder().target_fps(0.0).build().is_err());
+        // GPU settings only matter when a GPU renderer will be created
+        let no_samples = GpuRendererConfig { samples: 0, ..GpuRendererConfig::default() };
+        assert!(EngineConfig::builder().gpu_renderer_config(no_samples.clone()).build().is_ok());
+        assert!(EngineConfig::builder()
+            .renderer_mode(RendererMode::GpuHeadless)
+            .gpu_renderer_config(no_samples)
+            .build()
+            .is_err());
+
+        // A GPU size of 0 inherits the CPU renderer's
+        let inherited = EngineConfig::builder()
+            .renderer_mode(RendererMode::GpuHeadless)
+            .renderer_config(RaytracerConfig::builder().width(320).height(240).build().unwrap())
+            .gpu_renderer_config(GpuRendererConfig { width: 0, height: 0, ..GpuRendererConfig::default() })
+            .build()
+            .unwrap();
+        let engine = Engine::new(inherited).unwrap();
+        let gpu_config = engine.gpu_config_for_init().unwrap();
+        assert_eq!((gpu_config.width, gpu_config.height), (320, 240));
+    }
+
+    #[test]
+    fn engine_input_update_times_held_keys_and_double_clicks() {
+        let config = EngineConfig::builder().log_level(log::LevelFilter::Warn).build().unwrap();
+        let mut engine = Engine::new(config).unwrap();
+        let frame = std::time::Duration::from_millis(250);
+        let click = |engine: &mut Engine| {
+            let (button, x, y) = (MouseButton::Left, 5.0, 5.0);
+            engine.input_mut().handle_event(&SystemEvent::MousePressed { button: button.clone(), x, y });
+            engine.input_mut().handle_event(&SystemEvent::MouseReleased { button, x, y });
+        };
+
+        engine.input_mut().handle_event(&SystemEvent::key_press("Space", KeyModifiers::default()));
+        click(&mut engine);
+        for _ in 0..4 {
+            engine.time_mut().step(frame);
+            engine.update_input();
+        }
+        assert!((engine.input().key_held_duration("Space") - 1.0).abs() < 1e-5);
+
+        // A second after the first click is too slow for a double-click
+        click(&mut engine);
+        assert!(!engine.input().is_mouse_double_clicked(&MouseButton::Left));
+    }
+}
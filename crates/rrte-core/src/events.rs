@@ -1,4 +1,5 @@
-use std::collections::VecDeque;
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, VecDeque};
 use serde::{Deserialize, Serialize};
 
 /// System event types
@@ -17,6 +18,24 @@ pub enum SystemEvent {
     MouseReleased { button: MouseButton, x: f32, y: f32 },
     MouseMoved { x: f32, y: f32, delta_x: f32, delta_y: f32 },
     MouseWheelScrolled { delta_x: f32, delta_y: f32 },
+    /// A gamepad axis moved
+    GamepadAxisMoved {
+        /// Name of the axis
+        axis: String,
+        /// New position of the axis, from -1 to 1
+        value: f32,
+    },
+    /// A touch began, moved or ended
+    Touch {
+        /// Identifier of the finger, stable while it touches
+        id: u64,
+        /// Stage of the touch
+        phase: TouchPhase,
+        /// Horizontal position in pixels
+        x: f32,
+        /// Vertical position in pixels
+        y: f32,
+    },
     
     /// Engine events
     SceneChanged,
@@ -55,6 +74,22 @@ pub enum MouseButton {
     Other(u16),
 }
 
+/// Stage of a touch contact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TouchPhase {
+    /// The finger touched the screen
+    Began,
+    /// The finger moved
+    Moved,
+    /// The finger is down but has not moved since the last input update;
+    /// only reported by [`crate::Input`], never sent as an event
+    Stationary,
+    /// The finger was lifted
+    Ended,
+    /// The system stopped tracking the touch
+    Cancelled,
+}
+
 /// Event listener trait
 pub trait EventListener {
     fn handle_event(&mut self, event: &SystemEvent) -> bool;
@@ -153,6 +188,66 @@ impl std::fmt::Debug for Events {
     }
 }
 
+/// Queues of gameplay events, one per event type.
+///
+/// Gameplay code sends any `'static` type and drains the events of one type at
+/// a time, so messages stay typed and separate from [`SystemEvent`]s.
+#[derive(Default)]
+pub struct EventBus {
+    /// `Vec<T>` of pending events for each `T`
+    queues: HashMap<TypeId, Box<dyn Any + Send>>,
+}
+
+impl EventBus {
+    /// Create an empty event bus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue an event
+    pub fn send<T: Send + 'static>(&mut self, event: T) {
+        self.queue_mut::<T>().push(event);
+    }
+
+    /// Take all pending events of one type, oldest first
+    pub fn drain<T: Send + 'static>(&mut self) -> Vec<T> {
+        self.queues
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|queue| queue.downcast_mut::<Vec<T>>())
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Get the number of pending events of one type
+    pub fn pending<T: Send + 'static>(&self) -> usize {
+        self.queues
+            .get(&TypeId::of::<T>())
+            .and_then(|queue| queue.downcast_ref::<Vec<T>>())
+            .map_or(0, Vec::len)
+    }
+
+    /// Drop all pending events of every type
+    pub fn clear(&mut self) {
+        self.queues.clear();
+    }
+
+    fn queue_mut<T: Send + 'static>(&mut self) -> &mut Vec<T> {
+        self.queues
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Vec::<T>::new()))
+            .downcast_mut::<Vec<T>>()
+            .expect("event queue is keyed by its element type")
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus")
+            .field("event_types", &self.queues.len())
+            .finish()
+    }
+}
+
 /// Helper function to create common events
 impl SystemEvent {
     /// Create a window resize event
@@ -191,6 +286,19 @@ impl SystemEvent {
         SystemEvent::MouseMoved { x, y, delta_x, delta_y }
     }
 
+    /// Create a touch event for the contact with this id
+    pub fn touch(id: u64, phase: TouchPhase, x: f32, y: f32) -> Self {
+        SystemEvent::Touch { id, phase, x, y }
+    }
+
+    /// Create a gamepad axis event; `value` is in `-1.0..=1.0`
+    pub fn gamepad_axis(axis: impl Into<String>, value: f32) -> Self {
+        SystemEvent::GamepadAxisMoved {
+            axis: axis.into(),
+            value,
+        }
+    }
+
     /// Create a custom event
     pub fn custom(name: impl Into<String>, data: impl Into<String>) -> Self {
         SystemEvent::Custom {
@@ -199,3 +307,36 @@ impl SystemEvent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Scored(u32);
+
+    #[derive(Debug, PartialEq)]
+    struct Died {
+        name: &'static str,
+    }
+
+    #[test]
+    fn each_event_type_drains_independently() {
+        let mut bus = EventBus::new();
+        bus.send(Scored(10));
+        bus.send(Died { name: "goblin" });
+        bus.send(Scored(25));
+
+        assert_eq!(bus.pending::<Scored>(), 2);
+        assert_eq!(bus.pending::<Died>(), 1);
+
+        // Draining one type leaves the other queued
+        assert_eq!(bus.drain::<Scored>(), vec![Scored(10), Scored(25)]);
+        assert_eq!(bus.pending::<Scored>(), 0);
+        assert_eq!(bus.pending::<Died>(), 1);
+
+        assert_eq!(bus.drain::<Died>(), vec![Died { name: "goblin" }]);
+        assert!(bus.drain::<Died>().is_empty());
+        assert!(bus.drain::<u64>().is_empty());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{SystemEvent, KeyModifiers, MouseButton};
+use crate::{SystemEvent, KeyModifiers, MouseButton, Time, TouchPhase};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use rrte_math::Vec2;
 
@@ -11,6 +12,57 @@ pub enum KeyState {
     JustReleased,
 }
 
+/// A finger on a touch screen, tracked from its `Began` to its `Ended` event
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Touch {
+    /// Id shared by all events of this contact
+    pub id: u64,
+    /// Latest phase; `Stationary` if nothing happened since the last update
+    pub phase: TouchPhase,
+    /// Current position
+    pub position: Vec2,
+    /// Where the touch began
+    pub start_position: Vec2,
+    /// Movement since the last update
+    pub delta: Vec2,
+}
+
+/// An event captured by [`Input::start_recording`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Number of [`Input::update`] calls between the start of the recording and the event
+    pub frame: u64,
+    /// Seconds of input time between the start of the recording and the event
+    pub time: f32,
+    /// The handled event
+    pub event: SystemEvent,
+}
+
+/// Events handled by an [`Input`] while recording, in order, for replaying
+/// with [`Input::play`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// Recorded events, oldest first
+    pub events: Vec<RecordedEvent>,
+}
+
+impl InputRecording {
+    /// Get the number of frames from the start of the recording to its last event
+    pub fn frame_count(&self) -> u64 {
+        self.events.last().map_or(0, |event| event.frame + 1)
+    }
+}
+
+/// A recording being recorded or replayed, and the frame it started on
+#[derive(Debug)]
+struct RecordingSession {
+    recording: InputRecording,
+    start_frame: u64,
+    start_time: f32,
+    /// Index of the next event to replay
+    next_event: usize,
+}
+
 /// Input system for tracking keyboard and mouse state
 #[derive(Debug)]
 pub struct Input {
@@ -24,12 +76,34 @@ pub struct Input {
     last_mouse_position: Vec2,
     mouse_delta: Vec2,
     mouse_wheel_delta: Vec2,
+    smooth_scroll: f32,
+    scroll_decay: f32,
+
+    // Gamepad state
+    gamepad_axes: HashMap<String, f32>,
+
+    // Touch state, in the order the touches began
+    touches: Vec<Touch>,
+
+    // Gesture state
+    current_time: f32,
+    double_click_time: f32,
+    last_press_times: HashMap<MouseButton, f32>,
+    press_positions: HashMap<MouseButton, Vec2>,
+    double_clicked_mouse_buttons: Vec<MouseButton>,
+    key_press_times: HashMap<String, f32>,
+    mouse_press_times: HashMap<MouseButton, f32>,
     
     // Internal state
     just_pressed_keys: Vec<String>,
     just_released_keys: Vec<String>,
     just_pressed_mouse_buttons: Vec<MouseButton>,
     just_released_mouse_buttons: Vec<MouseButton>,
+
+    // Recording and playback
+    frame: u64,
+    recording: Option<RecordingSession>,
+    playback: Option<RecordingSession>,
 }
 
 impl Input {
@@ -43,10 +117,24 @@ impl Input {
             last_mouse_position: Vec2::ZERO,
             mouse_delta: Vec2::ZERO,
             mouse_wheel_delta: Vec2::ZERO,
+            smooth_scroll: 0.0,
+            scroll_decay: 0.8,
+            gamepad_axes: HashMap::new(),
+            touches: Vec::new(),
+            current_time: 0.0,
+            double_click_time: 0.3,
+            last_press_times: HashMap::new(),
+            press_positions: HashMap::new(),
+            double_clicked_mouse_buttons: Vec::new(),
+            key_press_times: HashMap::new(),
+            mouse_press_times: HashMap::new(),
             just_pressed_keys: Vec::new(),
             just_released_keys: Vec::new(),
             just_pressed_mouse_buttons: Vec::new(),
             just_released_mouse_buttons: Vec::new(),
+            frame: 0,
+            recording: None,
+            playback: None,
         }
     }
 
@@ -91,33 +179,168 @@ impl Input {
         self.just_released_keys.clear();
         self.just_pressed_mouse_buttons.clear();
         self.just_released_mouse_buttons.clear();
+        self.double_clicked_mouse_buttons.clear();
 
         // Reset mouse delta and wheel delta
         self.mouse_wheel_delta = Vec2::ZERO;
+
+        // Drop finished touches; the rest are stationary until their next event
+        self.touches.retain(|touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled));
+        for touch in &mut self.touches {
+            touch.phase = TouchPhase::Stationary;
+            touch.delta = Vec2::ZERO;
+        }
+
+        // Ease accumulated scroll toward zero for momentum
+        self.smooth_scroll *= self.scroll_decay;
+        if self.smooth_scroll.abs() < 1e-4 {
+            self.smooth_scroll = 0.0;
+        }
+
+        self.frame += 1;
+        self.replay_frame();
+    }
+
+    /// Start capturing every event passed to [`Input::handle_event`], with the
+    /// frame and time it arrived on, discarding any recording in progress
+    pub fn start_recording(&mut self) {
+        self.recording = Some(RecordingSession {
+            recording: InputRecording::default(),
+            start_frame: self.frame,
+            start_time: self.current_time,
+            next_event: 0,
+        });
+    }
+
+    /// Stop recording and get the captured events, or an empty recording if
+    /// none was in progress
+    pub fn stop_recording(&mut self) -> InputRecording {
+        self.recording.take().map(|session| session.recording).unwrap_or_default()
+    }
+
+    /// Check whether events are being recorded
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Replay a recording, feeding each event to [`Input::handle_event`] on
+    /// the same frame relative to this call as it was recorded on.
+    ///
+    /// Events of the first frame are handled immediately, later ones right
+    /// after the matching [`Input::update`]. Replaces any playback in progress.
+    pub fn play(&mut self, recording: InputRecording) {
+        self.playback = Some(RecordingSession {
+            recording,
+            start_frame: self.frame,
+            start_time: self.current_time,
+            next_event: 0,
+        });
+        self.replay_frame();
+    }
+
+    /// Stop replaying the current recording
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+    }
+
+    /// Check whether a recording is being replayed
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Handle the events of the playback recorded for the current frame, and
+    /// end the playback after its last event
+    fn replay_frame(&mut self) {
+        let Some(mut playback) = self.playback.take() else {
+            return;
+        };
+        let frame = self.frame - playback.start_frame;
+        while let Some(recorded) = playback.recording.events.get(playback.next_event) {
+            if recorded.frame > frame {
+                break;
+            }
+            let event = recorded.event.clone();
+            playback.next_event += 1;
+            self.handle_event(&event);
+        }
+        if playback.next_event < playback.recording.events.len() {
+            self.playback = Some(playback);
+        }
+    }
+
+    /// Record the current engine time used for gesture timing (call once per
+    /// frame; [`crate::Engine::update_input`] does this before updating)
+    pub fn sync_time(&mut self, time: &Time) {
+        self.set_current_time(time.elapsed_time());
+    }
+
+    /// Set the current time in seconds used for gesture timing
+    pub fn set_current_time(&mut self, seconds: f32) {
+        self.current_time = seconds;
+    }
+
+    /// Set the maximum time in seconds between two presses for a double-click
+    pub fn set_double_click_time(&mut self, seconds: f32) {
+        self.double_click_time = seconds.max(0.0);
+    }
+
+    /// Get the maximum time in seconds between two presses for a double-click
+    pub fn double_click_time(&self) -> f32 {
+        self.double_click_time
     }
 
     /// Handle system events
     pub fn handle_event(&mut self, event: &SystemEvent) {
+        if let Some(session) = &mut self.recording {
+            session.recording.events.push(RecordedEvent {
+                frame: self.frame - session.start_frame,
+                time: self.current_time - session.start_time,
+                event: event.clone(),
+            });
+        }
+
         match event {
             SystemEvent::KeyPressed { key, modifiers } => {
                 self.key_modifiers = modifiers.clone();
+                // Auto-repeat presses of a held key keep the original press time
+                if !self.is_key_pressed(key) {
+                    self.key_press_times.insert(key.clone(), self.current_time);
+                }
                 self.keys.insert(key.clone(), KeyState::JustPressed);
                 self.just_pressed_keys.push(key.clone());
             }
             SystemEvent::KeyReleased { key, modifiers } => {
                 self.key_modifiers = modifiers.clone();
                 self.keys.insert(key.clone(), KeyState::JustReleased);
+                self.key_press_times.remove(key);
                 self.just_released_keys.push(key.clone());
             }
             SystemEvent::MousePressed { button, x, y } => {
                 self.mouse_position = Vec2::new(*x, *y);
+                if !self.is_mouse_button_pressed(button) {
+                    self.mouse_press_times.insert(button.clone(), self.current_time);
+                }
                 self.mouse_buttons.insert(button.clone(), KeyState::JustPressed);
                 self.just_pressed_mouse_buttons.push(button.clone());
+
+                // A second press within the window completes a double-click; the
+                // press that completes it does not start another one.
+                match self.last_press_times.remove(button) {
+                    Some(last) if self.current_time - last <= self.double_click_time => {
+                        self.double_clicked_mouse_buttons.push(button.clone());
+                    }
+                    _ => {
+                        self.last_press_times.insert(button.clone(), self.current_time);
+                    }
+                }
+                self.press_positions.insert(button.clone(), self.mouse_position);
             }
             SystemEvent::MouseReleased { button, x, y } => {
                 self.mouse_position = Vec2::new(*x, *y);
                 self.mouse_buttons.insert(button.clone(), KeyState::JustReleased);
                 self.just_released_mouse_buttons.push(button.clone());
+                self.press_positions.remove(button);
+                self.mouse_press_times.remove(button);
             }
             SystemEvent::MouseMoved { x, y, delta_x, delta_y } => {
                 self.last_mouse_position = self.mouse_position;
@@ -126,11 +349,42 @@ impl Input {
             }
             SystemEvent::MouseWheelScrolled { delta_x, delta_y } => {
                 self.mouse_wheel_delta = Vec2::new(*delta_x, *delta_y);
+                self.smooth_scroll += *delta_y;
+            }
+            SystemEvent::GamepadAxisMoved { axis, value } => {
+                self.gamepad_axes.insert(axis.clone(), value.clamp(-1.0, 1.0));
+            }
+            SystemEvent::Touch { id, phase, x, y } => {
+                self.handle_touch(*id, *phase, Vec2::new(*x, *y));
             }
             _ => {}
         }
     }
 
+    /// Start, move or end a tracked touch.
+    ///
+    /// Ended and cancelled touches stay visible until the next `update`, so
+    /// their final phase can be observed for one frame.
+    fn handle_touch(&mut self, id: u64, phase: TouchPhase, position: Vec2) {
+        if phase == TouchPhase::Began {
+            self.touches.retain(|touch| touch.id != id);
+            self.touches.push(Touch {
+                id,
+                phase,
+                position,
+                start_position: position,
+                delta: Vec2::ZERO,
+            });
+            return;
+        }
+
+        if let Some(touch) = self.touches.iter_mut().find(|touch| touch.id == id) {
+            touch.delta += position - touch.position;
+            touch.position = position;
+            touch.phase = phase;
+        }
+    }
+
     // Keyboard queries
     
     /// Check if a key is currently pressed
@@ -141,6 +395,15 @@ impl Input {
         )
     }
 
+    /// Get how long a key has been held in seconds, or `0.0` if it is not pressed.
+    ///
+    /// Measured from the press using the time set by [`Input::sync_time`].
+    pub fn key_held_duration(&self, key: &str) -> f32 {
+        self.key_press_times
+            .get(key)
+            .map_or(0.0, |pressed_at| (self.current_time - pressed_at).max(0.0))
+    }
+
     /// Check if a key was just pressed this frame
     pub fn is_key_just_pressed(&self, key: &str) -> bool {
         matches!(self.keys.get(key), Some(KeyState::JustPressed))
@@ -166,6 +429,13 @@ impl Input {
         )
     }
 
+    /// Get how long a mouse button has been held in seconds, or `0.0` if it is not pressed
+    pub fn mouse_button_held_duration(&self, button: &MouseButton) -> f32 {
+        self.mouse_press_times
+            .get(button)
+            .map_or(0.0, |pressed_at| (self.current_time - pressed_at).max(0.0))
+    }
+
     /// Check if a mouse button was just pressed this frame
     pub fn is_mouse_button_just_pressed(&self, button: &MouseButton) -> bool {
         matches!(self.mouse_buttons.get(button), Some(KeyState::JustPressed))
@@ -176,6 +446,18 @@ impl Input {
         matches!(self.mouse_buttons.get(button), Some(KeyState::JustReleased))
     }
 
+    /// Check if a mouse button was double-clicked this frame
+    pub fn is_mouse_double_clicked(&self, button: &MouseButton) -> bool {
+        self.double_clicked_mouse_buttons.contains(button)
+    }
+
+    /// Get the drag delta since the button was pressed, if it is still held
+    pub fn mouse_drag(&self, button: &MouseButton) -> Option<Vec2> {
+        self.press_positions
+            .get(button)
+            .map(|start| self.mouse_position - *start)
+    }
+
     /// Get the current mouse position
     pub fn mouse_position(&self) -> Vec2 {
         self.mouse_position
@@ -191,11 +473,46 @@ impl Input {
         self.mouse_wheel_delta
     }
 
+    /// Get the vertical scroll with momentum: wheel input accumulates here and
+    /// decays by [`Input::scroll_decay`] on each `update` instead of resetting
+    pub fn smooth_scroll(&self) -> f32 {
+        self.smooth_scroll
+    }
+
+    /// Set the fraction of smooth scroll retained per update (0 disables momentum)
+    pub fn set_scroll_decay(&mut self, decay: f32) {
+        self.scroll_decay = decay.clamp(0.0, 1.0);
+    }
+
+    /// Get the fraction of smooth scroll retained per update
+    pub fn scroll_decay(&self) -> f32 {
+        self.scroll_decay
+    }
+
     /// Get the previous mouse position
     pub fn last_mouse_position(&self) -> Vec2 {
         self.last_mouse_position
     }
 
+    // Gamepad queries
+
+    /// Get the last reported value of a gamepad axis, or `0.0` if it has never moved
+    pub fn gamepad_axis(&self, axis: &str) -> f32 {
+        self.gamepad_axes.get(axis).copied().unwrap_or(0.0)
+    }
+
+    // Touch queries
+
+    /// Get the active touches, in the order they began
+    pub fn touches(&self) -> &[Touch] {
+        &self.touches
+    }
+
+    /// Get the touch with this id, if it is active
+    pub fn touch(&self, id: u64) -> Option<&Touch> {
+        self.touches.iter().find(|touch| touch.id == id)
+    }
+
     // Convenience methods for common keys/buttons
     
     /// Check if the left mouse button is pressed
@@ -237,10 +554,18 @@ impl Input {
         self.last_mouse_position = Vec2::ZERO;
         self.mouse_delta = Vec2::ZERO;
         self.mouse_wheel_delta = Vec2::ZERO;
+        self.smooth_scroll = 0.0;
+        self.gamepad_axes.clear();
+        self.touches.clear();
         self.just_pressed_keys.clear();
         self.just_released_keys.clear();
         self.just_pressed_mouse_buttons.clear();
         self.just_released_mouse_buttons.clear();
+        self.last_press_times.clear();
+        self.press_positions.clear();
+        self.double_clicked_mouse_buttons.clear();
+        self.key_press_times.clear();
+        self.mouse_press_times.clear();
     }
 }
 
@@ -267,3 +592,188 @@ impl std::hash::Hash for MouseButton {
 
 // Eq implementation for MouseButton
 impl Eq for MouseButton {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut Input, x: f32, y: f32) {
+        input.handle_event(&SystemEvent::MousePressed { button: MouseButton::Left, x, y });
+    }
+
+    fn release(input: &mut Input, x: f32, y: f32) {
+        input.handle_event(&SystemEvent::MouseReleased { button: MouseButton::Left, x, y });
+    }
+
+    #[test]
+    fn two_quick_presses_are_a_double_click() {
+        let mut input = Input::new();
+        input.set_current_time(1.0);
+        press(&mut input, 10.0, 10.0);
+        assert!(!input.is_mouse_double_clicked(&MouseButton::Left));
+        release(&mut input, 10.0, 10.0);
+        input.update();
+
+        input.set_current_time(1.2);
+        press(&mut input, 10.0, 10.0);
+        assert!(input.is_mouse_double_clicked(&MouseButton::Left));
+
+        input.update();
+        assert!(!input.is_mouse_double_clicked(&MouseButton::Left));
+    }
+
+    #[test]
+    fn slow_presses_are_not_a_double_click() {
+        let mut input = Input::new();
+        input.set_current_time(1.0);
+        press(&mut input, 10.0, 10.0);
+        release(&mut input, 10.0, 10.0);
+        input.update();
+
+        input.set_current_time(1.5);
+        press(&mut input, 10.0, 10.0);
+        assert!(!input.is_mouse_double_clicked(&MouseButton::Left));
+    }
+
+    #[test]
+    fn press_move_release_reports_the_drag_delta() {
+        let mut input = Input::new();
+        assert_eq!(input.mouse_drag(&MouseButton::Left), None);
+
+        press(&mut input, 10.0, 20.0);
+        input.update();
+        input.handle_event(&SystemEvent::MouseMoved {
+            x: 40.0,
+            y: 15.0,
+            delta_x: 30.0,
+            delta_y: -5.0,
+        });
+        assert_eq!(input.mouse_drag(&MouseButton::Left), Some(Vec2::new(30.0, -5.0)));
+
+        release(&mut input, 40.0, 15.0);
+        assert_eq!(input.mouse_drag(&MouseButton::Left), None);
+    }
+
+    #[test]
+    fn smooth_scroll_decays_over_several_updates() {
+        let mut input = Input::new();
+        input.handle_event(&SystemEvent::MouseWheelScrolled { delta_x: 0.0, delta_y: 3.0 });
+        assert_eq!(input.smooth_scroll(), 3.0);
+
+        let mut previous = input.smooth_scroll();
+        for _ in 0..3 {
+            input.update();
+            assert_eq!(input.mouse_wheel_delta(), Vec2::ZERO);
+            assert!(input.smooth_scroll() > 0.0 && input.smooth_scroll() < previous);
+            previous = input.smooth_scroll();
+        }
+
+        // Without momentum it drops to zero on the next update
+        input.set_scroll_decay(0.0);
+        input.update();
+        assert_eq!(input.smooth_scroll(), 0.0);
+    }
+
+    #[test]
+    fn key_held_duration_accumulates_frame_time() {
+        let mut input = Input::new();
+        let mut time = Time::new();
+        input.sync_time(&time);
+        input.handle_event(&SystemEvent::key_press("Space", KeyModifiers::default()));
+
+        for _ in 0..2 {
+            time.step(std::time::Duration::from_millis(250));
+            input.sync_time(&time);
+            input.update();
+        }
+        assert!((input.key_held_duration("Space") - 0.5).abs() < 1e-5);
+
+        input.handle_event(&SystemEvent::key_release("Space", KeyModifiers::default()));
+        assert_eq!(input.key_held_duration("Space"), 0.0);
+    }
+
+    #[test]
+    fn concurrent_touches_are_tracked_independently_until_they_end() {
+        let mut input = Input::new();
+        input.handle_event(&SystemEvent::touch(1, TouchPhase::Began, 10.0, 10.0));
+        input.handle_event(&SystemEvent::touch(2, TouchPhase::Began, 100.0, 50.0));
+        assert_eq!(input.touches().len(), 2);
+        input.update();
+
+        // Only the first finger moves
+        input.handle_event(&SystemEvent::touch(1, TouchPhase::Moved, 15.0, 20.0));
+        let first = input.touch(1).unwrap();
+        assert_eq!(first.phase, TouchPhase::Moved);
+        assert_eq!(first.delta, Vec2::new(5.0, 10.0));
+        assert_eq!(first.start_position, Vec2::new(10.0, 10.0));
+        let second = input.touch(2).unwrap();
+        assert_eq!(second.phase, TouchPhase::Stationary);
+        assert_eq!(second.position, Vec2::new(100.0, 50.0));
+        input.update();
+
+        // Ending the second leaves the first, which is cancelled a frame later
+        input.handle_event(&SystemEvent::touch(2, TouchPhase::Ended, 100.0, 50.0));
+        assert_eq!(input.touch(2).unwrap().phase, TouchPhase::Ended);
+        input.update();
+        assert!(input.touch(2).is_none());
+        assert_eq!(input.touches().iter().map(|touch| touch.id).collect::<Vec<_>>(), [1]);
+
+        input.handle_event(&SystemEvent::touch(1, TouchPhase::Cancelled, 15.0, 20.0));
+        input.update();
+        assert!(input.touches().is_empty());
+    }
+
+    #[test]
+    fn replaying_recorded_key_events_reproduces_the_key_state_frame_by_frame() {
+        let key_event = |pressed: bool, key: &str| {
+            let (key, modifiers) = (key.to_string(), KeyModifiers::default());
+            if pressed {
+                SystemEvent::KeyPressed { key, modifiers }
+            } else {
+                SystemEvent::KeyReleased { key, modifiers }
+            }
+        };
+        // Events arriving on each frame
+        let frames = [
+            vec![key_event(true, "W")],
+            vec![],
+            vec![key_event(true, "A")],
+            vec![key_event(false, "W")],
+            vec![],
+            vec![key_event(false, "A"), key_event(true, "Space")],
+            vec![key_event(false, "Space")],
+        ];
+        let key_state = |input: &Input| {
+            ["W", "A", "Space"].map(|key| (input.is_key_pressed(key), input.is_key_just_pressed(key)))
+        };
+
+        let mut live = Input::new();
+        live.update();
+        live.start_recording();
+        let mut expected = Vec::new();
+        for events in &frames {
+            for event in events {
+                live.handle_event(event);
+            }
+            expected.push(key_state(&live));
+            live.update();
+        }
+        let recording = live.stop_recording();
+        assert_eq!(recording.events.len(), 6);
+        assert_eq!(recording.frame_count(), 7);
+
+        // The recording survives serialization
+        let json = serde_json::to_string(&recording).unwrap();
+        let recording: InputRecording = serde_json::from_str(&json).unwrap();
+
+        let mut replay = Input::new();
+        replay.play(recording);
+        let mut replayed = Vec::new();
+        for _ in &frames {
+            replayed.push(key_state(&replay));
+            replay.update();
+        }
+        assert_eq!(replayed, expected);
+        assert!(!replay.is_playing());
+    }
+}
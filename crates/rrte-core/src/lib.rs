@@ -5,11 +5,19 @@
 pub mod engine;
 pub mod time;
 pub mod input;
+pub mod action;
 pub mod events;
 pub mod camera;
+pub mod constraint;
+pub mod command;
+pub mod benchmark;
 
 pub use engine::*;
 pub use time::*;
 pub use input::*;
+pub use action::*;
 pub use events::*;
 pub use camera::*;
+pub use constraint::*;
+pub use command::*;
+pub use benchmark::*;
@@ -30,6 +30,13 @@ impl Time {
         self.delta_time = self.current_frame - self.last_frame;
     }
 
+    /// Advance by a fixed step instead of the wall clock (for deterministic playback)
+    pub fn step(&mut self, delta: Duration) {
+        self.last_frame = self.current_frame;
+        self.current_frame += delta;
+        self.delta_time = delta;
+    }
+
     /// Get delta time in seconds
     pub fn delta_time(&self) -> f32 {
         self.delta_time.as_secs_f32() * self.time_scale
@@ -0,0 +1,38 @@
+//! Component change notifications
+
+use crate::Entity;
+use std::any::TypeId;
+
+/// Kind of change made to a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentEventKind {
+    /// The component was attached to the entity
+    Added,
+    /// An existing component was replaced with a new value
+    Changed,
+    /// The component was detached from the entity
+    Removed,
+}
+
+/// Notification that a component on an entity was added, changed or removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentEvent {
+    /// Entity the component belongs to
+    pub entity: Entity,
+    /// Type of the component
+    pub type_id: TypeId,
+    /// What happened to the component
+    pub kind: ComponentEventKind,
+}
+
+impl ComponentEvent {
+    /// Create a new component event
+    pub fn new(entity: Entity, type_id: TypeId, kind: ComponentEventKind) -> Self {
+        Self { entity, type_id, kind }
+    }
+
+    /// Check whether this event concerns components of type `T`
+    pub fn is<T: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+}
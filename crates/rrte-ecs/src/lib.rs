@@ -7,9 +7,11 @@ pub mod component;
 pub mod system;
 pub mod world;
 pub mod query;
+pub mod event;
 
 pub use entity::*;
 pub use component::*;
 pub use system::*;
 pub use world::*;
 pub use query::*;
+pub use event::*;
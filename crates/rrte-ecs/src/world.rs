@@ -1,4 +1,4 @@
-use crate::{Entity, Component, ComponentStorage};
+use crate::{Entity, Component, ComponentStorage, ComponentEvent, ComponentEventKind};
 use std::collections::HashMap;
 use std::any::TypeId;
 
@@ -7,6 +7,7 @@ pub struct World {
     entities: Vec<Entity>,
     next_entity_id: u64,
     component_managers: HashMap<TypeId, ComponentStorage>,
+    events: Vec<ComponentEvent>,
 }
 
 impl World {
@@ -15,6 +16,7 @@ impl World {
             entities: Vec::new(),
             next_entity_id: 0,
             component_managers: HashMap::new(),
+            events: Vec::new(),
         }
     }
 
@@ -41,7 +43,12 @@ impl World {
         }
         
         if let Some(storage) = self.component_managers.get_mut(&type_id) {
-            storage.insert(entity, component);
+            let kind = if storage.insert(entity, component).is_some() {
+                ComponentEventKind::Changed
+            } else {
+                ComponentEventKind::Added
+            };
+            self.events.push(ComponentEvent::new(entity, type_id, kind));
         }
     }
 
@@ -58,7 +65,11 @@ impl World {
     pub fn remove_component<T: Component + 'static>(&mut self, entity: Entity) -> bool {
         let type_id = TypeId::of::<T>();
         if let Some(storage) = self.component_managers.get_mut(&type_id) {
-            storage.remove(entity).is_some()
+            let removed = storage.remove(entity).is_some();
+            if removed {
+                self.events.push(ComponentEvent::new(entity, type_id, ComponentEventKind::Removed));
+            }
+            removed
         } else {
             false
         }
@@ -73,9 +84,37 @@ impl World {
         }
     }
 
+    /// Get the live entities in creation order.
+    ///
+    /// The slice borrows the world, so it cannot be held while entities are
+    /// created or destroyed; use [`World::entities_snapshot`] for that.
     pub fn get_entities(&self) -> &[Entity] {
         &self.entities
     }
+
+    /// Copy the live entities, in creation order, for iterating while mutating the world.
+    ///
+    /// The snapshot is never updated: entities created afterwards are missing
+    /// from it until the next snapshot, and entities destroyed afterwards are
+    /// still listed, so check [`World::contains_entity`] before using one.
+    pub fn entities_snapshot(&self) -> Vec<Entity> {
+        self.entities.clone()
+    }
+
+    /// Check whether an entity exists and has not been destroyed
+    pub fn contains_entity(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    /// Take all component events queued since the last drain, in the order they occurred
+    pub fn drain_events(&mut self) -> Vec<ComponentEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Peek at the queued component events without consuming them
+    pub fn pending_events(&self) -> &[ComponentEvent] {
+        &self.events
+    }
 }
 
 impl Default for World {
@@ -83,3 +122,54 @@ impl Default for World {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn adding_then_removing_a_component_emits_added_then_removed() {
+        let mut world = World::new();
+        let entity = world.create_entity();
+        world.add_component(entity, Health(10));
+        world.add_component(entity, Health(5));
+        assert!(world.remove_component::<Health>(entity));
+        assert!(!world.remove_component::<Health>(entity));
+
+        let events = world.drain_events();
+        let kinds: Vec<ComponentEventKind> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            [ComponentEventKind::Added, ComponentEventKind::Changed, ComponentEventKind::Removed]
+        );
+        assert!(events.iter().all(|event| event.entity == entity && event.is::<Health>()));
+        assert!(world.pending_events().is_empty());
+    }
+
+    #[test]
+    fn entities_spawned_while_iterating_a_snapshot_appear_in_the_next_one() {
+        let mut world = World::new();
+        let first = world.create_entity();
+        let second = world.create_entity();
+
+        let snapshot = world.entities_snapshot();
+        let mut visited = Vec::new();
+        let mut spawned = Vec::new();
+        for &entity in &snapshot {
+            visited.push(entity);
+            spawned.push(world.create_entity());
+        }
+        assert_eq!(visited, [first, second]);
+
+        let next = world.entities_snapshot();
+        assert_eq!(next.len(), 4);
+        assert!(spawned.iter().all(|entity| next.contains(entity)));
+
+        // Destroyed entities stay in old snapshots but are no longer contained
+        world.destroy_entity(first);
+        assert!(next.contains(&first) && !world.contains_entity(first));
+    }
+}
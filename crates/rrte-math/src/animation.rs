@@ -0,0 +1,120 @@
+//! Keyframed transform animation
+
+use crate::Transform;
+use serde::{Deserialize, Serialize};
+
+/// A transform pinned to a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Time of the keyframe in seconds
+    pub time: f32,
+    /// Transform at `time`
+    pub transform: Transform,
+}
+
+impl Keyframe {
+    /// Create a new keyframe
+    pub fn new(time: f32, transform: Transform) -> Self {
+        Self { time, transform }
+    }
+}
+
+/// Keyframed transform animation, interpolating position and scale linearly
+/// and rotation spherically between neighbouring keyframes
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct AnimationTrack {
+    keyframes: Vec<Keyframe>,
+    /// Wrap time past the last keyframe back to the start instead of holding the final pose
+    pub looping: bool,
+}
+
+impl AnimationTrack {
+    /// Create an empty, non-looping track
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether the track loops
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Add a keyframe, keeping keyframes ordered by time
+    pub fn add_keyframe(&mut self, time: f32, transform: Transform) {
+        let index = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        self.keyframes.insert(index, Keyframe::new(time, transform));
+    }
+
+    /// Builder-style variant of [`AnimationTrack::add_keyframe`]
+    pub fn with_keyframe(mut self, time: f32, transform: Transform) -> Self {
+        self.add_keyframe(time, transform);
+        self
+    }
+
+    /// Get the keyframes in time order
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Sample the track at `time`, returning `None` if it has no keyframes.
+    ///
+    /// Times before the first keyframe hold the first pose; times after the last
+    /// hold the last pose unless the track loops.
+    pub fn sample(&self, time: f32) -> Option<Transform> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+
+        let time = if self.looping && last.time > first.time {
+            first.time + (time - first.time).rem_euclid(last.time - first.time)
+        } else {
+            time
+        };
+
+        if time <= first.time {
+            return Some(first.transform.clone());
+        }
+        if time >= last.time {
+            return Some(last.transform.clone());
+        }
+
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        let from = &self.keyframes[next - 1];
+        let to = &self.keyframes[next];
+        let t = (time - from.time) / (to.time - from.time);
+        Some(from.transform.lerp(&to.transform, t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    fn track() -> AnimationTrack {
+        AnimationTrack::new()
+            .with_keyframe(2.0, Transform::from_position(Vec3::new(4.0, 0.0, 0.0)))
+            .with_keyframe(0.0, Transform::from_position(Vec3::ZERO))
+    }
+
+    #[test]
+    fn sample_interpolates_between_keyframes_and_holds_the_ends() {
+        let track = track();
+        assert_eq!(track.duration(), 2.0);
+        assert_eq!(track.sample(0.5).unwrap().position, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(track.sample(-1.0).unwrap().position, Vec3::ZERO);
+        assert_eq!(track.sample(5.0).unwrap().position, Vec3::new(4.0, 0.0, 0.0));
+        assert!(AnimationTrack::new().sample(0.0).is_none());
+    }
+
+    #[test]
+    fn looping_track_wraps_past_the_last_keyframe() {
+        let track = track().with_looping(true);
+        assert_eq!(track.sample(2.5).unwrap().position, Vec3::new(1.0, 0.0, 0.0));
+    }
+}
@@ -1,4 +1,4 @@
-use glam::Vec3;
+use glam::{Mat4, Vec3, Vec4};
 use serde::{Deserialize, Serialize};
 
 /// Axis-aligned bounding box
@@ -9,6 +9,10 @@ pub struct AABB {
 }
 
 impl AABB {
+    /// Half-size of [`AABB::unbounded`] along each axis: beyond any scene, yet
+    /// finite so that arithmetic on the box stays well-defined
+    pub const UNBOUNDED_EXTENT: f32 = 1.0e9;
+
     /// Create a new AABB
     pub fn new(min: Vec3, max: Vec3) -> Self {
         Self { min, max }
@@ -22,6 +26,18 @@ impl AABB {
         }
     }
 
+    /// Get a huge box around the origin standing in for the bounds of
+    /// unbounded objects, such as infinite planes
+    pub fn unbounded() -> Self {
+        Self::from_center_extents(Vec3::ZERO, Vec3::splat(Self::UNBOUNDED_EXTENT))
+    }
+
+    /// Check whether the box spans [`AABB::unbounded`] along at least one axis,
+    /// as the bounds of unbounded objects do
+    pub fn is_unbounded(&self) -> bool {
+        self.size().max_element() >= 2.0 * Self::UNBOUNDED_EXTENT
+    }
+
     /// Get the center of the AABB
     pub fn center(&self) -> Vec3 {
         (self.min + self.max) * 0.5
@@ -63,6 +79,41 @@ impl AABB {
         self.max = self.max.max(other.max);
     }
 
+    /// Get the point inside the AABB closest to `point`
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        point.clamp(self.min, self.max)
+    }
+
+    /// Check if a sphere overlaps the AABB
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.closest_point(center).distance_squared(center) <= radius * radius
+    }
+
+    /// Get the eight corners of the AABB
+    pub fn corners(&self) -> [Vec3; 8] {
+        [
+            Vec3::new(self.min.x, self.min.y, self.min.z),
+            Vec3::new(self.max.x, self.min.y, self.min.z),
+            Vec3::new(self.min.x, self.max.y, self.min.z),
+            Vec3::new(self.max.x, self.max.y, self.min.z),
+            Vec3::new(self.min.x, self.min.y, self.max.z),
+            Vec3::new(self.max.x, self.min.y, self.max.z),
+            Vec3::new(self.min.x, self.max.y, self.max.z),
+            Vec3::new(self.max.x, self.max.y, self.max.z),
+        ]
+    }
+
+    /// Get the AABB enclosing this box after transforming it by a matrix
+    pub fn transformed(&self, matrix: &Mat4) -> Self {
+        let corners = self.corners();
+        let first = matrix.transform_point3(corners[0]);
+        let mut result = Self::new(first, first);
+        for corner in &corners[1..] {
+            result.expand_to_include(matrix.transform_point3(*corner));
+        }
+        result
+    }
+
     /// Test ray intersection with AABB
     pub fn intersect_ray(&self, ray: &crate::Ray) -> Option<(f32, f32)> {
         let inv_dir = 1.0 / ray.direction;
@@ -83,3 +134,60 @@ impl AABB {
         }
     }
 }
+
+/// View frustum described by six inward-facing planes
+///
+/// Each plane is stored as `(normal.x, normal.y, normal.z, distance)` so that
+/// `normal.dot(point) + distance >= 0` for points inside the frustum.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far planes
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extract the frustum planes from a view-projection matrix with a `[0, 1]` depth range
+    pub fn from_view_projection(view_projection: &Mat4) -> Self {
+        let r0 = view_projection.row(0);
+        let r1 = view_projection.row(1);
+        let r2 = view_projection.row(2);
+        let r3 = view_projection.row(3);
+
+        let planes = [
+            r3 + r0, // Left
+            r3 - r0, // Right
+            r3 + r1, // Bottom
+            r3 - r1, // Top
+            r2,      // Near
+            r3 - r2, // Far
+        ]
+        .map(|plane| {
+            let length = plane.truncate().length();
+            if length > 0.0 { plane / length } else { plane }
+        });
+
+        Self { planes }
+    }
+
+    /// Check if a point lies inside the frustum
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.intersects_sphere(point, 0.0)
+    }
+
+    /// Check if a sphere intersects or lies inside the frustum
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+    }
+
+    /// Check if an AABB intersects or lies inside the frustum
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            // Test the corner furthest along the plane normal
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
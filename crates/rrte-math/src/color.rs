@@ -1,8 +1,28 @@
 use glam::{Vec3, Vec4};
-use serde::{Deserialize, Serialize};
+use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 
-/// RGBA color representation
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Blend modes used when compositing one color over another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlendMode {
+    /// Multiply the channels (darkens)
+    Multiply,
+    /// Inverse multiply of the inverted channels (lightens)
+    Screen,
+    /// Multiply or screen depending on the base channel
+    Overlay,
+    /// Add the channels together
+    Additive,
+    /// Standard "over" alpha compositing of the layer on top of the base
+    AlphaOver,
+}
+
+/// RGBA color representation.
+///
+/// Serializes as an `[r, g, b, a]` array; the `{r, g, b, a}` form written by
+/// older versions is still accepted when deserializing.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -64,6 +84,56 @@ impl Color {
         }
     }
 
+    /// Get the relative luminance of the linear RGB channels (Rec. 709 weights)
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    /// Encode a linear color as 8-bit sRGB RGBA.
+    ///
+    /// Out-of-gamut channels are clamped to `[0, 1]` first. RGB goes through the
+    /// piecewise sRGB transfer function (matching `*UnormSrgb` textures); alpha
+    /// stays linear.
+    pub fn to_srgb8(&self) -> [u8; 4] {
+        let color = self.clamp();
+        [
+            quantize(linear_to_srgb(color.r)),
+            quantize(linear_to_srgb(color.g)),
+            quantize(linear_to_srgb(color.b)),
+            quantize(color.a),
+        ]
+    }
+
+    /// Decode 8-bit sRGB RGBA into a linear color; the inverse of [`Color::to_srgb8`]
+    pub fn from_srgb8(rgba: [u8; 4]) -> Self {
+        Self {
+            r: srgb_to_linear(f32::from(rgba[0]) / 255.0),
+            g: srgb_to_linear(f32::from(rgba[1]) / 255.0),
+            b: srgb_to_linear(f32::from(rgba[2]) / 255.0),
+            a: f32::from(rgba[3]) / 255.0,
+        }
+    }
+
+    /// Compress linear HDR RGB into `[0, 1]` with the Reinhard operator
+    /// `x / (1 + x)`; alpha is unchanged
+    pub fn reinhard(&self) -> Self {
+        let curve = |x: f32| {
+            let x = x.max(0.0);
+            x / (1.0 + x)
+        };
+        Self::new(curve(self.r), curve(self.g), curve(self.b), self.a)
+    }
+
+    /// Compress linear HDR RGB into `[0, 1]` with Krzysztof Narkowicz's fit of
+    /// the ACES filmic curve; alpha is unchanged
+    pub fn aces_filmic(&self) -> Self {
+        let curve = |x: f32| {
+            let x = x.max(0.0);
+            ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+        };
+        Self::new(curve(self.r), curve(self.g), curve(self.b), self.a)
+    }
+
     /// Linear interpolation between colors
     pub fn lerp(&self, other: &Color, t: f32) -> Self {
         Self {
@@ -73,6 +143,156 @@ impl Color {
             a: self.a + (other.a - self.a) * t,
         }
     }
+
+    /// Blend another color (the layer) on top of this color (the base).
+    ///
+    /// All modes except `AlphaOver` operate on the RGB channels and keep the
+    /// base alpha. `AlphaOver` treats both colors as straight (non-premultiplied) alpha.
+    pub fn blend(&self, other: &Color, mode: BlendMode) -> Self {
+        match mode {
+            BlendMode::Multiply => self.map_rgb(other, |a, b| a * b),
+            BlendMode::Screen => self.map_rgb(other, |a, b| 1.0 - (1.0 - a) * (1.0 - b)),
+            BlendMode::Overlay => self.map_rgb(other, |a, b| {
+                if a < 0.5 {
+                    2.0 * a * b
+                } else {
+                    1.0 - 2.0 * (1.0 - a) * (1.0 - b)
+                }
+            }),
+            BlendMode::Additive => self.map_rgb(other, |a, b| a + b),
+            BlendMode::AlphaOver => {
+                let alpha = other.a + self.a * (1.0 - other.a);
+                if alpha <= 0.0 {
+                    return Color::TRANSPARENT;
+                }
+                let base_weight = self.a * (1.0 - other.a);
+                Self {
+                    r: (other.r * other.a + self.r * base_weight) / alpha,
+                    g: (other.g * other.a + self.g * base_weight) / alpha,
+                    b: (other.b * other.a + self.b * base_weight) / alpha,
+                    a: alpha,
+                }
+            }
+        }
+    }
+
+    /// Apply a per-channel function to the RGB channels, keeping this color's alpha
+    fn map_rgb(&self, other: &Color, f: impl Fn(f32, f32) -> f32) -> Self {
+        Self {
+            r: f(self.r, other.r),
+            g: f(self.g, other.g),
+            b: f(self.b, other.b),
+            a: self.a,
+        }
+    }
+}
+
+/// Apply the sRGB transfer function to a linear channel value in `[0, 1]`
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Invert the sRGB transfer function, returning a linear channel value
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode linear colors into a tightly packed 8-bit sRGB RGBA buffer
+pub fn colors_to_srgb8(colors: &[Color]) -> Vec<u8> {
+    colors.iter().flat_map(Color::to_srgb8).collect()
+}
+
+/// Decode a tightly packed 8-bit sRGB RGBA buffer into linear colors.
+///
+/// Trailing bytes that do not form a whole pixel are ignored.
+pub fn srgb8_to_colors(bytes: &[u8]) -> Vec<Color> {
+    bytes
+        .chunks_exact(4)
+        .map(|rgba| Color::from_srgb8([rgba[0], rgba[1], rgba[2], rgba[3]]))
+        .collect()
+}
+
+/// Round a `[0, 1]` value to the nearest 8-bit level
+fn quantize(value: f32) -> u8 {
+    (value * 255.0).round() as u8
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        [self.r, self.g, self.b, self.a].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Binary formats cannot tell an array from a map, but lay out both the same
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(ColorVisitor)
+        } else {
+            deserializer.deserialize_tuple(4, ColorVisitor)
+        }
+    }
+}
+
+/// Key of the legacy `{r, g, b, a}` form of a [`Color`]
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum ColorField {
+    R,
+    G,
+    B,
+    A,
+    #[serde(other)]
+    Other,
+}
+
+/// Reads a [`Color`] from either an `[r, g, b, a]` sequence or an `{r, g, b, a}` map
+struct ColorVisitor;
+
+impl<'de> Visitor<'de> for ColorVisitor {
+    type Value = Color;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an [r, g, b, a] array or a map with r, g, b and a")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Color, A::Error> {
+        let mut channels = [0.0; 4];
+        for (index, channel) in channels.iter_mut().enumerate() {
+            *channel = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(index, &self))?;
+        }
+        Ok(Color::new(channels[0], channels[1], channels[2], channels[3]))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Color, A::Error> {
+        let mut channels = [None; 4];
+        while let Some(field) = map.next_key()? {
+            match field {
+                ColorField::R => channels[0] = Some(map.next_value()?),
+                ColorField::G => channels[1] = Some(map.next_value()?),
+                ColorField::B => channels[2] = Some(map.next_value()?),
+                ColorField::A => channels[3] = Some(map.next_value()?),
+                ColorField::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        let [r, g, b, a] = channels;
+        Ok(Color::new(
+            r.ok_or_else(|| de::Error::missing_field("r"))?,
+            g.ok_or_else(|| de::Error::missing_field("g"))?,
+            b.ok_or_else(|| de::Error::missing_field("b"))?,
+            a.ok_or_else(|| de::Error::missing_field("a"))?,
+        ))
+    }
 }
 
 impl From<Vec3> for Color {
@@ -112,3 +332,114 @@ impl std::ops::Mul<f32> for Color {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Color, expected: Color) {
+        let difference = (actual.to_vec4() - expected.to_vec4()).abs().max_element();
+        assert!(difference < 1e-6, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn multiply_darkens() {
+        let blended = Color::gray(0.5).blend(&Color::rgb(0.5, 1.0, 0.0), BlendMode::Multiply);
+        assert_close(blended, Color::rgb(0.25, 0.5, 0.0));
+    }
+
+    #[test]
+    fn screen_lightens() {
+        let blended = Color::gray(0.5).blend(&Color::rgb(0.5, 1.0, 0.0), BlendMode::Screen);
+        assert_close(blended, Color::rgb(0.75, 1.0, 0.5));
+    }
+
+    #[test]
+    fn additive_sums_channels_without_clamping() {
+        let blended = Color::rgb(0.5, 0.25, 0.0).blend(&Color::rgb(0.25, 1.0, 0.5), BlendMode::Additive);
+        assert_close(blended, Color::rgb(0.75, 1.25, 0.5));
+    }
+
+    #[test]
+    fn overlay_multiplies_dark_bases_and_screens_light_ones() {
+        let dark = Color::gray(0.25).blend(&Color::gray(0.5), BlendMode::Overlay);
+        let light = Color::gray(0.75).blend(&Color::gray(0.5), BlendMode::Overlay);
+        assert_close(dark, Color::gray(0.25));
+        assert_close(light, Color::gray(0.75));
+
+        let blended = Color::gray(0.25).blend(&Color::gray(0.2), BlendMode::Overlay);
+        assert_close(blended, Color::gray(0.1));
+        let blended = Color::gray(0.75).blend(&Color::gray(0.2), BlendMode::Overlay);
+        assert_close(blended, Color::gray(0.6));
+    }
+
+    #[test]
+    fn blend_modes_keep_the_base_alpha() {
+        let base = Color::new(0.5, 0.5, 0.5, 0.4);
+        for mode in [
+            BlendMode::Multiply,
+            BlendMode::Screen,
+            BlendMode::Overlay,
+            BlendMode::Additive,
+        ] {
+            assert!((base.blend(&Color::WHITE, mode).a - 0.4).abs() < 1e-6, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn alpha_over_composites_straight_alpha() {
+        let half_white = Color::new(1.0, 1.0, 1.0, 0.5);
+        assert_close(Color::BLACK.blend(&half_white, BlendMode::AlphaOver), Color::gray(0.5));
+        assert_close(Color::RED.blend(&Color::TRANSPARENT, BlendMode::AlphaOver), Color::RED);
+        let empty = Color::TRANSPARENT.blend(&Color::TRANSPARENT, BlendMode::AlphaOver);
+        assert_close(empty, Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn linear_half_encodes_to_srgb_188() {
+        assert_eq!(Color::new(0.5, 0.5, 0.5, 0.5).to_srgb8(), [188, 188, 188, 128]);
+        assert_eq!(Color::new(2.0, -1.0, 0.0, 1.0).to_srgb8(), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn srgb_buffers_round_trip() {
+        let bytes: Vec<u8> = (0..=255).flat_map(|value| [value, value, 255 - value, value]).collect();
+        assert_eq!(colors_to_srgb8(&srgb8_to_colors(&bytes)), bytes);
+        assert_eq!(srgb8_to_colors(&[1, 2, 3, 4, 5]).len(), 1);
+        assert!((srgb_to_linear(linear_to_srgb(0.002)) - 0.002).abs() < 1e-6);
+    }
+
+    #[test]
+    fn serializes_as_an_array_and_reads_the_legacy_object_form() {
+        let color = Color::new(0.25, 0.5, 0.75, 1.0);
+        let json = serde_json::to_string(&color).unwrap();
+        assert_eq!(json, "[0.25,0.5,0.75,1.0]");
+        assert_close(serde_json::from_str(&json).unwrap(), color);
+
+        let legacy = r#"{"r": 0.25, "g": 0.5, "b": 0.75, "a": 1.0}"#;
+        assert_close(serde_json::from_str(legacy).unwrap(), color);
+        assert!(serde_json::from_str::<Color>(r#"{"r": 0.25, "g": 0.5}"#).is_err());
+        assert!(serde_json::from_str::<Color>("[0.25, 0.5, 0.75]").is_err());
+
+        // Binary formats store the four channels back to back
+        let bytes = bincode::serialize(&color).unwrap();
+        assert_eq!(bytes.len(), 16);
+        assert_close(bincode::deserialize(&bytes).unwrap(), color);
+    }
+
+    #[test]
+    fn reinhard_maps_known_values_and_keeps_alpha() {
+        let mapped = Color::new(1.0, 3.0, 0.0, 0.4).reinhard();
+        assert_close(mapped, Color::new(0.5, 0.75, 0.0, 0.4));
+        // Negative channels are treated as black
+        assert_close(Color::rgb(-1.0, 0.25, 9.0).reinhard(), Color::rgb(0.0, 0.2, 0.9));
+    }
+
+    #[test]
+    fn aces_filmic_maps_known_values_and_saturates_at_one() {
+        // 18% gray, 1 and far above white
+        let mapped = Color::new(0.18, 1.0, 100.0, 0.4).aces_filmic();
+        assert_close(mapped, Color::new(0.266_898_9, 0.803_797_5, 1.0, 0.4));
+        assert_close(Color::rgb(0.0, -2.0, 0.0).aces_filmic(), Color::BLACK);
+    }
+}
@@ -0,0 +1,175 @@
+//! Analytic shapes and closest-point queries for gameplay code
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// Analytic sphere for gameplay and SDF queries (not a renderable primitive)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoSphere {
+    /// Center of the sphere
+    pub center: Vec3,
+    /// Radius of the sphere
+    pub radius: f32,
+}
+
+impl GeoSphere {
+    /// Create a new sphere
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Signed distance from a point to the surface (negative inside)
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        (point - self.center).length() - self.radius
+    }
+
+    /// Check if a point lies inside or on the sphere
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.signed_distance(point) <= 0.0
+    }
+
+    /// Get the point on the surface closest to `point`
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        let offset = point - self.center;
+        if offset.length_squared() < 1e-12 {
+            // Every surface point is equally close to the center
+            return self.center + Vec3::Y * self.radius;
+        }
+        self.center + offset.normalize() * self.radius
+    }
+
+    /// Get a sphere enclosing every point using Ritter's algorithm, or `None` if
+    /// there are no points.
+    ///
+    /// The result is at most about 5% larger than the minimal enclosing sphere.
+    pub fn enclosing_points(points: &[Vec3]) -> Option<Self> {
+        let first = *points.first()?;
+        let farthest_from = |origin: Vec3| {
+            points
+                .iter()
+                .copied()
+                .max_by(|a, b| a.distance_squared(origin).total_cmp(&b.distance_squared(origin)))
+                .unwrap_or(origin)
+        };
+
+        // Start from the two points that are (approximately) farthest apart
+        let a = farthest_from(first);
+        let b = farthest_from(a);
+        let mut sphere = Self::new((a + b) * 0.5, a.distance(b) * 0.5);
+
+        // Grow the sphere just enough to take in each point left outside
+        for &point in points {
+            let distance = point.distance(sphere.center);
+            if distance > sphere.radius {
+                let radius = (sphere.radius + distance) * 0.5;
+                sphere.center += (point - sphere.center) * ((radius - sphere.radius) / distance);
+                sphere.radius = radius;
+            }
+        }
+        Some(sphere)
+    }
+}
+
+/// Analytic infinite plane `normal.dot(p) == distance`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoPlane {
+    /// Unit normal of the plane
+    pub normal: Vec3,
+    /// Signed distance of the plane from the origin along `normal`
+    pub distance: f32,
+}
+
+impl GeoPlane {
+    /// Create a plane from a normal and distance from the origin along that normal
+    pub fn new(normal: Vec3, distance: f32) -> Self {
+        Self {
+            normal: normal.normalize(),
+            distance,
+        }
+    }
+
+    /// Create a plane passing through a point
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Self {
+        let normal = normal.normalize();
+        Self {
+            normal,
+            distance: normal.dot(point),
+        }
+    }
+
+    /// Signed distance from a point to the plane (negative behind the normal)
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) - self.distance
+    }
+
+    /// Project a point onto the plane
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        point - self.normal * self.signed_distance(point)
+    }
+}
+
+/// Get the point on the segment `a`-`b` closest to `point`
+pub fn closest_point_on_segment(point: Vec3, a: Vec3, b: Vec3) -> Vec3 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared < 1e-12 {
+        return a;
+    }
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    a + ab * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_distance_is_negative_inside_and_positive_outside() {
+        let sphere = GeoSphere::new(Vec3::new(1.0, 0.0, 0.0), 2.0);
+        assert!((sphere.signed_distance(Vec3::new(1.0, 0.5, 0.0)) + 1.5).abs() < 1e-6);
+        assert!((sphere.signed_distance(Vec3::new(1.0, 0.0, 5.0)) - 3.0).abs() < 1e-6);
+        assert!(sphere.signed_distance(Vec3::new(3.0, 0.0, 0.0)).abs() < 1e-6);
+        assert!(sphere.contains_point(Vec3::new(2.0, 0.0, 0.0)));
+        assert!(!sphere.contains_point(Vec3::new(4.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn plane_distance_is_negative_behind_and_positive_in_front() {
+        let plane = GeoPlane::from_point_normal(Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 3.0, 0.0));
+        assert!((plane.signed_distance(Vec3::new(5.0, 5.0, -1.0)) - 3.0).abs() < 1e-6);
+        assert!((plane.signed_distance(Vec3::new(5.0, -1.0, 7.0)) + 3.0).abs() < 1e-6);
+        let projected = plane.closest_point(Vec3::new(5.0, 5.0, -1.0));
+        assert!(projected.abs_diff_eq(Vec3::new(5.0, 2.0, -1.0), 1e-6));
+    }
+
+    #[test]
+    fn closest_points_lie_on_the_shape() {
+        let sphere = GeoSphere::new(Vec3::ZERO, 2.0);
+        let surface = sphere.closest_point(Vec3::new(0.0, 0.0, 10.0));
+        assert!(surface.abs_diff_eq(Vec3::new(0.0, 0.0, 2.0), 1e-6));
+
+        let (a, b) = (Vec3::ZERO, Vec3::new(4.0, 0.0, 0.0));
+        assert_eq!(closest_point_on_segment(Vec3::new(1.0, 3.0, 0.0), a, b), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(closest_point_on_segment(Vec3::new(-2.0, 1.0, 0.0), a, b), a);
+        assert_eq!(closest_point_on_segment(Vec3::new(9.0, 1.0, 0.0), a, b), b);
+    }
+
+    #[test]
+    fn enclosing_sphere_contains_every_point_and_is_nearly_minimal() {
+        assert!(GeoSphere::enclosing_points(&[]).is_none());
+
+        let corners: Vec<Vec3> = (0..8)
+            .map(|i| {
+                let x = if i & 1 == 0 { -1.0 } else { 1.0 };
+                let y = if i & 2 == 0 { -2.0 } else { 2.0 };
+                let z = if i & 4 == 0 { 0.0 } else { 2.0 };
+                Vec3::new(x, y, z)
+            })
+            .collect();
+        let sphere = GeoSphere::enclosing_points(&corners).unwrap();
+        assert!(corners.iter().all(|&corner| sphere.signed_distance(corner) <= 1e-5));
+        // The minimal sphere is centered on the box with half its diagonal as radius
+        let minimal = Vec3::new(1.0, 2.0, 1.0).length();
+        assert!(sphere.radius <= minimal * 1.05, "{} > {minimal}", sphere.radius);
+    }
+}
@@ -4,6 +4,10 @@ pub mod ray;
 pub mod bounds;
 pub mod transform;
 pub mod color;
+pub mod geometry;
+pub mod animation;
+pub mod random;
+pub mod noise;
 
 pub use glam::{Vec2, Vec3, Vec4, Mat3, Mat4, Quat};
 pub use vector::*;
@@ -12,6 +16,9 @@ pub use ray::*;
 pub use bounds::*;
 pub use transform::*;
 pub use color::*;
+pub use geometry::*;
+pub use animation::*;
+pub use noise::{fbm, noise3, PerlinNoise};
 
 /// Common mathematical constants
 pub mod constants {
@@ -49,4 +56,16 @@ pub mod utils {
     pub fn rad_to_deg(radians: f32) -> f32 {
         radians * 180.0 / super::constants::PI
     }
+
+    /// Element `index` of the Halton low-discrepancy sequence in the given base, in [0, 1)
+    pub fn halton(mut index: u32, base: u32) -> f32 {
+        let mut fraction = 1.0;
+        let mut result = 0.0;
+        while index > 0 {
+            fraction /= base as f32;
+            result += fraction * (index % base) as f32;
+            index /= base;
+        }
+        result
+    }
 }
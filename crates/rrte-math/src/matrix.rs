@@ -1,11 +1,39 @@
 // Matrix utilities beyond glam
 pub use glam::{Mat3, Mat4};
+use glam::Vec3;
+use crate::Transform;
+
+// Engine convention: right-handed world space with +Y up and cameras looking down -Z.
+// Projections map view depth to the [0, 1] clip range used by wgpu.
+
+/// Build a right-handed view matrix for a camera at `eye` looking at `target`.
+///
+/// The camera looks down its local -Z axis, with +Y as close to `up` as possible.
+pub fn look_at_rh(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+    Mat4::look_at_rh(eye, target, up)
+}
+
+/// Build a right-handed perspective projection from a vertical field of view in radians.
+///
+/// View-space depth `-near` maps to clip depth 0 and `-far` to 1.
+pub fn perspective_rh_fov(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    Mat4::perspective_rh(fov_y, aspect, near, far)
+}
+
+/// Build a right-handed orthographic projection for the given view volume.
+///
+/// View-space depth `-near` maps to clip depth 0 and `-far` to 1.
+pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+    Mat4::orthographic_rh(left, right, bottom, top, near, far)
+}
 
 /// Matrix extension traits
 pub trait Mat4Ext {
     fn look_at_rh(eye: glam::Vec3, center: glam::Vec3, up: glam::Vec3) -> Mat4;
     fn perspective_rh(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4;
     fn orthographic_rh(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4;
+    /// Split an affine matrix into translation, rotation and scale
+    fn decompose_trs(&self) -> Transform;
 }
 
 impl Mat4Ext for Mat4 {
@@ -23,4 +51,79 @@ impl Mat4Ext for Mat4 {
     fn orthographic_rh(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
         Mat4::orthographic_rh(left, right, bottom, top, near, far)
     }
+
+    /// Split an affine matrix into translation, rotation and scale.
+    ///
+    /// Shear and projection cannot be represented and are discarded.
+    fn decompose_trs(&self) -> Transform {
+        let (scale, rotation, position) = self.to_scale_rotation_translation();
+        Transform {
+            position,
+            rotation,
+            scale,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Quat, Vec4};
+
+    fn assert_mat_close(actual: Mat4, expected: Mat4) {
+        assert!(actual.abs_diff_eq(expected, 1e-6), "{actual} != {expected}");
+    }
+
+    #[test]
+    fn look_at_matches_hand_computed_views() {
+        let view = look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+        assert_mat_close(view, Mat4::from_translation(Vec3::new(0.0, 0.0, -5.0)));
+
+        // Looking down +X turns the world's -X axis into the view's +Z
+        let view = look_at_rh(Vec3::ZERO, Vec3::X, Vec3::Y);
+        let expected = Mat4::from_cols(
+            Vec4::new(0.0, 0.0, -1.0, 0.0),
+            Vec4::new(0.0, 1.0, 0.0, 0.0),
+            Vec4::new(1.0, 0.0, 0.0, 0.0),
+            Vec4::W,
+        );
+        assert_mat_close(view, expected);
+    }
+
+    #[test]
+    fn perspective_maps_near_to_zero_and_far_to_one() {
+        // 90° vertical FOV, square, near 1 and far 3
+        let projection = perspective_rh_fov(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 3.0);
+        let expected = Mat4::from_cols(
+            Vec4::X,
+            Vec4::Y,
+            Vec4::new(0.0, 0.0, -1.5, -1.0),
+            Vec4::new(0.0, 0.0, -1.5, 0.0),
+        );
+        assert_mat_close(projection, expected);
+        assert!(projection.project_point3(Vec3::new(0.0, 0.0, -1.0)).z.abs() < 1e-6);
+        assert!((projection.project_point3(Vec3::new(0.0, 0.0, -3.0)).z - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn orthographic_scales_the_view_volume_to_clip_space() {
+        let projection = orthographic(-2.0, 2.0, -1.0, 1.0, 0.0, 10.0);
+        let expected = Mat4::from_cols(
+            Vec4::new(0.5, 0.0, 0.0, 0.0),
+            Vec4::Y,
+            Vec4::new(0.0, 0.0, -0.1, 0.0),
+            Vec4::W,
+        );
+        assert_mat_close(projection, expected);
+    }
+
+    #[test]
+    fn decompose_recovers_translation_rotation_and_scale() {
+        let rotation = Quat::from_rotation_z(0.5);
+        let matrix = Mat4::from_scale_rotation_translation(Vec3::new(1.0, 2.0, 3.0), rotation, Vec3::X);
+        let transform = matrix.decompose_trs();
+        assert!(transform.position.abs_diff_eq(Vec3::X, 1e-6));
+        assert!(transform.rotation.abs_diff_eq(rotation, 1e-6));
+        assert!(transform.scale.abs_diff_eq(Vec3::new(1.0, 2.0, 3.0), 1e-5));
+    }
 }
@@ -0,0 +1,170 @@
+//! Gradient noise for procedural deformation and materials.
+
+use crate::Vec3;
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::sync::OnceLock;
+
+/// Improved Perlin noise (Perlin 2002) over a permutation table shuffled from a seed.
+///
+/// The same seed always gives the same noise field.
+#[derive(Debug, Clone)]
+pub struct PerlinNoise {
+    /// Shuffled `0..256`, repeated so lookups never wrap
+    permutation: [u8; 512],
+}
+
+impl PerlinNoise {
+    /// Create a noise field from a seed
+    pub fn new(seed: u64) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        table.shuffle(&mut SmallRng::seed_from_u64(seed));
+
+        let mut permutation = [0; 512];
+        for (i, entry) in permutation.iter_mut().enumerate() {
+            *entry = table[i & 255];
+        }
+        Self { permutation }
+    }
+
+    /// Sample the noise at a point, in `[-1, 1]`.
+    ///
+    /// The field is smooth, and zero at every integer lattice point.
+    pub fn noise3(&self, point: Vec3) -> f32 {
+        let cell = point.floor();
+        let local = point - cell;
+        let (x, y, z) = (lattice(cell.x), lattice(cell.y), lattice(cell.z));
+        let (u, v, w) = (fade(local.x), fade(local.y), fade(local.z));
+
+        let p = &self.permutation;
+        let hash = |x: usize, y: usize, z: usize| p[p[p[x] as usize + y] as usize + z];
+        let corner = |dx: usize, dy: usize, dz: usize| {
+            let offset = local - Vec3::new(dx as f32, dy as f32, dz as f32);
+            gradient(hash(x + dx, y + dy, z + dz), offset)
+        };
+
+        let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+        let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+        let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+        let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+        lerp(lerp(x00, x10, v), lerp(x01, x11, v), w).clamp(-1.0, 1.0)
+    }
+
+    /// Sum `octaves` layers of noise, each at twice the frequency and half the
+    /// amplitude of the last, normalized to `[-1, 1]`
+    pub fn fbm(&self, point: Vec3, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        let mut frequency = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise3(point * frequency);
+            total_amplitude += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        if total_amplitude > 0.0 {
+            sum / total_amplitude
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for PerlinNoise {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Shared noise field with seed 0
+fn default_noise() -> &'static PerlinNoise {
+    static NOISE: OnceLock<PerlinNoise> = OnceLock::new();
+    NOISE.get_or_init(PerlinNoise::default)
+}
+
+/// Sample the default (seed 0) noise field; see [`PerlinNoise::noise3`]
+pub fn noise3(point: Vec3) -> f32 {
+    default_noise().noise3(point)
+}
+
+/// Fractal noise from the default (seed 0) noise field; see [`PerlinNoise::fbm`]
+pub fn fbm(point: Vec3, octaves: u32) -> f32 {
+    default_noise().fbm(point, octaves)
+}
+
+/// Index of a lattice coordinate into the permutation table
+fn lattice(coordinate: f32) -> usize {
+    (coordinate as i32 & 255) as usize
+}
+
+/// Quintic fade curve `6t^5 - 15t^4 + 10t^3`, with zero first and second
+/// derivatives at 0 and 1
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Dot product of `offset` with one of the 12 cube-edge gradients picked by `hash`
+fn gradient(hash: u8, offset: Vec3) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { offset.x } else { offset.y };
+    let v = if h < 4 {
+        offset.y
+    } else if h == 12 || h == 14 {
+        offset.x
+    } else {
+        offset.z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points scattered over several lattice cells, including negative ones
+    fn sample_points() -> impl Iterator<Item = Vec3> {
+        (0..2000u16).map(|i| {
+            let t = f32::from(i);
+            Vec3::new(t * 0.137 - 50.0, t * 0.071 + 3.3, t * -0.053 + 7.9)
+        })
+    }
+
+    #[test]
+    fn noise_and_fbm_stay_within_unit_range() {
+        let noise = PerlinNoise::new(42);
+        for point in sample_points() {
+            let value = noise.noise3(point);
+            assert!((-1.0..=1.0).contains(&value), "noise3({point:?}) = {value}");
+            let fractal = noise.fbm(point, 5);
+            assert!((-1.0..=1.0).contains(&fractal), "fbm({point:?}) = {fractal}");
+        }
+    }
+
+    #[test]
+    fn nearby_inputs_give_nearby_outputs() {
+        let noise = PerlinNoise::new(42);
+        let step = Vec3::new(1e-3, -1e-3, 1e-3);
+        for point in sample_points() {
+            let difference = (noise.noise3(point + step) - noise.noise3(point)).abs();
+            assert!(difference < 0.02, "jump of {difference} at {point:?}");
+        }
+    }
+
+    #[test]
+    fn a_fixed_seed_reproduces_the_same_field() {
+        let first = PerlinNoise::new(7);
+        let second = PerlinNoise::new(7);
+        let other = PerlinNoise::new(8);
+        let seed_zero = PerlinNoise::new(0);
+
+        assert!(sample_points().all(|p| first.noise3(p).to_bits() == second.noise3(p).to_bits()));
+        assert!(sample_points().any(|p| first.noise3(p) != other.noise3(p)));
+        assert!(sample_points().all(|p| noise3(p).to_bits() == seed_zero.noise3(p).to_bits()));
+    }
+}
@@ -0,0 +1,51 @@
+//! Per-thread random numbers for sampling that can be reseeded for
+//! reproducible renders.
+//!
+//! Every thread owns a small fast generator, seeded from entropy on first use.
+//! Renderers call [`seed`] with a seed derived from the pixel and sample index
+//! (see [`derive_seed`]) before tracing a sample, which makes the result
+//! independent of how the work is spread over threads.
+
+use rand::distributions::uniform::{SampleRange, SampleUniform};
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+
+thread_local! {
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+}
+
+/// Reseed the calling thread's generator
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
+
+/// Draw a random value from the calling thread's generator; floats are in `[0, 1)`
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+/// Draw a random value in `range` from the calling thread's generator
+pub fn gen_range<T: SampleUniform, R: SampleRange<T>>(range: R) -> T {
+    RNG.with(|rng| rng.borrow_mut().gen_range(range))
+}
+
+/// Combine a master seed with indices (pixel coordinates, sample number, ...)
+/// into a well-mixed seed for [`seed`]
+pub fn derive_seed(master: u64, indices: &[u64]) -> u64 {
+    indices
+        .iter()
+        .fold(splitmix64(master), |hash, &index| splitmix64(hash ^ index))
+}
+
+/// SplitMix64 finalizer, mapping nearby inputs to unrelated outputs
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
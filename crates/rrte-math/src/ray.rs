@@ -1,4 +1,4 @@
-use glam::Vec3;
+use glam::{Vec2, Vec3};
 use serde::{Deserialize, Serialize};
 
 /// A ray in 3D space with origin and direction
@@ -22,6 +22,21 @@ impl Ray {
         self.origin + t * self.direction
     }
 
+    /// Get the parameter `s` of the point `line_origin + s * line_direction`
+    /// closest to the ray's line, or `None` if the two are parallel.
+    ///
+    /// The ray is extended backwards too, so the closest point may lie behind
+    /// its origin. `line_direction` must be normalized.
+    pub fn closest_point_on_line(&self, line_origin: Vec3, line_direction: Vec3) -> Option<f32> {
+        let alignment = self.direction.dot(line_direction);
+        let denominator = 1.0 - alignment * alignment;
+        if denominator < 1e-6 {
+            return None;
+        }
+        let offset = line_origin - self.origin;
+        Some((alignment * self.direction.dot(offset) - line_direction.dot(offset)) / denominator)
+    }
+
     /// Transform the ray by a matrix
     pub fn transform(&self, transform: &glam::Mat4) -> Self {
         let origin = transform.transform_point3(self.origin);
@@ -38,6 +53,9 @@ pub struct HitInfo {
     pub normal: Vec3,
     pub front_face: bool,
     pub material_id: Option<u32>,
+    /// Surface texture coordinates at the hit; `(0, 0)` for primitives that
+    /// do not provide them
+    pub uv: Vec2,
 }
 
 impl HitInfo {
@@ -52,12 +70,39 @@ impl HitInfo {
             normal,
             front_face,
             material_id: None,
+            uv: Vec2::ZERO,
         }
     }
 
+    /// Set the texture coordinates
+    pub fn with_uv(mut self, uv: Vec2) -> Self {
+        self.uv = uv;
+        self
+    }
+
     /// Set the material ID
     pub fn with_material(mut self, material_id: u32) -> Self {
         self.material_id = Some(material_id);
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closest_point_on_line_finds_where_the_ray_passes_the_line() {
+        // The ray crosses the X axis at x = 2, from above and in front
+        let ray = Ray::new(Vec3::new(1.0, 3.0, 4.0), Vec3::new(2.0, 0.0, 0.0) - Vec3::new(1.0, 3.0, 4.0));
+        let s = ray.closest_point_on_line(Vec3::ZERO, Vec3::X).unwrap();
+        assert!((s - 2.0).abs() < 1e-5, "{s}");
+
+        // Skew lines: the closest point is where the ray passes over the line
+        let above = Ray::new(Vec3::new(-3.0, 1.0, 5.0), Vec3::NEG_Z);
+        let s = above.closest_point_on_line(Vec3::new(1.0, 0.0, 0.0), Vec3::X).unwrap();
+        assert!((s + 4.0).abs() < 1e-5, "{s}");
+
+        assert!(Ray::new(Vec3::Y, Vec3::X).closest_point_on_line(Vec3::ZERO, Vec3::X).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use glam::{Vec3, Mat4, Quat};
+use glam::{Vec3, Mat3, Mat4, Quat};
 use serde::{Deserialize, Serialize};
 
 /// 3D transformation combining position, rotation, and scale
@@ -46,24 +46,140 @@ impl Transform {
         }
     }
 
+    /// Compose a child transform expressed in `parent`'s local space into world space.
+    ///
+    /// Scale is combined component-wise, so a non-uniformly scaled parent with a
+    /// rotated child loses the resulting shear. A follow camera is
+    /// `Transform::combine(&target, &offset)`.
+    pub fn combine(parent: &Self, child_local: &Self) -> Self {
+        Self {
+            position: parent.transform_point(child_local.position),
+            rotation: parent.rotation * child_local.rotation,
+            scale: parent.scale * child_local.scale,
+        }
+    }
+
+    /// Express this world transform in `parent`'s local space (the inverse of [`Transform::combine`])
+    pub fn relative_to(&self, parent: &Self) -> Self {
+        let inverse_rotation = parent.rotation.inverse();
+        Self {
+            position: inverse_rotation * (self.position - parent.position) / parent.scale,
+            rotation: inverse_rotation * self.rotation,
+            scale: self.scale / parent.scale,
+        }
+    }
+
+    /// Interpolate towards `other`: position and scale linearly, rotation spherically
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(other.position, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+
+    /// Create a camera-facing transform for billboards (sprites, labels, particles).
+    ///
+    /// The resulting `forward()` points from `position` toward `camera_position`,
+    /// with `up()` as close to `up` as possible.
+    pub fn billboard(position: Vec3, camera_position: Vec3, up: Vec3) -> Self {
+        let to_camera = camera_position - position;
+        if to_camera.length_squared() < 1e-12 {
+            return Self::from_position(position);
+        }
+        let forward = to_camera.normalize();
+
+        // Fall back to another axis when `up` is parallel to the view direction
+        let mut right = forward.cross(up);
+        if right.length_squared() < 1e-12 {
+            right = forward.cross(forward.any_orthogonal_vector());
+        }
+        let right = right.normalize();
+        let actual_up = right.cross(forward);
+
+        let rotation = Quat::from_mat3(&Mat3::from_cols(right, actual_up, -forward));
+        Self {
+            position,
+            rotation,
+            scale: Vec3::ONE,
+        }
+    }
+
+    /// Reflect this transform across the plane through `plane_point` with
+    /// normal `plane_normal`.
+    ///
+    /// A reflection flips handedness, which a rotation cannot express, so the
+    /// local X scale is negated: the position, `forward()` and `up()` are the
+    /// reflections of the originals, `right()` is the opposite of its reflection,
+    /// and the negative scale mirrors the geometry itself. A zero normal
+    /// returns the transform unchanged.
+    pub fn mirror(&self, plane_normal: Vec3, plane_point: Vec3) -> Self {
+        let normal = plane_normal.normalize_or_zero();
+        if normal == Vec3::ZERO {
+            return self.clone();
+        }
+
+        let reflection = Mat3::IDENTITY - 2.0 * Mat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z);
+        let flip_x = Mat3::from_diagonal(Vec3::new(-1.0, 1.0, 1.0));
+        Self {
+            position: self.position - 2.0 * normal * normal.dot(self.position - plane_point),
+            rotation: Quat::from_mat3(&(reflection * Mat3::from_quat(self.rotation) * flip_x)).normalize(),
+            scale: self.scale * Vec3::new(-1.0, 1.0, 1.0),
+        }
+    }
+
     /// Convert to a 4x4 transformation matrix
     pub fn to_matrix(&self) -> Mat4 {
         Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.position)
     }
 
-    /// Get the inverse transformation matrix
+    /// Get the scale factor if all three axes are scaled equally
+    pub fn uniform_scale(&self) -> Option<f32> {
+        let scale = self.scale;
+        let tolerance = 1e-6 * scale.abs().max_element().max(1.0);
+        ((scale.x - scale.y).abs() <= tolerance && (scale.x - scale.z).abs() <= tolerance).then_some(scale.x)
+    }
+
+    /// Check whether all three axes are scaled equally (including the identity)
+    pub fn is_uniform_scale(&self) -> bool {
+        self.uniform_scale().is_some()
+    }
+
+    /// Get the inverse transformation matrix.
+    ///
+    /// Uniformly scaled transforms, the common case, are inverted directly from
+    /// their components instead of with a general 4x4 inverse.
     pub fn inverse_matrix(&self) -> Mat4 {
-        self.to_matrix().inverse()
+        match self.uniform_scale() {
+            Some(scale) if scale != 0.0 => {
+                let inverse_rotation = self.rotation.inverse();
+                Mat4::from_scale_rotation_translation(
+                    Vec3::splat(scale.recip()),
+                    inverse_rotation,
+                    inverse_rotation * -self.position / scale,
+                )
+            }
+            _ => self.to_matrix().inverse(),
+        }
     }
 
     /// Transform a point
     pub fn transform_point(&self, point: Vec3) -> Vec3 {
-        self.to_matrix().transform_point3(point)
+        self.rotation * (point * self.scale) + self.position
     }
 
     /// Transform a vector (no translation)
     pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
-        self.to_matrix().transform_vector3(vector)
+        self.rotation * (vector * self.scale)
+    }
+
+    /// Transform a surface normal and normalize it.
+    ///
+    /// Normals use the inverse transpose, which for a scale-rotation-translation
+    /// transform is the rotation applied after dividing by the scale, so they
+    /// stay perpendicular to surfaces under non-uniform scale.
+    pub fn transform_normal(&self, normal: Vec3) -> Vec3 {
+        (self.rotation * (normal / self.scale)).normalize()
     }
 
     /// Get the forward direction
@@ -87,3 +203,96 @@ impl Default for Transform {
         Self::identity()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_vec_close(actual: Vec3, expected: Vec3) {
+        assert!(actual.abs_diff_eq(expected, 1e-5), "{actual} != {expected}");
+    }
+
+    #[test]
+    fn billboard_faces_the_camera() {
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        let camera = Vec3::new(-4.0, 6.0, 10.0);
+        let billboard = Transform::billboard(position, camera, Vec3::Y);
+
+        assert_vec_close(billboard.position, position);
+        assert_vec_close(billboard.forward(), (camera - position).normalize());
+        assert!(billboard.up().dot(Vec3::Y) > 0.0);
+        assert!(billboard.right().dot(billboard.forward()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn billboard_looking_along_up_still_faces_the_camera() {
+        let billboard = Transform::billboard(Vec3::ZERO, Vec3::new(0.0, 5.0, 0.0), Vec3::Y);
+        assert_vec_close(billboard.forward(), Vec3::Y);
+        assert!(billboard.rotation.is_normalized());
+    }
+
+    #[test]
+    fn relative_to_undoes_combine() {
+        let parent = Transform {
+            position: Vec3::new(3.0, -1.0, 2.0),
+            rotation: Quat::from_euler(glam::EulerRot::YXZ, 0.7, -0.3, 0.2),
+            scale: Vec3::splat(2.0),
+        };
+        let local = Transform {
+            position: Vec3::new(0.0, 1.5, -4.0),
+            rotation: Quat::from_rotation_x(0.4),
+            scale: Vec3::new(1.0, 0.5, 1.0),
+        };
+
+        let world = Transform::combine(&parent, &local);
+        assert_vec_close(world.position, parent.transform_point(local.position));
+
+        let recovered = world.relative_to(&parent);
+        assert_vec_close(recovered.position, local.position);
+        assert!(recovered.rotation.abs_diff_eq(local.rotation, 1e-5));
+        assert_vec_close(recovered.scale, local.scale);
+    }
+
+    #[test]
+    fn uniform_scale_inverse_matches_the_full_matrix_inverse() {
+        let transform = Transform {
+            position: Vec3::new(3.0, -2.0, 7.5),
+            rotation: Quat::from_euler(glam::EulerRot::YXZ, 0.7, -1.2, 0.3),
+            scale: Vec3::splat(2.5),
+        };
+        assert_eq!(transform.uniform_scale(), Some(2.5));
+        let fast = transform.inverse_matrix();
+        let full = transform.to_matrix().inverse();
+        assert!(fast.abs_diff_eq(full, 1e-5), "{fast} != {full}");
+        assert!((fast * transform.to_matrix()).abs_diff_eq(Mat4::IDENTITY, 1e-5));
+
+        // Non-uniform scale falls back to the general inverse
+        let stretched = Transform { scale: Vec3::new(1.0, 2.0, 3.0), ..transform };
+        assert!(!stretched.is_uniform_scale());
+        assert!(stretched.inverse_matrix().abs_diff_eq(stretched.to_matrix().inverse(), 1e-5));
+    }
+
+    #[test]
+    fn mirroring_across_the_yz_plane_negates_x_and_reflects_forward() {
+        let transform = Transform {
+            position: Vec3::new(2.0, 1.0, -3.0),
+            rotation: Quat::from_euler(glam::EulerRot::YXZ, 0.6, 0.2, 0.1),
+            scale: Vec3::new(1.0, 2.0, 0.5),
+        };
+        let mirrored = transform.mirror(Vec3::X, Vec3::ZERO);
+        let reflect = |v: Vec3| Vec3::new(-v.x, v.y, v.z);
+
+        assert_vec_close(mirrored.position, Vec3::new(-2.0, 1.0, -3.0));
+        assert_vec_close(mirrored.forward(), reflect(transform.forward()));
+        assert_vec_close(mirrored.up(), reflect(transform.up()));
+        assert_vec_close(mirrored.scale, Vec3::new(-1.0, 2.0, 0.5));
+        // The mirrored geometry is the reflection of the original
+        let local = Vec3::new(0.3, -0.7, 1.1);
+        assert_vec_close(mirrored.transform_point(local), reflect(transform.transform_point(local)));
+
+        // Mirroring twice restores the original
+        let restored = mirrored.mirror(Vec3::X, Vec3::ZERO);
+        assert_vec_close(restored.position, transform.position);
+        assert_vec_close(restored.transform_point(local), transform.transform_point(local));
+    }
+}
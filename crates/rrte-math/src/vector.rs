@@ -35,9 +35,9 @@ impl Vec3Ext for Vec3 {
     fn random_in_unit_sphere() -> Vec3 {
         loop {
             let p = Vec3::new(
-                rand::random::<f32>() * 2.0 - 1.0,
-                rand::random::<f32>() * 2.0 - 1.0,
-                rand::random::<f32>() * 2.0 - 1.0,
+                crate::random::random::<f32>() * 2.0 - 1.0,
+                crate::random::random::<f32>() * 2.0 - 1.0,
+                crate::random::random::<f32>() * 2.0 - 1.0,
             );
             if p.length_squared() < 1.0 {
                 return p;
@@ -61,18 +61,18 @@ impl Vec3Ext for Vec3 {
     /// Generate random vector with components in [0, 1)
     fn random() -> Vec3 {
         Vec3::new(
-            rand::random::<f32>(),
-            rand::random::<f32>(),
-            rand::random::<f32>(),
+            crate::random::random::<f32>(),
+            crate::random::random::<f32>(),
+            crate::random::random::<f32>(),
         )
     }
 
     /// Generate random vector with components in [min, max)
     fn random_range(min: f32, max: f32) -> Vec3 {
         Vec3::new(
-            min + (max - min) * rand::random::<f32>(),
-            min + (max - min) * rand::random::<f32>(),
-            min + (max - min) * rand::random::<f32>(),
+            min + (max - min) * crate::random::random::<f32>(),
+            min + (max - min) * crate::random::random::<f32>(),
+            min + (max - min) * crate::random::random::<f32>(),
         )
     }
 }
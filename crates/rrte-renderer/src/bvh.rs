@@ -0,0 +1,286 @@
+use crate::SceneObject;
+use rrte_math::{HitInfo, Ray, AABB};
+use std::sync::Arc;
+
+/// Most objects stored in one leaf
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Bounding volume hierarchy over scene objects, for finding the closest hit
+/// without testing every object.
+///
+/// Objects with [unbounded](AABB::is_unbounded) boxes (infinite planes) would
+/// make every node enclosing them useless, so they are kept out of the tree and
+/// tested against every ray instead.
+///
+/// A hierarchy can be [layered](Bvh::layered) on a shared base hierarchy, so
+/// objects that move are rebuilt every frame while the static ones are not.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    /// Bounded objects, ordered so every leaf covers a contiguous range
+    objects: Vec<Arc<dyn SceneObject>>,
+    /// Nodes in depth-first order; the root is first
+    nodes: Vec<BvhNode>,
+    /// Objects tested against every ray
+    unbounded: Vec<Arc<dyn SceneObject>>,
+    /// Hierarchy queried together with this one
+    base: Option<Arc<Self>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BvhNode {
+    bounds: AABB,
+    /// For leaves the first object, for interior nodes the right child; the
+    /// left child always directly follows its parent
+    offset: usize,
+    /// Number of objects in a leaf, 0 for interior nodes
+    count: usize,
+}
+
+impl Bvh {
+    /// Build a hierarchy over a list of objects, splitting at the median
+    /// centroid along the longest axis
+    pub fn new(objects: &[Arc<dyn SceneObject>]) -> Self {
+        let mut bounded = Vec::with_capacity(objects.len());
+        let mut unbounded = Vec::new();
+        for object in objects {
+            let bounds = object.bounding_box();
+            if bounds.is_unbounded() {
+                unbounded.push(Arc::clone(object));
+            } else {
+                bounded.push((bounds, Arc::clone(object)));
+            }
+        }
+
+        let mut nodes = Vec::with_capacity(2 * bounded.len() / MAX_LEAF_SIZE + 1);
+        if !bounded.is_empty() {
+            build_node(&mut bounded, 0, &mut nodes);
+        }
+        Self {
+            objects: bounded.into_iter().map(|(_, object)| object).collect(),
+            nodes,
+            unbounded,
+            base: None,
+        }
+    }
+
+    /// Build a hierarchy over `objects` on top of `base`, which is shared
+    /// rather than rebuilt; queries search both
+    pub fn layered(base: Arc<Self>, objects: &[Arc<dyn SceneObject>]) -> Self {
+        Self {
+            base: Some(base),
+            ..Self::new(objects)
+        }
+    }
+
+    /// Get the hierarchy this one was [layered](Bvh::layered) on
+    #[must_use]
+    pub const fn base(&self) -> Option<&Arc<Self>> {
+        self.base.as_ref()
+    }
+
+    /// Get the number of objects, bounded or not, including the base's
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.objects.len() + self.unbounded.len() + self.base.as_ref().map_or(0, |base| base.len())
+    }
+
+    /// Check whether the hierarchy holds no objects
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the number of nodes in the tree, not counting the base's
+    #[must_use]
+    pub const fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Get the bounds of every bounded object, or `None` if there are none
+    #[must_use]
+    pub fn bounds(&self) -> Option<AABB> {
+        let bounds = self.nodes.first().map(|root| root.bounds);
+        match (bounds, self.base.as_ref().and_then(|base| base.bounds())) {
+            (Some(mut bounds), Some(base_bounds)) => {
+                bounds.expand_to_include_aabb(&base_bounds);
+                Some(bounds)
+            }
+            (bounds, base_bounds) => bounds.or(base_bounds),
+        }
+    }
+
+    /// Find the closest hit along a ray between `t_min` and `t_max` and the
+    /// object it hit
+    #[must_use]
+    pub fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<(HitInfo, &Arc<dyn SceneObject>)> {
+        let mut closest = self.base.as_ref().and_then(|base| base.hit(ray, t_min, t_max));
+        let mut closest_t = closest.as_ref().map_or(t_max, |(hit, _)| hit.t);
+        let consider = |object: &Arc<dyn SceneObject>, closest_t: &mut f32| {
+            object.intersect(ray, t_min, *closest_t).inspect(|hit| *closest_t = hit.t)
+        };
+
+        for object in &self.unbounded {
+            if let Some(hit) = consider(object, &mut closest_t) {
+                closest = Some((hit, object));
+            }
+        }
+
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            match node.bounds.intersect_ray(ray) {
+                Some((near, far)) if near <= closest_t && far >= t_min => {}
+                _ => continue,
+            }
+
+            if node.count > 0 {
+                for object in &self.objects[node.offset..node.offset + node.count] {
+                    if let Some(hit) = consider(object, &mut closest_t) {
+                        closest = Some((hit, object));
+                    }
+                }
+            } else {
+                stack.push(node.offset);
+                stack.push(index + 1);
+            }
+        }
+        closest
+    }
+
+    /// Find the closest hit along a ray between `t_min` and `t_max`
+    #[must_use]
+    pub fn raycast(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
+        self.hit(ray, t_min, t_max).map(|(hit, _)| hit)
+    }
+
+    /// Check whether any object accepted by `filter` is hit along a ray between
+    /// `t_min` and `t_max`, stopping at the first such hit
+    pub fn any_hit(&self, ray: &Ray, t_min: f32, t_max: f32, filter: impl Fn(&Arc<dyn SceneObject>) -> bool) -> bool {
+        self.any_hit_filtered(ray, t_min, t_max, &filter)
+    }
+
+    /// [`Bvh::any_hit`] with the filter behind a reference, so the base can be
+    /// searched without instantiating it again
+    fn any_hit_filtered(&self, ray: &Ray, t_min: f32, t_max: f32, filter: &dyn Fn(&Arc<dyn SceneObject>) -> bool) -> bool {
+        if self.base.as_ref().is_some_and(|base| base.any_hit_filtered(ray, t_min, t_max, filter)) {
+            return true;
+        }
+        let blocks = |object: &Arc<dyn SceneObject>| filter(object) && object.intersect(ray, t_min, t_max).is_some();
+        if self.unbounded.iter().any(blocks) {
+            return true;
+        }
+
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            match node.bounds.intersect_ray(ray) {
+                Some((near, far)) if near <= t_max && far >= t_min => {}
+                _ => continue,
+            }
+
+            if node.count > 0 {
+                if self.objects[node.offset..node.offset + node.count].iter().any(blocks) {
+                    return true;
+                }
+            } else {
+                stack.push(node.offset);
+                stack.push(index + 1);
+            }
+        }
+        false
+    }
+}
+
+/// Append the subtree over `objects` (starting at `first` in the final object
+/// order) to `nodes`, returning its index
+fn build_node(objects: &mut [(AABB, Arc<dyn SceneObject>)], first: usize, nodes: &mut Vec<BvhNode>) -> usize {
+    let mut bounds = objects[0].0;
+    for (object_bounds, _) in &objects[1..] {
+        bounds.expand_to_include_aabb(object_bounds);
+    }
+
+    let index = nodes.len();
+    nodes.push(BvhNode { bounds, offset: first, count: objects.len() });
+    if objects.len() <= MAX_LEAF_SIZE {
+        return index;
+    }
+
+    let mut centroid_bounds = AABB::new(objects[0].0.center(), objects[0].0.center());
+    for (object_bounds, _) in &objects[1..] {
+        centroid_bounds.expand_to_include(object_bounds.center());
+    }
+    let size = centroid_bounds.size();
+    let axis = if size.x >= size.y && size.x >= size.z {
+        0
+    } else if size.y >= size.z {
+        1
+    } else {
+        2
+    };
+
+    let middle = objects.len() / 2;
+    objects.select_nth_unstable_by(middle, |(a, _), (b, _)| a.center()[axis].total_cmp(&b.center()[axis]));
+    let (left, right) = objects.split_at_mut(middle);
+    build_node(left, first, nodes);
+    let right_index = build_node(right, first + middle, nodes);
+    nodes[index] = BvhNode { bounds, offset: right_index, count: 0 };
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Plane, Sphere};
+    use rrte_math::Vec3;
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)] // Small grid indices
+    fn raycast_finds_the_same_closest_hit_as_testing_every_object() {
+        // Enough spheres to split into several leaves, plus an unbounded floor
+        let mut objects: Vec<Arc<dyn SceneObject>> = (0..25)
+            .map(|i| -> Arc<dyn SceneObject> {
+                let (x, z) = ((i % 5) as f32, (i / 5) as f32);
+                Arc::new(Sphere::new(Vec3::new(x.mul_add(2.0, -4.0), 1.0, -z.mul_add(2.0, 3.0)), 0.6))
+            })
+            .collect();
+        objects.push(Arc::new(Plane::new(Vec3::ZERO, Vec3::Y)));
+        let bvh = Bvh::new(&objects);
+        assert_eq!(bvh.len(), 26);
+
+        let origin = Vec3::new(0.0, 2.0, 4.0);
+        for target in [Vec3::new(0.0, 1.0, -7.0), Vec3::new(-4.0, 1.0, -3.0), Vec3::new(3.0, 1.2, -9.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 5.0, 0.0)] {
+            let ray = Ray::new(origin, (target - origin).normalize());
+            let expected = objects
+                .iter()
+                .filter_map(|object| object.intersect(&ray, 0.001, f32::INFINITY))
+                .map(|hit| hit.t)
+                .min_by(f32::total_cmp);
+            assert_eq!(bvh.raycast(&ray, 0.001, f32::INFINITY).map(|hit| hit.t), expected, "toward {target:?}");
+        }
+    }
+
+    #[test]
+    fn layered_hierarchy_finds_hits_and_shadows_in_either_layer() {
+        let base = Arc::new(Bvh::new(&[Arc::new(Sphere::new(Vec3::new(0.0, 0.0, -10.0), 1.0)) as Arc<dyn SceneObject>]));
+        let moving: Arc<dyn SceneObject> = Arc::new(Sphere::new(Vec3::new(0.0, 0.0, -5.0), 1.0));
+        let bvh = Bvh::layered(Arc::clone(&base), &[moving]);
+        assert_eq!(bvh.len(), 2);
+        assert!(Arc::ptr_eq(bvh.base().unwrap(), &base));
+        assert_eq!(bvh.bounds().map(|bounds| (bounds.min.z, bounds.max.z)), Some((-11.0, -4.0)));
+
+        // The closer object wins whichever layer it is in
+        let ray = Ray::new(Vec3::ZERO, Vec3::NEG_Z);
+        assert_eq!(bvh.raycast(&ray, 0.001, f32::INFINITY).map(|hit| hit.t), Some(4.0));
+        assert_eq!(bvh.raycast(&ray, 0.001, 8.0).map(|hit| hit.t), Some(4.0));
+        let behind = Ray::new(Vec3::new(0.0, 0.0, -7.0), Vec3::NEG_Z);
+        assert_eq!(bvh.raycast(&behind, 0.001, f32::INFINITY).map(|hit| hit.t), Some(2.0));
+        assert!(bvh.any_hit(&behind, 0.001, f32::INFINITY, |_| true));
+        assert!(!bvh.any_hit(&behind, 0.001, 1.0, |_| true));
+    }
+}
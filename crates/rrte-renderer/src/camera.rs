@@ -1,7 +1,8 @@
-use rrte_math::{Transform, Mat4, Vec3, Ray, Quat};
+use rrte_math::{random, Transform, Mat4, Vec2, Vec3, Ray, Quat, Frustum};
+use serde::{Deserialize, Serialize};
 
 /// Camera projection types
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProjectionType {
     Perspective {
         fov: f32,
@@ -19,8 +20,112 @@ pub enum ProjectionType {
     },
 }
 
+/// Axis a perspective camera's field of view is held constant along when the
+/// aspect ratio changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FovAxis {
+    /// Keep the vertical extent; wider windows see more to the sides
+    #[default]
+    Vertical,
+    /// Keep the horizontal extent; wider windows see less above and below
+    Horizontal,
+    /// Keep the diagonal extent, trading width for height as the aspect changes
+    Fit,
+}
+
+impl FovAxis {
+    /// Convert a field of view measured along this axis to the vertical field
+    /// of view at `aspect_ratio`
+    #[must_use]
+    pub fn vertical_fov(self, fov: f32, aspect_ratio: f32) -> f32 {
+        let half_tan = (fov * 0.5).tan();
+        let vertical_half_tan = match self {
+            Self::Vertical => return fov,
+            Self::Horizontal => half_tan / aspect_ratio,
+            Self::Fit => half_tan / aspect_ratio.mul_add(aspect_ratio, 1.0).sqrt(),
+        };
+        2.0 * vertical_half_tan.atan()
+    }
+
+    /// Convert a vertical field of view at `aspect_ratio` to the field of view
+    /// measured along this axis
+    #[must_use]
+    pub fn axis_fov(self, vertical_fov: f32, aspect_ratio: f32) -> f32 {
+        let half_tan = (vertical_fov * 0.5).tan();
+        let axis_half_tan = match self {
+            Self::Vertical => return vertical_fov,
+            Self::Horizontal => half_tan * aspect_ratio,
+            Self::Fit => half_tan * aspect_ratio.mul_add(aspect_ratio, 1.0).sqrt(),
+        };
+        2.0 * axis_half_tan.atan()
+    }
+}
+
+/// Photographic exposure settings of a camera.
+///
+/// Uses the saturation-based sensitivity model: radiance is scaled by
+/// `iso * shutter_speed / (1.2 * 100 * aperture^2)`, so one stop more on any
+/// control doubles or halves the image brightness.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraExposure {
+    /// Aperture as an f-number (f/2.8 is `2.8`)
+    pub aperture: f32,
+    /// Shutter speed in seconds
+    pub shutter_speed: f32,
+    /// Sensor sensitivity
+    pub iso: f32,
+}
+
+impl CameraExposure {
+    /// Create exposure settings from an f-number, shutter time in seconds and ISO
+    #[must_use]
+    pub const fn new(aperture: f32, shutter_speed: f32, iso: f32) -> Self {
+        Self { aperture, shutter_speed, iso }
+    }
+
+    /// Create exposure settings that scale rendered radiance by `multiplier`,
+    /// at f/1 and ISO 100
+    #[must_use]
+    pub fn from_multiplier(multiplier: f32) -> Self {
+        Self::new(1.0, 1.2 * multiplier, 100.0)
+    }
+
+    /// Get the exposure value normalized to ISO 100
+    #[must_use]
+    pub fn ev100(&self) -> f32 {
+        (self.aperture * self.aperture / self.shutter_speed * 100.0 / self.iso).log2()
+    }
+
+    /// Get the factor rendered radiance is multiplied by before display
+    #[must_use]
+    pub fn multiplier(&self) -> f32 {
+        1.0 / (1.2 * self.ev100().exp2())
+    }
+}
+
+/// Thin-lens settings of a perspective camera, for depth of field.
+///
+/// Rays start at a random point on a disk of radius `aperture` around the
+/// camera position and pass through the point the pinhole ray would reach on
+/// the focal plane, so only that plane is sharp. An aperture of 0 is a pinhole.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LensConfig {
+    /// Radius of the lens disk in world units
+    pub aperture: f32,
+    /// Distance from the camera to the plane in focus, along the view direction
+    pub focus_distance: f32,
+}
+
+impl LensConfig {
+    /// Create lens settings from an aperture radius and focus distance
+    #[must_use]
+    pub const fn new(aperture: f32, focus_distance: f32) -> Self {
+        Self { aperture, focus_distance }
+    }
+}
+
 /// Camera component for rendering
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Camera {
     /// Camera transform in world space
     pub transform: Transform,
@@ -28,6 +133,18 @@ pub struct Camera {
     pub projection: ProjectionType,
     /// Whether the camera is currently active
     pub is_active: bool,
+    /// Sub-pixel projection offset in normalized device coordinates (for TAA)
+    pub jitter: Vec2,
+    /// Axis whose extent [`Camera::set_aspect_ratio`] preserves
+    #[serde(default)]
+    pub fov_axis: FovAxis,
+    /// Photographic exposure scaling rendered colors; `None` leaves them unscaled
+    #[serde(default)]
+    pub exposure: Option<CameraExposure>,
+    /// Thin lens blurring out-of-focus geometry; `None` renders a pinhole camera.
+    /// Only perspective cameras use it.
+    #[serde(default)]
+    pub lens: Option<LensConfig>,
 }
 
 impl Camera {
@@ -42,6 +159,10 @@ impl Camera {
                 far,
             },
             is_active: true,
+            jitter: Vec2::ZERO,
+            fov_axis: FovAxis::Vertical,
+            exposure: None,
+            lens: None,
         }
     }
 
@@ -58,29 +179,129 @@ impl Camera {
                 far,
             },
             is_active: true,
+            jitter: Vec2::ZERO,
+            fov_axis: FovAxis::Vertical,
+            exposure: None,
+            lens: None,
         }
-    }    /// Get the view matrix
+    }
+
+    /// Get the factor [`Camera::exposure`] scales rendered colors by, 1 without one
+    #[must_use]
+    pub fn exposure_multiplier(&self) -> f32 {
+        self.exposure.map_or(1.0, |exposure| exposure.multiplier())
+    }
+
+    /// Change a perspective camera's aspect ratio.
+    ///
+    /// The field of view along [`Camera::fov_axis`] is kept and the vertical
+    /// field of view used for projection is recomputed. Orthographic cameras
+    /// are unaffected.
+    pub fn set_aspect_ratio(&mut self, new_aspect_ratio: f32) {
+        if let ProjectionType::Perspective { fov, aspect_ratio, .. } = &mut self.projection {
+            let axis_fov = self.fov_axis.axis_fov(*fov, *aspect_ratio);
+            *fov = self.fov_axis.vertical_fov(axis_fov, new_aspect_ratio);
+            *aspect_ratio = new_aspect_ratio;
+        }
+    }
+
+    /// Get a perspective camera's field of view measured along [`Camera::fov_axis`]
+    #[must_use]
+    pub fn axis_fov(&self) -> Option<f32> {
+        match &self.projection {
+            ProjectionType::Perspective { fov, aspect_ratio, .. } => {
+                Some(self.fov_axis.axis_fov(*fov, *aspect_ratio))
+            }
+            ProjectionType::Orthographic { .. } => None,
+        }
+    }
+
+    /// Get the view matrix
     pub fn view_matrix(&self) -> Mat4 {
         self.transform.to_matrix().inverse()
     }
 
-    /// Get the projection matrix
+    /// Get the projection matrix, including any sub-pixel jitter
     pub fn projection_matrix(&self) -> Mat4 {
-        match &self.projection {
+        let projection = match &self.projection {
             ProjectionType::Perspective { fov, aspect_ratio, near, far } => {
                 Mat4::perspective_rh(*fov, *aspect_ratio, *near, *far)
             },
             ProjectionType::Orthographic { left, right, bottom, top, near, far } => {
                 Mat4::orthographic_rh(*left, *right, *bottom, *top, *near, *far)
             }
+        };
+        if self.jitter == Vec2::ZERO {
+            projection
+        } else {
+            // Offset in clip space so the shift is constant in NDC after the divide
+            Mat4::from_translation(self.jitter.extend(0.0)) * projection
         }
     }
 
+    /// Return a copy of this camera with a sub-pixel projection offset in NDC
+    #[must_use]
+    pub fn with_jitter(&self, offset: Vec2) -> Self {
+        Self {
+            jitter: offset,
+            ..self.clone()
+        }
+    }
+
+    /// Sub-pixel jitter for temporal anti-aliasing following the Halton(2, 3) sequence.
+    ///
+    /// Returns the NDC offset for `frame_index` on a `width` x `height` target,
+    /// cycling every `cycle_length` frames. Offsets stay within half a pixel of
+    /// the pixel center.
+    #[must_use]
+    pub fn halton_jitter(frame_index: u32, cycle_length: u32, width: u32, height: u32) -> Vec2 {
+        let index = frame_index % cycle_length.max(1) + 1;
+        let offset = Vec2::new(
+            rrte_math::utils::halton(index, 2) - 0.5,
+            rrte_math::utils::halton(index, 3) - 0.5,
+        );
+        // One pixel spans 2 / size in NDC
+        offset * Vec2::new(2.0 / width.max(1) as f32, 2.0 / height.max(1) as f32)
+    }
+
     /// Get the view-projection matrix
     pub fn view_projection_matrix(&self) -> Mat4 {
         self.projection_matrix() * self.view_matrix()
     }
 
+    /// Get the view frustum in world space
+    #[must_use]
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_view_projection(&self.view_projection_matrix())
+    }
+
+    /// Move the camera back along its view direction until a sphere fills the view.
+    ///
+    /// Perspective cameras fit the sphere within the narrower field of view;
+    /// orthographic cameras are centered on it and resized to cover it, keeping
+    /// their aspect ratio. The orientation is unchanged.
+    pub fn frame_sphere(&mut self, center: Vec3, radius: f32) {
+        let forward = self.transform.rotation * Vec3::NEG_Z;
+        match &mut self.projection {
+            ProjectionType::Perspective { fov, aspect_ratio, .. } => {
+                let vertical_half_tan = (*fov * 0.5).tan();
+                let half_tan = vertical_half_tan.min(vertical_half_tan * *aspect_ratio);
+                let distance = radius / half_tan.atan().sin();
+                self.transform.position = center - forward * distance;
+            }
+            ProjectionType::Orthographic { left, right, bottom, top, near, .. } => {
+                let aspect_ratio = (*right - *left) / (*top - *bottom);
+                let (half_width, half_height) = if aspect_ratio >= 1.0 {
+                    (radius * aspect_ratio, radius)
+                } else {
+                    (radius, radius / aspect_ratio)
+                };
+                (*left, *right, *bottom, *top) = (-half_width, half_width, -half_height, half_height);
+                self.transform.position = center - forward * (radius + *near);
+            }
+        }
+    }
+
     /// Look at a target position
     pub fn look_at(&mut self, target: Vec3, up: Vec3) {
         // Ensure self.transform.position is set before calling this
@@ -94,11 +315,48 @@ impl Camera {
         // For now, this matches the rrte_core::Camera implementation.
     }
 
-    /// Generate a ray from screen coordinates (normalized 0-1)
+    /// Find the screen coordinates (normalized 0-1, as taken by
+    /// [`Camera::generate_ray`]) whose ray passes through a world-space point.
+    ///
+    /// Returns `None` for points behind the camera. Points outside the view give
+    /// coordinates outside `0..=1`.
+    #[must_use]
+    pub fn project_to_screen(&self, point: Vec3) -> Option<Vec2> {
+        match &self.projection {
+            ProjectionType::Perspective { fov, aspect_ratio, .. } => {
+                let local = self.transform.rotation.inverse() * (point - self.transform.position);
+                if local.z >= 0.0 {
+                    return None;
+                }
+                let half_height = (fov * 0.5).tan();
+                let half_width = aspect_ratio * half_height;
+                let ndc_x = local.x / (-local.z * half_width);
+                let ndc_y = local.y / (-local.z * half_height);
+                Some(Vec2::new(
+                    (ndc_x + self.jitter.x + 1.0) * 0.5,
+                    (1.0 - self.jitter.y - ndc_y) * 0.5,
+                ))
+            }
+            ProjectionType::Orthographic { left, right, bottom, top, .. } => {
+                let local = self.transform.inverse_matrix().transform_point3(point);
+                if local.z > 0.0 {
+                    return None;
+                }
+                Some(Vec2::new((local.x - left) / (right - left), (local.y - bottom) / (top - bottom)))
+            }
+        }
+    }
+
+    /// Generate a ray from screen coordinates (normalized 0-1).
+    ///
+    /// With a [`Camera::lens`] of non-zero aperture, every call picks a new
+    /// point on the lens, so averaging many rays per pixel gives depth of field.
     pub fn generate_ray(&self, u: f32, v: f32) -> Ray {
         // Convert from screen space to world space
-        let ndc_x = 2.0 * u - 1.0;
-        let ndc_y = 1.0 - 2.0 * v; // Flip Y for screen coordinates
+        // The jitter shifts the image by `jitter` like the projection matrix does,
+        // so the ray through a pixel comes from the opposite offset
+        let ndc_x = 2.0f32.mul_add(u, -1.0) - self.jitter.x;
+        let ndc_y = 2.0f32.mul_add(-v, 1.0) - self.jitter.y; // Flip Y for screen coordinates
         
         match &self.projection {
             ProjectionType::Perspective { fov, aspect_ratio, .. } => {
@@ -110,6 +368,18 @@ impl Camera {
                 
                 // Direction in camera space (looking down -Z)
                 let camera_dir = Vec3::new(world_x, world_y, -1.0).normalize();
+
+                if let Some(lens) = self.lens.filter(|lens| lens.aperture > 0.0) {
+                    // Aim from a point on the lens at where the pinhole ray meets the focal plane
+                    let focus_point = Vec3::new(world_x, world_y, -1.0) * lens.focus_distance;
+                    let radius = lens.aperture * random::random::<f32>().sqrt();
+                    let angle = std::f32::consts::TAU * random::random::<f32>();
+                    let lens_point = Vec3::new(radius * angle.cos(), radius * angle.sin(), 0.0);
+                    let world_origin = self.transform.position + self.transform.rotation * lens_point;
+                    let world_direction = self.transform.rotation * (focus_point - lens_point).normalize();
+                    return Ray::new(world_origin, world_direction);
+                }
+
                   // Transform to world space
                 let world_origin = self.transform.position;
                 let world_direction = self.transform.rotation * camera_dir;
@@ -132,3 +402,85 @@ impl Camera {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_follows_halton_2_3() {
+        // On a 2x2 target one pixel spans one NDC unit, so offsets are in pixels
+        let expected = [
+            (1.0 / 2.0, 1.0 / 3.0),
+            (1.0 / 4.0, 2.0 / 3.0),
+            (3.0 / 4.0, 1.0 / 9.0),
+            (1.0 / 8.0, 4.0 / 9.0),
+        ];
+        for (frame, (x, y)) in (0..).zip(expected) {
+            let jitter = Camera::halton_jitter(frame, 8, 2, 2);
+            assert!(jitter.abs_diff_eq(Vec2::new(x - 0.5, y - 0.5), 1e-6), "frame {frame}: {jitter}");
+        }
+        assert_eq!(Camera::halton_jitter(8, 8, 2, 2), Camera::halton_jitter(0, 8, 2, 2));
+    }
+
+    #[test]
+    fn jitter_averages_to_zero_over_a_cycle() {
+        let cycle = 16;
+        let sum: Vec2 = (0..cycle).map(|frame| Camera::halton_jitter(frame, cycle, 2, 2)).sum();
+        let mean = sum / 16.0;
+        assert!(mean.abs().max_element() < 0.05, "{mean}");
+    }
+
+    #[test]
+    fn jittered_rays_pass_through_the_pixel_the_jittered_projection_maps_them_to() {
+        let camera = Camera::new_perspective(1.0, 1.5, 0.1, 100.0).with_jitter(Vec2::new(0.1, -0.05));
+        let (u, v) = (0.3, 0.6);
+        let ray = camera.generate_ray(u, v);
+        let point = ray.at(5.0);
+
+        let clip = camera.view_projection_matrix() * point.extend(1.0);
+        let ndc = clip.truncate().truncate() / clip.w;
+        assert!(ndc.abs_diff_eq(Vec2::new(2.0f32.mul_add(u, -1.0), 2.0f32.mul_add(-v, 1.0)), 1e-5), "{ndc}");
+        let screen = camera.project_to_screen(point).unwrap();
+        assert!(screen.abs_diff_eq(Vec2::new(u, v), 1e-5), "{screen}");
+    }
+
+    #[test]
+    fn widening_with_a_horizontal_fov_keeps_the_width_and_shrinks_the_height() {
+        let half_extents = |camera: &Camera| match camera.projection {
+            ProjectionType::Perspective { fov, aspect_ratio, .. } => {
+                let half_height = (fov * 0.5).tan();
+                (half_height * aspect_ratio, half_height)
+            }
+            ProjectionType::Orthographic { .. } => unreachable!(),
+        };
+        let mut camera = Camera::new_perspective(1.0, 4.0 / 3.0, 0.1, 100.0);
+        camera.fov_axis = FovAxis::Horizontal;
+        let (width, height) = half_extents(&camera);
+        let axis_fov = camera.axis_fov().unwrap();
+
+        camera.set_aspect_ratio(21.0 / 9.0);
+        let (wide_width, wide_height) = half_extents(&camera);
+        assert!((wide_width - width).abs() < 1e-5, "{wide_width} != {width}");
+        assert!(wide_height < height);
+        assert!((camera.axis_fov().unwrap() - axis_fov).abs() < 1e-5);
+
+        // The default vertical axis keeps the height instead
+        let mut camera = Camera::new_perspective(1.0, 4.0 / 3.0, 0.1, 100.0);
+        camera.set_aspect_ratio(21.0 / 9.0);
+        assert!((half_extents(&camera).1 - height).abs() < 1e-5);
+    }
+
+    #[test]
+    fn one_stop_of_iso_doubles_the_exposure_multiplier() {
+        let base = CameraExposure::new(2.8, 1.0 / 60.0, 100.0);
+        let doubled = CameraExposure { iso: 200.0, ..base };
+        assert!((doubled.multiplier() / base.multiplier() - 2.0).abs() < 1e-4);
+        assert!((base.ev100() - doubled.ev100() - 1.0).abs() < 1e-4);
+
+        let mut camera = Camera::new_perspective(1.0, 1.0, 0.1, 100.0);
+        assert!((camera.exposure_multiplier() - 1.0).abs() < 1e-6);
+        camera.exposure = Some(CameraExposure::from_multiplier(0.25));
+        assert!((camera.exposure_multiplier() - 0.25).abs() < 1e-5);
+    }
+}
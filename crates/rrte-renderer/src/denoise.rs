@@ -0,0 +1,199 @@
+use rrte_math::{Color, Vec3};
+use rayon::prelude::*;
+
+/// Per-pixel auxiliary buffers describing the first surface seen through each pixel.
+///
+/// Pixels whose primary ray escapes have a zero normal, black albedo and
+/// infinite depth.
+#[derive(Debug, Clone)]
+pub struct GuideBuffers {
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+    /// World-space surface normal, facing the camera
+    pub normals: Vec<Vec3>,
+    /// Material albedo of the surface
+    pub albedo: Vec<Color>,
+    /// Distance along the primary ray
+    pub depth: Vec<f32>,
+}
+
+impl GuideBuffers {
+    /// Create guide buffers for a resolution, initialized as all background
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        let pixel_count = width * height;
+        Self {
+            width,
+            height,
+            normals: vec![Vec3::ZERO; pixel_count],
+            albedo: vec![Color::BLACK; pixel_count],
+            depth: vec![f32::INFINITY; pixel_count],
+        }
+    }
+}
+
+/// Edge-stopping parameters for [`atrous_denoise`]
+#[derive(Debug, Clone, Copy)]
+pub struct DenoiseSettings {
+    /// Number of filter passes; each doubles the kernel footprint
+    pub iterations: u32,
+    /// Color difference tolerance, halved every pass
+    pub sigma_color: f32,
+    /// Exponent applied to normal agreement; higher keeps creases sharper
+    pub sigma_normal: f32,
+    /// Depth difference tolerance, scaled by the pass step
+    pub sigma_depth: f32,
+    /// Albedo difference tolerance, preserving texture and material edges
+    pub sigma_albedo: f32,
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        Self {
+            iterations: 4,
+            sigma_color: 0.6,
+            sigma_normal: 64.0,
+            sigma_depth: 0.5,
+            sigma_albedo: 0.2,
+        }
+    }
+}
+
+/// B3-spline weights of the 5x5 à-trous kernel, per axis
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Edge-avoiding à-trous wavelet filter (Dammertz et al. 2010).
+///
+/// Each pass blurs with a sparse 5x5 kernel whose taps are spread `2^pass`
+/// pixels apart, and down-weights taps that differ from the center pixel in
+/// color, normal, depth or albedo, so noise is smoothed within a surface
+/// but not across geometric edges. Alpha is passed through unchanged.
+pub fn atrous_denoise(colors: &[Color], guides: &GuideBuffers, settings: &DenoiseSettings) -> Vec<Color> {
+    let width = guides.width;
+    let height = guides.height;
+    debug_assert_eq!(colors.len(), width * height);
+
+    let mut current: Vec<Vec3> = colors.iter().map(Color::to_vec3).collect();
+    let mut sigma_color = settings.sigma_color;
+
+    for pass in 0..settings.iterations {
+        let step = 1usize << pass;
+        let source = &current;
+        let filtered: Vec<Vec3> = (0..width * height)
+            .into_par_iter()
+            .map(|index| {
+                let x = index % width;
+                let y = index / width;
+                let center_color = source[index];
+                let center_normal = guides.normals[index];
+                let center_depth = guides.depth[index];
+                let center_albedo = guides.albedo[index].to_vec3();
+
+                let mut sum = Vec3::ZERO;
+                let mut weight_sum = 0.0;
+                for (ky, kernel_y) in KERNEL.iter().enumerate() {
+                    let Some(sy) = offset(y, ky, step, height) else { continue };
+                    for (kx, kernel_x) in KERNEL.iter().enumerate() {
+                        let Some(sx) = offset(x, kx, step, width) else { continue };
+                        let sample = sy * width + sx;
+
+                        let color_distance = (source[sample] - center_color).length_squared();
+                        let w_color = (-color_distance / (sigma_color * sigma_color).max(1e-8)).exp();
+                        let w_normal = normal_weight(center_normal, guides.normals[sample], settings.sigma_normal);
+                        let w_depth = depth_weight(center_depth, guides.depth[sample], settings.sigma_depth * step as f32);
+                        let albedo_distance = (guides.albedo[sample].to_vec3() - center_albedo).length_squared();
+                        let w_albedo = (-albedo_distance / (settings.sigma_albedo * settings.sigma_albedo).max(1e-8)).exp();
+
+                        let weight = kernel_x * kernel_y * w_color * w_normal * w_depth * w_albedo;
+                        sum += source[sample] * weight;
+                        weight_sum += weight;
+                    }
+                }
+
+                // The center tap always has full edge weight, so weight_sum > 0
+                sum / weight_sum
+            })
+            .collect();
+
+        current = filtered;
+        sigma_color *= 0.5;
+    }
+
+    current
+        .into_iter()
+        .zip(colors)
+        .map(|(rgb, original)| Color::new(rgb.x, rgb.y, rgb.z, original.a))
+        .collect()
+}
+
+/// Coordinate of kernel tap `k` around `center`, or `None` if it falls outside the image
+fn offset(center: usize, k: usize, step: usize, size: usize) -> Option<usize> {
+    (center + k * step)
+        .checked_sub(2 * step)
+        .filter(|&coordinate| coordinate < size)
+}
+
+/// Normal agreement weight; background pixels (zero normal) only match each other
+fn normal_weight(center: Vec3, sample: Vec3, sigma: f32) -> f32 {
+    match (center == Vec3::ZERO, sample == Vec3::ZERO) {
+        (true, true) => 1.0,
+        (false, false) => center.dot(sample).max(0.0).powf(sigma),
+        _ => 0.0,
+    }
+}
+
+/// Depth agreement weight; background pixels (infinite depth) only match each other
+fn depth_weight(center: f32, sample: f32, sigma: f32) -> f32 {
+    match (center.is_finite(), sample.is_finite()) {
+        (false, false) => 1.0,
+        (true, true) => (-(center - sample).abs() / sigma.max(1e-8)).exp(),
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rrte_math::random;
+
+    /// Mean and variance of the red channel over a set of pixels
+    fn red_statistics(colors: &[Color], pixels: &[usize]) -> (f32, f32) {
+        let count = f32::from(u16::try_from(pixels.len()).unwrap());
+        let mean = pixels.iter().map(|&i| colors[i].r).sum::<f32>() / count;
+        let variance = pixels.iter().map(|&i| (colors[i].r - mean).powi(2)).sum::<f32>() / count;
+        (mean, variance)
+    }
+
+    #[test]
+    fn denoising_reduces_noise_but_keeps_a_geometric_edge() {
+        // Two surfaces meeting at x = 8: dark and near on the left, bright and far on the right
+        let (width, height) = (16, 16);
+        let mut guides = GuideBuffers::new(width, height);
+        let mut colors = Vec::with_capacity(width * height);
+        random::seed(3);
+        for index in 0..width * height {
+            let left = index % width < 8;
+            guides.normals[index] = if left { Vec3::Z } else { Vec3::X };
+            guides.depth[index] = if left { 1.0 } else { 5.0 };
+            guides.albedo[index] = Color::gray(0.5);
+            let base = if left { 0.2 } else { 0.8 };
+            colors.push(Color::gray((random::random::<f32>() - 0.5).mul_add(0.2, base)));
+        }
+
+        let denoised = atrous_denoise(&colors, &guides, &DenoiseSettings::default());
+
+        let left: Vec<usize> = (0..width * height).filter(|i| i % width < 8).collect();
+        let (_, noisy_variance) = red_statistics(&colors, &left);
+        let (left_mean, denoised_variance) = red_statistics(&denoised, &left);
+        assert!(denoised_variance < noisy_variance * 0.25, "{denoised_variance} vs {noisy_variance}");
+        assert!((left_mean - 0.2).abs() < 0.03, "{left_mean}");
+
+        // The columns either side of the edge keep their own surface's brightness
+        let column = |x: usize| (0..height).map(|y| y * width + x).collect::<Vec<_>>();
+        let (inner, _) = red_statistics(&denoised, &column(7));
+        let (outer, _) = red_statistics(&denoised, &column(8));
+        assert!((inner - 0.2).abs() < 0.05 && (outer - 0.8).abs() < 0.05, "{inner} | {outer}");
+    }
+}
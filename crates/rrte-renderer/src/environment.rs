@@ -0,0 +1,220 @@
+// Pixel coordinates, sizes and sample counts stay far below 2^24, so
+// converting them to f32 is exact
+#![allow(clippy::cast_precision_loss)]
+
+use rrte_math::{Color, Vec2, Vec3};
+use image::DynamicImage;
+use std::f32::consts::PI;
+
+/// A direction sampled from an [`EnvironmentMap`]
+#[derive(Debug, Clone, Copy)]
+pub struct EnvironmentSample {
+    /// Unit direction toward the sampled texel
+    pub direction: Vec3,
+    /// Radiance arriving from that direction
+    pub radiance: Color,
+    /// Probability density of the direction per unit solid angle
+    pub pdf: f32,
+}
+
+/// Equirectangular environment map with a luminance CDF for importance sampling.
+///
+/// The top row looks straight up (+Y) and the center column looks down -Z.
+/// Texels are chosen with probability proportional to their luminance times
+/// the solid angle they cover, so bright regions such as the sun in an HDRI
+/// are found by light samples instead of by chance.
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    /// Per-row cumulative distribution over columns, `width` entries per row
+    conditional_cdf: Vec<f32>,
+    /// Cumulative distribution over rows
+    marginal_cdf: Vec<f32>,
+    /// Sum of all texel weights; zero for a black map
+    total_weight: f32,
+}
+
+impl EnvironmentMap {
+    /// Create an environment map from linear texels in row-major order.
+    ///
+    /// Returns `None` if the dimensions are zero or do not match `pixels`.
+    #[must_use]
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Option<Self> {
+        if width == 0 || height == 0 || pixels.len() != width * height {
+            return None;
+        }
+
+        let mut conditional_cdf = Vec::with_capacity(width * height);
+        let mut marginal_cdf = Vec::with_capacity(height);
+        let mut total_weight = 0.0;
+        for y in 0..height {
+            // Rows near the poles cover less solid angle
+            let sin_theta = ((y as f32 + 0.5) / height as f32 * PI).sin();
+            let mut row_weight = 0.0;
+            for pixel in &pixels[y * width..(y + 1) * width] {
+                row_weight += pixel.luminance().max(0.0) * sin_theta;
+                conditional_cdf.push(row_weight);
+            }
+            total_weight += row_weight;
+            marginal_cdf.push(total_weight);
+        }
+
+        Some(Self {
+            width,
+            height,
+            pixels,
+            conditional_cdf,
+            marginal_cdf,
+            total_weight,
+        })
+    }
+
+    /// Create an environment map from an image, e.g. a loaded `.hdr` file
+    #[must_use]
+    pub fn from_image(image: &DynamicImage) -> Option<Self> {
+        let rgb = image.to_rgb32f();
+        let pixels = rgb.pixels().map(|p| Color::rgb(p[0], p[1], p[2])).collect();
+        Self::new(rgb.width() as usize, rgb.height() as usize, pixels)
+    }
+
+    /// Get the width in texels
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the height in texels
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get the radiance seen in a direction (nearest texel)
+    #[must_use]
+    pub fn lookup(&self, direction: Vec3) -> Color {
+        let (x, y) = self.texel(direction_to_uv(direction));
+        self.pixels[y * self.width + x]
+    }
+
+    /// Sample a direction proportionally to luminance, using `u` in `[0, 1)^2`.
+    ///
+    /// Returns `None` for a completely black map, which emits no light.
+    #[must_use]
+    pub fn sample(&self, u: Vec2) -> Option<EnvironmentSample> {
+        if self.total_weight <= 0.0 {
+            return None;
+        }
+
+        let (y, v_offset) = sample_cdf(&self.marginal_cdf, u.y * self.total_weight);
+        let row = &self.conditional_cdf[y * self.width..(y + 1) * self.width];
+        let (x, u_offset) = sample_cdf(row, u.x * row[self.width - 1]);
+
+        let uv = Vec2::new(
+            (x as f32 + u_offset) / self.width as f32,
+            (y as f32 + v_offset) / self.height as f32,
+        );
+        let direction = uv_to_direction(uv);
+        Some(EnvironmentSample {
+            direction,
+            radiance: self.pixels[y * self.width + x],
+            pdf: self.texel_pdf(x, y),
+        })
+    }
+
+    /// Get the solid-angle density with which [`EnvironmentMap::sample`] picks a direction
+    #[must_use]
+    pub fn pdf(&self, direction: Vec3) -> f32 {
+        if self.total_weight <= 0.0 {
+            return 0.0;
+        }
+        let (x, y) = self.texel(direction_to_uv(direction));
+        self.texel_pdf(x, y)
+    }
+
+    /// Solid-angle density of directions within texel `(x, y)`
+    fn texel_pdf(&self, x: usize, y: usize) -> f32 {
+        let index = y * self.width + x;
+        let previous = if x == 0 { 0.0 } else { self.conditional_cdf[index - 1] };
+        let probability = (self.conditional_cdf[index] - previous) / self.total_weight;
+
+        // Convert from the texel grid to solid angle: the map spans 2π x π and
+        // a texel's area shrinks with sin(theta)
+        let sin_theta = ((y as f32 + 0.5) / self.height as f32 * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        probability * (self.width * self.height) as f32 / (2.0 * PI * PI * sin_theta)
+    }
+
+    /// Texel containing a UV coordinate
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // UVs are in [0, 1], so the product is a small positive index
+    fn texel(&self, uv: Vec2) -> (usize, usize) {
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((uv.y * self.height as f32) as usize).min(self.height - 1);
+        (x, y)
+    }
+}
+
+/// Find the bucket of a non-decreasing CDF containing `value`, and how far into it `value` lies
+fn sample_cdf(cdf: &[f32], value: f32) -> (usize, f32) {
+    let index = cdf.partition_point(|&c| c <= value).min(cdf.len() - 1);
+    let start = if index == 0 { 0.0 } else { cdf[index - 1] };
+    let width = cdf[index] - start;
+    let offset = if width > 0.0 { ((value - start) / width).clamp(0.0, 1.0) } else { 0.5 };
+    (index, offset)
+}
+
+/// Map a direction to equirectangular UV: `u` follows the azimuth, `v` runs from +Y down to -Y
+fn direction_to_uv(direction: Vec3) -> Vec2 {
+    let direction = direction.normalize_or_zero();
+    let u = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    Vec2::new(u, v)
+}
+
+/// Inverse of [`direction_to_uv`]
+fn uv_to_direction(uv: Vec2) -> Vec3 {
+    let phi = (uv.x - 0.5) * 2.0 * PI;
+    let theta = uv.y * PI;
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    Vec3::new(sin_theta * phi.sin(), cos_theta, -sin_theta * phi.cos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_concentrate_on_a_single_bright_texel() {
+        let (width, height, bright) = (8, 4, 8 + 5);
+        let mut pixels = vec![Color::rgb(0.01, 0.01, 0.01); width * height];
+        pixels[bright] = Color::rgb(100.0, 100.0, 100.0);
+        let map = EnvironmentMap::new(width, height, pixels).unwrap();
+
+        // A stratified grid over [0, 1)^2 instead of random numbers
+        let steps = 32;
+        let mut hits = 0;
+        for i in 0..steps {
+            for j in 0..steps {
+                let u = Vec2::new((i as f32 + 0.5) / steps as f32, (j as f32 + 0.5) / steps as f32);
+                let sample = map.sample(u).unwrap();
+                if sample.radiance.r > 1.0 {
+                    hits += 1;
+                    assert!(map.lookup(sample.direction).r > 1.0);
+                    assert!(sample.pdf > map.pdf(Vec3::NEG_Y));
+                }
+            }
+        }
+        assert!(hits * 100 > steps * steps * 95, "{hits} of {} samples", steps * steps);
+    }
+
+    #[test]
+    fn black_map_has_no_samples() {
+        let map = EnvironmentMap::new(2, 2, vec![Color::BLACK; 4]).unwrap();
+        assert!(map.sample(Vec2::new(0.5, 0.5)).is_none());
+        assert!(map.pdf(Vec3::Y) == 0.0);
+        assert!(EnvironmentMap::new(2, 2, vec![Color::BLACK; 3]).is_none());
+    }
+}
@@ -3,22 +3,85 @@ use winit::window::Window;
 use anyhow::Result;
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
-use glam::Mat4;
+use glam::{Mat4, Vec3};
+use bytemuck::Zeroable;
 // use crate::RendererConfig; // Commented out to investigate usage
 use crate::camera::Camera as RendererCamera; // Added import for RendererCamera
-use crate::primitives::Sphere; // Added for sphere handling
-use crate::light::PointLight; // Added for light handling
-use std::collections::HashMap; // Added for material map
+use crate::primitives::{Cone, Cube, Cylinder, ShadowFlags, Sphere, Triangle}; // Added for sphere, box, mesh, cylinder and cone handling
+use crate::SceneObject;
+use crate::light::{InstancedLight, PointLight}; // Added for light handling
+use crate::material::{DielectricMaterial, Material, MetalMaterial, RegisteredMaterial};
+use crate::viewport::{Viewport, ViewportRect};
+use crate::raytracer::{Background, ToneMapper};
+use rrte_math::Color;
+use std::collections::{hash_map::Entry, BTreeMap, HashMap}; // Added for material map
 use log::{info, warn};
 
+/// `MaterialGpu::material_type` of diffuse surfaces
+const MATERIAL_TYPE_LAMBERTIAN: u32 = 0;
+/// `MaterialGpu::material_type` of metals, with their roughness in `smoothness`
+const MATERIAL_TYPE_METAL: u32 = 1;
+/// `MaterialGpu::material_type` of glass, with its index of refraction in `smoothness`
+const MATERIAL_TYPE_DIELECTRIC: u32 = 2;
+/// `MaterialGpu::material_type` of surfaces that emit `color` and do not scatter
+const MATERIAL_TYPE_EMISSIVE: u32 = 3;
+
+/// `shadow_flags` of objects that cast and receive shadows, see [`ShadowFlags::bits`]
+const DEFAULT_SHADOW_FLAGS: u32 = ShadowFlags::CASTS_SHADOW | ShadowFlags::RECEIVES_SHADOW;
+
+/// Frames after which the sub-pixel jitter of [`GpuRendererConfig::taa_jitter`] repeats
+const TAA_JITTER_CYCLE: u32 = 16;
+
+/// Pixel format of thumbnails returned by [`GpuRenderer::read_thumbnail`]
+const THUMBNAIL_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+/// Pick the surface format to render to: `preferred` if the surface supports it,
+/// otherwise the first supported sRGB format, otherwise the first supported one.
+///
+/// Any of them displays correctly, since the blit sRGB encodes the linear
+/// output itself for non-sRGB formats; sRGB formats are preferred because the
+/// hardware encoding is free.
+#[must_use]
+pub fn choose_surface_format(preferred: TextureFormat, supported: &[TextureFormat]) -> TextureFormat {
+    if supported.contains(&preferred) {
+        return preferred;
+    }
+    supported
+        .iter()
+        .find(|format| format.is_srgb())
+        .or_else(|| supported.first())
+        .copied()
+        .unwrap_or(preferred)
+}
+
 /// GPU renderer configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GpuRendererConfig {
     pub width: u32,
     pub height: u32,
     pub format: TextureFormat,
     pub present_mode: wgpu::PresentMode,
     pub samples: u32,
+    /// Maximum number of bounces traced per pixel by the compute shader
+    pub max_depth: u32,
+    /// Color of rays that hit nothing, matching [`crate::RaytracerConfig::background`]
+    pub background: Background,
+    /// Curve applied after the camera exposure and `exposure`, matching [`crate::RaytracerConfig::tonemap`]
+    pub tonemap: ToneMapper,
+    /// Factor the output is scaled by on top of the camera exposure, matching
+    /// [`crate::RaytracerConfig::exposure`]
+    pub exposure: f32,
+    /// Object kinds besides spheres that may be uploaded and drawn
+    pub primitives: GpuPrimitives,
+    /// Raytrace into two output textures alternately, and have
+    /// [`GpuRenderer::render`] blit the previous frame while raytracing the next
+    /// one. The two passes share no textures, so the GPU can overlap them, at
+    /// the cost of presenting every frame one call late
+    pub double_buffer_output: bool,
+    /// Offset each accumulated frame's primary rays by the next step of
+    /// [`RendererCamera::halton_jitter`], restarting with the accumulation, so
+    /// the average covers the whole pixel like the CPU raytracer's samples
+    pub taa_jitter: bool,
 }
 
 impl Default for GpuRendererConfig {
@@ -29,7 +92,112 @@ impl Default for GpuRendererConfig {
             format: TextureFormat::Rgba8UnormSrgb,
             present_mode: wgpu::PresentMode::Fifo,
             samples: 1,
+            max_depth: 8,
+            background: Background::solid(Color::new(0.5, 0.7, 1.0, 1.0)), // Same sky blue as the CPU default
+            tonemap: ToneMapper::None,
+            exposure: 1.0,
+            primitives: GpuPrimitives::ALL,
+            double_buffer_output: false,
+            taa_jitter: true,
+        }
+    }
+}
+
+/// Object kinds the GPU raytracer can draw besides spheres.
+///
+/// Every kind needs its own storage buffers and shader code, and devices with
+/// default limits allow only 8 storage buffers per shader stage. The renderer
+/// therefore binds only the kinds a frame contains, out of those enabled in
+/// [`GpuRendererConfig::primitives`]; a frame with every kind needs 9.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GpuPrimitives {
+    /// Cubes, as oriented boxes; one buffer
+    pub boxes: bool,
+    /// Triangles; vertex, triangle and mesh buffers
+    pub meshes: bool,
+    /// Cylinders; one buffer
+    pub cylinders: bool,
+    /// Cones; one buffer
+    pub cones: bool,
+}
+
+impl GpuPrimitives {
+    /// Every kind
+    pub const ALL: Self = Self { boxes: true, meshes: true, cylinders: true, cones: true };
+    /// Spheres only
+    pub const NONE: Self = Self { boxes: false, meshes: false, cylinders: false, cones: false };
+
+    /// Get the kinds enabled in both `self` and `other`
+    #[must_use]
+    pub const fn intersection(self, other: Self) -> Self {
+        Self {
+            boxes: self.boxes && other.boxes,
+            meshes: self.meshes && other.meshes,
+            cylinders: self.cylinders && other.cylinders,
+            cones: self.cones && other.cones,
+        }
+    }
+
+    /// Get the number of storage buffers the raytracing shader binds with these
+    /// kinds, including the sphere, material and light buffers
+    #[must_use]
+    pub fn storage_buffer_count(self) -> u32 {
+        3 + u32::from(self.boxes) + 3 * u32::from(self.meshes) + u32::from(self.cylinders) + u32::from(self.cones)
+    }
+
+    /// Check whether a kind named in a shader `// #if` line is enabled, or
+    /// `None` if the name is not a kind
+    fn contains(self, kind: &str) -> Option<bool> {
+        match kind {
+            "boxes" => Some(self.boxes),
+            "meshes" => Some(self.meshes),
+            "cylinders" => Some(self.cylinders),
+            "cones" => Some(self.cones),
+            _ => None,
+        }
+    }
+
+    /// Get the raytracing shader with the code between `// #if <kind>` and
+    /// `// #endif` lines removed for every disabled kind
+    fn shader_source(self) -> Result<String> {
+        self.preprocess(include_str!("shaders/raytrace.wgsl"))
+    }
+
+    /// Remove the code between `// #if <kind>` and `// #endif` lines of
+    /// `shader` for every disabled kind.
+    ///
+    /// Blocks may nest, and code is kept only if every enclosing kind is
+    /// enabled. Unknown kinds and unbalanced directives are errors.
+    fn preprocess(self, shader: &str) -> Result<String> {
+        let mut source = String::new();
+        // Whether each enclosing block is enabled, innermost last
+        let mut blocks = Vec::new();
+        for (index, line) in shader.lines().enumerate() {
+            let directive = line.trim();
+            if let Some(kind) = directive.strip_prefix("// #if ") {
+                let enabled = self
+                    .contains(kind)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown object kind `{}` on shader line {}", kind, index + 1))?;
+                blocks.push(enabled);
+            } else if directive == "// #endif" {
+                blocks
+                    .pop()
+                    .ok_or_else(|| anyhow::anyhow!("`// #endif` without `// #if` on shader line {}", index + 1))?;
+            } else if blocks.iter().all(|&enabled| enabled) {
+                source.push_str(line);
+                source.push('\n');
+            }
+        }
+        if !blocks.is_empty() {
+            return Err(anyhow::anyhow!("{} `// #if` blocks are not closed", blocks.len()));
         }
+        Ok(source)
+    }
+}
+
+impl Default for GpuPrimitives {
+    fn default() -> Self {
+        Self::ALL
     }
 }
 
@@ -44,25 +212,177 @@ pub struct CameraGpu {
 }
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct SphereGpu {
     pub center: [f32; 4], // vec3 + padding for radius or material_id alignment
     pub radius: f32,
     pub material_index: u32,
-    _padding: [u32; 2], // Ensure alignment to 16 bytes if needed, or for future fields
+    /// [`ShadowFlags::bits`] of the object
+    pub shadow_flags: u32,
+    _padding: u32, // Ensure alignment to 16 bytes
 }
 
+/// An oriented box: an axis-aligned box in local space plus the transform
+/// into world space
 #[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct BoxGpu {
+    /// Local-space center, w unused
+    pub center: [f32; 4],
+    /// Local-space half size, w unused
+    pub half_extents: [f32; 4],
+    /// World to local Mat4; rays are intersected in local space
+    pub inv_transform: [[f32; 4]; 4],
+    /// Index into the material buffer
+    pub material_index: u32,
+    /// [`ShadowFlags::bits`] of the object
+    pub shadow_flags: u32,
+    _padding: [u32; 2], // Ensure alignment to 16 bytes
+}
+
+impl BoxGpu {
+    /// Pack a cube for upload
+    #[must_use]
+    pub fn from_cube(cube: &Cube, material_index: u32) -> Self {
+        let half_extents = cube.size * 0.5;
+        Self {
+            center: [cube.center.x, cube.center.y, cube.center.z, 0.0],
+            half_extents: [half_extents.x, half_extents.y, half_extents.z, 0.0],
+            inv_transform: cube.transform.inverse_matrix().to_cols_array_2d(),
+            material_index,
+            shadow_flags: DEFAULT_SHADOW_FLAGS,
+            _padding: [0; 2],
+        }
+    }
+}
+
+/// An open cylinder around the local y axis: a local-space center, radius and
+/// height plus the transform into world space
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CylinderGpu {
+    /// Local-space center, w unused
+    pub center: [f32; 4],
+    /// World to local Mat4; rays are intersected in local space
+    pub inv_transform: [[f32; 4]; 4],
+    /// Local-space radius
+    pub radius: f32,
+    /// Local-space height along y
+    pub height: f32,
+    /// Index into the material buffer
+    pub material_index: u32,
+    /// [`ShadowFlags::bits`] of the object
+    pub shadow_flags: u32,
+}
+
+impl CylinderGpu {
+    /// Pack a cylinder for upload
+    #[must_use]
+    pub fn from_cylinder(cylinder: &Cylinder, material_index: u32) -> Self {
+        Self {
+            center: [cylinder.center.x, cylinder.center.y, cylinder.center.z, 0.0],
+            inv_transform: cylinder.transform.inverse_matrix().to_cols_array_2d(),
+            radius: cylinder.radius,
+            height: cylinder.height,
+            material_index,
+            shadow_flags: DEFAULT_SHADOW_FLAGS,
+        }
+    }
+}
+
+/// An open cone around the local y axis with its apex at the top, laid out
+/// like [`CylinderGpu`]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ConeGpu {
+    /// Local-space center, w unused
+    pub center: [f32; 4],
+    /// World to local Mat4; rays are intersected in local space
+    pub inv_transform: [[f32; 4]; 4],
+    /// Radius of the base
+    pub radius: f32,
+    /// Height from the base to the apex along y
+    pub height: f32,
+    /// Index into the material buffer
+    pub material_index: u32,
+    /// [`ShadowFlags::bits`] of the object
+    pub shadow_flags: u32,
+}
+
+impl ConeGpu {
+    /// Pack a cone for upload
+    #[must_use]
+    pub fn from_cone(cone: &Cone, material_index: u32) -> Self {
+        Self {
+            center: [cone.center.x, cone.center.y, cone.center.z, 0.0],
+            inv_transform: cone.transform.inverse_matrix().to_cols_array_2d(),
+            radius: cone.radius,
+            height: cone.height,
+            material_index,
+            shadow_flags: DEFAULT_SHADOW_FLAGS,
+        }
+    }
+}
+
+/// A mesh vertex, shared by the triangles that index it
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexGpu {
+    /// Position, w unused
+    pub position: [f32; 4],
+    /// Normal, interpolated across the triangle; w unused
+    pub normal: [f32; 4],
+}
+
+impl VertexGpu {
+    /// Pack a vertex position and normal
+    #[must_use]
+    pub const fn new(position: Vec3, normal: Vec3) -> Self {
+        Self {
+            position: [position.x, position.y, position.z, 0.0],
+            normal: [normal.x, normal.y, normal.z, 0.0],
+        }
+    }
+}
+
+/// Three indices into the vertex buffer and the triangle's material
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TriangleGpu {
+    /// Indices of the corners in the vertex buffer
+    pub indices: [u32; 3],
+    /// Index into the material buffer
+    pub material_index: u32,
+}
+
+/// A range of the triangle buffer drawn with one transform
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MeshGpu {
+    /// World to local Mat4; rays are intersected in local space
+    pub inv_transform: [[f32; 4]; 4],
+    /// Index of the mesh's first triangle in the triangle buffer
+    pub triangle_offset: u32,
+    /// Number of triangles in the mesh
+    pub triangle_count: u32,
+    /// [`ShadowFlags::bits`] of the object
+    pub shadow_flags: u32,
+    _padding: u32, // Ensure alignment to 16 bytes
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct MaterialGpu {
     pub color: [f32; 4], // rgba
-    pub material_type: u32, // 0: Lambertian, 1: Metal, etc.
-    pub smoothness: f32, // For metal, roughness etc.
+    /// 0: Lambertian, 1: Metal, 2: Dielectric, 3: Emissive
+    pub material_type: u32,
+    /// Roughness for metals, index of refraction for dielectrics
+    pub smoothness: f32,
     _padding: [u32; 2], // Ensure alignment
 }
 
 #[repr(C)]
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct PointLightGpu {
     pub position: [f32; 4], // xyz + padding or intensity
     pub color: [f32; 4], // rgba
@@ -71,43 +391,583 @@ pub struct PointLightGpu {
     _padding: [u32; 2], // Ensure alignment to 16 bytes
 }
 
+impl MaterialGpu {
+    /// Pack a material for upload.
+    ///
+    /// Metals carry their roughness and dielectrics their index of refraction
+    /// in `smoothness`; emitters carry their HDR emission in place of the
+    /// albedo. Other materials are drawn as Lambertian with their albedo.
+    pub fn from_material(material: &dyn Material) -> Self {
+        if let Some(registered) = material.as_any().downcast_ref::<RegisteredMaterial>() {
+            return Self::from_material(registered.resolve().as_ref());
+        }
+        let albedo = material.albedo();
+        let emission = material.emitted();
+        let (color, material_type, smoothness) = if emission.to_vec3() != glam::Vec3::ZERO {
+            ([emission.r, emission.g, emission.b, albedo.a], MATERIAL_TYPE_EMISSIVE, 0.0)
+        } else if let Some(metal) = material.as_any().downcast_ref::<MetalMaterial>() {
+            ([albedo.r, albedo.g, albedo.b, albedo.a], MATERIAL_TYPE_METAL, metal.roughness)
+        } else if let Some(dielectric) = material.as_any().downcast_ref::<DielectricMaterial>() {
+            ([albedo.r, albedo.g, albedo.b, albedo.a], MATERIAL_TYPE_DIELECTRIC, dielectric.ior)
+        } else {
+            ([albedo.r, albedo.g, albedo.b, albedo.a], MATERIAL_TYPE_LAMBERTIAN, material.get_properties().roughness)
+        };
+        Self {
+            color,
+            material_type,
+            smoothness,
+            _padding: [0,0],
+        }
+    }
+}
+
+impl PointLightGpu {
+    /// Pack a point light for upload
+    #[must_use]
+    pub fn from_light(light: &PointLight) -> Self {
+        Self {
+            position: [light.position.x, light.position.y, light.position.z, 0.0],
+            color: [light.color.r, light.color.g, light.color.b, light.color.a],
+            intensity: light.radiometric_intensity(),
+            range: light.range,
+            _padding: [0, 0],
+        }
+    }
+}
+
+/// Pack point lights followed by every instance of the instanced lights, in
+/// the layout of the GPU light buffer.
+///
+/// Each instanced light is packed once and copied to its instance positions.
+pub fn pack_point_lights(lights: &[Arc<PointLight>], instanced: &[InstancedLight]) -> Vec<PointLightGpu> {
+    let instance_count: usize = instanced.iter().map(InstancedLight::len).sum();
+    let mut packed = Vec::with_capacity(lights.len() + instance_count);
+    packed.extend(lights.iter().map(|light| PointLightGpu::from_light(light)));
+    for instanced_light in instanced {
+        let template = PointLightGpu::from_light(&instanced_light.light);
+        packed.extend(instanced_light.positions.iter().map(|position| PointLightGpu {
+            position: [position.x, position.y, position.z, 0.0],
+            ..template
+        }));
+    }
+    packed
+}
+
+/// Spheres, boxes, triangle meshes, materials and point lights packed in the
+/// layout of the GPU buffers, ready for upload.
+///
+/// Materials are deduplicated by `Arc`; index 0 is always the default
+/// material used by objects without one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpuSceneData {
+    /// Spheres, in the order they were passed
+    pub spheres: Vec<SphereGpu>,
+    /// Boxes, in the order they were passed
+    pub boxes: Vec<BoxGpu>,
+    /// Vertices of every mesh
+    pub vertices: Vec<VertexGpu>,
+    /// Triangles of every mesh, indexing `vertices`
+    pub triangles: Vec<TriangleGpu>,
+    /// Meshes, each a range of `triangles`
+    pub meshes: Vec<MeshGpu>,
+    /// Cylinders, in the order they were passed
+    pub cylinders: Vec<CylinderGpu>,
+    /// Cones, in the order they were passed
+    pub cones: Vec<ConeGpu>,
+    /// Distinct materials referenced by the objects' `material_index`
+    pub materials: Vec<MaterialGpu>,
+    /// Point lights followed by the instanced light instances
+    pub lights: Vec<PointLightGpu>,
+}
+
+impl GpuSceneData {
+    /// Pack spheres, the GPU-supported scene objects, point lights and every
+    /// instance of the instanced lights.
+    ///
+    /// Cubes in `objects` become boxes, triangles are gathered into one mesh
+    /// per set of shadow flags and cylinders and cones are packed as they
+    /// are; other objects, including spheres, are skipped, as spheres are
+    /// taken from `spheres`.
+    pub fn pack(
+        spheres: &[Arc<Sphere>],
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<PointLight>],
+        instanced: &[InstancedLight],
+    ) -> Self {
+        let mut material_map: HashMap<usize, u32> = HashMap::new(); // Using usize from Arc pointer for Material
+
+        // Add a default material for objects without one, or if lookup fails
+        let mut materials = vec![MaterialGpu {
+            color: [1.0, 0.0, 1.0, 1.0], // Magenta for error/default
+            material_type: 0, // Lambertian
+            smoothness: 0.5,
+            _padding: [0,0],
+        }];
+        let default_material_idx = 0u32;
+
+        let mut material_index = |material: &Option<Arc<dyn Material>>| {
+            if let Some(mat_arc) = material {
+                // Use data pointer of Arc as key for uniqueness.
+                // Arc::as_ptr returns *const dyn Material (fat pointer), we need just the data part.
+                let mat_ptr = Arc::as_ptr(mat_arc) as *const () as usize;
+                *material_map.entry(mat_ptr).or_insert_with(|| {
+                    materials.push(MaterialGpu::from_material(mat_arc.as_ref()));
+                    materials.len() as u32 - 1
+                })
+            } else {
+                default_material_idx
+            }
+        };
+
+        let spheres = spheres
+            .iter()
+            .map(|sphere| {
+                let center = sphere.world_center();
+                SphereGpu {
+                    center: [center.x, center.y, center.z, 0.0], // w = 0 for position vector
+                    radius: sphere.world_radius(),
+                    material_index: material_index(&sphere.material),
+                    shadow_flags: sphere.shadow_flags.bits(),
+                    _padding: 0,
+                }
+            })
+            .collect();
+        let mut boxes = Vec::new();
+        // Vertices and triangles by shadow flags, as flags are set per mesh
+        let mut triangle_groups: BTreeMap<u32, (Vec<VertexGpu>, Vec<TriangleGpu>)> = BTreeMap::new();
+        let mut cylinders = Vec::new();
+        let mut cones = Vec::new();
+        for object in objects {
+            let flags = object.shadow_flags().bits();
+            let object = object.as_any();
+            if let Some(cube) = object.downcast_ref::<Cube>() {
+                boxes.push(BoxGpu { shadow_flags: flags, ..BoxGpu::from_cube(cube, material_index(&cube.material)) });
+            } else if let Some(triangle) = object.downcast_ref::<Triangle>() {
+                let (vertices, triangles) = triangle_groups.entry(flags).or_default();
+                let first = vertices.len() as u32;
+                vertices.extend((0..3).map(|i| VertexGpu::new(triangle.vertices[i], triangle.normals[i])));
+                triangles.push(TriangleGpu {
+                    indices: [first, first + 1, first + 2],
+                    material_index: material_index(&triangle.material),
+                });
+            } else if let Some(cylinder) = object.downcast_ref::<Cylinder>() {
+                let material = material_index(&cylinder.material);
+                cylinders.push(CylinderGpu { shadow_flags: flags, ..CylinderGpu::from_cylinder(cylinder, material) });
+            } else if let Some(cone) = object.downcast_ref::<Cone>() {
+                cones.push(ConeGpu { shadow_flags: flags, ..ConeGpu::from_cone(cone, material_index(&cone.material)) });
+            }
+        }
+
+        let mut data = Self {
+            spheres,
+            boxes,
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+            meshes: Vec::new(),
+            cylinders,
+            cones,
+            materials,
+            lights: pack_point_lights(lights, instanced),
+        };
+        // Triangle vertices are in world space, as the CPU raytracer treats them
+        for (flags, (vertices, triangles)) in triangle_groups {
+            data.push_mesh(&vertices, &triangles, Mat4::IDENTITY);
+            if let Some(mesh) = data.meshes.last_mut() {
+                mesh.shadow_flags = flags;
+            }
+        }
+        data
+    }
+
+    /// Get the kinds of objects besides spheres present
+    #[must_use]
+    pub const fn primitives(&self) -> GpuPrimitives {
+        GpuPrimitives {
+            boxes: !self.boxes.is_empty(),
+            meshes: !self.meshes.is_empty(),
+            cylinders: !self.cylinders.is_empty(),
+            cones: !self.cones.is_empty(),
+        }
+    }
+
+    /// Append a mesh whose triangles index into `vertices` and whose material
+    /// indices refer to `materials`, drawn with a local-to-world transform
+    pub fn push_mesh(&mut self, vertices: &[VertexGpu], triangles: &[TriangleGpu], transform: Mat4) {
+        let vertex_offset = self.vertices.len() as u32;
+        self.meshes.push(MeshGpu {
+            inv_transform: transform.inverse().to_cols_array_2d(),
+            triangle_offset: self.triangles.len() as u32,
+            triangle_count: triangles.len() as u32,
+            shadow_flags: DEFAULT_SHADOW_FLAGS,
+            _padding: 0,
+        });
+        self.vertices.extend_from_slice(vertices);
+        self.triangles.extend(triangles.iter().map(|triangle| TriangleGpu {
+            indices: triangle.indices.map(|index| index + vertex_offset),
+            ..*triangle
+        }));
+    }
+}
+
+/// Per-frame settings for the raytracing compute pass
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RenderParamsGpu {
+    /// Bounce limit for the path tracing loop
+    pub max_depth: u32,
+    /// Seeds the shader's random numbers
+    pub frame_index: u32,
+    /// Camera exposure multiplier times [`GpuRendererConfig::exposure`],
+    /// applied before the output is clamped
+    pub exposure: f32,
+    /// Frames already summed in the accumulation texture; 0 starts over
+    pub accumulated_samples: u32,
+    /// Background color looking straight down
+    pub background_bottom: [f32; 4],
+    /// Background color looking straight up
+    pub background_top: [f32; 4],
+    /// [`ToneMapper`] applied after the exposure: 0 clips, 1 is Reinhard, 2 ACES filmic
+    pub tonemap: u32,
+    _padding: [u32; 3],
+}
+
+impl RenderParamsGpu {
+    const fn new(config: &GpuRendererConfig, frame_index: u32, exposure: f32, accumulated_samples: u32) -> Self {
+        let bottom = config.background.bottom;
+        let top = config.background.top;
+        Self {
+            max_depth: config.max_depth,
+            frame_index,
+            exposure: exposure * config.exposure,
+            accumulated_samples,
+            background_bottom: [bottom.r, bottom.g, bottom.b, bottom.a],
+            background_top: [top.r, top.g, top.b, top.a],
+            tonemap: match config.tonemap {
+                ToneMapper::None => 0,
+                ToneMapper::Reinhard => 1,
+                ToneMapper::AcesFilmic => 2,
+            },
+            _padding: [0; 3],
+        }
+    }
+}
 
 // END NEW GPU DATA STRUCTURES
 
+/// How [`StorageBuffer::write`] changed a buffer, ordered by the work it implies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum BufferUpdate {
+    /// The contents were already in the buffer
+    Unchanged,
+    /// New contents of the same size were written in place
+    Written,
+    /// The bound range changed or the buffer was reallocated, so bind groups
+    /// using it must be recreated
+    Rebind,
+}
+
+/// Read-only storage buffer that is updated in place with `queue.write_buffer`,
+/// growing its allocation only when new contents do not fit
+struct StorageBuffer {
+    label: &'static str,
+    buffer: wgpu::Buffer,
+    /// Bytes last written; the compute pass binds exactly this range, so the
+    /// shader's `arrayLength` counts only them
+    contents: Vec<u8>,
+}
+
+impl StorageBuffer {
+    /// Create a buffer holding `contents`, which must not be empty
+    fn new<T: bytemuck::Pod>(device: &wgpu::Device, label: &'static str, contents: &[T]) -> Self {
+        let contents: &[u8] = bytemuck::cast_slice(contents);
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+        Self { label, buffer, contents: contents.to_vec() }
+    }
+
+    /// Replace the contents, which must not be empty, if they differ from the last write
+    fn write<T: bytemuck::Pod>(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, contents: &[T]) -> BufferUpdate {
+        let contents: &[u8] = bytemuck::cast_slice(contents);
+        if contents == self.contents.as_slice() {
+            return BufferUpdate::Unchanged;
+        }
+
+        let update = if contents.len() as u64 > self.buffer.size() {
+            // Round up so a slowly growing scene does not reallocate every frame
+            self.buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(self.label),
+                size: (contents.len() as u64).next_power_of_two(),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            BufferUpdate::Rebind
+        } else if contents.len() == self.contents.len() {
+            BufferUpdate::Written
+        } else {
+            BufferUpdate::Rebind
+        };
+        queue.write_buffer(&self.buffer, 0, contents);
+        self.contents.clear();
+        self.contents.extend_from_slice(contents);
+        update
+    }
+
+    /// Bind the range holding the last written contents
+    const fn binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.buffer,
+            offset: 0,
+            size: wgpu::BufferSize::new(self.contents.len() as u64),
+        })
+    }
+}
+
+/// Create a texture the raytracing pass writes finished frames into
+fn create_output_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Output Texture (Rgba8Unorm)"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+/// Create one of the two textures the raytracing pass sums frames into, alternately
+/// reading one and writing the other
+fn create_accumulation_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Accumulation Texture (Rgba32Float)"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba32Float,
+        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+/// Create a bind group sampling each output texture in the blit pass
+fn create_blit_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    output_texture_views: &[wgpu::TextureView],
+    sampler: &wgpu::Sampler,
+    label: &str,
+) -> Vec<wgpu::BindGroup> {
+    output_texture_views
+        .iter()
+        .map(|view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            })
+        })
+        .collect()
+}
+
+/// Raytracing compute pipeline for one set of [`GpuPrimitives`]
+struct RaytracePipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+/// Layout entry for a read-only storage buffer of `T` in the raytracing pass
+const fn storage_layout_entry<T>(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<T>() as u64),
+        },
+        count: None,
+    }
+}
+
+/// Build the raytracing shader, bind group layout and pipeline for a set of
+/// object kinds; kinds that are left out get no bindings
+fn create_raytrace_pipeline(device: &wgpu::Device, primitives: GpuPrimitives) -> Result<RaytracePipeline> {
+    let compute_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Raytrace Shader Module"),
+        source: wgpu::ShaderSource::Wgsl(primitives.shader_source()?.into()),
+    });
+
+    let mut entries = vec![
+        wgpu::BindGroupLayoutEntry { // CameraGpu
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<CameraGpu>() as u64),
+            },
+            count: None,
+        },
+        storage_layout_entry::<SphereGpu>(1),
+        storage_layout_entry::<MaterialGpu>(2),
+        storage_layout_entry::<PointLightGpu>(3),
+        wgpu::BindGroupLayoutEntry { // Output Texture (Storage)
+            binding: 4,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry { // Render Params
+            binding: 5,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<RenderParamsGpu>() as u64),
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry { // Previous Accumulation
+            binding: 12,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        },
+        wgpu::BindGroupLayoutEntry { // Accumulation (Storage)
+            binding: 13,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::StorageTexture {
+                access: wgpu::StorageTextureAccess::WriteOnly,
+                format: wgpu::TextureFormat::Rgba32Float,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
+            count: None,
+        },
+    ];
+    if primitives.boxes {
+        entries.push(storage_layout_entry::<BoxGpu>(6));
+    }
+    if primitives.meshes {
+        entries.push(storage_layout_entry::<VertexGpu>(7));
+        entries.push(storage_layout_entry::<TriangleGpu>(8));
+        entries.push(storage_layout_entry::<MeshGpu>(9));
+    }
+    if primitives.cylinders {
+        entries.push(storage_layout_entry::<CylinderGpu>(10));
+    }
+    if primitives.cones {
+        entries.push(storage_layout_entry::<ConeGpu>(11));
+    }
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Raytrace Compute Bind Group Layout"),
+        entries: &entries,
+    });
+
+    let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Raytrace Compute Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Raytrace Compute Pipeline"),
+        layout: Some(&compute_pipeline_layout),
+        module: &compute_shader_module,
+        entry_point: "main",
+    });
+
+    Ok(RaytracePipeline { bind_group_layout, pipeline })
+}
+
 /// GPU-based renderer using wgpu
 pub struct GpuRenderer {
     config: GpuRendererConfig,
     pub device: Arc<wgpu::Device>,
     pub queue: Arc<wgpu::Queue>,
+    /// Output size and blit format, also when headless
     pub surface_config: wgpu::SurfaceConfiguration,
-    pub surface: Arc<wgpu::Surface<'static>>,
-    
+    /// Window surface; `None` when rendering headless
+    pub surface: Option<Arc<wgpu::Surface<'static>>>,
+
     // Compute pass resources
     camera_buffer: wgpu::Buffer,
-    sphere_buffer: wgpu::Buffer,
-    material_buffer: wgpu::Buffer,
-    light_buffer: wgpu::Buffer, // Added for point lights
-    output_texture: wgpu::Texture,          // Stores the result of the compute shader (Rgba8Unorm)
-    output_texture_view: wgpu::TextureView,
-    compute_pipeline: wgpu::ComputePipeline,
-    compute_bind_group_layout: wgpu::BindGroupLayout, // Renamed for clarity
-    compute_bind_group: wgpu::BindGroup,           // Renamed for clarity
+    sphere_buffer: StorageBuffer,
+    box_buffer: StorageBuffer,
+    vertex_buffer: StorageBuffer,
+    triangle_buffer: StorageBuffer,
+    mesh_buffer: StorageBuffer,
+    cylinder_buffer: StorageBuffer,
+    cone_buffer: StorageBuffer,
+    material_buffer: StorageBuffer,
+    light_buffer: StorageBuffer, // Added for point lights
+    params_buffer: wgpu::Buffer, // Per-frame render parameters (bounce limit, RNG seed)
+    frame_index: u32,
+    instanced_lights: Vec<InstancedLight>, // Expanded into the light buffer on upload
+    output_textures: Vec<wgpu::Texture>, // Store the result of the compute shader (Rgba8Unorm); two when double buffering
+    output_texture_views: Vec<wgpu::TextureView>,
+    output_index: usize, // Output texture holding the last rendered frame
+    output_written: bool, // Whether the output texture at output_index holds a frame yet
+    accumulation_textures: [wgpu::Texture; 2], // Running radiance sums (Rgba32Float), read and written alternately
+    accumulation_texture_views: [wgpu::TextureView; 2],
+    accumulated_samples: u32, // Frames summed since the last reset
+    accumulation_index: usize, // Accumulation texture the next frame reads from
+    scene_version: Option<u64>, // Version of the scene passed to the render calls, if known
+    uploaded_scene_version: Option<u64>, // Scene version whose objects and materials are in the buffers
+    raytrace_pipelines: HashMap<GpuPrimitives, RaytracePipeline>, // Built on first use of each set of kinds
+    bound_primitives: GpuPrimitives, // Kinds bound by compute_bind_groups
+    compute_bind_groups: Vec<[wgpu::BindGroup; 2]>, // Per output texture, one per accumulation texture read
 
     // Blit pass resources (for copying output_texture to swap chain)
     sampler: wgpu::Sampler,
     blit_bind_group_layout: wgpu::BindGroupLayout,
-    blit_bind_group: wgpu::BindGroup,
+    blit_bind_groups: Vec<wgpu::BindGroup>, // One per output texture
     blit_pipeline: wgpu::RenderPipeline,
+    thumbnail_pipeline: wgpu::RenderPipeline, // Blits output_texture into a small readback texture
 }
 
 impl GpuRenderer {
-    /// Create a new GPU renderer
+    /// Create a new GPU renderer.
+    ///
+    /// Without a surface the renderer is headless: frames are rendered into the
+    /// output texture with [`GpuRenderer::render_to_texture`] and read back with
+    /// [`GpuRenderer::read_pixels`], and `surface_config` only gives their size.
     pub async fn new(
         config: &GpuRendererConfig,
         device: Arc<wgpu::Device>,
         queue: Arc<wgpu::Queue>,
         surface_config: wgpu::SurfaceConfiguration,
-        surface: Arc<wgpu::Surface<'static>>,
+        surface: Option<Arc<wgpu::Surface<'static>>>,
         _window: Option<Arc<Window>> // May be needed for aspect ratio, etc.
     ) -> anyhow::Result<Self> {
         info!("Initializing GpuRenderer");
@@ -128,127 +988,53 @@ impl GpuRenderer {
         });
 
         let initial_spheres_gpu: Vec<SphereGpu> = vec![SphereGpu {
-            center: [0.0, 0.0, 0.0, 0.0], radius: 1.0, material_index: 0, _padding: [0,0]
+            center: [0.0, 0.0, 0.0, 0.0], radius: 1.0, material_index: 0, shadow_flags: DEFAULT_SHADOW_FLAGS, _padding: 0
         }; 1];
-        let sphere_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Sphere Buffer (Initial)"),
-            contents: bytemuck::cast_slice(&initial_spheres_gpu),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        });
-        
+        let sphere_buffer = StorageBuffer::new(&device, "Sphere Buffer", &initial_spheres_gpu);
+
+        // Only spheres are bound until a frame contains other kinds; these are never read
+        let box_buffer = StorageBuffer::new(&device, "Box Buffer", &[BoxGpu::zeroed()]);
+        let cylinder_buffer = StorageBuffer::new(&device, "Cylinder Buffer", &[CylinderGpu::zeroed()]);
+        let cone_buffer = StorageBuffer::new(&device, "Cone Buffer", &[ConeGpu::zeroed()]);
+        let vertex_buffer = StorageBuffer::new(&device, "Vertex Buffer", &[VertexGpu::zeroed()]);
+        let triangle_buffer = StorageBuffer::new(&device, "Triangle Buffer", &[TriangleGpu::zeroed()]);
+        let mesh_buffer = StorageBuffer::new(&device, "Mesh Buffer", &[MeshGpu::zeroed()]);
+
         let initial_materials_gpu: Vec<MaterialGpu> = vec![MaterialGpu {
             color: [0.8, 0.8, 0.8, 1.0], material_type: 0, smoothness: 0.5, _padding: [0,0]
         }; 1];
-        let material_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Material Buffer (Initial)"),
-            contents: bytemuck::cast_slice(&initial_materials_gpu),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        });
+        let material_buffer = StorageBuffer::new(&device, "Material Buffer", &initial_materials_gpu);
 
         let initial_lights_gpu: Vec<PointLightGpu> = vec![PointLightGpu {
             position: [0.0, 10.0, 0.0, 0.0], color: [1.0, 1.0, 1.0, 1.0], intensity: 100.0, range: 50.0, _padding: [0,0]
         }; 1];
-        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Light Buffer (Initial)"),
-            contents: bytemuck::cast_slice(&initial_lights_gpu),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let output_texture_descriptor = wgpu::TextureDescriptor {
-            label: Some("Output Texture (Rgba8Unorm)"),
-            size: wgpu::Extent3d {
-                width: surface_config.width,
-                height: surface_config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC, // COPY_SRC might not be needed if only blitting
-            view_formats: &[],
-        };
-        let output_texture = device.create_texture(&output_texture_descriptor);
-        let output_texture_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let light_buffer = StorageBuffer::new(&device, "Light Buffer", &initial_lights_gpu);
 
-        let compute_shader_source = include_str!("shaders/raytrace.wgsl");
-        let compute_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Raytrace Shader Module"),
-            source: wgpu::ShaderSource::Wgsl(compute_shader_source.into()),
+        let params_gpu = RenderParamsGpu::new(config, 0, 1.0, 0);
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Render Params Buffer"),
+            contents: bytemuck::bytes_of(&params_gpu),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        let compute_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Raytrace Compute Bind Group Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry { // CameraGpu
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<CameraGpu>() as u64),
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry { // Spheres
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<SphereGpu>() as u64),
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry { // Materials
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<MaterialGpu>() as u64),
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry { // Lights
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<PointLightGpu>() as u64),
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry { // Output Texture (Storage)
-                    binding: 4,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::StorageTexture {
-                        access: wgpu::StorageTextureAccess::WriteOnly,
-                        format: wgpu::TextureFormat::Rgba8Unorm,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-            ],
-        });
+        let output_texture_count = if config.double_buffer_output { 2 } else { 1 };
+        let output_textures: Vec<wgpu::Texture> = (0..output_texture_count)
+            .map(|_| create_output_texture(&device, surface_config.width, surface_config.height))
+            .collect();
+        let output_texture_views: Vec<wgpu::TextureView> = output_textures
+            .iter()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            .collect();
 
-        let compute_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Raytrace Compute Pipeline Layout"),
-            bind_group_layouts: &[&compute_bind_group_layout],
-            push_constant_ranges: &[],
-        });
+        let accumulation_textures = [0, 1].map(|_| create_accumulation_texture(&device, surface_config.width, surface_config.height));
+        let accumulation_texture_views = accumulation_textures.each_ref().map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
 
-        let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Raytrace Compute Pipeline"),
-            layout: Some(&compute_pipeline_layout),
-            module: &compute_shader_module,
-            entry_point: "main",
-        });
+        let bound_primitives = GpuPrimitives::NONE;
+        let raytrace_pipeline = create_raytrace_pipeline(&device, bound_primitives)?;
 
-        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let compute_bind_groups = output_texture_views.iter().map(|output_texture_view| [0, 1].map(|read| device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Raytrace Compute Bind Group"),
-            layout: &compute_bind_group_layout,
+            layout: &raytrace_pipeline.bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -256,22 +1042,35 @@ impl GpuRenderer {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: sphere_buffer.as_entire_binding(),
+                    resource: sphere_buffer.binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: material_buffer.as_entire_binding(),
+                    resource: material_buffer.binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 3,
-                    resource: light_buffer.as_entire_binding(),
+                    resource: light_buffer.binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 4,
-                    resource: wgpu::BindingResource::TextureView(&output_texture_view),
+                    resource: wgpu::BindingResource::TextureView(output_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 12,
+                    resource: wgpu::BindingResource::TextureView(&accumulation_texture_views[read]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 13,
+                    resource: wgpu::BindingResource::TextureView(&accumulation_texture_views[1 - read]),
                 },
             ],
-        });
+        }))).collect();
+        let raytrace_pipelines = HashMap::from([(bound_primitives, raytrace_pipeline)]);
 
         // --- Blit Pass Resources ---
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -307,21 +1106,8 @@ impl GpuRenderer {
             ],
         });
 
-        let blit_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Blit Bind Group"),
-            layout: &blit_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&output_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-            ],
-        });
-        
+        let blit_bind_groups = create_blit_bind_groups(&device, &blit_bind_group_layout, &output_texture_views, &sampler, "Blit Bind Group");
+
         let blit_shader_source = include_str!("shaders/blit.wgsl");
         let blit_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Blit Shader Module"),
@@ -334,19 +1120,87 @@ impl GpuRenderer {
             push_constant_ranges: &[],
         });
 
-        let blit_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Blit Render Pipeline"),
-            layout: Some(&blit_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &blit_shader_module,
-                entry_point: "vs_main",
-                buffers: &[], // No vertex buffers, vertices generated in shader
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &blit_shader_module,
-                entry_point: "fs_main",
+        let blit_pipeline = Self::create_blit_pipeline(
+            &device,
+            &blit_pipeline_layout,
+            &blit_shader_module,
+            surface_config.format, // Target the swap chain format
+            "Blit Render Pipeline",
+        );
+
+        // Same blit, but into a fixed RGBA target for thumbnail readback
+        let thumbnail_pipeline = Self::create_blit_pipeline(
+            &device,
+            &blit_pipeline_layout,
+            &blit_shader_module,
+            THUMBNAIL_FORMAT,
+            "Thumbnail Blit Pipeline",
+        );
+
+        Ok(Self {
+            config: config.clone(),
+            device,
+            queue,
+            surface_config,
+            surface,
+            camera_buffer,
+            sphere_buffer,
+            box_buffer,
+            vertex_buffer,
+            triangle_buffer,
+            mesh_buffer,
+            cylinder_buffer,
+            cone_buffer,
+            material_buffer,
+            light_buffer,
+            params_buffer,
+            frame_index: 0,
+            instanced_lights: Vec::new(),
+            output_textures,
+            output_texture_views,
+            output_index: 0,
+            output_written: false,
+            accumulation_textures,
+            accumulation_texture_views,
+            accumulated_samples: 0,
+            accumulation_index: 0,
+            scene_version: None,
+            uploaded_scene_version: None,
+            raytrace_pipelines,
+            bound_primitives,
+            compute_bind_groups,
+            sampler,
+            blit_bind_group_layout,
+            blit_bind_groups,
+            blit_pipeline,
+            thumbnail_pipeline,
+        })
+    }
+
+    /// Create a fullscreen-triangle pipeline that samples the output texture into `format`.
+    ///
+    /// The output texture is linear, so non-sRGB targets get a fragment shader
+    /// that encodes it; either way the stored pixels are sRGB.
+    fn create_blit_pipeline(
+        device: &Device,
+        layout: &wgpu::PipelineLayout,
+        module: &wgpu::ShaderModule,
+        format: TextureFormat,
+        label: &str,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module,
+                entry_point: "vs_main",
+                buffers: &[], // No vertex buffers, vertices generated in shader
+            },
+            fragment: Some(wgpu::FragmentState {
+                module,
+                entry_point: if format.is_srgb() { "fs_main" } else { "fs_main_encode_srgb" },
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_config.format, // Target the swap chain format
+                    format,
                     blend: Some(wgpu::BlendState::REPLACE), // Opaque
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -367,27 +1221,6 @@ impl GpuRenderer {
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        });
-
-        Ok(Self {
-            config: config.clone(),
-            device,
-            queue,
-            surface_config,
-            surface,
-            camera_buffer,
-            sphere_buffer,
-            material_buffer,
-            light_buffer,
-            output_texture,
-            output_texture_view,
-            compute_pipeline,
-            compute_bind_group_layout, // Renamed
-            compute_bind_group,      // Renamed
-            sampler,
-            blit_bind_group_layout,
-            blit_bind_group,
-            blit_pipeline,
         })
     }
 
@@ -401,7 +1234,7 @@ impl GpuRenderer {
 
         // Create surface
         let surface = instance.create_surface(window.clone())?;
-        
+
         // Request adapter
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
@@ -426,14 +1259,8 @@ impl GpuRenderer {
 
         // Get surface capabilities
         let surface_caps = surface.get_capabilities(&adapter);
-        
-        // Choose the first supported format, or fall back to the first one
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .find(|f| **f == self.config.format)
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
+
+        let surface_format = choose_surface_format(self.config.format, &surface_caps.formats);
 
         // Create surface configuration
         let surface_config = SurfaceConfiguration {
@@ -452,21 +1279,183 @@ impl GpuRenderer {
         // Store the initialized components
         self.device = Arc::new(device);
         self.queue = Arc::new(queue);
-        self.surface = Arc::new(surface);
+        self.surface = Some(Arc::new(surface));
         self.surface_config = surface_config;
 
         Ok(())
     }
 
-    /// Render a frame
+    /// Render a frame, blitting it into a swap chain texture if one is given.
+    ///
+    /// With [`GpuRendererConfig::double_buffer_output`] the texture receives the
+    /// frame rendered by the previous call instead, once there is one.
     pub fn render(
         &mut self,
-        target_swap_chain_texture: &wgpu::Texture, // This is the actual swap chain texture
+        target_swap_chain_texture: Option<&wgpu::Texture>, // None leaves the frame in the output texture
         spheres: &[Arc<Sphere>], // Pass spheres directly instead of Scene
+        objects: &[Arc<dyn SceneObject>], // Cubes and triangles among them are drawn too
         lights: &[Arc<PointLight>], // Added lights parameter
         renderer_camera: &RendererCamera
     ) -> anyhow::Result<()> {
-        // 1. Update Camera Buffer
+        self.upload_scene(spheres, objects, lights);
+        self.write_frame_uniforms(renderer_camera);
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+        let target_swap_chain_view =
+            target_swap_chain_texture.map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        if let Some(target_swap_chain_view) = &target_swap_chain_view {
+            if self.config.double_buffer_output && self.output_written {
+                // Show the previous frame while the raytrace writes the other output texture
+                self.encode_blit(&mut encoder, target_swap_chain_view, None, wgpu::LoadOp::Clear(wgpu::Color::BLACK));
+                self.encode_raytrace(&mut encoder);
+                self.queue.submit(std::iter::once(encoder.finish()));
+                return Ok(());
+            }
+        }
+        self.encode_raytrace(&mut encoder);
+        if let Some(target_swap_chain_view) = &target_swap_chain_view {
+            self.encode_blit(&mut encoder, target_swap_chain_view, None, wgpu::LoadOp::Clear(wgpu::Color::BLACK));
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        Ok(())
+    }
+
+    /// Raytrace a frame into the output texture without drawing it to any target.
+    ///
+    /// The frame can be read back with [`GpuRenderer::read_thumbnail`] and shown
+    /// later with [`GpuRenderer::present`], so capture and presentation can be
+    /// scheduled independently of rendering.
+    ///
+    /// # Errors
+    ///
+    /// Never fails at present; the `Result` keeps the signature in line with
+    /// [`GpuRenderer::render`].
+    pub fn render_to_texture(
+        &mut self,
+        spheres: &[Arc<Sphere>],
+        objects: &[Arc<dyn SceneObject>], // Cubes and triangles among them are drawn too
+        lights: &[Arc<PointLight>],
+        renderer_camera: &RendererCamera,
+    ) -> anyhow::Result<()> {
+        self.upload_scene(spheres, objects, lights);
+        self.write_frame_uniforms(renderer_camera);
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render To Texture Encoder"),
+        });
+        self.encode_raytrace(&mut encoder);
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        Ok(())
+    }
+
+    /// Draw the last rendered frame to the next swap chain texture and present it.
+    ///
+    /// Returns `Ok(false)` if the surface was not ready or the renderer is
+    /// headless and nothing was presented; see [`GpuRenderer::acquire_frame`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if acquiring the surface texture runs out of memory.
+    pub fn present(&self) -> anyhow::Result<bool> {
+        if self.is_headless() {
+            return Ok(false);
+        }
+        let Some(frame) = self.acquire_frame()? else {
+            return Ok(false);
+        };
+
+        let target_view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Present Encoder"),
+        });
+        self.encode_blit(&mut encoder, &target_view, None, wgpu::LoadOp::Clear(wgpu::Color::BLACK));
+        self.queue.submit(std::iter::once(encoder.finish()));
+        frame.present();
+
+        Ok(true)
+    }
+
+    /// Render several viewports, each with its own camera, into one target.
+    ///
+    /// The target is cleared to black, then every viewport is raytraced at the
+    /// output texture resolution and blitted into its rectangle. Rectangles are
+    /// clipped to the target; viewports later in the slice draw on top.
+    ///
+    /// # Errors
+    ///
+    /// Never fails at present; the `Result` keeps the signature in line with
+    /// [`GpuRenderer::render`].
+    pub fn render_viewports(
+        &mut self,
+        target_swap_chain_texture: &wgpu::Texture,
+        spheres: &[Arc<Sphere>],
+        objects: &[Arc<dyn SceneObject>], // Cubes and triangles among them are drawn too
+        lights: &[Arc<PointLight>],
+        viewports: &[Viewport],
+    ) -> anyhow::Result<()> {
+        self.upload_scene(spheres, objects, lights);
+
+        let target_swap_chain_view = target_swap_chain_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let target_width = target_swap_chain_texture.width();
+        let target_height = target_swap_chain_texture.height();
+
+        let mut load = wgpu::LoadOp::Clear(wgpu::Color::BLACK);
+        for viewport in viewports {
+            let Some(rect) = viewport.rect.clipped(target_width, target_height) else {
+                continue;
+            };
+
+            // Buffer writes take effect at the next submit, so each camera needs its own submission.
+            // Viewports share the accumulation textures, so none of them accumulates
+            self.reset_accumulation();
+            self.write_frame_uniforms(&viewport.camera);
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Viewport Render Encoder"),
+            });
+            self.encode_raytrace(&mut encoder);
+            self.encode_blit(&mut encoder, &target_swap_chain_view, Some(rect), load);
+            self.queue.submit(std::iter::once(encoder.finish()));
+            load = wgpu::LoadOp::Load;
+        }
+
+        // Nothing was drawn; still clear the target so stale contents are not presented
+        if matches!(load, wgpu::LoadOp::Clear(_)) {
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Viewport Clear Encoder"),
+            });
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Viewport Clear Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target_swap_chain_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.queue.submit(std::iter::once(encoder.finish()));
+        }
+
+        Ok(())
+    }
+
+    /// Write the camera and per-frame render parameters for the next submission
+    fn write_frame_uniforms(&mut self, renderer_camera: &RendererCamera) {
+        let jittered;
+        let renderer_camera = if self.config.taa_jitter {
+            jittered = renderer_camera.with_jitter(renderer_camera.jitter + self.frame_jitter());
+            &jittered
+        } else {
+            renderer_camera
+        };
         let view_matrix = renderer_camera.view_matrix();
         let projection_matrix = renderer_camera.projection_matrix();
         let camera_world_pos = renderer_camera.transform.position;
@@ -479,195 +1468,266 @@ impl GpuRenderer {
         };
         self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::bytes_of(&camera_gpu));
 
-        // 2. Update Sphere and Material Buffers
-        let mut material_map: HashMap<usize, u32> = HashMap::new(); // Using usize from Arc pointer for Material
-        let mut materials_gpu_list: Vec<MaterialGpu> = Vec::new();
-        let mut spheres_gpu_list: Vec<SphereGpu> = Vec::new();
+        // Update render params; the frame index varies the shader's random sequence
+        let params_gpu = RenderParamsGpu::new(
+            &self.config,
+            self.frame_index,
+            renderer_camera.exposure_multiplier(),
+            self.accumulated_samples,
+        );
+        self.queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params_gpu));
+        self.frame_index = self.frame_index.wrapping_add(1);
+        self.accumulated_samples = self.accumulated_samples.saturating_add(1);
+    }
 
-        // Add a default material for objects without one, or if lookup fails
-        let default_material_gpu = MaterialGpu {
-            color: [1.0, 0.0, 1.0, 1.0], // Magenta for error/default
-            material_type: 0, // Lambertian
-            smoothness: 0.5,
-            _padding: [0,0],
-        };
-        materials_gpu_list.push(default_material_gpu);
-        let default_material_idx = 0u32;
+    /// Start averaging samples anew with the next frame.
+    ///
+    /// Every frame adds one sample per pixel to a running sum and outputs the
+    /// average, so a still image converges over time. Call this when the camera
+    /// moves; resizing and uploading a different scene reset it automatically.
+    pub const fn reset_accumulation(&mut self) {
+        self.accumulated_samples = 0;
+    }
 
-        for sphere_arc in spheres { // Use passed spheres slice
-            let sphere_item: &Sphere = sphere_arc; // Deref Arc<Sphere> to &Sphere
+    /// Get the sub-pixel jitter, in NDC, that [`GpuRendererConfig::taa_jitter`]
+    /// adds to the camera for the next frame.
+    ///
+    /// It follows the Halton(2, 3) sequence by the number of accumulated samples,
+    /// so it restarts whenever the accumulation does.
+    #[must_use]
+    pub fn frame_jitter(&self) -> glam::Vec2 {
+        RendererCamera::halton_jitter(self.accumulated_samples, TAA_JITTER_CYCLE, self.config.width, self.config.height)
+    }
 
-            let material_idx = if let Some(mat_arc) = &sphere_item.material {
-                // Use data pointer of Arc as key for uniqueness.
-                // Arc::as_ptr returns *const dyn Material (fat pointer), we need just the data part.
-                let mat_ptr = Arc::as_ptr(mat_arc) as *const () as usize; 
-                
-                *material_map.entry(mat_ptr).or_insert_with(|| {
-                    let new_idx = materials_gpu_list.len() as u32;
-                    // Attempt to downcast or identify material type
-                    // For now, only supporting Lambertian explicitly from scene.
-                    // In a real scenario, you'd check mat_arc.is::<LambertianMaterial>() etc.
-                    // Or have Material trait provide MaterialGpu directly.
-                    let albedo = mat_arc.albedo(); // From Material trait
-                    let material_gpu = MaterialGpu {
-                        color: [albedo.r, albedo.g, albedo.b, albedo.a],
-                        material_type: 0, // Assume Lambertian
-                        smoothness: mat_arc.get_properties().roughness, // Example
-                        _padding: [0,0],
-                    };
-                    materials_gpu_list.push(material_gpu);
-                    new_idx
-                })
-            } else {
-                default_material_idx
-            };
-            
-            // Assuming sphere_item.center is world-space
-            let sphere_gpu = SphereGpu {
-                center: [sphere_item.center.x, sphere_item.center.y, sphere_item.center.z, 0.0], // w = 0 for position vector
-                radius: sphere_item.radius,
-                material_index: material_idx,
-                _padding: [0,0],
-            };
-            spheres_gpu_list.push(sphere_gpu);
-        }
+    /// Get the number of output textures frames are raytraced into: two with
+    /// [`GpuRendererConfig::double_buffer_output`], otherwise one
+    pub const fn output_texture_count(&self) -> usize {
+        self.output_textures.len()
+    }
 
-        // Recreate sphere buffer if data exists
-        if !spheres_gpu_list.is_empty() {
-            self.sphere_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Sphere Buffer (Dynamic)"),
-                contents: bytemuck::cast_slice(&spheres_gpu_list),
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            });
-        } else {
-            // Handle no spheres: create a minimal buffer to satisfy binding
-            let dummy_sphere = SphereGpu { center: [0.0,0.0,0.0,0.0], radius: 0.0, material_index: 0, _padding: [0,0]};
-             self.sphere_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Sphere Buffer (Empty Placeholder)"),
-                contents: bytemuck::bytes_of(&dummy_sphere),
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            });
+    /// Get the index of the output texture holding the last rendered frame
+    pub const fn output_texture_index(&self) -> usize {
+        self.output_index
+    }
+
+    /// Get the number of samples per pixel averaged into the last rendered frame
+    pub const fn accumulated_samples(&self) -> u32 {
+        self.accumulated_samples
+    }
+
+    /// Set the color of rays that hit nothing, from the next frame on
+    pub fn set_background(&mut self, background: Background) {
+        if self.config.background != background {
+            self.config.background = background;
+            self.reset_accumulation();
         }
+    }
 
-        // Recreate material buffer (even if only default material exists)
-        self.material_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Material Buffer (Dynamic)"),
-            contents: bytemuck::cast_slice(&materials_gpu_list),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        });
-        
-        // 3. Update Light Buffer
-        let mut lights_gpu_list: Vec<PointLightGpu> = Vec::new();
-        
-        for light_arc in lights {
-            let light_item: &PointLight = light_arc;
-            let light_gpu = PointLightGpu {
-                position: [light_item.position.x, light_item.position.y, light_item.position.z, 0.0],
-                color: [light_item.color.r, light_item.color.g, light_item.color.b, light_item.color.a],
-                intensity: light_item.intensity,
-                range: light_item.range, // Use the range from PointLight
-                _padding: [0, 0],
+    /// Set the curve mapping exposed colors to the output, from the next frame on.
+    ///
+    /// Applied to the averaged samples, so accumulation carries on.
+    pub const fn set_tonemap(&mut self, tonemap: ToneMapper) {
+        self.config.tonemap = tonemap;
+    }
+
+    /// Set the instanced lights uploaded with every frame, in addition to the
+    /// point lights passed to the render calls
+    pub fn set_instanced_lights(&mut self, lights: &[InstancedLight]) {
+        self.instanced_lights = lights.to_vec();
+    }
+
+    /// Set the version of the scene whose objects are passed to the render
+    /// calls that follow, such as `rrte_scene::Scene::scene_version`.
+    ///
+    /// While it matches the version last uploaded, objects and materials are
+    /// not packed again. Lights are still compared every frame, as they are
+    /// culled against each camera. Without a version, the packed objects are
+    /// compared against the buffers instead.
+    pub const fn set_scene_version(&mut self, version: u64) {
+        self.scene_version = Some(version);
+    }
+
+    /// Upload spheres, the other kinds of objects, materials and lights that
+    /// changed since the last frame, rebinding them for the compute pass if needed
+    fn upload_scene(&mut self, spheres: &[Arc<Sphere>], objects: &[Arc<dyn SceneObject>], lights: &[Arc<PointLight>]) {
+        let mut update = BufferUpdate::Unchanged;
+
+        if self.scene_version.is_none() || self.scene_version != self.uploaded_scene_version {
+            let scene_data = GpuSceneData::pack(spheres, objects, &[], &[]);
+            let primitives = scene_data.primitives().intersection(self.config.primitives);
+            let (device, queue) = (&self.device, &self.queue);
+
+            // An empty array cannot be bound, so no spheres leaves a zero-radius placeholder
+            update = if scene_data.spheres.is_empty() {
+                self.sphere_buffer.write(device, queue, &[SphereGpu::zeroed()])
+            } else {
+                self.sphere_buffer.write(device, queue, &scene_data.spheres)
             };
-            lights_gpu_list.push(light_gpu);
+            update = update.max(self.material_buffer.write(device, queue, &scene_data.materials));
+
+            // Write the buffers of the other kinds present; the rest are left unbound
+            if primitives.boxes {
+                update = update.max(self.box_buffer.write(device, queue, &scene_data.boxes));
+            }
+            if primitives.meshes {
+                update = update.max(self.vertex_buffer.write(device, queue, &scene_data.vertices));
+                update = update.max(self.triangle_buffer.write(device, queue, &scene_data.triangles));
+                update = update.max(self.mesh_buffer.write(device, queue, &scene_data.meshes));
+            }
+            if primitives.cylinders {
+                update = update.max(self.cylinder_buffer.write(device, queue, &scene_data.cylinders));
+            }
+            if primitives.cones {
+                update = update.max(self.cone_buffer.write(device, queue, &scene_data.cones));
+            }
+            if primitives != self.bound_primitives {
+                if let Entry::Vacant(entry) = self.raytrace_pipelines.entry(primitives) {
+                    match create_raytrace_pipeline(device, primitives) {
+                        Ok(pipeline) => {
+                            entry.insert(pipeline);
+                        }
+                        Err(e) => warn!("Failed to build raytracing pipeline for {primitives:?}: {e}"),
+                    }
+                }
+                // Without a pipeline for the new kinds, keep drawing the ones already bound
+                if self.raytrace_pipelines.contains_key(&primitives) {
+                    self.bound_primitives = primitives;
+                    update = BufferUpdate::Rebind;
+                }
+            }
+            self.uploaded_scene_version = self.scene_version;
         }
-        
+
+        let mut lights_gpu_list = pack_point_lights(lights, &self.instanced_lights);
         // Handle case with no lights - add a default disabled light
         if lights_gpu_list.is_empty() {
-            let default_light = PointLightGpu {
-                position: [0.0, 0.0, 0.0, 0.0],
-                color: [0.0, 0.0, 0.0, 0.0], // Black light (disabled)
-                intensity: 0.0,
-                range: 0.0,
-                _padding: [0, 0],
-            };
-            lights_gpu_list.push(default_light);
+            lights_gpu_list.push(PointLightGpu::zeroed());
         }
-        
-        // Recreate light buffer
-        self.light_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Light Buffer (Dynamic)"),
-            contents: bytemuck::cast_slice(&lights_gpu_list),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        });
+        update = update.max(self.light_buffer.write(&self.device, &self.queue, &lights_gpu_list));
 
-        // Recreate compute bind group
-        self.compute_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Raytrace Compute Bind Group (Recreated)"),
-            layout: &self.compute_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: self.camera_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: self.sphere_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: self.material_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: self.light_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: wgpu::BindingResource::TextureView(&self.output_texture_view),
-                },
-            ],
-        });
+        if update != BufferUpdate::Unchanged {
+            self.reset_accumulation();
+        }
+        if update == BufferUpdate::Rebind {
+            self.rebind_compute("Raytrace Compute Bind Group (Recreated)");
+        }
+    }
 
-        // 3. Create Command Encoder
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder"),
-        });
+    /// Recreate the compute bind groups from the current buffers and textures,
+    /// binding the buffers of the kinds in `bound_primitives`
+    fn rebind_compute(&mut self, label: &str) {
+        let mut entries = vec![
+            wgpu::BindGroupEntry { // Camera
+                binding: 0,
+                resource: self.camera_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry { // Spheres
+                binding: 1,
+                resource: self.sphere_buffer.binding(),
+            },
+            wgpu::BindGroupEntry { // Materials
+                binding: 2,
+                resource: self.material_buffer.binding(),
+            },
+            wgpu::BindGroupEntry { // Lights
+                binding: 3,
+                resource: self.light_buffer.binding(),
+            },
+            wgpu::BindGroupEntry { // Render Params
+                binding: 5,
+                resource: self.params_buffer.as_entire_binding(),
+            },
+        ];
+        let primitives = self.bound_primitives;
+        let optional_buffers = [
+            (primitives.boxes, 6, &self.box_buffer),
+            (primitives.meshes, 7, &self.vertex_buffer),
+            (primitives.meshes, 8, &self.triangle_buffer),
+            (primitives.meshes, 9, &self.mesh_buffer),
+            (primitives.cylinders, 10, &self.cylinder_buffer),
+            (primitives.cones, 11, &self.cone_buffer),
+        ];
+        for (bound, binding, buffer) in optional_buffers {
+            if bound {
+                entries.push(wgpu::BindGroupEntry { binding, resource: buffer.binding() });
+            }
+        }
 
-        // 4. Run Compute Pass (Raytracing)
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Raytrace Compute Pass"),
-                timestamp_writes: None,
-            });
-            compute_pass.set_pipeline(&self.compute_pipeline);
-            compute_pass.set_bind_group(0, &self.compute_bind_group, &[]);
-            
-            // Dispatch based on output texture dimensions
-            // Divide by workgroup size (e.g., 8x8 as defined in raytrace.wgsl)
-            let workgroup_size_x = 8; 
-            let workgroup_size_y = 8;
-            let num_workgroups_x = (self.output_texture.width() + workgroup_size_x - 1) / workgroup_size_x;
-            let num_workgroups_y = (self.output_texture.height() + workgroup_size_y - 1) / workgroup_size_y;
-            compute_pass.dispatch_workgroups(num_workgroups_x, num_workgroups_y, 1);
-        } // compute_pass is dropped, releasing the borrow on encoder
-
-        // 5. Blit Pass (Copy compute output_texture to swap_chain_texture via render pipeline)
-        let target_swap_chain_view = target_swap_chain_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Blit Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &target_swap_chain_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 }), // Clear to black
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
+        // Bind group `read` sums into the other accumulation texture
+        let layout = &self.raytrace_pipelines[&primitives].bind_group_layout;
+        let views = &self.accumulation_texture_views;
+        self.compute_bind_groups = self
+            .output_texture_views
+            .iter()
+            .map(|output_view| {
+                [0, 1].map(|read| {
+                    let mut entries = entries.clone();
+                    entries.push(wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(output_view) });
+                    entries.push(wgpu::BindGroupEntry { binding: 12, resource: wgpu::BindingResource::TextureView(&views[read]) });
+                    entries.push(wgpu::BindGroupEntry { binding: 13, resource: wgpu::BindingResource::TextureView(&views[1 - read]) });
+                    self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some(label),
+                        layout,
+                        entries: &entries,
+                    })
+                })
+            })
+            .collect();
+    }
 
-            render_pass.set_pipeline(&self.blit_pipeline);
-            render_pass.set_bind_group(0, &self.blit_bind_group, &[]);
-            render_pass.draw(0..3, 0..1); // Draw 3 vertices for the fullscreen triangle
-        } // render_pass is dropped
+    /// Record the raytracing compute pass into the next output texture, swapping
+    /// the accumulation textures for the next frame
+    fn encode_raytrace(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        self.output_index = (self.output_index + 1) % self.output_textures.len();
+        self.output_written = true;
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Raytrace Compute Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.raytrace_pipelines[&self.bound_primitives].pipeline);
+        compute_pass.set_bind_group(0, &self.compute_bind_groups[self.output_index][self.accumulation_index], &[]);
 
-        // 6. Submit command buffer
-        self.queue.submit(std::iter::once(encoder.finish()));
+        // Dispatch based on output texture dimensions
+        // Divide by workgroup size (e.g., 8x8 as defined in raytrace.wgsl)
+        let workgroup_size_x = 8;
+        let workgroup_size_y = 8;
+        let output_texture = &self.output_textures[self.output_index];
+        let num_workgroups_x = (output_texture.width() + workgroup_size_x - 1) / workgroup_size_x;
+        let num_workgroups_y = (output_texture.height() + workgroup_size_y - 1) / workgroup_size_y;
+        compute_pass.dispatch_workgroups(num_workgroups_x, num_workgroups_y, 1);
+        drop(compute_pass);
+        self.accumulation_index = 1 - self.accumulation_index;
+    }
 
-        Ok(())
+    /// Record a blit of the output texture into `target_view`, restricted to `rect` if given
+    fn encode_blit(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        rect: Option<ViewportRect>,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Blit Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        if let Some(rect) = rect {
+            render_pass.set_viewport(rect.x as f32, rect.y as f32, rect.width as f32, rect.height as f32, 0.0, 1.0);
+        }
+        render_pass.set_pipeline(&self.blit_pipeline);
+        render_pass.set_bind_group(0, &self.blit_bind_groups[self.output_index], &[]);
+        render_pass.draw(0..3, 0..1); // Draw 3 vertices for the fullscreen triangle
     }
 
     /// Resize GPU resources (e.g., output texture) when window size changes
@@ -683,69 +1743,38 @@ impl GpuRenderer {
         // Update surface configuration with new size
         self.surface_config.width = width;
         self.surface_config.height = height;
-        self.surface.configure(&self.device, &self.surface_config);
-
-        // Recreate output texture with new size
-        let output_texture_descriptor = wgpu::TextureDescriptor {
-            label: Some("Output Texture (Rgba8Unorm)"),
-            size: wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm, 
-            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
-            view_formats: &[],
-        };
-        self.output_texture = self.device.create_texture(&output_texture_descriptor);
-        self.output_texture_view = self.output_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.reconfigure_surface();
 
-        // Recreate compute bind group because output_texture_view changed
-        self.compute_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Raytrace Compute Bind Group (resized)"),
-            layout: &self.compute_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry { // Camera
-                    binding: 0,
-                    resource: self.camera_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry { // Spheres
-                    binding: 1,
-                    resource: self.sphere_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry { // Materials
-                    binding: 2,
-                    resource: self.material_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry { // Lights
-                    binding: 3,
-                    resource: self.light_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry { // Output Texture View
-                    binding: 4,
-                    resource: wgpu::BindingResource::TextureView(&self.output_texture_view),
-                },
-            ],
-        });
-        
-        // Recreate blit bind group because output_texture_view changed
-        self.blit_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Blit Bind Group (resized)"),
-            layout: &self.blit_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&self.output_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&self.sampler),
-                },
-            ],
-        });
+        // Recreate output textures with new size
+        self.output_textures = self
+            .output_textures
+            .iter()
+            .map(|_| create_output_texture(&self.device, width, height))
+            .collect();
+        self.output_texture_views = self
+            .output_textures
+            .iter()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            .collect();
+        self.output_written = false;
+        self.accumulation_textures = [0, 1].map(|_| create_accumulation_texture(&self.device, width, height));
+        self.accumulation_texture_views = self
+            .accumulation_textures
+            .each_ref()
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        self.reset_accumulation();
+
+        // Recreate compute bind groups because the texture views changed
+        self.rebind_compute("Raytrace Compute Bind Group (resized)");
+
+        // Recreate blit bind groups because the output texture views changed
+        self.blit_bind_groups = create_blit_bind_groups(
+            &self.device,
+            &self.blit_bind_group_layout,
+            &self.output_texture_views,
+            &self.sampler,
+            "Blit Bind Group (resized)",
+        );
         Ok(())
     }
 
@@ -754,13 +1783,6 @@ impl GpuRenderer {
         &self.config
     }
 
-    /// Check if the renderer is initialized
-    pub fn is_initialized(&self) -> bool {
-        // If the GpuRenderer instance exists, we assume it's initialized
-        // as device, queue, surface are not Options in the struct definition.
-        true 
-    }
-
     /// Get the device reference
     pub fn device(&self) -> Option<&Device> {
         Some(self.device.as_ref())
@@ -776,10 +1798,206 @@ impl GpuRenderer {
         Some(&self.surface_config) // Assuming this should also be wrapped if consistent
     }
 
-    pub fn get_current_texture(&self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
-        self.surface.get_current_texture()
+    /// Get the next swap chain texture, or `None` for a headless renderer
+    pub fn get_current_texture(&self) -> Option<Result<wgpu::SurfaceTexture, wgpu::SurfaceError>> {
+        self.surface.as_ref().map(|surface| surface.get_current_texture())
+    }
+
+    /// Check whether the renderer has no surface to present to
+    pub const fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    /// Reconfigure the surface with the current surface configuration, if there is one
+    pub fn reconfigure_surface(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.surface_config);
+        }
+    }
+
+    /// Acquire the next swap chain texture, recovering from transient surface errors.
+    ///
+    /// Returns `Ok(None)` when the frame should be skipped: a `Lost` or `Outdated`
+    /// surface (common on minimize/resize) is reconfigured, and a `Timeout` is
+    /// retried next frame. Only `OutOfMemory` and having no surface to acquire
+    /// from are reported as errors.
+    ///
+    /// # Errors
+    ///
+    /// Fails on `OutOfMemory` or when the renderer is headless.
+    pub fn acquire_frame(&self) -> Result<Option<wgpu::SurfaceTexture>> {
+        let Some(current_texture) = self.get_current_texture() else {
+            return Err(anyhow::anyhow!("Headless GpuRenderer has no surface to acquire a frame from"));
+        };
+        match current_texture {
+            Ok(texture) => Ok(Some(texture)),
+            Err(error) => self.recover_from_surface_error(error).map(|()| None),
+        }
+    }
+
+    /// Recover from a failure to acquire a swap chain texture so the next frame
+    /// can be acquired, or report it if it cannot be recovered from.
+    ///
+    /// # Errors
+    ///
+    /// Fails on `OutOfMemory`.
+    pub fn recover_from_surface_error(&self, error: wgpu::SurfaceError) -> Result<()> {
+        match error {
+            wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated => {
+                warn!("Surface lost or outdated, reconfiguring and skipping frame.");
+                self.reconfigure_surface();
+                Ok(())
+            }
+            wgpu::SurfaceError::Timeout => {
+                warn!("Timed out acquiring surface texture, skipping frame.");
+                Ok(())
+            }
+            e @ wgpu::SurfaceError::OutOfMemory => {
+                Err(anyhow::anyhow!("Failed to acquire surface texture: {}", e))
+            }
+        }
+    }
+
+    /// Read back a downscaled copy of the last rendered frame as tightly packed,
+    /// sRGB-encoded RGBA8, like the output of [`crate::Raytracer::render`].
+    ///
+    /// The output texture is blitted with linear filtering into a `width` x `height`
+    /// texture and only that is copied to the CPU, so editors can show a live
+    /// preview without reading the full frame. Blocks until the copy completes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `width` or `height` is zero or the copy cannot be mapped.
+    pub fn read_thumbnail(&self, width: u32, height: u32) -> Result<Vec<u8>> {
+        if width == 0 || height == 0 {
+            return Err(anyhow::anyhow!("Thumbnail dimensions must be non-zero, got {}x{}", width, height));
+        }
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let thumbnail_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Thumbnail Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: THUMBNAIL_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let thumbnail_view = thumbnail_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Thumbnail Encoder"),
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Thumbnail Blit Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &thumbnail_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&self.thumbnail_pipeline);
+            render_pass.set_bind_group(0, &self.blit_bind_groups[self.output_index], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        self.read_texture(encoder, &thumbnail_texture)
+    }
+
+    /// Read back the last rendered frame at full resolution as tightly packed,
+    /// sRGB-encoded RGBA8, like the output of [`crate::Raytracer::render`].
+    ///
+    /// The output texture is copied as is and encoded on the CPU. Blocks until
+    /// the copy completes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the copy cannot be mapped.
+    pub fn read_pixels(&self) -> Result<Vec<u8>> {
+        let encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Read Pixels Encoder"),
+        });
+        let mut pixels = self.read_texture(encoder, &self.output_textures[self.output_index])?;
+
+        // The output texture holds linear values; encode them through a table of all 256
+        let srgb: [u8; 256] = std::array::from_fn(|value| {
+            (rrte_math::color::linear_to_srgb(value as f32 / 255.0) * 255.0).round() as u8
+        });
+        for pixel in pixels.chunks_exact_mut(4) {
+            for channel in &mut pixel[..3] {
+                *channel = srgb[usize::from(*channel)];
+            }
+        }
+        Ok(pixels)
+    }
+
+    /// Submit `encoder` with a copy of an RGBA8 `texture` appended, then read
+    /// the copy back as tightly packed rows
+    fn read_texture(&self, mut encoder: wgpu::CommandEncoder, texture: &wgpu::Texture) -> Result<Vec<u8>> {
+        let (width, height) = (texture.width(), texture.height());
+
+        // Rows in a texture-to-buffer copy must be padded to COPY_BYTES_PER_ROW_ALIGNMENT
+        let unpadded_bytes_per_row = width * 4;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(alignment) * alignment;
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: u64::from(padded_bytes_per_row) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let buffer_slice = readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let data = buffer_slice.get_mapped_range();
+            for row in data.chunks_exact(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        readback_buffer.unmap();
+
+        Ok(pixels)
     }
 
+    /// Get the width of the render target divided by its height, or 1 while it has no height
     pub fn get_aspect_ratio(&self) -> f32 {
         if self.surface_config.height == 0 { return 1.0; } // Avoid division by zero
         self.surface_config.width as f32 / self.surface_config.height as f32
@@ -790,7 +2008,351 @@ impl std::fmt::Debug for GpuRenderer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("GpuRenderer")
             .field("config", &self.config)
-            .field("initialized", &self.is_initialized())
+            .field("headless", &self.surface.is_none())
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::naga;
+
+    /// Every combination of the optional kinds
+    fn all_combinations() -> impl Iterator<Item = GpuPrimitives> {
+        (0..16).map(|mask: u32| GpuPrimitives {
+            boxes: mask & 1 != 0,
+            meshes: mask & 2 != 0,
+            cylinders: mask & 4 != 0,
+            cones: mask & 8 != 0,
+        })
+    }
+
+    #[test]
+    fn stripped_shader_is_valid_for_every_combination_of_kinds() {
+        for primitives in all_combinations() {
+            let source = primitives.shader_source().unwrap();
+            let module = naga::front::wgsl::parse_str(&source)
+                .unwrap_or_else(|e| panic!("{primitives:?}: {}", e.emit_to_string(&source)));
+            naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+                .validate(&module)
+                .unwrap_or_else(|e| panic!("{primitives:?}: {e:?}"));
+
+            for (enabled, name) in [
+                (primitives.boxes, "struct Box "),
+                (primitives.meshes, "struct Mesh "),
+                (primitives.cylinders, "struct Cylinder "),
+                (primitives.cones, "struct Cone "),
+            ] {
+                assert_eq!(source.contains(name), enabled, "{primitives:?}: {name}");
+            }
+        }
+    }
+
+    #[test]
+    fn nested_blocks_need_every_enclosing_kind() {
+        let shader = "a\n// #if boxes\nb\n  // #if cones\n  c\n  // #endif\nd\n// #endif\ne\n";
+        let only_cones = GpuPrimitives { cones: true, ..GpuPrimitives::NONE };
+        let only_boxes = GpuPrimitives { boxes: true, ..GpuPrimitives::NONE };
+
+        assert_eq!(GpuPrimitives::ALL.preprocess(shader).unwrap(), "a\nb\n  c\nd\ne\n");
+        assert_eq!(only_boxes.preprocess(shader).unwrap(), "a\nb\nd\ne\n");
+        assert_eq!(only_cones.preprocess(shader).unwrap(), "a\ne\n");
+    }
+
+    #[test]
+    fn unknown_kinds_and_unbalanced_blocks_are_rejected() {
+        for shader in ["// #if spheres\na\n// #endif\n", "// #if boxes\na\n", "a\n// #endif\n"] {
+            assert!(GpuPrimitives::ALL.preprocess(shader).is_err(), "{shader:?}");
+        }
+    }
+
+    /// A headless renderer on the first available adapter, or `None` when the
+    /// machine has none so the GPU tests can be skipped
+    #[cfg(feature = "gpu")]
+    fn headless_renderer(width: u32, height: u32) -> Option<GpuRenderer> {
+        headless_renderer_with_format(width, height, GpuRendererConfig::default().format)
+    }
+
+    /// A headless renderer whose blit targets textures of `format`
+    #[cfg(feature = "gpu")]
+    fn headless_renderer_with_format(width: u32, height: u32, format: TextureFormat) -> Option<GpuRenderer> {
+        headless_renderer_with_config(&GpuRendererConfig { width, height, format, ..GpuRendererConfig::default() })
+    }
+
+    /// A headless renderer with any configuration
+    #[cfg(feature = "gpu")]
+    fn headless_renderer_with_config(config: &GpuRendererConfig) -> Option<GpuRenderer> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()));
+        let Some(adapter) = adapter else {
+            eprintln!("No GPU adapter available, skipping");
+            return None;
+        };
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+        let surface_config = SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: config.format,
+            width: config.width,
+            height: config.height,
+            present_mode: config.present_mode,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let renderer = GpuRenderer::new(config, Arc::new(device), Arc::new(queue), surface_config, None, None);
+        Some(pollster::block_on(renderer).unwrap())
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn lost_or_outdated_surface_is_recovered_instead_of_failing() {
+        let Some(renderer) = headless_renderer(16, 16) else { return };
+        for error in [
+            wgpu::SurfaceError::Outdated,
+            wgpu::SurfaceError::Lost,
+            wgpu::SurfaceError::Timeout,
+        ] {
+            assert!(renderer.recover_from_surface_error(error).is_ok());
+        }
+        assert!(renderer.recover_from_surface_error(wgpu::SurfaceError::OutOfMemory).is_err());
+    }
+
+    /// A camera at `z = 5` looking at the origin
+    #[cfg(feature = "gpu")]
+    fn camera_looking_at_origin(aspect_ratio: f32) -> RendererCamera {
+        let mut camera = RendererCamera::new_perspective(1.0, aspect_ratio, 0.1, 100.0);
+        camera.transform.position = Vec3::new(0.0, 0.0, 5.0);
+        camera.look_at(Vec3::ZERO, Vec3::Y);
+        camera
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn thumbnail_has_the_requested_size() {
+        let Some(mut renderer) = headless_renderer(64, 48) else { return };
+        let spheres = [Arc::new(Sphere::new(Vec3::ZERO, 1.0))];
+        renderer.render_to_texture(&spheres, &[], &[], &camera_looking_at_origin(64.0 / 48.0)).unwrap();
+
+        for (width, height) in [(16, 12), (7, 5), (64, 48)] {
+            let thumbnail = renderer.read_thumbnail(width, height).unwrap();
+            assert_eq!(thumbnail.len(), (width * height * 4) as usize);
+        }
+        assert!(renderer.read_thumbnail(0, 12).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn mirror_sphere_reflects_a_sphere_behind_the_camera() {
+        use crate::material::EmissiveMaterial;
+
+        let Some(mut renderer) = headless_renderer(33, 33) else { return };
+        let mut mirror = Sphere::new(Vec3::ZERO, 1.0);
+        mirror.set_material(MetalMaterial::new(Color::WHITE, 0.0));
+        // Behind the camera, so it can only be seen in the mirror
+        let mut red = Sphere::new(Vec3::new(0.0, 0.0, 8.0), 1.0);
+        red.set_material(EmissiveMaterial::new(Color::RED, 1.0));
+        let camera = camera_looking_at_origin(1.0);
+
+        let center_pixel = |renderer: &mut GpuRenderer, spheres: &[Arc<Sphere>]| {
+            renderer.render_to_texture(spheres, &[], &[], &camera).unwrap();
+            let pixels = renderer.read_pixels().unwrap();
+            let center = (16 * 33 + 16) * 4;
+            [pixels[center], pixels[center + 1], pixels[center + 2]]
+        };
+        let without = center_pixel(&mut renderer, &[Arc::new(mirror.clone())]);
+        let with = center_pixel(&mut renderer, &[Arc::new(mirror), Arc::new(red)]);
+
+        assert!(with[0] > 200 && with[1] < 50 && with[2] < 50, "{with:?}");
+        assert_ne!(with, without);
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn empty_scene_renders_the_same_gradient_as_the_cpu() {
+        use crate::raytracer::{Raytracer, RaytracerConfig};
+
+        let (bottom, top) = (Color::new(0.9, 0.6, 0.2, 1.0), Color::new(0.1, 0.3, 0.8, 1.0));
+        let Some(mut renderer) = headless_renderer(16, 16) else { return };
+        renderer.set_background(Background::gradient(bottom, top));
+        let camera = camera_looking_at_origin(1.0);
+        renderer.render_to_texture(&[], &[], &[], &camera).unwrap();
+        let gpu = renderer.read_pixels().unwrap();
+
+        let raytracer = Raytracer::new(RaytracerConfig {
+            width: 16,
+            height: 16,
+            samples_per_pixel: 4,
+            background_color: bottom,
+            background_top: Some(top),
+            seed: Some(3),
+            ..RaytracerConfig::default()
+        });
+        let cpu = raytracer.render(&[], &[], &[], &camera);
+
+        assert_eq!(gpu.len(), cpu.len());
+        for (i, (gpu, cpu)) in gpu.iter().zip(&cpu).enumerate() {
+            assert!(gpu.abs_diff(*cpu) <= 3, "byte {i}: gpu {gpu} != cpu {cpu}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn frame_rendered_without_presenting_can_be_read_back() {
+        use crate::material::EmissiveMaterial;
+
+        let Some(mut renderer) = headless_renderer(17, 17) else { return };
+        let mut sphere = Sphere::new(Vec3::ZERO, 1.0);
+        sphere.set_material(EmissiveMaterial::new(Color::GREEN, 1.0));
+        renderer.render_to_texture(&[Arc::new(sphere)], &[], &[], &camera_looking_at_origin(1.0)).unwrap();
+
+        let pixels = renderer.read_pixels().unwrap();
+        let center = (8 * 17 + 8) * 4;
+        let green = &pixels[center..center + 3];
+        assert!(green[1] > 200 && green[0] < 50 && green[2] < 50, "{green:?}");
+        let thumbnail = renderer.read_thumbnail(1, 1).unwrap();
+        assert!(thumbnail[1] > thumbnail[0]);
+
+        // A headless renderer has nothing to present to
+        assert!(!renderer.present().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn mid_gray_is_stored_alike_on_srgb_and_linear_surfaces() {
+        let camera = camera_looking_at_origin(1.0);
+        let stored = |format: TextureFormat| {
+            let mut renderer = headless_renderer_with_format(8, 8, format)?;
+            renderer.set_background(Background::solid(Color::new(0.5, 0.5, 0.5, 1.0)));
+            let target = renderer.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Test Target"),
+                size: wgpu::Extent3d { width: 8, height: 8, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            renderer.render(Some(&target), &[], &[], &[], &camera).unwrap();
+            let encoder = renderer.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            Some(renderer.read_texture(encoder, &target).unwrap())
+        };
+        let (Some(srgb), Some(linear)) = (stored(TextureFormat::Rgba8UnormSrgb), stored(TextureFormat::Rgba8Unorm))
+        else {
+            return;
+        };
+
+        // Both hold the sRGB encoding of linear 0.5, so they display the same
+        for (srgb, linear) in srgb.chunks_exact(4).zip(linear.chunks_exact(4)) {
+            for channel in 0..3 {
+                assert!(srgb[channel].abs_diff(188) <= 1, "{srgb:?}");
+                assert!(linear[channel].abs_diff(srgb[channel]) <= 1, "{linear:?} vs {srgb:?}");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn bright_scene_tone_mapped_on_the_gpu_matches_the_cpu() {
+        use crate::camera::CameraExposure;
+        use crate::material::EmissiveMaterial;
+        use crate::parity::{check_render_parity, DiffTolerance};
+        use crate::raytracer::{Raytracer, RaytracerConfig};
+
+        let Some(mut renderer) = headless_renderer(32, 32) else { return };
+        let target = renderer.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Test Target"),
+            size: wgpu::Extent3d { width: 32, height: 32, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: renderer.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        // Far above 1 once exposed, so clipping and tone mapping differ clearly
+        let mut sphere = Sphere::new(Vec3::ZERO, 1.5);
+        sphere.set_material(EmissiveMaterial::new(Color::new(1.0, 0.5, 0.25, 1.0), 4.0));
+        let spheres = [Arc::new(sphere)];
+        let mut camera = camera_looking_at_origin(1.0);
+        camera.exposure = Some(CameraExposure::from_multiplier(1.5));
+
+        let raytracer = Raytracer::new(RaytracerConfig {
+            width: 32,
+            height: 32,
+            samples_per_pixel: 4,
+            tonemap: ToneMapper::Reinhard,
+            seed: Some(3),
+            ..RaytracerConfig::default()
+        });
+        let tolerance = DiffTolerance { max_mean_error: 0.02, min_ssim: 0.9 };
+        let parity = |renderer: &mut GpuRenderer| {
+            renderer.reset_accumulation();
+            check_render_parity(&raytracer, renderer, &target, &spheres, &[], &camera, &tolerance)
+        };
+
+        // Clipped on the GPU, the bright sphere saturates where the CPU compresses it
+        assert!(parity(&mut renderer).is_err());
+        renderer.set_tonemap(ToneMapper::Reinhard);
+        let diff = parity(&mut renderer).unwrap();
+        assert!(diff.mean_color_error() < 0.02, "{diff:?}");
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn double_buffered_output_alternates_between_two_textures() {
+        let config = GpuRendererConfig { width: 8, height: 8, double_buffer_output: true, ..GpuRendererConfig::default() };
+        let Some(mut renderer) = headless_renderer_with_config(&config) else { return };
+        assert_eq!(renderer.output_texture_count(), 2);
+
+        let camera = camera_looking_at_origin(1.0);
+        let spheres = [Arc::new(Sphere::new(Vec3::ZERO, 1.0))];
+        let mut indices = Vec::new();
+        for _ in 0..4 {
+            renderer.render_to_texture(&spheres, &[], &[], &camera).unwrap();
+            indices.push(renderer.output_texture_index());
+        }
+        assert!(indices.windows(2).all(|pair| pair[0] != pair[1]), "{indices:?}");
+        // The frame just written is the one read back
+        assert_eq!(renderer.read_pixels().unwrap().len(), 8 * 8 * 4);
+
+        // A swap chain texture is shown the previous frame while the next one is raytraced
+        let target = renderer.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Test Target"),
+            size: wgpu::Extent3d { width: 8, height: 8, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: renderer.config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let read_target = |renderer: &GpuRenderer| {
+            let encoder = renderer.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            renderer.read_texture(encoder, &target).unwrap()
+        };
+        let shows_sphere = |pixels: &[u8]| pixels.chunks_exact(4).any(|pixel| pixel != &pixels[..4]);
+        renderer.render(Some(&target), &[], &[], &[], &camera).unwrap();
+        assert!(shows_sphere(&read_target(&renderer)));
+        assert!(!shows_sphere(&renderer.read_pixels().unwrap()));
+        renderer.render(Some(&target), &[], &[], &[], &camera).unwrap();
+        assert!(!shows_sphere(&read_target(&renderer)));
+    }
+
+    #[test]
+    #[cfg(feature = "gpu")]
+    fn taa_jitter_steps_through_halton_and_restarts_with_the_accumulation() {
+        let Some(mut renderer) = headless_renderer(8, 8) else { return };
+        let camera = camera_looking_at_origin(1.0);
+        let spheres = [Arc::new(Sphere::new(Vec3::ZERO, 1.0))];
+
+        for frame in 0..3 {
+            assert_eq!(renderer.frame_jitter(), RendererCamera::halton_jitter(frame, TAA_JITTER_CYCLE, 8, 8));
+            renderer.render_to_texture(&spheres, &[], &[], &camera).unwrap();
+        }
+        renderer.reset_accumulation();
+        assert_eq!(renderer.frame_jitter(), RendererCamera::halton_jitter(0, TAA_JITTER_CYCLE, 8, 8));
+    }
+}
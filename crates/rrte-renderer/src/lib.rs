@@ -17,10 +17,31 @@ pub mod light;
 pub mod gpu_renderer;
 /// Camera types.
 pub mod camera;
+/// Edge-avoiding denoising for CPU renders.
+pub mod denoise;
+/// Sub-rectangle rendering for multiple cameras.
+pub mod viewport;
+/// Importance-sampled environment lighting.
+pub mod environment;
+/// Image comparison and CPU/GPU render parity checks.
+pub mod parity;
+/// Caustic photon mapping for the CPU raytracer.
+pub mod photon;
+/// Cost-balanced image tiling for the CPU raytracer.
+pub mod tiles;
+/// Bounding volume hierarchy over scene objects.
+pub mod bvh;
 
 pub use raytracer::*;
 pub use material::*;
 pub use primitives::*;
 pub use light::*;
-pub use gpu_renderer::{GpuRenderer, GpuRendererConfig};
+pub use gpu_renderer::{choose_surface_format, pack_point_lights, BoxGpu, ConeGpu, CylinderGpu, GpuPrimitives, GpuRenderer, GpuRendererConfig, GpuSceneData, MaterialGpu, MeshGpu, PointLightGpu, SphereGpu, TriangleGpu, VertexGpu};
 pub use camera::*;
+pub use denoise::*;
+pub use viewport::*;
+pub use environment::*;
+pub use parity::*;
+pub use photon::*;
+pub use tiles::*;
+pub use bvh::*;
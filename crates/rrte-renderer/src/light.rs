@@ -1,10 +1,27 @@
-use rrte_math::{Vec3, Color, Transform};
+use rrte_assets::IesProfile;
+use rrte_math::{Vec2, Vec3, Color, Quat, Transform};
+use std::any::Any;
 use serde::{Deserialize, Serialize};
+use image::{DynamicImage, GenericImageView};
+use std::sync::Arc;
+use crate::{SceneObject, Sphere};
 
 /// Trait for all light sources
 pub trait Light: Send + Sync + std::fmt::Debug {
+    /// Get this light as `Any` for downcasting to a concrete type
+    fn as_any(&self) -> &dyn Any;
+
     /// Get the light's contribution at a given point
     fn illuminate(&self, point: Vec3, normal: Vec3) -> LightContribution;
+
+    /// Get the light's contribution at a point from the part of the light
+    /// chosen by `u` in `[0, 1)^2`.
+    ///
+    /// Averaging over many `u` gives the light's soft shadows. The default
+    /// ignores `u` and returns [`Light::illuminate`], as for delta lights.
+    fn illuminate_sampled(&self, point: Vec3, normal: Vec3, _u: Vec2) -> LightContribution {
+        self.illuminate(point, normal)
+    }
     
     /// Get the light's position
     fn position(&self) -> Vec3;
@@ -12,7 +29,7 @@ pub trait Light: Send + Sync + std::fmt::Debug {
     /// Get the light's color
     fn color(&self) -> Color;
     
-    /// Get the light's intensity
+    /// Get the light's intensity in the renderer's radiometric units
     fn intensity(&self) -> f32;
     
     /// Check if the light affects a given point
@@ -23,6 +40,81 @@ pub trait Light: Send + Sync + std::fmt::Debug {
     
     /// Set the transform of the light
     fn set_transform(&mut self, transform: Transform);
+
+    /// Sample a point on the light as seen from `from`, using `u` in `[0, 1)^2`.
+    ///
+    /// The default treats the light as a delta light located where
+    /// [`Light::illuminate`] points, with a PDF of one.
+    fn sample(&self, from: Vec3, _u: Vec2) -> LightSample {
+        let contribution = self.illuminate(from, Vec3::ZERO);
+        let distance = if contribution.distance.is_finite() {
+            contribution.distance
+        } else {
+            DIRECTIONAL_SAMPLE_DISTANCE
+        };
+        LightSample::new(
+            from + contribution.direction * distance,
+            contribution.color * contribution.attenuation,
+            1.0,
+        )
+    }
+}
+
+/// Distance at which samples of infinitely distant lights are placed
+pub const DIRECTIONAL_SAMPLE_DISTANCE: f32 = 1.0e6;
+
+/// Lumens per watt at the peak of human photopic vision (555 nm)
+pub const LUMINOUS_EFFICACY: f32 = 683.0;
+
+/// Unit a light's `intensity` is given in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntensityUnit {
+    /// Used by the renderer as-is
+    #[default]
+    Raw,
+    /// Luminous flux in lumens (illuminance in lux for directional lights)
+    Lumens,
+    /// Radiant flux in watts (irradiance in W/m² for directional lights)
+    Watts,
+}
+
+impl IntensityUnit {
+    /// Convert an intensity in this unit to the renderer's radiometric value.
+    ///
+    /// Flux is spread over `extent`: the solid angle the light emits into for
+    /// point and spot lights (radiant intensity, W/sr), `pi * area` for area
+    /// lights (radiance), or one for directional lights (irradiance).
+    #[must_use]
+    pub fn to_radiometric(self, value: f32, extent: f32) -> f32 {
+        match self {
+            Self::Raw => value,
+            Self::Watts => value / extent,
+            Self::Lumens => value / (LUMINOUS_EFFICACY * extent),
+        }
+    }
+}
+
+/// A point sampled on a light for next-event estimation
+#[derive(Debug, Clone)]
+pub struct LightSample {
+    /// Sampled position on the light
+    pub position: Vec3,
+    /// Radiance arriving from the sampled position (before the receiver's cosine)
+    pub radiance: Color,
+    /// Probability density of the sample: one for delta lights, per unit area for area lights
+    pub pdf: f32,
+}
+
+impl LightSample {
+    /// Create a new light sample
+    #[must_use]
+    pub const fn new(position: Vec3, radiance: Color, pdf: f32) -> Self {
+        Self {
+            position,
+            radiance,
+            pdf,
+        }
+    }
 }
 
 /// Light contribution result
@@ -61,6 +153,9 @@ pub struct DirectionalLight {
     pub color: Color,
     pub intensity: f32,
     pub transform: Transform,
+    /// Unit of `intensity`
+    #[serde(default)]
+    pub intensity_unit: IntensityUnit,
 }
 
 impl DirectionalLight {
@@ -71,9 +166,23 @@ impl DirectionalLight {
             color,
             intensity,
             transform: Transform::identity(),
+            intensity_unit: IntensityUnit::Raw,
         }
     }
 
+    /// Interpret `intensity` as illuminance (lux) or irradiance (W/m²) instead of a raw value
+    #[must_use]
+    pub const fn with_intensity_unit(mut self, unit: IntensityUnit) -> Self {
+        self.intensity_unit = unit;
+        self
+    }
+
+    /// Get the irradiance the renderer uses
+    #[must_use]
+    pub fn radiometric_intensity(&self) -> f32 {
+        self.intensity_unit.to_radiometric(self.intensity, 1.0)
+    }
+
     /// Create a default sun-like directional light
     pub fn sun() -> Self {
         Self::new(
@@ -85,9 +194,22 @@ impl DirectionalLight {
 }
 
 impl Light for DirectionalLight {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn sample(&self, from: Vec3, _u: Vec2) -> LightSample {
+        // Delta direction: the only sample lies infinitely far against the light direction
+        LightSample::new(
+            from - self.direction.normalize() * DIRECTIONAL_SAMPLE_DISTANCE,
+            self.color * self.radiometric_intensity(),
+            1.0,
+        )
+    }
+
     fn illuminate(&self, _point: Vec3, _normal: Vec3) -> LightContribution {
         LightContribution::new(
-            self.color * self.intensity,
+            self.color * self.radiometric_intensity(),
             -self.direction,
             f32::INFINITY,
             1.0,
@@ -104,7 +226,7 @@ impl Light for DirectionalLight {
     }
 
     fn intensity(&self) -> f32 {
-        self.intensity
+        self.radiometric_intensity()
     }
 
     fn affects_point(&self, _point: Vec3) -> bool {
@@ -130,6 +252,13 @@ pub struct PointLight {
     pub linear_attenuation: f32,
     pub quadratic_attenuation: f32,
     pub transform: Transform,
+    /// Unit of `intensity`
+    #[serde(default)]
+    pub intensity_unit: IntensityUnit,
+    /// Optional photometric profile shaping the light by direction, pointing
+    /// down the transform's local -Y axis. Only the CPU renderer applies it.
+    #[serde(skip)]
+    pub ies_profile: Option<Arc<IesProfile>>,
 }
 
 impl PointLight {
@@ -143,6 +272,8 @@ impl PointLight {
             linear_attenuation: 0.09,
             quadratic_attenuation: 0.032,
             transform: Transform::identity(),
+            intensity_unit: IntensityUnit::Raw,
+            ies_profile: None,
         }
     }
 
@@ -163,9 +294,38 @@ impl PointLight {
             linear_attenuation: linear,
             quadratic_attenuation: quadratic,
             transform: Transform::identity(),
+            intensity_unit: IntensityUnit::Raw,
+            ies_profile: None,
         }
     }
 
+    /// Shape the light's intensity by direction with an IES profile
+    #[must_use]
+    pub fn with_ies_profile(mut self, profile: Arc<IesProfile>) -> Self {
+        self.ies_profile = Some(profile);
+        self
+    }
+
+    /// Interpret `intensity` as flux emitted in every direction instead of a raw value
+    #[must_use]
+    pub const fn with_intensity_unit(mut self, unit: IntensityUnit) -> Self {
+        self.intensity_unit = unit;
+        self
+    }
+
+    /// Get the radiant intensity the renderer uses
+    #[must_use]
+    pub fn radiometric_intensity(&self) -> f32 {
+        self.intensity_unit.to_radiometric(self.intensity, 4.0 * std::f32::consts::PI)
+    }
+
+    /// Get the IES profile's relative intensity toward a point, 1 without a profile
+    fn profile_factor(&self, point: Vec3) -> f32 {
+        self.ies_profile.as_ref().map_or(1.0, |profile| {
+            profile.relative_intensity(self.transform.rotation.inverse() * (point - self.position))
+        })
+    }
+
     /// Calculate attenuation based on distance
     fn calculate_attenuation(&self, distance: f32) -> f32 {
         if distance > self.range {
@@ -179,6 +339,20 @@ impl PointLight {
 }
 
 impl Light for PointLight {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn sample(&self, from: Vec3, _u: Vec2) -> LightSample {
+        // Delta position: every sample is the light itself
+        let distance = (self.position - from).length();
+        LightSample::new(
+            self.position,
+            self.color * (self.radiometric_intensity() * self.profile_factor(from) * self.calculate_attenuation(distance)),
+            1.0,
+        )
+    }
+
     fn illuminate(&self, point: Vec3, _normal: Vec3) -> LightContribution {
         let light_vector = self.position - point;
         let distance = light_vector.length();
@@ -186,7 +360,7 @@ impl Light for PointLight {
         let attenuation = self.calculate_attenuation(distance);
         
         LightContribution::new(
-            self.color * self.intensity,
+            self.color * (self.radiometric_intensity() * self.profile_factor(point)),
             direction,
             distance,
             attenuation,
@@ -202,7 +376,7 @@ impl Light for PointLight {
     }
 
     fn intensity(&self) -> f32 {
-        self.intensity
+        self.radiometric_intensity()
     }
 
     fn affects_point(&self, point: Vec3) -> bool {
@@ -219,6 +393,44 @@ impl Light for PointLight {
     }
 }
 
+/// One point light definition repeated at several positions, e.g. a ceiling of
+/// identical bulbs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstancedLight {
+    /// Shared definition; its own `position` is ignored
+    pub light: PointLight,
+    /// Position of every instance
+    pub positions: Vec<Vec3>,
+}
+
+impl InstancedLight {
+    /// Create an instanced light from a shared definition and instance positions
+    #[must_use]
+    pub const fn new(light: PointLight, positions: Vec<Vec3>) -> Self {
+        Self { light, positions }
+    }
+
+    /// Get the number of instances
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Check whether there are no instances
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Get a copy of the shared light at every instance position
+    pub fn instances(&self) -> impl Iterator<Item = PointLight> + '_ {
+        self.positions.iter().map(|&position| PointLight {
+            position,
+            ..self.light.clone()
+        })
+    }
+}
+
 /// Spot light (cone-shaped light)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpotLight {
@@ -232,6 +444,16 @@ pub struct SpotLight {
     pub linear_attenuation: f32,
     pub quadratic_attenuation: f32,
     pub transform: Transform,
+    /// Optional projected texture ("cookie"/gobo) modulating the light across its cone
+    #[serde(skip)]
+    pub cookie: Option<Arc<DynamicImage>>,
+    /// Unit of `intensity`
+    #[serde(default)]
+    pub intensity_unit: IntensityUnit,
+    /// Optional photometric profile shaping the light within its cone, with
+    /// the profile's downward axis along `direction`
+    #[serde(skip)]
+    pub ies_profile: Option<Arc<IesProfile>>,
 }
 
 impl SpotLight {
@@ -255,9 +477,77 @@ impl SpotLight {
             linear_attenuation: 0.09,
             quadratic_attenuation: 0.032,
             transform: Transform::identity(),
+            cookie: None,
+            intensity_unit: IntensityUnit::Raw,
+            ies_profile: None,
         }
     }
 
+    /// Shape the light's intensity by direction with an IES profile
+    #[must_use]
+    pub fn with_ies_profile(mut self, profile: Arc<IesProfile>) -> Self {
+        self.ies_profile = Some(profile);
+        self
+    }
+
+    /// Project a cookie texture through the light's cone
+    #[must_use]
+    pub fn with_cookie(mut self, cookie: Arc<DynamicImage>) -> Self {
+        self.cookie = Some(cookie);
+        self
+    }
+
+    /// Interpret `intensity` as flux emitted into the outer cone instead of a raw value
+    #[must_use]
+    pub const fn with_intensity_unit(mut self, unit: IntensityUnit) -> Self {
+        self.intensity_unit = unit;
+        self
+    }
+
+    /// Get the radiant intensity the renderer uses
+    #[must_use]
+    pub fn radiometric_intensity(&self) -> f32 {
+        let cone_solid_angle = 2.0 * std::f32::consts::PI * (1.0 - self.outer_angle.cos());
+        self.intensity_unit.to_radiometric(self.intensity, cone_solid_angle.max(f32::EPSILON))
+    }
+
+    /// Sample the cookie texture for a direction from the point toward the light.
+    ///
+    /// The texture spans the outer cone, with its center on the light direction.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)] // Clamped UVs give a small positive index
+    fn sample_cookie(&self, direction_to_point: Vec3) -> Color {
+        let Some(cookie) = &self.cookie else {
+            return Color::WHITE;
+        };
+        let (width, height) = cookie.dimensions();
+        if width == 0 || height == 0 {
+            return Color::WHITE;
+        }
+
+        let light_dir = -direction_to_point;
+        let forward_amount = self.direction.dot(light_dir);
+        if forward_amount <= 0.0 {
+            return Color::BLACK;
+        }
+
+        // Perspective-project the direction onto the plane of the cone's opening
+        let (right, up) = self.direction.any_orthonormal_pair();
+        let extent = self.outer_angle.tan().max(1e-6);
+        let x = right.dot(light_dir) / forward_amount / extent;
+        let y = up.dot(light_dir) / forward_amount / extent;
+
+        let u = (x * 0.5 + 0.5).clamp(0.0, 1.0);
+        let v = (0.5 - y * 0.5).clamp(0.0, 1.0);
+        let px = ((u * width as f32) as u32).min(width - 1);
+        let py = ((v * height as f32) as u32).min(height - 1);
+        let texel = cookie.get_pixel(px, py);
+        Color::rgb(
+            f32::from(texel[0]) / 255.0,
+            f32::from(texel[1]) / 255.0,
+            f32::from(texel[2]) / 255.0,
+        )
+    }
+
     /// Calculate attenuation based on distance
     fn calculate_distance_attenuation(&self, distance: f32) -> f32 {
         if distance > self.range {
@@ -269,6 +559,15 @@ impl SpotLight {
         attenuation.max(0.0)
     }
 
+    /// Get the IES profile's relative intensity for a direction from the point
+    /// toward the light, 1 without a profile
+    fn profile_factor(&self, direction_to_point: Vec3) -> f32 {
+        self.ies_profile.as_ref().map_or(1.0, |profile| {
+            let to_profile = Quat::from_rotation_arc(self.direction, Vec3::NEG_Y);
+            profile.relative_intensity(to_profile * -direction_to_point)
+        })
+    }
+
     /// Calculate angular attenuation based on angle from light direction
     fn calculate_angular_attenuation(&self, direction_to_point: Vec3) -> f32 {
         let angle = self.direction.dot(-direction_to_point).acos();
@@ -286,6 +585,10 @@ impl SpotLight {
 }
 
 impl Light for SpotLight {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn illuminate(&self, point: Vec3, _normal: Vec3) -> LightContribution {
         let light_vector = self.position - point;
         let distance = light_vector.length();
@@ -294,9 +597,10 @@ impl Light for SpotLight {
         let distance_attenuation = self.calculate_distance_attenuation(distance);
         let angular_attenuation = self.calculate_angular_attenuation(direction);
         let total_attenuation = distance_attenuation * angular_attenuation;
+        let cookie = self.sample_cookie(direction);
         
         LightContribution::new(
-            self.color * self.intensity,
+            Color::from(self.color.to_vec3() * cookie.to_vec3() * (self.radiometric_intensity() * self.profile_factor(direction))),
             direction,
             distance,
             total_attenuation,
@@ -312,7 +616,7 @@ impl Light for SpotLight {
     }
 
     fn intensity(&self) -> f32 {
-        self.intensity
+        self.radiometric_intensity()
     }
 
     fn affects_point(&self, point: Vec3) -> bool {
@@ -337,6 +641,272 @@ impl Light for SpotLight {
     }
 }
 
+/// Rectangular area light, emitting from the front side of the parallelogram
+/// spanned by `edge_u` and `edge_v` around `position`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AreaLight {
+    /// Center of the rectangle
+    pub position: Vec3,
+    /// First edge of the rectangle
+    pub edge_u: Vec3,
+    /// Second edge of the rectangle
+    pub edge_v: Vec3,
+    /// Color of the emitted light
+    pub color: Color,
+    /// Strength of the emitted light, in `intensity_unit`
+    pub intensity: f32,
+    /// Transform of the light
+    pub transform: Transform,
+    /// Unit of `intensity`
+    #[serde(default)]
+    pub intensity_unit: IntensityUnit,
+}
+
+impl AreaLight {
+    /// Create a new area light centered at `position`, emitting along `edge_u x edge_v`
+    #[must_use]
+    pub fn new(position: Vec3, edge_u: Vec3, edge_v: Vec3, color: Color, intensity: f32) -> Self {
+        Self {
+            position,
+            edge_u,
+            edge_v,
+            color,
+            intensity,
+            transform: Transform::identity(),
+            intensity_unit: IntensityUnit::Raw,
+        }
+    }
+
+    /// Interpret `intensity` as flux emitted from the front side instead of a raw value
+    #[must_use]
+    pub const fn with_intensity_unit(mut self, unit: IntensityUnit) -> Self {
+        self.intensity_unit = unit;
+        self
+    }
+
+    /// Get the radiance the renderer uses
+    #[must_use]
+    pub fn radiometric_intensity(&self) -> f32 {
+        let extent = std::f32::consts::PI * self.area();
+        self.intensity_unit.to_radiometric(self.intensity, extent.max(f32::EPSILON))
+    }
+
+    /// Get the unit normal of the emitting side
+    #[must_use]
+    pub fn normal(&self) -> Vec3 {
+        self.edge_u.cross(self.edge_v).normalize_or_zero()
+    }
+
+    /// Get the surface area of the light
+    #[must_use]
+    pub fn area(&self) -> f32 {
+        self.edge_u.cross(self.edge_v).length()
+    }
+
+    /// Map `u` in `[0, 1)^2` to a point on the light surface
+    #[must_use]
+    pub fn point_at(&self, u: Vec2) -> Vec3 {
+        self.position + self.edge_u * (u.x - 0.5) + self.edge_v * (u.y - 0.5)
+    }
+
+    /// Light reaching `point` from the whole surface as if it all emitted from
+    /// `source`, weighted by the solid angle it would subtend there
+    fn illuminate_from(&self, source: Vec3, point: Vec3) -> LightContribution {
+        let light_vector = source - point;
+        let distance = light_vector.length();
+        let direction = light_vector / distance;
+        let emitter_cosine = (-direction).dot(self.normal()).max(0.0);
+        let attenuation = (emitter_cosine * self.area() / (distance * distance)).min(1.0);
+
+        LightContribution::new(
+            self.color * self.radiometric_intensity(),
+            direction,
+            distance,
+            attenuation,
+        )
+    }
+}
+
+impl Light for AreaLight {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn illuminate(&self, point: Vec3, _normal: Vec3) -> LightContribution {
+        // Approximate the whole surface by its center
+        self.illuminate_from(self.position, point)
+    }
+
+    fn illuminate_sampled(&self, point: Vec3, _normal: Vec3, u: Vec2) -> LightContribution {
+        self.illuminate_from(self.point_at(u), point)
+    }
+
+    fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn intensity(&self) -> f32 {
+        self.radiometric_intensity()
+    }
+
+    fn affects_point(&self, point: Vec3) -> bool {
+        (point - self.position).dot(self.normal()) > 0.0
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn sample(&self, from: Vec3, u: Vec2) -> LightSample {
+        // Uniform over the surface, so the area-measure PDF is 1 / area
+        let position = self.point_at(u);
+        let area = self.area();
+        let faces_receiver = (from - position).dot(self.normal()) > 0.0;
+        let radiance = if faces_receiver {
+            self.color * self.radiometric_intensity()
+        } else {
+            Color::BLACK
+        };
+        let pdf = if area > 0.0 { 1.0 / area } else { 0.0 };
+        LightSample::new(position, radiance, pdf)
+    }
+}
+
+/// Spherical light bridging an emissive [`Sphere`] into light sampling, so
+/// diffuse surfaces receive its emission through next-event estimation
+/// instead of only when a scattered ray happens to hit it.
+///
+/// The raytracer registers one for every sphere whose material emits light;
+/// build them with [`SphereLight::from_emissive`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SphereLight {
+    /// Center of the sphere in world space
+    pub center: Vec3,
+    /// Radius of the sphere in world space
+    pub radius: f32,
+    /// Radiance emitted from every point of the surface
+    pub radiance: Color,
+    /// Transform of the light
+    pub transform: Transform,
+}
+
+impl SphereLight {
+    /// Create a new spherical light
+    #[must_use]
+    pub fn new(center: Vec3, radius: f32, radiance: Color) -> Self {
+        Self {
+            center,
+            radius,
+            radiance,
+            transform: Transform::identity(),
+        }
+    }
+
+    /// Get a light for an object if it is a sphere whose material emits light
+    #[must_use]
+    pub fn from_emissive(object: &dyn SceneObject) -> Option<Self> {
+        let sphere = object.as_any().downcast_ref::<Sphere>()?;
+        let radiance = sphere.material.as_ref()?.emitted();
+        let emits = radiance.r > 0.0 || radiance.g > 0.0 || radiance.b > 0.0;
+        (emits && sphere.world_radius() > 0.0)
+            .then(|| Self::new(sphere.world_center(), sphere.world_radius(), radiance))
+    }
+
+    /// Get the cosine of the half-angle of the cone the sphere fills as seen
+    /// from a point `distance` from its center outside it
+    fn cone_cosine(&self, distance: f32) -> f32 {
+        let sine = self.radius / distance;
+        sine.mul_add(-sine, 1.0).max(0.0).sqrt()
+    }
+}
+
+impl Light for SphereLight {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn illuminate(&self, point: Vec3, _normal: Vec3) -> LightContribution {
+        // Aim at the center, weighted by the solid angle the sphere subtends
+        let to_center = self.center - point;
+        let distance = to_center.length();
+        if distance <= self.radius {
+            return LightContribution::none();
+        }
+        let solid_angle = 2.0 * std::f32::consts::PI * (1.0 - self.cone_cosine(distance));
+        LightContribution::new(self.radiance, to_center / distance, distance - self.radius, solid_angle)
+    }
+
+    fn illuminate_sampled(&self, point: Vec3, _normal: Vec3, u: Vec2) -> LightContribution {
+        // Uniform over the cone the sphere fills, so the PDF is one over its solid angle
+        let to_center = self.center - point;
+        let distance = to_center.length();
+        if distance <= self.radius {
+            return LightContribution::none();
+        }
+        let axis = to_center / distance;
+        let cone_cosine = self.cone_cosine(distance);
+        let cosine = (1.0 - cone_cosine).mul_add(-u.x, 1.0);
+        let sine = (1.0 - cosine * cosine).max(0.0).sqrt();
+        let (tangent, bitangent) = axis.any_orthonormal_pair();
+        let (phi_sine, phi_cosine) = (2.0 * std::f32::consts::PI * u.y).sin_cos();
+        let direction = tangent * (sine * phi_cosine) + bitangent * (sine * phi_sine) + axis * cosine;
+
+        // Nearest intersection of the sampled direction with the sphere
+        let offset = self.radius.mul_add(self.radius, -(distance * distance * sine * sine)).max(0.0).sqrt();
+        let solid_angle = 2.0 * std::f32::consts::PI * (1.0 - cone_cosine);
+        LightContribution::new(self.radiance, direction, distance.mul_add(cosine, -offset), solid_angle)
+    }
+
+    fn position(&self) -> Vec3 {
+        self.center
+    }
+
+    fn color(&self) -> Color {
+        self.radiance
+    }
+
+    fn intensity(&self) -> f32 {
+        1.0
+    }
+
+    fn affects_point(&self, point: Vec3) -> bool {
+        point.distance(self.center) > self.radius
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn sample(&self, from: Vec3, u: Vec2) -> LightSample {
+        // Uniform over the surface, so the area-measure PDF is 1 / (4 pi r^2)
+        let z = 2.0f32.mul_add(-u.x, 1.0);
+        let ring = (1.0 - z * z).max(0.0).sqrt();
+        let (phi_sine, phi_cosine) = (2.0 * std::f32::consts::PI * u.y).sin_cos();
+        let normal = Vec3::new(ring * phi_cosine, ring * phi_sine, z);
+        let position = self.center + normal * self.radius;
+        let radiance = if (from - position).dot(normal) > 0.0 {
+            self.radiance
+        } else {
+            Color::BLACK
+        };
+        let area = 4.0 * std::f32::consts::PI * self.radius * self.radius;
+        let pdf = if area > 0.0 { 1.0 / area } else { 0.0 };
+        LightSample::new(position, radiance, pdf)
+    }
+}
+
 /// Ambient light (uniform lighting)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AmbientLight {
@@ -362,6 +932,10 @@ impl AmbientLight {
 }
 
 impl Light for AmbientLight {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn illuminate(&self, _point: Vec3, _normal: Vec3) -> LightContribution {
         LightContribution::new(
             self.color * self.intensity,
@@ -395,3 +969,113 @@ impl Light for AmbientLight {
         self.transform = transform;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A spotlight 4 units above the origin shining straight down
+    fn downward_spot() -> SpotLight {
+        SpotLight::new(Vec3::new(0.0, 4.0, 0.0), Vec3::NEG_Y, Color::WHITE, 10.0, 0.5, 0.6)
+    }
+
+    /// Radiance reaching a point on the ground, ignoring the surface
+    fn received(light: &dyn Light, x: f32) -> f32 {
+        let contribution = light.illuminate(Vec3::new(x, 0.0, 0.0), Vec3::Y);
+        contribution.color.r * contribution.attenuation
+    }
+
+    #[test]
+    fn cookie_with_black_center_darkens_the_middle_of_the_disk() {
+        let cookie = image::RgbImage::from_fn(9, 9, |x, y| {
+            let center = x.abs_diff(4) <= 1 && y.abs_diff(4) <= 1;
+            image::Rgb(if center { [0, 0, 0] } else { [255, 255, 255] })
+        });
+        let plain = downward_spot();
+        let with_cookie = downward_spot().with_cookie(Arc::new(DynamicImage::ImageRgb8(cookie)));
+
+        // The center of the disk is dark, its edge still lit like without a cookie
+        assert!(received(&plain, 0.0) > 0.0);
+        assert!(received(&with_cookie, 0.0) == 0.0);
+        let edge = 4.0 * 0.45_f32.tan();
+        assert!(received(&with_cookie, edge) > 0.0);
+        assert!((received(&with_cookie, edge) - received(&plain, edge)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn area_light_samples_cover_its_surface_with_pdf_one_over_area() {
+        // A 2 x 4 panel three units up, facing down
+        let light =
+            AreaLight::new(Vec3::new(0.0, 3.0, 0.0), Vec3::X * 2.0, Vec3::Z * 4.0, Color::WHITE, 1.0);
+        let below = Vec3::ZERO;
+
+        let mut quadrants = [0u32; 4];
+        for i in 0..16u16 {
+            for j in 0..16u16 {
+                let u = Vec2::new((f32::from(i) + 0.5) / 16.0, (f32::from(j) + 0.5) / 16.0);
+                let sample = light.sample(below, u);
+                assert!((sample.pdf - 1.0 / 8.0).abs() < 1e-6);
+                assert!(sample.radiance.r > 0.0);
+                let offset = sample.position - light.position;
+                assert!(offset.y.abs() < 1e-6, "{offset}");
+                assert!(offset.x.abs() <= 1.0 && offset.z.abs() <= 2.0, "{offset}");
+                quadrants[usize::from(offset.x > 0.0) + 2 * usize::from(offset.z > 0.0)] += 1;
+            }
+        }
+        assert_eq!(quadrants, [64; 4]);
+
+        // Nothing is emitted from the back
+        assert!(light.sample(Vec3::new(0.0, 5.0, 0.0), Vec2::splat(0.5)).radiance.r == 0.0);
+    }
+
+    #[test]
+    fn point_light_samples_are_deltas_at_its_position() {
+        let light = PointLight::new(Vec3::new(1.0, 2.0, 3.0), Color::WHITE, 1.0);
+        let sample = light.sample(Vec3::ZERO, Vec2::new(0.3, 0.7));
+        assert_eq!(sample.position, light.position);
+        assert!((sample.pdf - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lumens_convert_to_radiant_intensity_and_light_like_the_raw_value() {
+        use crate::gpu_renderer::pack_point_lights;
+        use std::f32::consts::PI;
+
+        let position = Vec3::new(0.0, 3.0, 0.0);
+        let lumens = PointLight::new(position, Color::WHITE, 2.0 * 4.0 * PI * LUMINOUS_EFFICACY)
+            .with_intensity_unit(IntensityUnit::Lumens);
+        let raw = PointLight::new(position, Color::WHITE, 2.0);
+        assert!((lumens.radiometric_intensity() - 2.0).abs() < 1e-4);
+        assert!((lumens.intensity() - raw.intensity()).abs() < 1e-4);
+
+        for x in [0.0, 1.0, 2.5] {
+            assert!((received(&lumens, x) - received(&raw, x)).abs() < 1e-4, "x = {x}");
+        }
+        // The GPU receives the converted value too
+        let packed = pack_point_lights(&[Arc::new(lumens), Arc::new(raw)], &[]);
+        assert!((packed[0].intensity - packed[1].intensity).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ies_profile_makes_a_point_light_brightest_along_its_peak() {
+        // Brightest straight down, fading to nothing at the horizon
+        let profile = IesProfile {
+            vertical_angles: vec![0.0, 45.0, 90.0],
+            horizontal_angles: vec![0.0],
+            candela: vec![1000.0, 500.0, 0.0],
+        };
+        let light = PointLight::new(Vec3::new(0.0, 4.0, 0.0), Color::WHITE, 10.0).with_ies_profile(Arc::new(profile));
+        let received = |point: Vec3| {
+            let contribution = light.illuminate(point, (light.position - point).normalize());
+            contribution.color.r * contribution.attenuation
+        };
+
+        // Three points at the same distance from the light
+        let below = received(Vec3::ZERO);
+        let diagonal = received(Vec3::new(4.0 / 2.0f32.sqrt(), 4.0 - 4.0 / 2.0f32.sqrt(), 0.0));
+        let beside = received(Vec3::new(4.0, 4.0, 0.0));
+        assert!(below > 0.0);
+        assert!((diagonal / below - 0.5).abs() < 1e-3, "{diagonal} vs {below}");
+        assert!(beside < 1e-3 * below, "{beside} vs {below}");
+    }
+}
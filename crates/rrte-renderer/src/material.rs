@@ -1,18 +1,73 @@
-use rrte_math::{Ray, HitInfo, Color, Vec3};
-use std::sync::Arc;
+use rrte_assets::ImageAsset;
+use rrte_math::{random, Ray, HitInfo, Color, Vec2, Vec3};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
 
 /// Trait for materials that determine how light interacts with surfaces
 pub trait Material: Send + Sync + std::fmt::Debug {
+    /// Get this material as `Any` for downcasting to a concrete type
+    fn as_any(&self) -> &dyn Any;
+
     /// Get the material's albedo (base color)
     fn albedo(&self) -> Color;
+
+    /// Get the albedo at a hit, from its point or texture coordinates;
+    /// uniform by default
+    fn albedo_at(&self, _hit: &HitInfo) -> Color {
+        self.albedo()
+    }
+
+    /// Remap the cosine (N·L) weight of direct light from a light source;
+    /// the default keeps it, giving Lambert shading
+    fn diffuse_response(&self, cosine: f32) -> f32 {
+        cosine
+    }
     
     /// Get the material's ambient color
     fn ambient_color(&self) -> Color {
         self.albedo() * 0.1
     }
     
+    /// Get the radiance the surface emits on its own, in linear HDR (channels may exceed 1)
+    fn emitted(&self) -> Color {
+        Color::BLACK
+    }
+
     /// Calculate scattered ray for reflections/refractions
     fn scatter(&self, ray_in: &Ray, hit: &HitInfo) -> Option<Ray>;
+
+    /// Whether scattering depends on wavelength, so each color channel should be
+    /// traced separately with [`Material::scatter_channel`]
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+
+    /// Scatter only the light of one color channel (0 = red, 1 = green, 2 = blue)
+    fn scatter_channel(&self, ray_in: &Ray, hit: &HitInfo, _channel: usize) -> Option<Ray> {
+        self.scatter(ray_in, hit)
+    }
+
+    /// Probability density of `scatter` producing the `scattered` direction.
+    ///
+    /// Used to weight BSDF samples against light samples for multiple importance
+    /// sampling. The default of zero describes a delta (perfectly specular)
+    /// distribution, which light sampling can never hit.
+    fn scatter_pdf(&self, _ray_in: &Ray, _hit: &HitInfo, _scattered: &Ray) -> f32 {
+        0.0
+    }
+
+    /// Whether the surface is a thin sheet (a leaf, paper, cloth) that light
+    /// can pass through, rather than the boundary of a solid
+    fn is_thin(&self) -> bool {
+        false
+    }
+
+    /// Share of the light reaching the back of a thin surface that passes
+    /// through to its front, tinted by the albedo; ignored unless [`Material::is_thin`]
+    fn translucency(&self) -> f32 {
+        0.0
+    }
     
     /// Get material properties for lighting calculations
     fn get_properties(&self) -> MaterialProperties;
@@ -44,31 +99,57 @@ impl Default for MaterialProperties {
 #[derive(Debug)]
 pub struct LambertianMaterial {
     pub albedo: Color,
+    /// Whether the surface is a thin translucent sheet
+    pub thin: bool,
+    /// Share of light scattered through a thin surface instead of off it, in `[0, 1]`
+    pub translucency: f32,
 }
 
 impl LambertianMaterial {
     pub fn new(albedo: Color) -> Arc<dyn Material> {
-        Arc::new(Self { albedo })
+        Arc::new(Self { albedo, thin: false, translucency: 0.0 })
+    }
+
+    /// Create a thin diffuse sheet that scatters `translucency` of the light
+    /// reaching it out of its other side
+    #[must_use]
+    pub fn thin(albedo: Color, translucency: f32) -> Arc<dyn Material> {
+        Arc::new(Self { albedo, thin: true, translucency: translucency.clamp(0.0, 1.0) })
     }
 }
 
 impl Material for LambertianMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn albedo(&self) -> Color {
         self.albedo
     }
 
     fn scatter(&self, _ray_in: &Ray, hit: &HitInfo) -> Option<Ray> {
-        use rrte_math::vector::Vec3Ext;
-        let scatter_direction = hit.normal + Vec3::random_unit_vector();
-        
-        // Catch degenerate scatter direction
-        let direction = if scatter_direction.length_squared() < 1e-8 {
-            hit.normal
-        } else {
-            scatter_direction
-        };
-        
-        Some(Ray::new(hit.point, direction))
+        // Thin surfaces send part of the light out of their other side
+        let transmit = self.thin && random::random::<f32>() < self.translucency;
+        let normal = if transmit { -hit.normal } else { hit.normal };
+        Some(scatter_diffuse(hit.point, normal))
+    }
+
+    fn scatter_pdf(&self, _ray_in: &Ray, hit: &HitInfo, scattered: &Ray) -> f32 {
+        // Cosine-weighted hemisphere distribution, on both sides for thin surfaces
+        let cosine = hit.normal.dot(scattered.direction.normalize());
+        if !self.thin {
+            return (cosine / std::f32::consts::PI).max(0.0);
+        }
+        let share = if cosine >= 0.0 { 1.0 - self.translucency } else { self.translucency };
+        share * cosine.abs() / std::f32::consts::PI
+    }
+
+    fn is_thin(&self) -> bool {
+        self.thin
+    }
+
+    fn translucency(&self) -> f32 {
+        self.translucency
     }
 
     fn get_properties(&self) -> MaterialProperties {
@@ -80,6 +161,26 @@ impl Material for LambertianMaterial {
     }
 }
 
+/// Scatter a ray from `point` with a cosine-weighted direction around `normal`
+fn scatter_diffuse(point: Vec3, normal: Vec3) -> Ray {
+    use rrte_math::vector::Vec3Ext;
+    let scatter_direction = normal + Vec3::random_unit_vector();
+
+    // Catch degenerate scatter direction
+    let direction = if scatter_direction.length_squared() < 1e-8 {
+        normal
+    } else {
+        scatter_direction
+    };
+
+    Ray::new(point, direction)
+}
+
+/// Probability density of [`scatter_diffuse`] producing `scattered` at a hit
+fn diffuse_pdf(hit: &HitInfo, scattered: &Ray) -> f32 {
+    (hit.normal.dot(scattered.direction.normalize()) / std::f32::consts::PI).max(0.0)
+}
+
 /// Metal material with configurable roughness
 #[derive(Debug)]
 pub struct MetalMaterial {
@@ -94,6 +195,10 @@ impl MetalMaterial {
 }
 
 impl Material for MetalMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn albedo(&self) -> Color {
         self.albedo
     }
@@ -124,15 +229,41 @@ impl Material for MetalMaterial {
 pub struct DielectricMaterial {
     pub ior: f32, // Index of refraction
     pub color: Color,
+    /// Index of refraction per color channel (red, green, blue) for dispersion
+    pub channel_iors: Option<[f32; 3]>,
 }
 
+/// Wavelengths in micrometers sampled for the red, green and blue channels:
+/// the Fraunhofer C, d and F lines used to define the Abbe number
+const CHANNEL_WAVELENGTHS: [f32; 3] = [0.6563, 0.5876, 0.4861];
+
 impl DielectricMaterial {
     pub fn new(ior: f32) -> Arc<dyn Material> {
-        Arc::new(Self { ior, color: Color::WHITE })
+        Arc::new(Self { ior, color: Color::WHITE, channel_iors: None })
     }
     
     pub fn with_color(ior: f32, color: Color) -> Arc<dyn Material> {
-        Arc::new(Self { ior, color })
+        Arc::new(Self { ior, color, channel_iors: None })
+    }
+
+    /// Create a dispersive dielectric with a separate index of refraction per
+    /// color channel; the green index is used when dispersion is disabled
+    #[must_use]
+    pub fn dispersive(channel_iors: [f32; 3], color: Color) -> Arc<dyn Material> {
+        Arc::new(Self { ior: channel_iors[1], color, channel_iors: Some(channel_iors) })
+    }
+
+    /// Create a dispersive dielectric from its index of refraction and Abbe number
+    /// (about 64 for crown glass, 36 for flint glass; lower disperses more)
+    #[must_use]
+    pub fn with_abbe(ior: f32, abbe: f32, color: Color) -> Arc<dyn Material> {
+        Self::dispersive(cauchy_iors(ior, abbe), color)
+    }
+
+    /// Get the index of refraction seen by a color channel
+    #[must_use]
+    pub fn channel_ior(&self, channel: usize) -> f32 {
+        self.channel_iors.map_or(self.ior, |iors| iors[channel])
     }
     
     fn reflectance(cosine: f32, ref_idx: f32) -> f32 {
@@ -141,20 +272,14 @@ impl DielectricMaterial {
         let r0 = r0 * r0;
         r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
     }
-}
-
-impl Material for DielectricMaterial {
-    fn albedo(&self) -> Color {
-        self.color
-    }
 
-    fn scatter(&self, ray_in: &Ray, hit: &HitInfo) -> Option<Ray> {
+    fn scatter_with_ior(ray_in: &Ray, hit: &HitInfo, ior: f32) -> Ray {
         use rrte_math::vector::Vec3Ext;
         
         let refraction_ratio = if hit.front_face {
-            1.0 / self.ior
+            1.0 / ior
         } else {
-            self.ior
+            ior
         };
 
         let unit_direction = ray_in.direction.normalize();
@@ -163,13 +288,46 @@ impl Material for DielectricMaterial {
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
         
-        let direction = if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > rand::random() {
+        let direction = if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > random::random() {
             unit_direction.reflect(hit.normal)
         } else {
             unit_direction.refract(hit.normal, refraction_ratio).unwrap_or(unit_direction.reflect(hit.normal))
         };
 
-        Some(Ray::new(hit.point, direction))
+        Ray::new(hit.point, direction)
+    }
+}
+
+/// Per-channel indices of refraction following Cauchy's equation `n = A + B / λ²`,
+/// fitted so the green channel has index `ior` and `(n_green - 1) / (n_blue - n_red)`
+/// equals the Abbe number
+#[must_use]
+pub fn cauchy_iors(ior: f32, abbe: f32) -> [f32; 3] {
+    let [red, green, blue] = CHANNEL_WAVELENGTHS.map(|wavelength| 1.0 / (wavelength * wavelength));
+    let b = (ior - 1.0) / (abbe.max(f32::EPSILON) * (blue - red));
+    let a = ior - b * green;
+    [a + b * red, ior, a + b * blue]
+}
+
+impl Material for DielectricMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn albedo(&self) -> Color {
+        self.color
+    }
+
+    fn scatter(&self, ray_in: &Ray, hit: &HitInfo) -> Option<Ray> {
+        Some(Self::scatter_with_ior(ray_in, hit, self.ior))
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.channel_iors.is_some()
+    }
+
+    fn scatter_channel(&self, ray_in: &Ray, hit: &HitInfo, channel: usize) -> Option<Ray> {
+        Some(Self::scatter_with_ior(ray_in, hit, self.channel_ior(channel)))
     }
 
     fn get_properties(&self) -> MaterialProperties {
@@ -182,7 +340,310 @@ impl Material for DielectricMaterial {
     }
 }
 
-/// Emissive material that acts as a light source
+/// Layered material: a glossy dielectric clearcoat over a base material (car paint, varnish)
+#[derive(Debug)]
+pub struct CoatedMaterial {
+    /// Material under the coat, lit by the light the coat transmits
+    pub base: Arc<dyn Material>,
+    /// Index of refraction of the coat
+    pub coat_ior: f32,
+    /// Roughness of the coat's reflection, from 0 (mirror) to 1
+    pub coat_roughness: f32,
+}
+
+impl CoatedMaterial {
+    /// Create a coated material; `coat_roughness` is clamped to `[0, 1]`
+    pub fn new(base: Arc<dyn Material>, coat_ior: f32, coat_roughness: f32) -> Arc<dyn Material> {
+        Arc::new(Self {
+            base,
+            coat_ior,
+            coat_roughness: coat_roughness.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Fraction of light reflected by the coat for an incoming ray
+    #[must_use]
+    pub fn coat_reflectance(&self, ray_in: &Ray, hit: &HitInfo) -> f32 {
+        let cos_theta = (-ray_in.direction.normalize()).dot(hit.normal).clamp(0.0, 1.0);
+        DielectricMaterial::reflectance(cos_theta, self.coat_ior)
+    }
+}
+
+impl Material for CoatedMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn albedo(&self) -> Color {
+        self.base.albedo()
+    }
+
+    fn albedo_at(&self, hit: &HitInfo) -> Color {
+        self.base.albedo_at(hit)
+    }
+
+    fn diffuse_response(&self, cosine: f32) -> f32 {
+        self.base.diffuse_response(cosine)
+    }
+
+    fn ambient_color(&self) -> Color {
+        self.base.ambient_color()
+    }
+
+    fn scatter(&self, ray_in: &Ray, hit: &HitInfo) -> Option<Ray> {
+        use rrte_math::vector::Vec3Ext;
+
+        // Reflect off the coat with Fresnel probability, otherwise pass through to the base
+        if self.coat_reflectance(ray_in, hit) > random::random() {
+            let reflected = ray_in.direction.normalize().reflect(hit.normal);
+            let scattered = reflected + self.coat_roughness * Vec3::random_in_unit_sphere();
+            if scattered.dot(hit.normal) > 0.0 {
+                return Some(Ray::new(hit.point, scattered));
+            }
+        }
+        self.base.scatter(ray_in, hit)
+    }
+
+    fn scatter_pdf(&self, ray_in: &Ray, hit: &HitInfo, scattered: &Ray) -> f32 {
+        // Only the base layer is sampleable; the coat is treated as a delta lobe
+        (1.0 - self.coat_reflectance(ray_in, hit)) * self.base.scatter_pdf(ray_in, hit, scattered)
+    }
+
+    fn get_properties(&self) -> MaterialProperties {
+        let r0 = DielectricMaterial::reflectance(1.0, self.coat_ior);
+        MaterialProperties {
+            specular: r0,
+            ..self.base.get_properties()
+        }
+    }
+}
+
+/// Diffuse material colored through a color ramp by the hit point's
+/// position along an axis, by default its height.
+///
+/// Points at `start` along `axis` take the first stop's color and points at
+/// `end` the last one's; points beyond either end keep the nearest color.
+#[derive(Debug)]
+pub struct GradientMaterial {
+    /// Ramp stops as `(position, color)` with positions in `[0, 1]`, ascending
+    pub stops: Vec<(f32, Color)>,
+    /// Unit direction the ramp runs along
+    pub axis: Vec3,
+    /// Distance along `axis` from the world origin where the ramp begins
+    pub start: f32,
+    /// Distance along `axis` from the world origin where the ramp ends
+    pub end: f32,
+}
+
+impl GradientMaterial {
+    /// Create a vertical gradient from `low` at height `bottom` to `high` at height `top`
+    #[must_use]
+    pub fn new(low: Color, high: Color, bottom: f32, top: f32) -> Arc<dyn Material> {
+        Self::with_ramp(vec![(0.0, low), (1.0, high)], Vec3::Y, bottom, top)
+    }
+
+    /// Create a gradient through several color stops along any axis
+    #[must_use]
+    pub fn with_ramp(mut stops: Vec<(f32, Color)>, axis: Vec3, start: f32, end: f32) -> Arc<dyn Material> {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Arc::new(Self {
+            stops,
+            axis: axis.normalize_or_zero(),
+            start,
+            end,
+        })
+    }
+
+    /// Get the ramp's color at `t` in `[0, 1]`, blending between the stops around it
+    #[must_use]
+    pub fn color_at(&self, t: f32) -> Color {
+        let (Some(&(first_t, first)), Some(&(last_t, last))) = (self.stops.first(), self.stops.last()) else {
+            return Color::BLACK;
+        };
+        if t <= first_t {
+            return first;
+        }
+        if t >= last_t {
+            return last;
+        }
+        let upper = self.stops.partition_point(|&(stop_t, _)| stop_t < t);
+        let (t0, c0) = self.stops[upper - 1];
+        let (t1, c1) = self.stops[upper];
+        c0.lerp(&c1, (t - t0) / (t1 - t0))
+    }
+
+    /// Get how far a point lies along the ramp, 0 at `start` and 1 at `end`
+    #[must_use]
+    pub fn ramp_position(&self, point: Vec3) -> f32 {
+        let span = self.end - self.start;
+        if span.abs() < f32::EPSILON {
+            return 0.0;
+        }
+        ((point.dot(self.axis) - self.start) / span).clamp(0.0, 1.0)
+    }
+}
+
+impl Material for GradientMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn albedo(&self) -> Color {
+        self.color_at(0.5)
+    }
+
+    fn albedo_at(&self, hit: &HitInfo) -> Color {
+        self.color_at(self.ramp_position(hit.point))
+    }
+
+    fn scatter(&self, _ray_in: &Ray, hit: &HitInfo) -> Option<Ray> {
+        Some(scatter_diffuse(hit.point, hit.normal))
+    }
+
+    fn scatter_pdf(&self, _ray_in: &Ray, hit: &HitInfo, scattered: &Ray) -> f32 {
+        diffuse_pdf(hit, scattered)
+    }
+
+    fn get_properties(&self) -> MaterialProperties {
+        MaterialProperties {
+            metallic: 0.0,
+            roughness: 1.0,
+            ..Default::default()
+        }
+    }
+}
+
+/// Diffuse material whose albedo comes from an image sampled at the hit's
+/// texture coordinates.
+///
+/// Texels are decoded from sRGB and multiplied by `tint`. Coordinates repeat
+/// outside `[0, 1]`; `v = 0` is the bottom row of the image.
+#[derive(Debug)]
+pub struct TexturedMaterial {
+    /// Image the albedo is sampled from
+    pub texture: Arc<ImageAsset>,
+    /// Color the texture is multiplied by
+    pub tint: Color,
+}
+
+impl TexturedMaterial {
+    /// Create an untinted textured material
+    #[must_use]
+    pub fn new(texture: Arc<ImageAsset>) -> Arc<dyn Material> {
+        Self::tinted(texture, Color::WHITE)
+    }
+
+    /// Create a textured material multiplied by a color
+    #[must_use]
+    pub fn tinted(texture: Arc<ImageAsset>, tint: Color) -> Arc<dyn Material> {
+        Arc::new(Self { texture, tint })
+    }
+
+    /// Get the nearest texel's linear color at texture coordinates `uv`
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)] // Wrapped UVs give a small positive index
+    pub fn sample(&self, uv: Vec2) -> Color {
+        use image::GenericImageView;
+        let (width, height) = self.texture.data.dimensions();
+        if width == 0 || height == 0 {
+            return self.tint;
+        }
+
+        let u = uv.x.rem_euclid(1.0);
+        let v = 1.0 - uv.y.rem_euclid(1.0);
+        let x = ((u * width as f32) as u32).min(width - 1);
+        let y = ((v * height as f32) as u32).min(height - 1);
+        let texel = Color::from_srgb8(self.texture.data.get_pixel(x, y).0);
+        Color::new(texel.r * self.tint.r, texel.g * self.tint.g, texel.b * self.tint.b, texel.a * self.tint.a)
+    }
+}
+
+impl Material for TexturedMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn albedo(&self) -> Color {
+        self.sample(Vec2::splat(0.5))
+    }
+
+    fn albedo_at(&self, hit: &HitInfo) -> Color {
+        self.sample(hit.uv)
+    }
+
+    fn scatter(&self, _ray_in: &Ray, hit: &HitInfo) -> Option<Ray> {
+        Some(scatter_diffuse(hit.point, hit.normal))
+    }
+
+    fn scatter_pdf(&self, _ray_in: &Ray, hit: &HitInfo, scattered: &Ray) -> f32 {
+        diffuse_pdf(hit, scattered)
+    }
+
+    fn get_properties(&self) -> MaterialProperties {
+        MaterialProperties {
+            metallic: 0.0,
+            roughness: 1.0,
+            ..Default::default()
+        }
+    }
+}
+
+/// Diffuse material for a cel-shaded look: direct light is quantized into
+/// `bands` flat steps instead of falling off smoothly
+#[derive(Debug)]
+pub struct ToonMaterial {
+    /// Color when fully lit
+    pub albedo: Color,
+    /// Number of brightness steps between unlit and fully lit, at least 1
+    pub bands: u32,
+}
+
+impl ToonMaterial {
+    /// Create a toon material; `bands` is raised to at least 1
+    #[must_use]
+    pub fn new(albedo: Color, bands: u32) -> Arc<dyn Material> {
+        Arc::new(Self { albedo, bands: bands.max(1) })
+    }
+}
+
+impl Material for ToonMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn albedo(&self) -> Color {
+        self.albedo
+    }
+
+    fn diffuse_response(&self, cosine: f32) -> f32 {
+        // Round up, so every lit point gets at least the first band
+        let bands = self.bands.max(1) as f32;
+        ((cosine.clamp(0.0, 1.0) * bands).ceil() / bands).min(1.0)
+    }
+
+    fn scatter(&self, _ray_in: &Ray, hit: &HitInfo) -> Option<Ray> {
+        Some(scatter_diffuse(hit.point, hit.normal))
+    }
+
+    fn scatter_pdf(&self, _ray_in: &Ray, hit: &HitInfo, scattered: &Ray) -> f32 {
+        diffuse_pdf(hit, scattered)
+    }
+
+    fn get_properties(&self) -> MaterialProperties {
+        MaterialProperties {
+            metallic: 0.0,
+            roughness: 1.0,
+            ..Default::default()
+        }
+    }
+}
+
+/// Emissive material that acts as a light source.
+///
+/// The emitted radiance is `color * intensity`; `color` is linear HDR, so
+/// channels above 1 express tints brighter than white. Emission is added to
+/// every ray that hits the surface, so it shows on the object itself and
+/// lights other surfaces through their bounces.
 #[derive(Debug)]
 pub struct EmissiveMaterial {
     pub color: Color,
@@ -193,21 +654,483 @@ impl EmissiveMaterial {
     pub fn new(color: Color, intensity: f32) -> Arc<dyn Material> {
         Arc::new(Self { color, intensity })
     }
+
+    /// Create an emissive material from an HDR emission color with unit intensity
+    #[must_use]
+    pub fn hdr(emission: Color) -> Arc<dyn Material> {
+        Self::new(emission, 1.0)
+    }
+
+    /// Get the emitted radiance, `color * intensity`
+    #[must_use]
+    pub fn emission(&self) -> Color {
+        Color::from(self.color.to_vec3() * self.intensity)
+    }
 }
 
 impl Material for EmissiveMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn albedo(&self) -> Color {
         self.color
     }
 
+    fn emitted(&self) -> Color {
+        self.emission()
+    }
+
     fn scatter(&self, _ray_in: &Ray, _hit: &HitInfo) -> Option<Ray> {
         None // Emissive materials don't scatter light
     }
 
     fn get_properties(&self) -> MaterialProperties {
         MaterialProperties {
-            emission: self.color * self.intensity,
+            emission: self.emission(),
             ..Default::default()
         }
     }
 }
+
+/// Identifier of a material in a [`MaterialRegistry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MaterialId(u32);
+
+impl MaterialId {
+    /// Get the index of the material in its registry
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Shared table of materials that objects reference by [`MaterialId`].
+///
+/// Objects hold a [`RegisteredMaterial`] from [`MaterialRegistry::reference`]
+/// instead of the material itself. A reference caches the entry it resolved to,
+/// so shading never touches the table; after replacing an entry with
+/// [`MaterialRegistry::set`], objects pick up the new material through
+/// [`SceneObject::refresh_materials`](crate::SceneObject::refresh_materials).
+/// Clones share the same table.
+#[derive(Debug, Clone, Default)]
+pub struct MaterialRegistry {
+    materials: Arc<RwLock<Vec<Arc<dyn Material>>>>,
+}
+
+impl MaterialRegistry {
+    /// Create an empty registry
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a material and return its id
+    pub fn register(&self, material: Arc<dyn Material>) -> MaterialId {
+        let mut materials = self.materials.write().unwrap_or_else(PoisonError::into_inner);
+        materials.push(material);
+        MaterialId((materials.len() - 1) as u32)
+    }
+
+    /// Get the material registered under `id`
+    pub fn get(&self, id: MaterialId) -> Option<Arc<dyn Material>> {
+        self.materials.read().unwrap_or_else(PoisonError::into_inner).get(id.index()).cloned()
+    }
+
+    /// Replace the material registered under `id`; returns false if there is none
+    pub fn set(&self, id: MaterialId, material: Arc<dyn Material>) -> bool {
+        let mut materials = self.materials.write().unwrap_or_else(PoisonError::into_inner);
+        materials.get_mut(id.index()).map(|entry| *entry = material).is_some()
+    }
+
+    /// Get a material that resolves to what is registered under `id`, for
+    /// assigning to objects; `None` if `id` is not registered
+    #[must_use]
+    pub fn reference(&self, id: MaterialId) -> Option<Arc<dyn Material>> {
+        let material = self.get(id)?;
+        Some(Arc::new(RegisteredMaterial {
+            id,
+            registry: self.clone(),
+            material,
+        }))
+    }
+
+    /// Check whether `material` is a reference into this registry whose entry
+    /// has been replaced since it was resolved
+    pub fn is_stale(&self, material: &dyn Material) -> bool {
+        material
+            .as_any()
+            .downcast_ref::<RegisteredMaterial>()
+            .filter(|registered| Arc::ptr_eq(&registered.registry.materials, &self.materials))
+            .and_then(|registered| {
+                self.get(registered.id)
+                    .map(|current| !Arc::ptr_eq(&current, &registered.material))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Replace a stale reference into this registry with a fresh one; returns
+    /// whether `material` changed
+    pub fn refresh(&self, material: &mut Arc<dyn Material>) -> bool {
+        if !self.is_stale(material.as_ref()) {
+            return false;
+        }
+        let fresh = material
+            .as_any()
+            .downcast_ref::<RegisteredMaterial>()
+            .and_then(|registered| self.reference(registered.id));
+        fresh.map(|fresh| *material = fresh).is_some()
+    }
+
+    /// Get the number of registered materials
+    pub fn len(&self) -> usize {
+        self.materials.read().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// Check whether no materials are registered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Material that forwards to an entry of a [`MaterialRegistry`].
+///
+/// The entry is resolved when the reference is made and cached, so shading
+/// does not lock the registry; [`MaterialRegistry::refresh`] swaps in a new
+/// reference once the entry is replaced.
+#[derive(Debug, Clone)]
+pub struct RegisteredMaterial {
+    id: MaterialId,
+    registry: MaterialRegistry,
+    material: Arc<dyn Material>,
+}
+
+impl RegisteredMaterial {
+    /// Get the id this material refers to
+    #[must_use]
+    pub const fn id(&self) -> MaterialId {
+        self.id
+    }
+
+    /// Get the material the id resolved to when this reference was made
+    #[must_use]
+    pub fn resolve(&self) -> Arc<dyn Material> {
+        Arc::clone(&self.material)
+    }
+}
+
+impl Material for RegisteredMaterial {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn albedo(&self) -> Color {
+        self.material.albedo()
+    }
+
+    fn albedo_at(&self, hit: &HitInfo) -> Color {
+        self.material.albedo_at(hit)
+    }
+
+    fn diffuse_response(&self, cosine: f32) -> f32 {
+        self.material.diffuse_response(cosine)
+    }
+
+    fn ambient_color(&self) -> Color {
+        self.material.ambient_color()
+    }
+
+    fn emitted(&self) -> Color {
+        self.material.emitted()
+    }
+
+    fn scatter(&self, ray_in: &Ray, hit: &HitInfo) -> Option<Ray> {
+        self.material.scatter(ray_in, hit)
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.material.is_dispersive()
+    }
+
+    fn scatter_channel(&self, ray_in: &Ray, hit: &HitInfo, channel: usize) -> Option<Ray> {
+        self.material.scatter_channel(ray_in, hit, channel)
+    }
+
+    fn scatter_pdf(&self, ray_in: &Ray, hit: &HitInfo, scattered: &Ray) -> f32 {
+        self.material.scatter_pdf(ray_in, hit, scattered)
+    }
+
+    fn is_thin(&self) -> bool {
+        self.material.is_thin()
+    }
+
+    fn translucency(&self) -> f32 {
+        self.material.translucency()
+    }
+
+    fn get_properties(&self) -> MaterialProperties {
+        self.material.get_properties()
+    }
+}
+
+/// Value of a material the pool can compare, with floats stored as bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MaterialKey {
+    Lambertian { albedo: [u32; 4], thin: bool, translucency: u32 },
+    Metal { albedo: [u32; 4], roughness: u32 },
+    Dielectric { ior: u32, color: [u32; 4], channel_iors: Option<[u32; 3]> },
+    Emissive { color: [u32; 4], intensity: u32 },
+}
+
+fn color_bits(color: Color) -> [u32; 4] {
+    [color.r, color.g, color.b, color.a].map(f32::to_bits)
+}
+
+impl MaterialKey {
+    /// Describe a material, or `None` for kinds the pool does not intern
+    fn of(material: &dyn Material) -> Option<Self> {
+        let any = material.as_any();
+        if let Some(lambertian) = any.downcast_ref::<LambertianMaterial>() {
+            return Some(Self::Lambertian {
+                albedo: color_bits(lambertian.albedo),
+                thin: lambertian.thin,
+                translucency: lambertian.translucency.to_bits(),
+            });
+        }
+        if let Some(metal) = any.downcast_ref::<MetalMaterial>() {
+            return Some(Self::Metal { albedo: color_bits(metal.albedo), roughness: metal.roughness.to_bits() });
+        }
+        if let Some(dielectric) = any.downcast_ref::<DielectricMaterial>() {
+            return Some(Self::Dielectric {
+                ior: dielectric.ior.to_bits(),
+                color: color_bits(dielectric.color),
+                channel_iors: dielectric.channel_iors.map(|iors| iors.map(f32::to_bits)),
+            });
+        }
+        any.downcast_ref::<EmissiveMaterial>().map(|emissive| Self::Emissive {
+            color: color_bits(emissive.color),
+            intensity: emissive.intensity.to_bits(),
+        })
+    }
+}
+
+/// Interning pool that hands out one shared `Arc` per distinct material value.
+///
+/// Materials requested with equal parameters share an allocation, and so a
+/// single entry in the GPU material buffer, which deduplicates by pointer.
+/// Lambertian, metal, dielectric and emissive materials are interned; other
+/// kinds pass through [`MaterialPool::intern`] unchanged. Clones share the
+/// same pool.
+#[derive(Debug, Clone, Default)]
+pub struct MaterialPool {
+    materials: Arc<RwLock<HashMap<MaterialKey, Arc<dyn Material>>>>,
+}
+
+impl MaterialPool {
+    /// Create an empty pool
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the pooled material equal to `material`, adding `material` if there
+    /// is none yet.
+    ///
+    /// The constructors below look the value up before allocating; prefer them
+    /// when building a new material.
+    pub fn intern(&self, material: Arc<dyn Material>) -> Arc<dyn Material> {
+        match MaterialKey::of(material.as_ref()) {
+            Some(key) => self.get_or_insert(key, || material),
+            None => material,
+        }
+    }
+
+    /// Get the pooled diffuse material of a color
+    #[must_use]
+    pub fn lambertian(&self, albedo: Color) -> Arc<dyn Material> {
+        let key = MaterialKey::Lambertian { albedo: color_bits(albedo), thin: false, translucency: 0.0f32.to_bits() };
+        self.get_or_insert(key, || LambertianMaterial::new(albedo))
+    }
+
+    /// Get the pooled metal of a color and roughness
+    #[must_use]
+    pub fn metal(&self, albedo: Color, roughness: f32) -> Arc<dyn Material> {
+        let key = MaterialKey::Metal { albedo: color_bits(albedo), roughness: roughness.clamp(0.0, 1.0).to_bits() };
+        self.get_or_insert(key, || MetalMaterial::new(albedo, roughness))
+    }
+
+    /// Get the pooled clear dielectric of an index of refraction
+    #[must_use]
+    pub fn dielectric(&self, ior: f32) -> Arc<dyn Material> {
+        let key = MaterialKey::Dielectric { ior: ior.to_bits(), color: color_bits(Color::WHITE), channel_iors: None };
+        self.get_or_insert(key, || DielectricMaterial::new(ior))
+    }
+
+    /// Get the pooled emitter of a color and intensity
+    #[must_use]
+    pub fn emissive(&self, color: Color, intensity: f32) -> Arc<dyn Material> {
+        let key = MaterialKey::Emissive { color: color_bits(color), intensity: intensity.to_bits() };
+        self.get_or_insert(key, || EmissiveMaterial::new(color, intensity))
+    }
+
+    /// Get the number of distinct materials in the pool
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.materials.read().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// Check whether the pool holds no materials
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every pooled material; `Arc`s already handed out stay valid
+    pub fn clear(&self) {
+        self.materials.write().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+
+    fn get_or_insert(&self, key: MaterialKey, make: impl FnOnce() -> Arc<dyn Material>) -> Arc<dyn Material> {
+        if let Some(material) = self.materials.read().unwrap_or_else(PoisonError::into_inner).get(&key) {
+            return Arc::clone(material);
+        }
+        Arc::clone(self.materials.write().unwrap_or_else(PoisonError::into_inner).entry(key).or_insert_with(make))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rrte_math::vector::Vec3Ext;
+
+    /// A hit on the XZ plane, seen from above
+    fn hit_from_above() -> (Ray, HitInfo) {
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::NEG_Y);
+        let hit = HitInfo::new(1.0, Vec3::ZERO, Vec3::Y, &ray);
+        (ray, hit)
+    }
+
+    #[test]
+    fn lambertian_pdf_integrates_to_one_over_the_hemisphere() {
+        random::seed(1);
+        let material = LambertianMaterial::new(Color::WHITE);
+        let (ray, hit) = hit_from_above();
+
+        // Uniform directions over the sphere have density 1 / 4π
+        let samples: u16 = 60_000;
+        let sum: f32 = (0..samples)
+            .map(|_| material.scatter_pdf(&ray, &hit, &Ray::new(hit.point, Vec3::random_unit_vector())))
+            .sum();
+        let integral = sum / f32::from(samples) * 4.0 * std::f32::consts::PI;
+        assert!((integral - 1.0).abs() < 0.02, "{integral}");
+    }
+
+    #[test]
+    fn specular_materials_have_a_delta_pdf() {
+        let (ray, hit) = hit_from_above();
+        let scattered = Ray::new(hit.point, Vec3::Y);
+        assert!(MetalMaterial::new(Color::WHITE, 0.0).scatter_pdf(&ray, &hit, &scattered) == 0.0);
+        assert!(DielectricMaterial::new(1.5).scatter_pdf(&ray, &hit, &scattered) == 0.0);
+    }
+
+    #[test]
+    fn clearcoat_reflects_more_often_at_grazing_angles() {
+        random::seed(2);
+        let coated = CoatedMaterial::new(LambertianMaterial::new(Color::WHITE), 1.5, 0.0);
+
+        // How many of 4000 smooth-coat scatters leave along the mirror direction
+        let mirrored = |incoming: Vec3| {
+            let ray = Ray::new(-incoming, incoming);
+            let hit = HitInfo::new(1.0, Vec3::ZERO, Vec3::Y, &ray);
+            let mirror = incoming.reflect(Vec3::Y);
+            (0..4000)
+                .filter_map(|_| coated.scatter(&ray, &hit))
+                .filter(|scattered| scattered.direction.normalize().abs_diff_eq(mirror, 1e-4))
+                .count()
+        };
+
+        // About 4% at normal incidence for an IOR of 1.5, over 40% at 85°
+        let normal = mirrored(Vec3::NEG_Y);
+        let grazing = mirrored(Vec3::new(85_f32.to_radians().sin(), -85_f32.to_radians().cos(), 0.0));
+        assert!((80..=240).contains(&normal), "{normal}");
+        assert!(grazing > 1200, "{grazing}");
+    }
+
+    #[test]
+    fn dispersive_prism_separates_red_and_blue() {
+        random::seed(3);
+        let prism = DielectricMaterial::with_abbe(1.5, 20.0, Color::WHITE);
+        assert!(prism.is_dispersive());
+
+        // Schlick's approximation reflects now and then; keep the first refraction
+        let refract = |ray: &Ray, point: Vec3, outward_normal: Vec3, channel: usize| {
+            let hit = HitInfo::new(1.0, point, outward_normal, ray);
+            let entering = ray.direction.dot(outward_normal) < 0.0;
+            (0..100)
+                .filter_map(|_| prism.scatter_channel(ray, &hit, channel))
+                .find(|scattered| (scattered.direction.dot(outward_normal) < 0.0) == entering)
+                .unwrap()
+        };
+
+        // A white ray enters the x = 0 face at 45 degrees and leaves through a
+        // face tilted against it, like a prism
+        let exit_normal = Vec3::new(1.0, -0.3, 0.0).normalize();
+        let exit_point = Vec3::new(1.0, 0.0, 0.0);
+        let white = Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0).normalize());
+        let exit = |channel: usize| {
+            let inside = refract(&white, Vec3::ZERO, Vec3::NEG_X, channel);
+            let t = (exit_point - inside.origin).dot(exit_normal) / inside.direction.dot(exit_normal);
+            let point = inside.origin + inside.direction * t;
+            (point, refract(&inside, point, exit_normal, channel).direction)
+        };
+
+        let (red_point, red_direction) = exit(0);
+        let (blue_point, blue_direction) = exit(2);
+        assert!(red_point.distance(blue_point) > 1e-3, "{red_point:?} {blue_point:?}");
+        assert!(red_direction.dot(blue_direction) < 1.0 - 1e-5);
+        // Blue sees the higher index, so it bends further toward the entry normal
+        assert!(blue_point.y > red_point.y);
+    }
+
+    #[test]
+    fn pool_hands_out_one_allocation_per_material_value() {
+        let pool = MaterialPool::new();
+        let red = Color::new(0.8, 0.1, 0.1, 1.0);
+        let first = pool.lambertian(red);
+        let second = pool.lambertian(red);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &pool.lambertian(Color::WHITE)));
+        assert!(!Arc::ptr_eq(&first, &pool.metal(red, 0.0)));
+
+        // An equal material built outside the pool resolves to the pooled one
+        assert!(Arc::ptr_eq(&pool.intern(LambertianMaterial::new(red)), &first));
+        assert_eq!(pool.len(), 3);
+
+        // Clones share the pool
+        assert!(Arc::ptr_eq(&pool.clone().lambertian(red), &first));
+        pool.clear();
+        assert!(pool.is_empty());
+        assert!(!Arc::ptr_eq(&pool.lambertian(red), &first));
+    }
+
+    #[test]
+    fn gradient_is_the_low_color_at_the_bottom_and_the_high_color_at_the_top() {
+        use crate::{SceneObject, Sphere};
+
+        let low = Color::new(1.0, 0.0, 0.0, 1.0);
+        let high = Color::new(0.0, 0.0, 1.0, 1.0);
+        // The sphere spans heights 0 to 2
+        let sphere = Sphere::with_material(Vec3::new(0.0, 1.0, 0.0), 1.0, GradientMaterial::new(low, high, 0.0, 2.0));
+        let material = sphere.material.clone().unwrap();
+        let hit_along = |origin: Vec3, direction: Vec3| sphere.intersect(&Ray::new(origin, direction), 0.001, f32::INFINITY).unwrap();
+
+        let bottom = hit_along(Vec3::new(0.0, -5.0, 0.0), Vec3::Y);
+        let top = hit_along(Vec3::new(0.0, 5.0, 0.0), Vec3::NEG_Y);
+        let side = hit_along(Vec3::new(5.0, 1.0, 0.0), Vec3::NEG_X);
+        assert_eq!(material.albedo_at(&bottom), low);
+        assert_eq!(material.albedo_at(&top), high);
+        let middle = material.albedo_at(&side);
+        assert!((middle.r - 0.5).abs() < 1e-5 && (middle.b - 0.5).abs() < 1e-5, "{middle:?}");
+    }
+}
@@ -0,0 +1,215 @@
+// Pixel coordinates, sizes and sample counts stay far below 2^24, so
+// converting them to f32 is exact
+#![allow(clippy::cast_precision_loss)]
+
+use crate::camera::Camera;
+use crate::gpu_renderer::GpuRenderer;
+use crate::light::PointLight;
+use crate::primitives::Sphere;
+use crate::raytracer::Raytracer;
+use crate::{Light, SceneObject};
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+
+/// Side length of the blocks the structural similarity is averaged over
+const SSIM_BLOCK: usize = 8;
+
+/// Differences between two RGBA8 images of the same size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDiff {
+    /// Mean absolute error per channel (RGBA), in `[0, 1]`
+    pub mean_error: [f32; 4],
+    /// Largest absolute error per channel (RGBA), in `[0, 1]`
+    pub max_error: [f32; 4],
+    /// Structural similarity of the luminance, averaged over 8x8 blocks; 1 for identical images
+    pub ssim: f32,
+}
+
+impl ImageDiff {
+    /// Get the mean absolute error over the color channels, ignoring alpha
+    #[must_use]
+    pub fn mean_color_error(&self) -> f32 {
+        (self.mean_error[0] + self.mean_error[1] + self.mean_error[2]) / 3.0
+    }
+
+    /// Check whether the difference is within a tolerance
+    #[must_use]
+    pub fn is_within(&self, tolerance: &DiffTolerance) -> bool {
+        self.mean_color_error() <= tolerance.max_mean_error && self.ssim >= tolerance.min_ssim
+    }
+}
+
+/// Largest [`ImageDiff`] considered a match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffTolerance {
+    /// Largest allowed [`ImageDiff::mean_color_error`]
+    pub max_mean_error: f32,
+    /// Smallest allowed [`ImageDiff::ssim`]
+    pub min_ssim: f32,
+}
+
+impl Default for DiffTolerance {
+    fn default() -> Self {
+        Self {
+            max_mean_error: 0.05,
+            min_ssim: 0.8,
+        }
+    }
+}
+
+/// Compare two tightly packed `width` x `height` RGBA8 images
+#[must_use]
+pub fn compare_images(a: &[u8], b: &[u8], width: usize, height: usize) -> ImageDiff {
+    debug_assert_eq!(a.len(), width * height * 4);
+    debug_assert_eq!(b.len(), width * height * 4);
+
+    let mut error_sum = [0.0f64; 4];
+    let mut max_error = [0.0f32; 4];
+    for (pixel_a, pixel_b) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+        for channel in 0..4 {
+            let error = f32::from(pixel_a[channel].abs_diff(pixel_b[channel])) / 255.0;
+            error_sum[channel] += f64::from(error);
+            max_error[channel] = max_error[channel].max(error);
+        }
+    }
+    let pixel_count = (width * height).max(1) as f64;
+
+    ImageDiff {
+        mean_error: error_sum.map(|sum| (sum / pixel_count) as f32),
+        max_error,
+        ssim: block_ssim(&luminance(a), &luminance(b), width, height),
+    }
+}
+
+/// Luminance of every pixel of an RGBA8 image, in `[0, 1]`
+fn luminance(pixels: &[u8]) -> Vec<f32> {
+    pixels
+        .chunks_exact(4)
+        .map(|rgba| 0.0722f32.mul_add(f32::from(rgba[2]), 0.2126f32.mul_add(f32::from(rgba[0]), 0.7152 * f32::from(rgba[1]))) / 255.0)
+        .collect()
+}
+
+/// Structural similarity (Wang et al. 2004) computed per block instead of with
+/// a sliding Gaussian window, then averaged
+fn block_ssim(a: &[f32], b: &[f32], width: usize, height: usize) -> f32 {
+    const C1: f32 = 0.01 * 0.01;
+    const C2: f32 = 0.03 * 0.03;
+
+    let mut ssim_sum = 0.0;
+    let mut block_count = 0;
+    for block_y in (0..height).step_by(SSIM_BLOCK) {
+        for block_x in (0..width).step_by(SSIM_BLOCK) {
+            let indices: Vec<usize> = (block_y..(block_y + SSIM_BLOCK).min(height))
+                .flat_map(|y| (block_x..(block_x + SSIM_BLOCK).min(width)).map(move |x| y * width + x))
+                .collect();
+            let n = indices.len() as f32;
+            let mean_a = indices.iter().map(|&i| a[i]).sum::<f32>() / n;
+            let mean_b = indices.iter().map(|&i| b[i]).sum::<f32>() / n;
+            let (mut variance_a, mut variance_b, mut covariance) = (0.0, 0.0, 0.0);
+            for &i in &indices {
+                let (da, db) = (a[i] - mean_a, b[i] - mean_b);
+                variance_a += da * da;
+                variance_b += db * db;
+                covariance += da * db;
+            }
+            variance_a /= n;
+            variance_b /= n;
+            covariance /= n;
+
+            ssim_sum += ((2.0 * mean_a).mul_add(mean_b, C1) * 2.0f32.mul_add(covariance, C2))
+                / ((mean_a.mul_add(mean_a, mean_b * mean_b) + C1) * (variance_a + variance_b + C2));
+            block_count += 1;
+        }
+    }
+
+    if block_count == 0 {
+        1.0
+    } else {
+        ssim_sum / block_count as f32
+    }
+}
+
+/// Render the same spheres and point lights with the CPU raytracer and the GPU
+/// renderer and compare the results.
+///
+/// The GPU frame is drawn into `target` and read back at the raytracer's
+/// resolution. Returns the difference, or an error if
+/// it exceeds `tolerance`.
+///
+/// # Errors
+///
+/// Fails if the GPU frame cannot be read back or the difference exceeds
+/// `tolerance`.
+pub fn check_render_parity(
+    raytracer: &Raytracer,
+    gpu_renderer: &mut GpuRenderer,
+    target: &wgpu::Texture,
+    spheres: &[Arc<Sphere>],
+    lights: &[Arc<PointLight>],
+    camera: &Camera,
+    tolerance: &DiffTolerance,
+) -> Result<ImageDiff> {
+    let width = raytracer.config().width;
+    let height = raytracer.config().height;
+
+    let objects: Vec<Arc<dyn SceneObject>> = spheres.iter().map(|sphere| sphere.clone() as Arc<dyn SceneObject>).collect();
+    let cpu_lights: Vec<Arc<dyn Light>> = lights.iter().map(|light| light.clone() as Arc<dyn Light>).collect();
+    let cpu_pixels = raytracer.render(&objects, &cpu_lights, &[], camera);
+
+    gpu_renderer.render(Some(target), spheres, &[], lights, camera)?;
+    let gpu_pixels = gpu_renderer.read_thumbnail(width, height)?;
+
+    let diff = compare_images(&cpu_pixels, &gpu_pixels, width as usize, height as usize);
+    if !diff.is_within(tolerance) {
+        return Err(anyhow!(
+            "CPU and GPU renders differ: mean error {:.4} (allowed {:.4}), SSIM {:.4} (required {:.4})",
+            diff.mean_color_error(),
+            tolerance.max_mean_error,
+            diff.ssim,
+            tolerance.min_ssim
+        ));
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16x16 RGBA8 image with a diagonal gradient
+    fn gradient() -> Vec<u8> {
+        (0..16u8)
+            .flat_map(|y| (0..16u8).flat_map(move |x| [x * 15, y * 15, (x + y) * 7, 255]))
+            .collect()
+    }
+
+    #[test]
+    fn near_identical_images_have_a_small_error() {
+        let a = gradient();
+        let same = compare_images(&a, &a, 16, 16);
+        assert!(same.max_error.iter().all(|&error| error == 0.0), "{same:?}");
+
+        // Nudge the red channel of every fourth pixel by two levels
+        let mut b = a.clone();
+        for value in b.iter_mut().step_by(16) {
+            *value = value.saturating_add(2);
+        }
+        let diff = compare_images(&a, &b, 16, 16);
+        assert!(diff.mean_color_error() > 0.0 && diff.mean_color_error() < 0.01, "{diff:?}");
+        assert!(diff.ssim > 0.95, "{diff:?}");
+        assert!(diff.is_within(&DiffTolerance::default()));
+    }
+
+    #[test]
+    fn very_different_images_have_a_large_error() {
+        let a = gradient();
+        let inverted: Vec<u8> = a
+            .chunks_exact(4)
+            .flat_map(|rgba| [255 - rgba[0], 255 - rgba[1], 255 - rgba[2], rgba[3]])
+            .collect();
+        let diff = compare_images(&a, &inverted, 16, 16);
+        assert!(diff.mean_color_error() > 0.3, "{diff:?}");
+        assert!(diff.ssim < 0.5, "{diff:?}");
+        assert!(!diff.is_within(&DiffTolerance::default()));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::raytracer::Raytracer;
+use crate::{Light, SceneObject};
+use rrte_math::{random, Ray, Vec3};
+use std::sync::Arc;
+
+/// A photon stored where it landed on a diffuse surface
+#[derive(Debug, Clone, Copy)]
+pub struct Photon {
+    /// Where the photon landed
+    pub position: Vec3,
+    /// Direction the photon was travelling when it landed
+    pub direction: Vec3,
+    /// Flux carried by the photon, per color channel
+    pub power: Vec3,
+}
+
+/// Caustic photon map: photons that reached a diffuse surface only after
+/// bouncing off or refracting through specular surfaces, in a kd-tree.
+///
+/// Photons are emitted uniformly from every light with a position, and carry
+/// the flux that makes their density reproduce the light's own falloff on an
+/// unobstructed surface. Direct and diffusely bounced light is left to the path
+/// tracer, so the map only adds the focused light that shadow rays miss.
+#[derive(Debug, Clone, Default)]
+pub struct PhotonMap {
+    /// Photons in implicit kd-tree order: each range's median is its node
+    photons: Vec<Photon>,
+    /// Split axis of the node stored at the same index
+    axes: Vec<u8>,
+}
+
+impl PhotonMap {
+    /// Build a map from a set of photons
+    #[must_use]
+    pub fn new(mut photons: Vec<Photon>) -> Self {
+        let mut axes = vec![0; photons.len()];
+        build_tree(&mut photons, &mut axes);
+        Self { photons, axes }
+    }
+
+    /// Shoot about `count` photons from the lights and keep those that reach a
+    /// diffuse surface after at least one specular bounce.
+    ///
+    /// Photons are split evenly between lights with a position; directional and
+    /// ambient lights emit none. Paths end after `max_bounces` specular bounces.
+    pub fn trace_caustics(
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<dyn Light>],
+        count: u32,
+        max_bounces: u32,
+    ) -> Self {
+        let emitters: Vec<&Arc<dyn Light>> = lights
+            .iter()
+            .filter(|light| {
+                let probe = light.illuminate(light.position() + Vec3::Y, Vec3::NEG_Y);
+                contribution_is_positional(probe.direction, probe.distance)
+            })
+            .collect();
+        if emitters.is_empty() || count == 0 {
+            return Self::default();
+        }
+
+        let photons_per_light = (count as usize / emitters.len()).max(1);
+        let mut photons = Vec::new();
+        for light in emitters {
+            let origin = light.position();
+            for _ in 0..photons_per_light {
+                let ray = Ray::new(origin, random_unit_vector());
+                if let Some(photon) = trace_photon(ray, light.as_ref(), photons_per_light, objects, max_bounces) {
+                    photons.push(photon);
+                }
+            }
+        }
+        Self::new(photons)
+    }
+
+    /// Get the number of stored photons
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    /// Check whether the map holds no photons
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    /// Get the stored photons, in kd-tree order
+    #[must_use]
+    pub fn photons(&self) -> &[Photon] {
+        &self.photons
+    }
+
+    /// Call `visit` for every photon within `radius` of `point`
+    pub fn for_each_within(&self, point: Vec3, radius: f32, mut visit: impl FnMut(&Photon)) {
+        self.visit_range(0, self.photons.len(), point, radius * radius, &mut visit);
+    }
+
+    /// Estimate the irradiance at a surface point from the photons within `radius`
+    /// that arrived at the side `normal` faces
+    #[must_use]
+    pub fn irradiance(&self, point: Vec3, normal: Vec3, radius: f32) -> Vec3 {
+        if self.photons.is_empty() || radius <= 0.0 {
+            return Vec3::ZERO;
+        }
+        let mut flux = Vec3::ZERO;
+        self.for_each_within(point, radius, |photon| {
+            if photon.direction.dot(normal) < 0.0 {
+                flux += photon.power;
+            }
+        });
+        flux / (std::f32::consts::PI * radius * radius)
+    }
+
+    fn visit_range(&self, start: usize, end: usize, point: Vec3, radius_squared: f32, visit: &mut impl FnMut(&Photon)) {
+        if start >= end {
+            return;
+        }
+        let middle = start + (end - start) / 2;
+        let photon = &self.photons[middle];
+        if photon.position.distance_squared(point) <= radius_squared {
+            visit(photon);
+        }
+
+        let axis = usize::from(self.axes[middle]);
+        let offset = point[axis] - photon.position[axis];
+        let (near, far) = if offset < 0.0 {
+            ((start, middle), (middle + 1, end))
+        } else {
+            ((middle + 1, end), (start, middle))
+        };
+        self.visit_range(near.0, near.1, point, radius_squared, visit);
+        if offset * offset <= radius_squared {
+            self.visit_range(far.0, far.1, point, radius_squared, visit);
+        }
+    }
+}
+
+/// Check whether a light contribution comes from a point in space, rather than
+/// from a direction (directional lights) or everywhere (ambient lights)
+fn contribution_is_positional(direction: Vec3, distance: f32) -> bool {
+    direction != Vec3::ZERO && distance.is_finite()
+}
+
+/// Follow one photon through specular bounces, returning it if it lands on a
+/// diffuse surface after at least one of them
+fn trace_photon(
+    mut ray: Ray,
+    light: &dyn Light,
+    photons_per_light: usize,
+    objects: &[Arc<dyn SceneObject>],
+    max_bounces: u32,
+) -> Option<Photon> {
+    let (mut hit, mut object) = Raytracer::closest_hit(&ray, objects)?;
+
+    // Flux per photon that makes the photon density on an unobstructed surface
+    // match the light's own falloff at the first hit: with uniform emission the
+    // density is count / (4 pi d^2) per unit area
+    let contribution = light.illuminate(hit.point, hit.normal);
+    let distance = contribution.distance;
+    let mut power = contribution.color.to_vec3()
+        * (contribution.attenuation * distance * distance * 4.0 * std::f32::consts::PI / photons_per_light as f32);
+
+    for bounce in 0..=max_bounces {
+        let material = object.material()?;
+        if Raytracer::is_diffuse(material.as_ref(), &ray, &hit) {
+            return (bounce > 0).then_some(Photon {
+                position: hit.point,
+                direction: ray.direction,
+                power,
+            });
+        }
+
+        ray = material.scatter(&ray, &hit)?;
+        power *= material.albedo_at(&hit).to_vec3();
+        (hit, object) = Raytracer::closest_hit(&ray, objects)?;
+    }
+    None
+}
+
+/// Uniformly distributed direction on the unit sphere
+fn random_unit_vector() -> Vec3 {
+    let z = 2.0f32.mul_add(-random::random::<f32>(), 1.0);
+    let phi = 2.0 * std::f32::consts::PI * random::random::<f32>();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Reorder photons into a balanced kd-tree, splitting each range at its median
+/// along the axis of largest extent
+fn build_tree(photons: &mut [Photon], axes: &mut [u8]) {
+    if photons.len() <= 1 {
+        return;
+    }
+
+    let (min, max) = photons.iter().fold(
+        (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+        |(min, max), photon| (min.min(photon.position), max.max(photon.position)),
+    );
+    let extent = max - min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+
+    let middle = photons.len() / 2;
+    photons.select_nth_unstable_by(middle, |a, b| a.position[axis].total_cmp(&b.position[axis]));
+    axes[middle] = axis as u8;
+
+    let (left, rest) = photons.split_at_mut(middle);
+    let (left_axes, rest_axes) = axes.split_at_mut(middle);
+    build_tree(left, left_axes);
+    build_tree(&mut rest[1..], &mut rest_axes[1..]);
+}
@@ -1,9 +1,14 @@
-use rrte_math::{Ray, Vec3, Transform, HitInfo};
-use crate::Material;
+use rrte_math::{Ray, Vec2, Vec3, Transform, HitInfo, PerlinNoise, AABB};
+use std::any::Any;
+use serde::{Deserialize, Serialize};
+use crate::{Material, MaterialRegistry};
 use std::sync::Arc;
 
 /// Trait for all renderable objects in the scene
 pub trait SceneObject: Send + Sync + std::fmt::Debug {
+    /// Get this object as `Any` for downcasting to a concrete primitive
+    fn as_any(&self) -> &dyn Any;
+
     /// Test if a ray intersects with this object
     fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo>;
     
@@ -15,6 +20,102 @@ pub trait SceneObject: Send + Sync + std::fmt::Debug {
     
     /// Set the transform of this object
     fn set_transform(&mut self, transform: Transform);
+
+    /// Clone this object into a new allocation, or `None` if it cannot be cloned.
+    ///
+    /// Used by [`make_object_mut`] to change objects whose `Arc` is shared.
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        None
+    }
+
+    /// Get whether this object casts and receives shadows; both by default
+    fn shadow_flags(&self) -> ShadowFlags {
+        ShadowFlags::default()
+    }
+
+    /// Set whether this object casts and receives shadows.
+    ///
+    /// Objects that do not store flags ignore this and keep the default.
+    fn set_shadow_flags(&mut self, _flags: ShadowFlags) {}
+
+    /// Check whether any material of this object refers to an entry of
+    /// `registry` that has since been replaced
+    fn has_stale_materials(&self, registry: &MaterialRegistry) -> bool {
+        self.material().is_some_and(|material| registry.is_stale(material.as_ref()))
+    }
+
+    /// Replace materials that refer to replaced entries of `registry` with
+    /// fresh references; returns whether anything changed.
+    ///
+    /// Objects that do not store a material keep the default, which does nothing.
+    fn refresh_materials(&mut self, _registry: &MaterialRegistry) -> bool {
+        false
+    }
+
+    /// Get the world-space bounding box.
+    ///
+    /// Objects that cannot bound themselves keep the default of
+    /// [`AABB::unbounded`], which BVHs, tiling and culling recognize through
+    /// [`AABB::is_unbounded`] and treat as covering everything, so they are
+    /// never culled incorrectly.
+    fn bounding_box(&self) -> AABB {
+        AABB::unbounded()
+    }
+
+    /// Check if a sphere overlaps this object.
+    ///
+    /// The default tests against the bounding box; primitives with a cheap exact
+    /// test override it.
+    fn overlaps_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.bounding_box().intersects_sphere(center, radius)
+    }
+}
+
+/// Get mutable access to an object, like [`Arc::make_mut`].
+///
+/// If other `Arc`s point to the object, it is first replaced by a copy from
+/// [`SceneObject::clone_object`], leaving the other holders unchanged. Returns
+/// `None` if the object is shared and cannot be cloned.
+pub fn make_object_mut(object: &mut Arc<dyn SceneObject>) -> Option<&mut (dyn SceneObject + 'static)> {
+    if Arc::get_mut(object).is_none() {
+        *object = object.clone_object()?;
+    }
+    Arc::get_mut(object)
+}
+
+/// Whether an object takes part in shadowing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ShadowFlags {
+    /// Whether the object blocks light from reaching other surfaces
+    pub casts_shadow: bool,
+    /// Whether light reaching the object is tested for blockers
+    pub receives_shadow: bool,
+}
+
+impl ShadowFlags {
+    /// Bit of [`ShadowFlags::bits`] set when the object casts shadows
+    pub const CASTS_SHADOW: u32 = 1;
+    /// Bit of [`ShadowFlags::bits`] set when the object receives shadows
+    pub const RECEIVES_SHADOW: u32 = 2;
+
+    /// Pack the flags into bits for GPU upload
+    #[must_use]
+    pub const fn bits(&self) -> u32 {
+        let mut bits = 0;
+        if self.casts_shadow {
+            bits |= Self::CASTS_SHADOW;
+        }
+        if self.receives_shadow {
+            bits |= Self::RECEIVES_SHADOW;
+        }
+        bits
+    }
+}
+
+impl Default for ShadowFlags {
+    fn default() -> Self {
+        Self { casts_shadow: true, receives_shadow: true }
+    }
 }
 
 /// Sphere primitive
@@ -24,6 +125,8 @@ pub struct Sphere {
     pub radius: f32,
     pub material: Option<Arc<dyn Material>>,
     pub transform: Transform,
+    /// Whether the object casts and receives shadows
+    pub shadow_flags: ShadowFlags,
 }
 
 impl Sphere {
@@ -34,6 +137,7 @@ impl Sphere {
             radius,
             material: None,
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -44,6 +148,7 @@ impl Sphere {
             radius,
             material: Some(material),
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -51,14 +156,32 @@ impl Sphere {
     pub fn set_material(&mut self, material: Arc<dyn Material>) {
         self.material = Some(material);
     }
+
+    /// Get the world-space center, `center` moved by the transform
+    #[must_use]
+    pub fn world_center(&self) -> Vec3 {
+        self.transform.transform_point(self.center)
+    }
+
+    /// Get the world-space radius, scaled by the transform's largest scale axis
+    #[must_use]
+    pub fn world_radius(&self) -> f32 {
+        self.radius * self.transform.scale.abs().max_element()
+    }
 }
 
 impl SceneObject for Sphere {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
-        let oc = ray.origin - self.center;
+        let center = self.world_center();
+        let radius = self.world_radius();
+        let oc = ray.origin - center;
         let a = ray.direction.length_squared();
         let half_b = oc.dot(ray.direction);
-        let c = oc.length_squared() - self.radius * self.radius;
+        let c = radius.mul_add(-radius, oc.length_squared());
         let discriminant = half_b * half_b - a * c;
 
         if discriminant < 0.0 {
@@ -76,8 +199,14 @@ impl SceneObject for Sphere {
         }
 
         let point = ray.at(root);
-        let outward_normal = (point - self.center) / self.radius;        
-        Some(HitInfo::new(root, point, outward_normal, &ray))
+        let outward_normal = (point - center) / radius;
+        // Longitude around local Y starting at -X, latitude from the south pole
+        let local_normal = self.transform.rotation.inverse() * outward_normal;
+        let uv = Vec2::new(
+            ((-local_normal.z).atan2(local_normal.x) + std::f32::consts::PI) / std::f32::consts::TAU,
+            (-local_normal.y).clamp(-1.0, 1.0).acos() / std::f32::consts::PI,
+        );
+        Some(HitInfo::new(root, point, outward_normal, ray).with_uv(uv))
     }
 
     fn material(&self) -> Option<Arc<dyn Material>> {
@@ -91,6 +220,31 @@ impl SceneObject for Sphere {
     fn set_transform(&mut self, transform: Transform) {
         self.transform = transform;
     }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.shadow_flags
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        self.shadow_flags = flags;
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        self.material.as_mut().is_some_and(|material| registry.refresh(material))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        AABB::from_center_extents(self.world_center(), Vec3::splat(self.world_radius()))
+    }
+
+    fn overlaps_sphere(&self, center: Vec3, radius: f32) -> bool {
+        let reach = self.world_radius() + radius;
+        self.world_center().distance_squared(center) <= reach * reach
+    }
 }
 
 /// Plane primitive
@@ -100,6 +254,8 @@ pub struct Plane {
     pub normal: Vec3,
     pub material: Option<Arc<dyn Material>>,
     pub transform: Transform,
+    /// Whether the object casts and receives shadows
+    pub shadow_flags: ShadowFlags,
 }
 
 impl Plane {
@@ -110,6 +266,7 @@ impl Plane {
             normal: normal.normalize(),
             material: None,
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -120,6 +277,7 @@ impl Plane {
             normal: normal.normalize(),
             material: Some(material),
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -130,6 +288,10 @@ impl Plane {
 }
 
 impl SceneObject for Plane {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
         let denom = self.normal.dot(ray.direction);
         
@@ -144,8 +306,12 @@ impl SceneObject for Plane {
             return None;
         }        let point = ray.at(t);
         let normal = if denom < 0.0 { self.normal } else { -self.normal };
-        
-        Some(HitInfo::new(t, point, normal, &ray))
+
+        // One texture repeat per world unit, along two axes in the plane
+        let (tangent, bitangent) = self.normal.any_orthonormal_pair();
+        let offset = point - self.point;
+        let uv = Vec2::new(offset.dot(tangent), offset.dot(bitangent));
+        Some(HitInfo::new(t, point, normal, ray).with_uv(uv))
     }
     fn material(&self) -> Option<Arc<dyn Material>> {
         self.material.clone()
@@ -158,6 +324,38 @@ impl SceneObject for Plane {
     fn set_transform(&mut self, transform: Transform) {
         self.transform = transform;
     }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.shadow_flags
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        self.shadow_flags = flags;
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        self.material.as_mut().is_some_and(|material| registry.refresh(material))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        // Infinite along the plane, and flat across it when axis-aligned
+        let mut bounds = AABB::unbounded();
+        for axis in 0..3 {
+            if (self.normal[axis].abs() - 1.0).abs() < 1e-6 {
+                bounds.min[axis] = self.point[axis];
+                bounds.max[axis] = self.point[axis];
+            }
+        }
+        bounds
+    }
+
+    fn overlaps_sphere(&self, center: Vec3, radius: f32) -> bool {
+        (center - self.point).dot(self.normal).abs() <= radius
+    }
 }
 
 /// Triangle primitive
@@ -168,6 +366,8 @@ pub struct Triangle {
     pub uvs: [Vec3; 3], // Using Vec3 for future barycentric coordinates
     pub material: Option<Arc<dyn Material>>,
     pub transform: Transform,
+    /// Whether the object casts and receives shadows
+    pub shadow_flags: ShadowFlags,
 }
 
 impl Triangle {
@@ -183,6 +383,7 @@ impl Triangle {
             uvs: [Vec3::ZERO, Vec3::X, Vec3::Y],
             material: None,
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -205,6 +406,10 @@ impl Triangle {
 }
 
 impl SceneObject for Triangle {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
         // Möller-Trumbore intersection algorithm
         let edge1 = self.vertices[1] - self.vertices[0];
@@ -239,8 +444,9 @@ impl SceneObject for Triangle {
           // Interpolate normal using barycentric coordinates
         let w = 1.0 - u - v;
         let normal = (w * self.normals[0] + u * self.normals[1] + v * self.normals[2]).normalize();
+        let uv = (w * self.uvs[0] + u * self.uvs[1] + v * self.uvs[2]).truncate();
         
-        Some(HitInfo::new(t, point, normal, &ray))
+        Some(HitInfo::new(t, point, normal, ray).with_uv(uv))
     }
 
     fn material(&self) -> Option<Arc<dyn Material>> {
@@ -254,6 +460,29 @@ impl SceneObject for Triangle {
     fn set_transform(&mut self, transform: Transform) {
         self.transform = transform;
     }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.shadow_flags
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        self.shadow_flags = flags;
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        self.material.as_mut().is_some_and(|material| registry.refresh(material))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let mut bounds = AABB::new(self.vertices[0], self.vertices[0]);
+        bounds.expand_to_include(self.vertices[1]);
+        bounds.expand_to_include(self.vertices[2]);
+        bounds
+    }
 }
 
 /// Cube primitive
@@ -263,6 +492,8 @@ pub struct Cube {
     pub size: Vec3,
     pub material: Option<Arc<dyn Material>>,
     pub transform: Transform,
+    /// Whether the object casts and receives shadows
+    pub shadow_flags: ShadowFlags,
 }
 
 impl Cube {
@@ -273,6 +504,7 @@ impl Cube {
             size,
             material: None,
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -288,6 +520,7 @@ impl Cube {
             size,
             material: Some(material),
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -298,6 +531,10 @@ impl Cube {
 }
 
 impl SceneObject for Cube {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
         // Transform ray to local space
         let inv_transform = self.transform.inverse_matrix();
@@ -357,10 +594,21 @@ impl SceneObject for Cube {
         }
         
         let local_point = local_ray.at(t);
-        let world_point = self.transform.to_matrix().transform_point3(local_point);
-        let world_normal = self.transform.to_matrix().transform_vector3(normal).normalize();
+        let world_point = self.transform.transform_point(local_point);
+        let world_normal = self.transform.transform_normal(normal);
+
+        // Each face spans the whole texture, using the two axes across it
+        let extent = (local_point - min_bounds) / self.size;
+        let axis = normal.abs();
+        let uv = if axis.x >= axis.y && axis.x >= axis.z {
+            Vec2::new(extent.z, extent.y)
+        } else if axis.y >= axis.z {
+            Vec2::new(extent.x, extent.z)
+        } else {
+            Vec2::new(extent.x, extent.y)
+        };
         
-        Some(HitInfo::new(t, world_point, world_normal, ray))
+        Some(HitInfo::new(t, world_point, world_normal, ray).with_uv(uv))
     }
 
     fn material(&self) -> Option<Arc<dyn Material>> {
@@ -374,6 +622,36 @@ impl SceneObject for Cube {
     fn set_transform(&mut self, transform: Transform) {
         self.transform = transform;
     }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.shadow_flags
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        self.shadow_flags = flags;
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        self.material.as_mut().is_some_and(|material| registry.refresh(material))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let local = AABB::from_center_extents(self.center, self.size * 0.5);
+        local.transformed(&self.transform.to_matrix())
+    }
+
+    fn overlaps_sphere(&self, center: Vec3, radius: f32) -> bool {
+        // Exact for rigid transforms; with scale the radius is conservatively
+        // mapped by the smallest scale axis.
+        let local_center = self.transform.inverse_matrix().transform_point3(center);
+        let local_radius = radius / self.transform.scale.abs().min_element().max(1e-6);
+        AABB::from_center_extents(self.center, self.size * 0.5)
+            .intersects_sphere(local_center, local_radius)
+    }
 }
 
 /// Cylinder primitive
@@ -384,6 +662,8 @@ pub struct Cylinder {
     pub height: f32,
     pub material: Option<Arc<dyn Material>>,
     pub transform: Transform,
+    /// Whether the object casts and receives shadows
+    pub shadow_flags: ShadowFlags,
 }
 
 impl Cylinder {
@@ -395,6 +675,7 @@ impl Cylinder {
             height,
             material: None,
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -406,6 +687,7 @@ impl Cylinder {
             height,
             material: Some(material),
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -416,6 +698,10 @@ impl Cylinder {
 }
 
 impl SceneObject for Cylinder {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
         // Transform ray to local space
         let inv_transform = self.transform.inverse_matrix();
@@ -448,13 +734,13 @@ impl SceneObject for Cylinder {
                 let y_dist = (point.y - self.center.y).abs();
                 
                 if y_dist <= half_height {
-                    let world_point = self.transform.to_matrix().transform_point3(point);
+                    let world_point = self.transform.transform_point(point);
                     let local_normal = Vec3::new(
                         (point.x - self.center.x) / self.radius,
                         0.0,
                         (point.z - self.center.z) / self.radius
                     );
-                    let world_normal = self.transform.to_matrix().transform_vector3(local_normal).normalize();
+                    let world_normal = self.transform.transform_normal(local_normal);
                     
                     return Some(HitInfo::new(t, world_point, world_normal, ray));
                 }
@@ -475,6 +761,28 @@ impl SceneObject for Cylinder {
     fn set_transform(&mut self, transform: Transform) {
         self.transform = transform;
     }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.shadow_flags
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        self.shadow_flags = flags;
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        self.material.as_mut().is_some_and(|material| registry.refresh(material))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let extents = Vec3::new(self.radius, self.height * 0.5, self.radius);
+        let local = AABB::from_center_extents(self.center, extents);
+        local.transformed(&self.transform.to_matrix())
+    }
 }
 
 /// Cone primitive
@@ -485,6 +793,8 @@ pub struct Cone {
     pub height: f32,
     pub material: Option<Arc<dyn Material>>,
     pub transform: Transform,
+    /// Whether the object casts and receives shadows
+    pub shadow_flags: ShadowFlags,
 }
 
 impl Cone {
@@ -496,6 +806,7 @@ impl Cone {
             height,
             material: None,
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -507,6 +818,7 @@ impl Cone {
             height,
             material: Some(material),
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -517,6 +829,10 @@ impl Cone {
 }
 
 impl SceneObject for Cone {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
         // Transform ray to local space
         let inv_transform = self.transform.inverse_matrix();
@@ -551,7 +867,7 @@ impl SceneObject for Cone {
                 let y_local = point.y - self.center.y;
                 
                 if y_local >= -half_height && y_local <= half_height {
-                    let world_point = self.transform.to_matrix().transform_point3(point);
+                    let world_point = self.transform.transform_point(point);
                     
                     // Calculate normal
                     let r = (point.x * point.x + point.z * point.z).sqrt();
@@ -560,7 +876,7 @@ impl SceneObject for Cone {
                         k,
                         point.z / r
                     ).normalize();
-                    let world_normal = self.transform.to_matrix().transform_vector3(local_normal).normalize();
+                    let world_normal = self.transform.transform_normal(local_normal);
                     
                     return Some(HitInfo::new(t, world_point, world_normal, ray));
                 }
@@ -581,6 +897,28 @@ impl SceneObject for Cone {
     fn set_transform(&mut self, transform: Transform) {
         self.transform = transform;
     }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.shadow_flags
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        self.shadow_flags = flags;
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        self.material.as_mut().is_some_and(|material| registry.refresh(material))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let extents = Vec3::new(self.radius, self.height * 0.5, self.radius);
+        let local = AABB::from_center_extents(self.center, extents);
+        local.transformed(&self.transform.to_matrix())
+    }
 }
 
 /// Capsule primitive (rounded cylinder)
@@ -591,6 +929,8 @@ pub struct Capsule {
     pub height: f32,
     pub material: Option<Arc<dyn Material>>,
     pub transform: Transform,
+    /// Whether the object casts and receives shadows
+    pub shadow_flags: ShadowFlags,
 }
 
 impl Capsule {
@@ -602,6 +942,7 @@ impl Capsule {
             height,
             material: None,
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -613,6 +954,7 @@ impl Capsule {
             height,
             material: Some(material),
             transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
         }
     }
 
@@ -620,108 +962,301 @@ impl Capsule {
     pub fn set_material(&mut self, material: Arc<dyn Material>) {
         self.material = Some(material);
     }
+
+    /// Get the closest point to `point` on the capsule's core segment, in local space
+    #[must_use]
+    pub fn axis_point(&self, point: Vec3) -> Vec3 {
+        let half_height = self.height * 0.5;
+        let y = (point.y - self.center.y).clamp(-half_height, half_height);
+        self.center + Vec3::new(0.0, y, 0.0)
+    }
 }
 
 impl SceneObject for Capsule {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
-        // Transform ray to local space
+        // Transform ray to local space. The direction is left unnormalized so
+        // local and world ray parameters agree.
         let inv_transform = self.transform.inverse_matrix();
-        let local_ray = Ray::new(
-            inv_transform.transform_point3(ray.origin),
-            inv_transform.transform_vector3(ray.direction).normalize()
-        );
-        
+        let origin = inv_transform.transform_point3(ray.origin);
+        let direction = inv_transform.transform_vector3(ray.direction);
+
         let half_height = self.height * 0.5;
-        let top_center = self.center + Vec3::new(0.0, half_height, 0.0);
-        let bottom_center = self.center - Vec3::new(0.0, half_height, 0.0);
-        
+        let oc = origin - self.center;
+        let a = direction.length_squared();
+        if a == 0.0 {
+            return None;
+        }
+
         let mut closest_t = f32::INFINITY;
-        let mut closest_hit: Option<HitInfo> = None;
-        
-        // Check intersection with top hemisphere
-        let oc_top = local_ray.origin - top_center;
-        let a = local_ray.direction.length_squared();
-        let half_b = oc_top.dot(local_ray.direction);
-        let c = oc_top.length_squared() - self.radius * self.radius;
-        let discriminant = half_b * half_b - a * c;
-        
-        if discriminant >= 0.0 {
-            let sqrt_d = discriminant.sqrt();
-            let t1 = (-half_b - sqrt_d) / a;
-            let t2 = (-half_b + sqrt_d) / a;
-            
-            for &t in &[t1, t2] {
-                if t >= t_min && t <= t_max && t < closest_t {
-                    let point = local_ray.at(t);
-                    if point.y >= self.center.y {
-                        let world_point = self.transform.to_matrix().transform_point3(point);
-                        let local_normal = (point - top_center).normalize();
-                        let world_normal = self.transform.to_matrix().transform_vector3(local_normal).normalize();
-                        closest_t = t;
-                        closest_hit = Some(HitInfo::new(t, world_point, world_normal, ray));
-                    }
+        let mut consider = |t: f32, in_region: bool| {
+            if in_region && t >= t_min && t <= t_max && t < closest_t {
+                closest_t = t;
+            }
+        };
+
+        // Body: the infinite cylinder, clamped to the core segment
+        let a_body = direction.x.mul_add(direction.x, direction.z * direction.z);
+        if a_body > f32::EPSILON * a {
+            let half_b = oc.x.mul_add(direction.x, oc.z * direction.z);
+            let c = self.radius.mul_add(-self.radius, oc.x.mul_add(oc.x, oc.z * oc.z));
+            let discriminant = half_b.mul_add(half_b, -(a_body * c));
+            if discriminant >= 0.0 {
+                let sqrt_d = discriminant.sqrt();
+                for t in [(-half_b - sqrt_d) / a_body, (-half_b + sqrt_d) / a_body] {
+                    let y = direction.y.mul_add(t, oc.y);
+                    consider(t, y.abs() <= half_height);
                 }
             }
         }
-        
-        // Check intersection with bottom hemisphere
-        let oc_bottom = local_ray.origin - bottom_center;
-        let half_b = oc_bottom.dot(local_ray.direction);
-        let c = oc_bottom.length_squared() - self.radius * self.radius;
-        let discriminant = half_b * half_b - a * c;
-        
-        if discriminant >= 0.0 {
-            let sqrt_d = discriminant.sqrt();
-            let t1 = (-half_b - sqrt_d) / a;
-            let t2 = (-half_b + sqrt_d) / a;
-            
-            for &t in &[t1, t2] {
-                if t >= t_min && t <= t_max && t < closest_t {
-                    let point = local_ray.at(t);
-                    if point.y <= self.center.y {
-                        let world_point = self.transform.to_matrix().transform_point3(point);
-                        let local_normal = (point - bottom_center).normalize();
-                        let world_normal = self.transform.to_matrix().transform_vector3(local_normal).normalize();
-                        closest_t = t;
-                        closest_hit = Some(HitInfo::new(t, world_point, world_normal, ray));
-                    }
+
+        // Caps: each sphere only counts on its own side of the core segment
+        for side in [1.0f32, -1.0] {
+            let oc_cap = oc - Vec3::new(0.0, side * half_height, 0.0);
+            let half_b = oc_cap.dot(direction);
+            let c = self.radius.mul_add(-self.radius, oc_cap.length_squared());
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let sqrt_d = discriminant.sqrt();
+                for t in [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a] {
+                    let y = direction.y.mul_add(t, oc_cap.y);
+                    consider(t, side * y >= 0.0);
                 }
             }
         }
-        
-        // Check intersection with cylindrical body
-        let oc = local_ray.origin - self.center;
-        let a_cyl = local_ray.direction.x * local_ray.direction.x + local_ray.direction.z * local_ray.direction.z;
-        let b_cyl = 2.0 * (oc.x * local_ray.direction.x + oc.z * local_ray.direction.z);
-        let c_cyl = oc.x * oc.x + oc.z * oc.z - self.radius * self.radius;
-        
-        let discriminant = b_cyl * b_cyl - 4.0 * a_cyl * c_cyl;
-        if discriminant >= 0.0 {
-            let sqrt_d = discriminant.sqrt();
-            let t1 = (-b_cyl - sqrt_d) / (2.0 * a_cyl);
-            let t2 = (-b_cyl + sqrt_d) / (2.0 * a_cyl);
-            
-            for &t in &[t1, t2] {
-                if t >= t_min && t <= t_max && t < closest_t {
-                    let point = local_ray.at(t);
-                    let y_dist = (point.y - self.center.y).abs();
-                    
-                    if y_dist <= half_height {
-                        let world_point = self.transform.to_matrix().transform_point3(point);
-                        let local_normal = Vec3::new(
-                            (point.x - self.center.x) / self.radius,
-                            0.0,
-                            (point.z - self.center.z) / self.radius
-                        );
-                        let world_normal = self.transform.to_matrix().transform_vector3(local_normal).normalize();
-                        closest_t = t;
-                        closest_hit = Some(HitInfo::new(t, world_point, world_normal, ray));
-                    }
-                }
+
+        if !closest_t.is_finite() {
+            return None;
+        }
+
+        // The normal points away from the closest point on the core segment,
+        // which is continuous across the cap/body seams
+        let point = origin + direction * closest_t;
+        let local_normal = (point - self.axis_point(point)).normalize();
+        let world_point = self.transform.transform_point(point);
+        let world_normal = self.transform.transform_normal(local_normal);
+        Some(HitInfo::new(closest_t, world_point, world_normal, ray))
+    }
+
+    fn material(&self) -> Option<Arc<dyn Material>> {
+        self.material.clone()
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.shadow_flags
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        self.shadow_flags = flags;
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        self.material.as_mut().is_some_and(|material| registry.refresh(material))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let extents = Vec3::new(self.radius, self.height.mul_add(0.5, self.radius), self.radius);
+        let local = AABB::from_center_extents(self.center, extents);
+        local.transformed(&self.transform.to_matrix())
+    }
+}
+
+/// Sphere whose surface is pushed in and out along its normal by fractal noise,
+/// for bumpy rocks and asteroids without a mesh.
+///
+/// The surface lies at `radius + amplitude * fbm(direction * frequency)` from the
+/// center, and is found by ray marching that distance field inside the sphere's
+/// `radius + amplitude` bound.
+#[derive(Debug, Clone)]
+pub struct DisplacedSphere {
+    /// Center before the transform is applied
+    pub center: Vec3,
+    /// Radius of the undisplaced surface
+    pub radius: f32,
+    /// Largest distance the surface moves from `radius`
+    pub amplitude: f32,
+    /// Number of noise features around the sphere
+    pub frequency: f32,
+    /// Noise layers summed into the displacement
+    pub octaves: u32,
+    /// Noise field the displacement is sampled from
+    pub noise: PerlinNoise,
+    /// Material of the surface
+    pub material: Option<Arc<dyn Material>>,
+    /// Transform into world space
+    pub transform: Transform,
+    /// Whether the object casts and receives shadows
+    pub shadow_flags: ShadowFlags,
+}
+
+impl DisplacedSphere {
+    /// Most ray marching steps before a ray is considered to miss
+    const MAX_STEPS: u32 = 256;
+
+    /// Create a displaced sphere using noise seed 0
+    #[must_use]
+    pub fn new(center: Vec3, radius: f32, amplitude: f32, frequency: f32) -> Self {
+        Self {
+            center,
+            radius,
+            amplitude,
+            frequency,
+            octaves: 4,
+            noise: PerlinNoise::default(),
+            material: None,
+            transform: Transform::identity(),
+            shadow_flags: ShadowFlags::default(),
+        }
+    }
+
+    /// Create a displaced sphere with material
+    pub fn with_material(center: Vec3, radius: f32, amplitude: f32, frequency: f32, material: Arc<dyn Material>) -> Self {
+        let mut sphere = Self::new(center, radius, amplitude, frequency);
+        sphere.material = Some(material);
+        sphere
+    }
+
+    /// Use a different noise seed, giving a differently shaped surface
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = PerlinNoise::new(seed);
+        self
+    }
+
+    /// Set the material
+    pub fn set_material(&mut self, material: Arc<dyn Material>) {
+        self.material = Some(material);
+    }
+
+    /// Get the distance from the center to the surface in a local-space direction
+    #[must_use]
+    pub fn surface_radius(&self, direction: Vec3) -> f32 {
+        let direction = direction.normalize_or_zero();
+        self.amplitude.mul_add(self.noise.fbm(direction * self.frequency, self.octaves), self.radius)
+    }
+
+    /// Signed distance estimate to the surface from a local-space point,
+    /// negative inside
+    fn distance(&self, point: Vec3) -> f32 {
+        let offset = point - self.center;
+        offset.length() - self.surface_radius(offset)
+    }
+
+    /// Factor keeping march steps below the true distance to the surface. The
+    /// noise gradient is bounded by a few units per unit input, and the input
+    /// moves `frequency / r` per unit distance at radius `r`.
+    fn step_scale(&self) -> f32 {
+        let inner_radius = (self.radius - self.amplitude.abs()).max(1e-3);
+        1.0 / (1.0 + 3.0 * self.amplitude.abs() * self.frequency / inner_radius)
+    }
+
+    /// Find where a local-space ray crosses the surface between `t_start` and `t_end`
+    fn march(&self, origin: Vec3, direction: Vec3, t_start: f32, t_end: f32) -> Option<f32> {
+        let speed = direction.length();
+        let epsilon = 1e-4 * self.radius.max(1e-3);
+        let step_scale = self.step_scale();
+
+        let mut t = t_start;
+        let mut distance = self.distance(origin + direction * t);
+        let inside = distance < 0.0;
+        for _ in 0..Self::MAX_STEPS {
+            if distance.abs() < epsilon {
+                return Some(t);
+            }
+            let previous_t = t;
+            t += (distance.abs() * step_scale).max(epsilon * 0.5) / speed;
+            if t > t_end {
+                return None;
+            }
+            distance = self.distance(origin + direction * t);
+            if (distance < 0.0) != inside {
+                return Some(self.bisect(origin, direction, previous_t, t, inside));
             }
         }
-        
-        closest_hit
+        None
+    }
+
+    /// Narrow a bracketed surface crossing down by bisection
+    fn bisect(&self, origin: Vec3, direction: Vec3, mut before: f32, mut after: f32, inside: bool) -> f32 {
+        for _ in 0..16 {
+            let middle = 0.5 * (before + after);
+            if (self.distance(origin + direction * middle) < 0.0) == inside {
+                before = middle;
+            } else {
+                after = middle;
+            }
+        }
+        after
+    }
+
+    /// Local-space surface normal from the central differences of the distance field
+    fn normal(&self, point: Vec3) -> Vec3 {
+        let h = 1e-3 * self.radius.max(1e-3);
+        let gradient = Vec3::new(
+            self.distance(point + Vec3::X * h) - self.distance(point - Vec3::X * h),
+            self.distance(point + Vec3::Y * h) - self.distance(point - Vec3::Y * h),
+            self.distance(point + Vec3::Z * h) - self.distance(point - Vec3::Z * h),
+        );
+        gradient.try_normalize().unwrap_or_else(|| (point - self.center).normalize())
+    }
+}
+
+impl SceneObject for DisplacedSphere {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
+        // Transform ray to local space, keeping local and world ray parameters equal
+        let inv_transform = self.transform.inverse_matrix();
+        let origin = inv_transform.transform_point3(ray.origin);
+        let direction = inv_transform.transform_vector3(ray.direction);
+
+        // Only march where the ray is inside the bounding sphere
+        let bound = self.radius + self.amplitude.abs();
+        let oc = origin - self.center;
+        let a = direction.length_squared();
+        let half_b = oc.dot(direction);
+        let c = bound.mul_add(-bound, oc.length_squared());
+        let discriminant = half_b.mul_add(half_b, -(a * c));
+        if a == 0.0 || discriminant < 0.0 {
+            return None;
+        }
+        let sqrt_d = discriminant.sqrt();
+        let t_start = ((-half_b - sqrt_d) / a).max(t_min);
+        let t_end = ((-half_b + sqrt_d) / a).min(t_max);
+        if t_start > t_end {
+            return None;
+        }
+
+        let t = if self.amplitude == 0.0 {
+            // Undisplaced: the bound is the surface
+            [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a]
+                .into_iter()
+                .find(|t| (t_min..=t_max).contains(t))?
+        } else {
+            self.march(origin, direction, t_start, t_end)?
+        };
+
+        let point = origin + direction * t;
+        let world_point = self.transform.transform_point(point);
+        let world_normal = self.transform.transform_normal(self.normal(point));
+        Some(HitInfo::new(t, world_point, world_normal, ray))
     }
 
     fn material(&self) -> Option<Arc<dyn Material>> {
@@ -735,4 +1270,114 @@ impl SceneObject for Capsule {
     fn set_transform(&mut self, transform: Transform) {
         self.transform = transform;
     }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(self.clone()))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.shadow_flags
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        self.shadow_flags = flags;
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        self.material.as_mut().is_some_and(|material| registry.refresh(material))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let local = AABB::from_center_extents(self.center, Vec3::splat(self.radius + self.amplitude.abs()));
+        local.transformed(&self.transform.to_matrix())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capsule_normals_are_continuous_across_the_cap_seams() {
+        let mut capsule = Capsule::new(Vec3::ZERO, 1.0, 2.0);
+        capsule.set_transform(Transform::from_position(Vec3::new(0.0, 0.0, -3.0)));
+
+        // Sweep horizontal rays over the lower and upper seams at y = -1 and y = 1
+        let steps: u16 = 400;
+        let mut previous: Option<Vec3> = None;
+        for i in 0..=steps {
+            let y = f32::from(i).mul_add(3.8 / f32::from(steps), -1.9);
+            let ray = Ray::new(Vec3::new(0.0, y, 0.0), Vec3::NEG_Z);
+            let hit = capsule
+                .intersect(&ray, 0.001, f32::INFINITY)
+                .unwrap_or_else(|| panic!("gap at y = {y}"));
+
+            // Every hit lies on the surface, one radius from the core segment
+            let local = hit.point - Vec3::new(0.0, 0.0, -3.0);
+            assert!((local.distance(capsule.axis_point(local)) - 1.0).abs() < 1e-4, "y = {y}");
+            assert!((hit.normal.length() - 1.0).abs() < 1e-4);
+            if let Some(previous) = previous {
+                assert!(previous.dot(hit.normal) > 0.99, "normal jumps at y = {y}");
+            }
+            previous = Some(hit.normal);
+        }
+    }
+
+    #[test]
+    fn capsule_is_missed_beyond_its_caps() {
+        let capsule = Capsule::new(Vec3::ZERO, 1.0, 2.0);
+        let ray = Ray::new(Vec3::new(0.0, 2.05, 5.0), Vec3::NEG_Z);
+        assert!(capsule.intersect(&ray, 0.001, f32::INFINITY).is_none());
+
+        // Straight down the axis, the top cap is hit at its pole
+        let ray = Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::NEG_Y);
+        let hit = capsule.intersect(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-5);
+        assert!(hit.normal.distance(Vec3::Y) < 1e-5);
+    }
+
+    #[test]
+    fn displaced_sphere_hits_follow_the_noise_around_the_base_radius() {
+        let sphere = DisplacedSphere::new(Vec3::ZERO, 1.0, 0.2, 3.0);
+
+        // Aim at the center from many directions; each hit lies at the noise radius
+        let (mut nearest, mut farthest) = (f32::INFINITY, 0.0_f32);
+        for i in 0..64_u16 {
+            let angle = f32::from(i) * 0.3;
+            let height = f32::from(i).mul_add(1.6 / 63.0, -0.8);
+            let outward = Vec3::new(angle.cos(), height, angle.sin()).normalize();
+            let ray = Ray::new(outward * 5.0, -outward);
+            let hit = sphere
+                .intersect(&ray, 0.001, f32::INFINITY)
+                .unwrap_or_else(|| panic!("missed along {outward:?}"));
+
+            let distance = hit.point.length();
+            assert!((distance - sphere.surface_radius(outward)).abs() < 2e-3, "{outward:?}");
+            assert!((distance - 1.0).abs() <= 0.2 + 2e-3);
+            nearest = nearest.min(distance);
+            farthest = farthest.max(distance);
+        }
+        assert!(nearest < 0.97 && farthest > 1.03, "surface barely moves: {nearest}..{farthest}");
+    }
+
+    #[test]
+    fn zero_amplitude_displacement_matches_a_plain_sphere() {
+        let displaced = DisplacedSphere::new(Vec3::new(0.5, 0.0, -4.0), 1.5, 0.0, 3.0);
+        let plain = Sphere::new(Vec3::new(0.5, 0.0, -4.0), 1.5);
+
+        for i in 0..20_u16 {
+            let x = f32::from(i).mul_add(0.2, -1.5);
+            let ray = Ray::new(Vec3::new(x, 0.3, 0.0), Vec3::new(0.05, 0.0, -1.0));
+            let expected = plain.intersect(&ray, 0.001, f32::INFINITY);
+            let actual = displaced.intersect(&ray, 0.001, f32::INFINITY);
+            match (expected, actual) {
+                (Some(expected), Some(actual)) => {
+                    assert!((expected.t - actual.t).abs() < 1e-4, "x = {x}");
+                    assert!(expected.normal.distance(actual.normal) < 1e-2, "x = {x}");
+                }
+                (None, None) => {}
+                (expected, actual) => panic!("x = {x}: {expected:?} vs {actual:?}"),
+            }
+        }
+    }
 }
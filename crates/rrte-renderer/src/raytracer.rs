@@ -1,16 +1,67 @@
-use rrte_math::{Ray, HitInfo, Color};
-use crate::{Material, SceneObject, Light, Camera};
+// Pixel coordinates, sizes and sample counts stay far below 2^24, so
+// converting them to f32 is exact
+#![allow(clippy::cast_precision_loss)]
+
+use rrte_math::{random, BlendMode, Ray, HitInfo, Color, Vec2, Vec3};
+use crate::{Bvh, Material, SceneObject, Light, SphereLight, Camera};
+use crate::denoise::{atrous_denoise, DenoiseSettings, GuideBuffers};
+use crate::environment::EnvironmentMap;
+use crate::photon::PhotonMap;
+use crate::tiles::{plan_tiles, TileSettings};
+use crate::viewport::ViewportRect;
+use anyhow::{anyhow, Result};
 use rayon::prelude::*;
+use std::collections::HashMap;
+use std::f32::consts::FRAC_1_PI;
+use std::io::{Read, Write};
 use std::sync::Arc;
 
 /// Raytracing renderer configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RaytracerConfig {
     pub max_depth: u32,
     pub samples_per_pixel: u32,
     pub width: u32,
     pub height: u32,
     pub background_color: Color,
+    /// Color seen by rays pointing straight up; when set, the background blends
+    /// vertically from `background_color` (straight down) to this color
+    pub background_top: Option<Color>,
+    /// Run the edge-avoiding à-trous denoiser on the output of [`Raytracer::render`]
+    pub denoise: bool,
+    /// Trace one refracted ray per color channel through dispersive materials.
+    /// Triples the cost of every dispersive hit reached by a full-color ray.
+    pub dispersion: bool,
+    /// Add caustics from a photon map traced through specular surfaces; see
+    /// [`Raytracer::update_photon_map`]
+    pub caustics: bool,
+    /// Number of photons shot from the lights when building the caustic photon map
+    pub caustic_photons: u32,
+    /// Radius around a surface point within which caustic photons are gathered
+    pub photon_radius: f32,
+    /// How [`Raytracer::render`] splits the image into tiles for its worker threads
+    pub tiles: TileSettings,
+    /// Seed for all sampling decisions. With a seed, rendering the same scene
+    /// twice gives identical images; without one, samples are drawn from entropy.
+    pub seed: Option<u64>,
+    /// Distance shadow rays start off the surface along its normal, and the
+    /// smallest distance they must travel before they can be blocked, in world units
+    pub shadow_bias: f32,
+    /// Shadow-ray offset as a share of the shaded hit's distance from the ray
+    /// origin; used when larger than `shadow_bias`, as the precision of hit
+    /// points shrinks with distance
+    pub shadow_bias_relative: f32,
+    /// Extra primary rays [`Raytracer::render`] traces through pixels on object
+    /// silhouettes, where the object hit at the pixel center differs from a
+    /// neighbor's; blended in by their share of the samples. 0 disables it.
+    pub edge_samples: u32,
+    /// Curve compressing linear HDR colors into displayable ones in the 8-bit
+    /// output, applied after the camera's [`Camera::exposure`] and `exposure`;
+    /// [`Raytracer::render_hdr`] is not affected
+    pub tonemap: ToneMapper,
+    /// Factor colors are scaled by before tonemapping the 8-bit output, on top
+    /// of the camera's [`Camera::exposure`]
+    pub exposure: f32,
 }
 
 impl Default for RaytracerConfig {
@@ -21,27 +72,516 @@ impl Default for RaytracerConfig {
             width: 800,
             height: 600,
             background_color: Color::new(0.5, 0.7, 1.0, 1.0), // Sky blue
+            background_top: None,
+            denoise: false,
+            dispersion: false,
+            caustics: false,
+            caustic_photons: 200_000,
+            photon_radius: 0.1,
+            tiles: TileSettings::default(),
+            seed: None,
+            shadow_bias: 0.001,
+            shadow_bias_relative: 1.0e-4,
+            edge_samples: 0,
+            tonemap: ToneMapper::None,
+            exposure: 1.0,
+        }
+    }
+}
+
+impl RaytracerConfig {
+    /// Start building a configuration from the defaults
+    #[must_use]
+    pub fn builder() -> RaytracerConfigBuilder {
+        RaytracerConfigBuilder::default()
+    }
+
+    /// Check that the configuration can be rendered with
+    ///
+    /// # Errors
+    ///
+    /// Fails if the resolution or sample count is zero or a setting is out of range.
+    pub fn validate(&self) -> Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(anyhow!("Raytracer resolution must be non-zero, got {}x{}", self.width, self.height));
+        }
+        if self.samples_per_pixel == 0 {
+            return Err(anyhow!("Raytracer needs at least one sample per pixel"));
+        }
+        if self.max_depth == 0 {
+            return Err(anyhow!("Raytracer max depth must be at least 1"));
+        }
+        if self.shadow_bias < 0.0 || self.shadow_bias_relative < 0.0 {
+            return Err(anyhow!("Shadow bias must not be negative"));
+        }
+        if self.caustics && (self.caustic_photons == 0 || self.photon_radius <= 0.0) {
+            return Err(anyhow!("Caustics need photons and a positive photon radius"));
+        }
+        if !self.exposure.is_finite() || self.exposure < 0.0 {
+            return Err(anyhow!("Exposure must be a finite non-negative factor, got {}", self.exposure));
+        }
+        if self.tiles.base_size == 0 || self.tiles.min_size == 0 {
+            return Err(anyhow!("Tile sizes must be non-zero"));
+        }
+        Ok(())
+    }
+
+    /// Get the offset of shadow rays leaving a hit at distance `hit_t`:
+    /// `max(shadow_bias, shadow_bias_relative * hit_t)`
+    #[must_use]
+    pub fn shadow_epsilon(&self, hit_t: f32) -> f32 {
+        self.shadow_bias.max(self.shadow_bias_relative * hit_t)
+    }
+
+    /// Get the color written to the 8-bit output for a linear color, already
+    /// scaled by the camera's exposure: scaled by `exposure`, then tonemapped
+    #[must_use]
+    pub fn display_color(&self, color: Color) -> Color {
+        let exposed = Color::new(color.r * self.exposure, color.g * self.exposure, color.b * self.exposure, color.a);
+        self.tonemap.apply(exposed)
+    }
+
+    /// Get the background described by `background_color` and `background_top`
+    #[must_use]
+    pub fn background(&self) -> Background {
+        self.background_top.map_or_else(
+            || Background::solid(self.background_color),
+            |top| Background::gradient(self.background_color, top),
+        )
+    }
+}
+
+/// Fluent builder for [`RaytracerConfig`], validated by [`RaytracerConfigBuilder::build`]
+#[derive(Debug, Clone, Default)]
+pub struct RaytracerConfigBuilder {
+    config: RaytracerConfig,
+}
+
+impl RaytracerConfigBuilder {
+    /// Set the maximum number of bounces per path
+    #[must_use]
+    pub const fn max_depth(mut self, max_depth: u32) -> Self {
+        self.config.max_depth = max_depth;
+        self
+    }
+
+    /// Set the samples per pixel
+    #[must_use]
+    pub const fn samples(mut self, samples: u32) -> Self {
+        self.config.samples_per_pixel = samples;
+        self
+    }
+
+    /// Set the image width in pixels
+    #[must_use]
+    pub const fn width(mut self, width: u32) -> Self {
+        self.config.width = width;
+        self
+    }
+
+    /// Set the image height in pixels
+    #[must_use]
+    pub const fn height(mut self, height: u32) -> Self {
+        self.config.height = height;
+        self
+    }
+
+    /// Set the background color, or its bottom when `background_top` is set
+    #[must_use]
+    pub const fn background_color(mut self, color: Color) -> Self {
+        self.config.background_color = color;
+        self
+    }
+
+    /// Blend the background vertically up to `color`
+    #[must_use]
+    pub const fn background_top(mut self, color: Color) -> Self {
+        self.config.background_top = Some(color);
+        self
+    }
+
+    /// Enable or disable denoising
+    #[must_use]
+    pub const fn denoise(mut self, denoise: bool) -> Self {
+        self.config.denoise = denoise;
+        self
+    }
+
+    /// Enable or disable dispersion
+    #[must_use]
+    pub const fn dispersion(mut self, dispersion: bool) -> Self {
+        self.config.dispersion = dispersion;
+        self
+    }
+
+    /// Enable or disable caustics
+    #[must_use]
+    pub const fn caustics(mut self, caustics: bool) -> Self {
+        self.config.caustics = caustics;
+        self
+    }
+
+    /// Set the number of photons in the caustic photon map
+    #[must_use]
+    pub const fn caustic_photons(mut self, photons: u32) -> Self {
+        self.config.caustic_photons = photons;
+        self
+    }
+
+    /// Set the caustic photon gather radius
+    #[must_use]
+    pub const fn photon_radius(mut self, radius: f32) -> Self {
+        self.config.photon_radius = radius;
+        self
+    }
+
+    /// Set how the image is split into tiles
+    #[must_use]
+    pub const fn tiles(mut self, tiles: TileSettings) -> Self {
+        self.config.tiles = tiles;
+        self
+    }
+
+    /// Seed all sampling decisions
+    #[must_use]
+    pub const fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Set the absolute shadow-ray offset
+    #[must_use]
+    pub const fn shadow_bias(mut self, bias: f32) -> Self {
+        self.config.shadow_bias = bias;
+        self
+    }
+
+    /// Set the shadow-ray offset relative to hit distance
+    #[must_use]
+    pub const fn shadow_bias_relative(mut self, bias: f32) -> Self {
+        self.config.shadow_bias_relative = bias;
+        self
+    }
+
+    /// Set the extra primary rays on silhouettes
+    #[must_use]
+    pub const fn edge_samples(mut self, samples: u32) -> Self {
+        self.config.edge_samples = samples;
+        self
+    }
+
+    /// Set the curve compressing HDR colors in the 8-bit output
+    #[must_use]
+    pub const fn tonemap(mut self, tonemap: ToneMapper) -> Self {
+        self.config.tonemap = tonemap;
+        self
+    }
+
+    /// Set the factor colors are scaled by before tonemapping
+    #[must_use]
+    pub const fn exposure(mut self, exposure: f32) -> Self {
+        self.config.exposure = exposure;
+        self
+    }
+
+    /// Finish the configuration, failing if [`RaytracerConfig::validate`] does
+    ///
+    /// # Errors
+    ///
+    /// Fails if the configuration is invalid.
+    pub fn build(self) -> Result<RaytracerConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Curve mapping linear HDR colors to the displayable `[0, 1]` range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ToneMapper {
+    /// Clip channels above 1
+    #[default]
+    None,
+    /// `x / (1 + x)`, see [`Color::reinhard`]
+    Reinhard,
+    /// Fitted ACES filmic curve, see [`Color::aces_filmic`]
+    AcesFilmic,
+}
+
+impl ToneMapper {
+    /// Map a linear color; alpha is unchanged
+    #[must_use]
+    pub fn apply(self, color: Color) -> Color {
+        match self {
+            Self::None => color,
+            Self::Reinhard => color.reinhard(),
+            Self::AcesFilmic => color.aces_filmic(),
+        }
+    }
+}
+
+/// Color seen by rays that escape the scene: a vertical gradient from `bottom`
+/// (straight down) to `top` (straight up). Shared by the CPU and GPU renderers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Background {
+    /// Color looking straight down
+    pub bottom: Color,
+    /// Color looking straight up
+    pub top: Color,
+}
+
+impl Background {
+    /// Create a background with the same color in every direction
+    #[must_use]
+    pub const fn solid(color: Color) -> Self {
+        Self { bottom: color, top: color }
+    }
+
+    /// Create a vertical gradient background
+    #[must_use]
+    pub const fn gradient(bottom: Color, top: Color) -> Self {
+        Self { bottom, top }
+    }
+
+    /// Get the background color in a direction
+    #[must_use]
+    pub fn sample(&self, direction: Vec3) -> Color {
+        let t = 0.5 * (direction.normalize_or_zero().y + 1.0);
+        self.bottom.lerp(&self.top, t)
+    }
+}
+
+/// Arbitrary output variables produced by [`Raytracer::render_aovs`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AovKind {
+    /// Final color: ambient, emission, diffuse and specular, background where rays miss
+    Beauty,
+    /// Unlit material albedo of the first surface hit
+    Albedo,
+    /// World-space normal of the first surface hit
+    Normal,
+    /// Direct and indirect light reflected by diffuse surfaces
+    Diffuse,
+    /// Direct and indirect light reflected or refracted by non-diffuse surfaces
+    Specular,
+    /// Direct light from the light at this index, shadowed on diffuse surfaces
+    Light(usize),
+}
+
+/// A finished tile reported by [`Raytracer::render_with_progress`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileResult {
+    /// Pixels the tile covers
+    pub rect: ViewportRect,
+    /// Tightly packed RGBA8 pixels of the tile, row by row
+    pub pixels: Vec<u8>,
+}
+
+/// Per-pixel sums of every AOV, before averaging over samples
+#[derive(Debug, Clone)]
+struct AovSample {
+    beauty: Vec3,
+    albedo: Vec3,
+    normal: Vec3,
+    diffuse: Vec3,
+    specular: Vec3,
+    lights: Vec<Vec3>,
+}
+
+/// Header identifying a serialized accumulation buffer
+const STATE_MAGIC: &[u8; 8] = b"RRTEACC2";
+
+/// Header of the previous format, which had no background sums
+const STATE_MAGIC_V1: &[u8; 8] = b"RRTEACC1";
+
+/// Fold `count` samples with mean `other` into a running `mean` of `total`
+/// samples, where `total` already includes the new ones
+fn merge_mean(mean: Vec3, other: Vec3, count: u32, total: u32) -> Vec3 {
+    if count == 0 {
+        return mean;
+    }
+    mean + (other - mean) * (count as f32 / total as f32)
+}
+
+/// Running per-pixel sample means used for progressive accumulation.
+///
+/// Hits and background samples keep separate running means instead of sums,
+/// so a run of identical samples averages to exactly that value.
+#[derive(Debug, Clone, Copy, Default)]
+struct PixelAccumulator {
+    hit_mean: Vec3,
+    hit_count: u32,
+    background_mean: Vec3,
+    background_count: u32,
+}
+
+impl PixelAccumulator {
+    /// Rebuild an accumulator from per-kind sums, as stored by [`Raytracer::save_state`]
+    fn from_sums(hit_sum: Vec3, hit_count: u32, background_sum: Vec3, background_count: u32) -> Self {
+        let mean = |sum: Vec3, count: u32| if count == 0 { Vec3::ZERO } else { sum / count as f32 };
+        Self {
+            hit_mean: mean(hit_sum, hit_count),
+            hit_count,
+            background_mean: mean(background_sum, background_count),
+            background_count,
+        }
+    }
+
+    /// Add a sample whose primary ray hit an object
+    fn add_hit(&mut self, radiance: Vec3) {
+        self.hit_count += 1;
+        self.hit_mean = merge_mean(self.hit_mean, radiance, 1, self.hit_count);
+    }
+
+    /// Add a sample whose primary ray missed every object
+    fn add_background(&mut self, radiance: Vec3) {
+        self.background_count += 1;
+        self.background_mean = merge_mean(self.background_mean, radiance, 1, self.background_count);
+    }
+
+    /// Merge another set of samples into this one
+    fn add(&mut self, other: &Self) {
+        self.hit_count += other.hit_count;
+        self.hit_mean = merge_mean(self.hit_mean, other.hit_mean, other.hit_count, self.hit_count);
+        self.background_count += other.background_count;
+        self.background_mean = merge_mean(
+            self.background_mean,
+            other.background_mean,
+            other.background_count,
+            self.background_count,
+        );
+    }
+
+    /// Average the samples in linear space.
+    ///
+    /// The hit and background means are weighted by the fraction of samples
+    /// that hit or missed, so every sample contributes equally and a pixel that
+    /// only sees the background resolves to exactly the background color.
+    fn resolve(&self, background_alpha: f32) -> Color {
+        let total = self.hit_count + self.background_count;
+        if total == 0 {
+            return Color::TRANSPARENT;
         }
+
+        let hit_coverage = self.hit_count as f32 / total as f32;
+        let background_coverage = self.background_count as f32 / total as f32;
+        let rgb = self.hit_mean * hit_coverage + self.background_mean * background_coverage;
+        let alpha = background_alpha.mul_add(background_coverage, hit_coverage);
+        Color::new(rgb.x, rgb.y, rgb.z, alpha)
     }
 }
 
+/// Expose and tonemap a linear color as `config` asks, encode it as sRGB
+/// and write it as RGBA8
+fn write_pixel(pixel: &mut [u8], color: Color, config: &RaytracerConfig) {
+    // sRGB-encode so the buffer matches what the GPU path's sRGB surface displays
+    pixel.copy_from_slice(&config.display_color(color).to_srgb8());
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32<R: Read>(reader: &mut R) -> Result<f32> {
+    Ok(f32::from_bits(read_u32(reader)?))
+}
+
 /// CPU-based raytracer
 pub struct Raytracer {
     config: RaytracerConfig,
+    accumulation: Vec<PixelAccumulator>,
+    accumulated_samples: u32,
+    environment: Option<Arc<EnvironmentMap>>,
+    photon_map: Option<Arc<PhotonMap>>,
 }
 
 impl Raytracer {
     /// Create a new raytracer with configuration
     pub fn new(config: RaytracerConfig) -> Self {
-        Self { config }
+        let pixel_count = config.width as usize * config.height as usize;
+        Self {
+            config,
+            accumulation: vec![PixelAccumulator::default(); pixel_count],
+            accumulated_samples: 0,
+            environment: None,
+            photon_map: None,
+        }
+    }
+
+    /// Light the scene with an environment map, or go back to the configured background.
+    ///
+    /// Rays that miss see the map instead of the background, and diffuse surfaces
+    /// importance sample it as a light. Accumulated samples are discarded.
+    pub fn set_environment(&mut self, environment: Option<Arc<EnvironmentMap>>) {
+        self.environment = environment;
+        self.reset_accumulation();
     }
 
-    /// Update the raytracer's configuration
+    /// Get the environment map lighting the scene, if any
+    #[must_use]
+    pub const fn environment(&self) -> Option<&Arc<EnvironmentMap>> {
+        self.environment.as_ref()
+    }
+
+    /// Trace the caustic photon map for a scene, or drop it if `caustics` is disabled.
+    ///
+    /// [`Raytracer::render`] uses the map from the last call, so call this
+    /// whenever the scene changes. [`Raytracer::render_samples`] calls it itself
+    /// at the start of each accumulation.
+    pub fn update_photon_map(&mut self, objects: &[Arc<dyn SceneObject>], lights: &[Arc<dyn Light>]) {
+        self.photon_map = self.config.caustics.then(|| {
+            if let Some(seed) = self.config.seed {
+                random::seed(random::derive_seed(seed, &[u64::MAX]));
+            }
+            Arc::new(PhotonMap::trace_caustics(objects, lights, self.config.caustic_photons, self.config.max_depth))
+        });
+    }
+
+    /// Get the caustic photon map, if one has been traced
+    #[must_use]
+    pub const fn photon_map(&self) -> Option<&Arc<PhotonMap>> {
+        self.photon_map.as_ref()
+    }
+
+    /// Get `lights` followed by a [`SphereLight`] for every emissive sphere
+    /// among `objects`, the lights a render samples
+    fn scene_lights(objects: &[Arc<dyn SceneObject>], lights: &[Arc<dyn Light>]) -> Vec<Arc<dyn Light>> {
+        let mut scene_lights = lights.to_vec();
+        scene_lights.extend(
+            objects
+                .iter()
+                .filter_map(|object| SphereLight::from_emissive(object.as_ref()))
+                .map(|light| Arc::new(light) as Arc<dyn Light>),
+        );
+        scene_lights
+    }
+
+    /// Get the objects that can shadow a hit on `receiver`: none if it does
+    /// not receive shadows
+    fn shadow_casters<'a>(receiver: &Arc<dyn SceneObject>, objects: &'a Bvh) -> Option<&'a Bvh> {
+        receiver.shadow_flags().receives_shadow.then_some(objects)
+    }
+
+    /// Get the current configuration
+    #[must_use]
+    pub const fn config(&self) -> &RaytracerConfig {
+        &self.config
+    }
+
+    /// Update the raytracer's configuration.
+    ///
+    /// Accumulated samples are discarded because they no longer match the new settings.
     pub fn update_config(&mut self, new_config: RaytracerConfig) {
         self.config = new_config;
+        self.reset_accumulation();
     }
 
-    /// Render a scene to a pixel buffer
+    /// Render a scene to a pixel buffer.
+    ///
+    /// Rays are intersected with a [`Bvh`] built over `objects` for this call,
+    /// and emissive spheres among them are sampled as lights next to `lights`.
+    /// The colors of [`Raytracer::render_hdr`] are clamped and sRGB-encoded.
     pub fn render(
         &self,
         objects: &[Arc<dyn SceneObject>],
@@ -49,70 +589,905 @@ impl Raytracer {
         materials: &[Arc<dyn Material>],
         camera: &Camera,
     ) -> Vec<u8> {
+        self.render_with_progress(objects, lights, materials, camera, |_| {})
+    }
+
+    /// Render a scene to a pixel buffer like [`Raytracer::render`], intersecting
+    /// rays with a hierarchy already built over `objects` instead of building one.
+    ///
+    /// Use this with a [layered](Bvh::layered) hierarchy to rebuild only the
+    /// objects that moved between frames.
+    pub fn render_with_bvh(
+        &self,
+        bvh: &Bvh,
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) -> Vec<u8> {
+        let colors = self.render_colors(bvh, objects, lights, materials, camera, |_| {});
+        self.encode_pixels(&colors)
+    }
+
+    /// Render a scene to a pixel buffer like [`Raytracer::render`], calling
+    /// `on_tile` from the worker threads as each tile finishes.
+    ///
+    /// Tiles come from [`Raytracer::plan_tiles`] and are reported in the order
+    /// they finish. Edge refinement and denoising run once every tile is done,
+    /// so reported tiles can differ slightly from the returned frame.
+    pub fn render_with_progress(
+        &self,
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+        on_tile: impl Fn(TileResult) + Sync,
+    ) -> Vec<u8> {
+        let colors = self.render_colors(&Bvh::new(objects), objects, lights, materials, camera, on_tile);
+        self.encode_pixels(&colors)
+    }
+
+    /// Expose, tonemap and encode linear colors into an RGBA8 pixel buffer
+    fn encode_pixels(&self, colors: &[Color]) -> Vec<u8> {
+        let mut pixels = vec![0u8; colors.len() * 4];
+        pixels
+            .par_chunks_mut(4)
+            .zip(colors.par_iter())
+            .for_each(|(pixel, color)| write_pixel(pixel, *color, &self.config));
+        pixels
+    }
+
+    /// Render a scene to linear RGBA radiance, one `[r, g, b, a]` per pixel row
+    /// by row, for tonemapping and post effects.
+    ///
+    /// Values are scaled by the camera's exposure but otherwise unclamped;
+    /// [`Raytracer::render`] encodes the same colors to RGBA8.
+    pub fn render_hdr(
+        &self,
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) -> Vec<[f32; 4]> {
+        self.render_colors(&Bvh::new(objects), objects, lights, materials, camera, |_| {})
+            .par_iter()
+            .map(|color| color.to_vec4().to_array())
+            .collect()
+    }
+
+    /// Blend an RGBA8 overlay, such as a HUD, over a rendered RGBA8 frame of
+    /// the same size.
+    ///
+    /// Both buffers are sRGB-encoded with straight alpha, as [`Raytracer::render`]
+    /// writes them. Pixels are composited "over" in linear space, like blending
+    /// onto an sRGB surface on the GPU; fully transparent overlay pixels leave
+    /// the frame untouched.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the buffers differ in length or hold a partial pixel.
+    pub fn composite_overlay(frame: &mut [u8], overlay: &[u8]) -> Result<()> {
+        if frame.len() != overlay.len() || !frame.len().is_multiple_of(4) {
+            return Err(anyhow!(
+                "Overlay of {} bytes does not match the frame's {} bytes",
+                overlay.len(),
+                frame.len()
+            ));
+        }
+
+        frame
+            .par_chunks_mut(4)
+            .zip(overlay.par_chunks(4))
+            .filter(|(_, layer)| layer[3] > 0)
+            .for_each(|(pixel, layer)| {
+                let base = Color::from_srgb8([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                let layer = Color::from_srgb8([layer[0], layer[1], layer[2], layer[3]]);
+                pixel.copy_from_slice(&base.blend(&layer, BlendMode::AlphaOver).to_srgb8());
+            });
+        Ok(())
+    }
+
+    /// Render the linear colors of a full frame in tiles, reporting each
+    /// finished tile to `on_tile`, then refine edges and denoise
+    fn render_colors(
+        &self,
+        bvh: &Bvh,
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+        on_tile: impl Fn(TileResult) + Sync,
+    ) -> Vec<Color> {
         let width = self.config.width as usize;
         let height = self.config.height as usize;
-        let mut pixels = vec![0u8; width * height * 4];
+        let lights = &Self::scene_lights(objects, lights);
 
-        // Parallel rendering
+        // Render tiles in parallel, then copy them into the frame
+        let tiles = self.plan_tiles(objects, camera);
+        let tile_colors: Vec<Vec<Color>> = tiles
+            .par_iter()
+            .map(|tile| {
+                let (x0, y0) = (tile.x as usize, tile.y as usize);
+                let tile_width = tile.width as usize;
+                let colors: Vec<Color> = (0..tile_width * tile.height as usize)
+                    .map(|i| self.sample_pixel(x0 + i % tile_width, y0 + i / tile_width, bvh, lights, materials, camera))
+                    .collect();
+                let mut tile_pixels = vec![0u8; colors.len() * 4];
+                for (pixel, color) in tile_pixels.chunks_exact_mut(4).zip(&colors) {
+                    write_pixel(pixel, *color, &self.config);
+                }
+                on_tile(TileResult {
+                    rect: *tile,
+                    pixels: tile_pixels,
+                });
+                colors
+            })
+            .collect();
+        let mut colors = vec![Color::TRANSPARENT; width * height];
+        for (tile, tile_colors) in tiles.iter().zip(&tile_colors) {
+            for (row, row_colors) in tile_colors.chunks(tile.width as usize).enumerate() {
+                let start = (tile.y as usize + row) * width + tile.x as usize;
+                colors[start..start + row_colors.len()].copy_from_slice(row_colors);
+            }
+        }
+        self.refine_edges(&mut colors, bvh, lights, materials, camera);
+        if self.config.denoise {
+            let guides = self.guides(bvh, camera);
+            colors = atrous_denoise(&colors, &guides, &DenoiseSettings::default());
+        }
+        colors
+    }
+
+    /// Trace [`RaytracerConfig::edge_samples`] more rays through every pixel of
+    /// a full frame on an object silhouette and blend them into its color.
+    ///
+    /// A pixel is on a silhouette when the object hit through its center is not
+    /// the one hit through a horizontal or vertical neighbor's, counting missing
+    /// everything as hitting the background. The extra rays are spread evenly
+    /// over the pixel, so the result approaches the share of it each side covers.
+    fn refine_edges(
+        &self,
+        colors: &mut [Color],
+        objects: &Bvh,
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) {
+        let edge_samples = self.config.edge_samples;
+        if edge_samples == 0 {
+            return;
+        }
+        let width = self.config.width as usize;
+        let height = self.config.height as usize;
+
+        // Object hit through each pixel center, by address; 0 for the background
+        let centers: Vec<usize> = (0..width * height)
+            .into_par_iter()
+            .map(|i| {
+                let u = ((i % width) as f32 + 0.5) / width as f32;
+                let v = ((i / width) as f32 + 0.5) / height as f32;
+                objects
+                    .hit(&camera.generate_ray(u, v), 0.001, f32::INFINITY)
+                    .map_or(0, |(_, object)| Arc::as_ptr(object).cast::<()>() as usize)
+            })
+            .collect();
+        let is_edge = |i: usize| {
+            let (x, y) = (i % width, i / width);
+            (x > 0 && centers[i - 1] != centers[i])
+                || (x + 1 < width && centers[i + 1] != centers[i])
+                || (y > 0 && centers[i - width] != centers[i])
+                || (y + 1 < height && centers[i + width] != centers[i])
+        };
+
+        // The pixel's existing samples keep their weight against the extra ones
+        let samples = self.config.samples_per_pixel.max(1);
+        let blend = edge_samples as f32 / (samples + edge_samples) as f32;
+        colors.par_iter_mut().enumerate().filter(|(i, _)| is_edge(*i)).for_each(|(i, color)| {
+            let (x, y) = (i % width, i / width);
+            let edge_color = self
+                .accumulate_offsets(x, y, samples, edge_samples, objects, lights, materials, camera)
+                .resolve(self.config.background_color.a);
+            *color = color.lerp(&edge_color, blend);
+        });
+    }
+
+    /// Like [`Raytracer::accumulate_pixel`], but through fixed points spread
+    /// over the pixel by the R2 low-discrepancy sequence instead of random ones
+    #[allow(clippy::too_many_arguments)]
+    fn accumulate_offsets(
+        &self,
+        x: usize,
+        y: usize,
+        first_sample: u32,
+        samples: u32,
+        objects: &Bvh,
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) -> PixelAccumulator {
+        const R2: Vec2 = Vec2::new(0.754_877_7, 0.569_840_3);
+        let mut sums = PixelAccumulator::default();
+        for index in 0..samples {
+            let offset = (Vec2::splat(0.5) + R2 * index as f32).fract();
+            let u = (x as f32 + offset.x) / self.config.width as f32;
+            let v = (y as f32 + offset.y) / self.config.height as f32;
+            self.seed_sample(x, y, first_sample + index);
+            self.add_primary_sample(&mut sums, &camera.generate_ray(u, v), objects, lights, materials, camera);
+        }
+        sums
+    }
+
+    /// Re-render only the pixels inside `rect` of a full-frame RGBA8 buffer
+    /// previously produced by [`Raytracer::render`], leaving the rest untouched.
+    ///
+    /// `rect` is clipped to the configured resolution. Use this to refresh the
+    /// part of the frame covered by an object that moved.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_region(
+        &self,
+        rect: ViewportRect,
+        pixels: &mut [u8],
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) {
+        let width = self.config.width as usize;
+        debug_assert_eq!(pixels.len(), width * self.config.height as usize * 4);
+        let Some(rect) = rect.clipped(self.config.width, self.config.height) else {
+            return;
+        };
+
+        let bvh = Bvh::new(objects);
+        let lights = &Self::scene_lights(objects, lights);
+        let (x0, y0) = (rect.x as usize, rect.y as usize);
+        let row_bytes = rect.width as usize * 4;
         pixels
-            .par_chunks_mut(4)
+            .par_chunks_mut(width * 4)
+            .skip(y0)
+            .take(rect.height as usize)
             .enumerate()
-            .for_each(|(i, pixel)| {
-                let x = i % width;
-                let y = i / width;
-                
-                let mut color = Color::BLACK;
-                  // Multi-sampling for anti-aliasing
-                for _ in 0..self.config.samples_per_pixel {
-                    let u = (x as f32 + rand::random::<f32>()) / width as f32;
-                    let v = (y as f32 + rand::random::<f32>()) / height as f32;
-                    
-                    let ray = camera.generate_ray(u, v);
-                    let sample_color = self.ray_color(&ray, objects, lights, materials, self.config.max_depth);
-                    color = color + sample_color;
+            .for_each(|(row, row_pixels)| {
+                let region = &mut row_pixels[x0 * 4..x0 * 4 + row_bytes];
+                for (column, pixel) in region.chunks_exact_mut(4).enumerate() {
+                    let color = self.sample_pixel(x0 + column, y0 + row, &bvh, lights, materials, camera);
+                    write_pixel(pixel, color, &self.config);
                 }
-                
-                // Average the samples
-                color = color * (1.0 / self.config.samples_per_pixel as f32);
-                
-                // Gamma correction
-                color = color.to_gamma(2.2).clamp();
-                
-                // Convert to u8 RGBA
-                pixel[0] = (color.r * 255.0) as u8;
-                pixel[1] = (color.g * 255.0) as u8;
-                pixel[2] = (color.b * 255.0) as u8;
-                pixel[3] = (color.a * 255.0) as u8;
             });
+    }
+
+    /// Split the image into the tiles [`Raytracer::render`] distributes across
+    /// threads, smaller where more geometry is visible; see [`plan_tiles`]
+    pub fn plan_tiles(&self, objects: &[Arc<dyn SceneObject>], camera: &Camera) -> Vec<ViewportRect> {
+        plan_tiles(self.config.width, self.config.height, objects, camera, &self.config.tiles)
+    }
+
+    /// Render the denoiser guide buffers: normal, albedo and depth of the first
+    /// surface hit by a ray through each pixel center
+    pub fn render_guides(&self, objects: &[Arc<dyn SceneObject>], camera: &Camera) -> GuideBuffers {
+        self.guides(&Bvh::new(objects), camera)
+    }
+
+    /// Render the guide buffers of [`Raytracer::render_guides`] from a built hierarchy
+    fn guides(&self, objects: &Bvh, camera: &Camera) -> GuideBuffers {
+        let width = self.config.width as usize;
+        let height = self.config.height as usize;
+        let mut guides = GuideBuffers::new(width, height);
+
+        let samples: Vec<Option<(Vec3, Color, f32)>> = (0..width * height)
+            .into_par_iter()
+            .map(|i| {
+                let u = ((i % width) as f32 + 0.5) / width as f32;
+                let v = ((i / width) as f32 + 0.5) / height as f32;
+                let ray = camera.generate_ray(u, v);
+                objects.hit(&ray, 0.001, f32::INFINITY).map(|(hit, object)| {
+                    let albedo = object.material().map_or(Color::BLACK, |material| material.albedo_at(&hit));
+                    (hit.normal, albedo, hit.t)
+                })
+            })
+            .collect();
+
+        for (i, sample) in samples.into_iter().enumerate() {
+            if let Some((normal, albedo, depth)) = sample {
+                guides.normals[i] = normal;
+                guides.albedo[i] = albedo;
+                guides.depth[i] = depth;
+            }
+        }
+        guides
+    }
+
+    /// Render the scene split into arbitrary output variables for compositing.
+    ///
+    /// Every buffer holds three linear floats per pixel (RGB, or XYZ for
+    /// [`AovKind::Normal`]) in row-major order, averaged over
+    /// `samples_per_pixel` jittered primary rays. Lighting AOVs describe the
+    /// first surface hit; there is one [`AovKind::Light`] buffer per light,
+    /// followed by one per emissive sphere, and they sum to the direct lighting
+    /// contained in the diffuse and specular passes.
+    /// Only [`AovKind::Beauty`] is scaled by the camera's exposure, so it matches
+    /// [`Raytracer::render_hdr`].
+    pub fn render_aovs(
+        &self,
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) -> HashMap<AovKind, Vec<f32>> {
+        let width = self.config.width as usize;
+        let height = self.config.height as usize;
+        let bvh = Bvh::new(objects);
+        let lights = &Self::scene_lights(objects, lights);
+        let samples: Vec<AovSample> = (0..width * height)
+            .into_par_iter()
+            .map(|i| self.sample_aovs(i % width, i / width, &bvh, lights, materials, camera))
+            .collect();
+
+        let mut kinds = vec![AovKind::Beauty, AovKind::Albedo, AovKind::Normal, AovKind::Diffuse, AovKind::Specular];
+        kinds.extend((0..lights.len()).map(AovKind::Light));
+
+        kinds
+            .into_iter()
+            .map(|kind| {
+                let buffer = samples
+                    .iter()
+                    .flat_map(|sample| {
+                        let value = match kind {
+                            AovKind::Beauty => sample.beauty,
+                            AovKind::Albedo => sample.albedo,
+                            AovKind::Normal => sample.normal,
+                            AovKind::Diffuse => sample.diffuse,
+                            AovKind::Specular => sample.specular,
+                            AovKind::Light(index) => sample.lights[index],
+                        };
+                        value.to_array()
+                    })
+                    .collect();
+                (kind, buffer)
+            })
+            .collect()
+    }
+
+    /// Trace `samples_per_pixel` jittered primary rays through a pixel and average every AOV
+    #[allow(clippy::too_many_arguments)]
+    fn sample_aovs(
+        &self,
+        x: usize,
+        y: usize,
+        objects: &Bvh,
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) -> AovSample {
+        let samples = self.config.samples_per_pixel.max(1);
+        let width = self.config.width as f32;
+        let height = self.config.height as f32;
+        let mut sums = AovSample {
+            beauty: Vec3::ZERO,
+            albedo: Vec3::ZERO,
+            normal: Vec3::ZERO,
+            diffuse: Vec3::ZERO,
+            specular: Vec3::ZERO,
+            lights: vec![Vec3::ZERO; lights.len()],
+        };
+
+        for sample in 0..samples {
+            self.seed_sample(x, y, sample);
+            let u = (x as f32 + random::random::<f32>()) / width;
+            let v = (y as f32 + random::random::<f32>()) / height;
+            let ray = camera.generate_ray(u, v);
+
+            let Some((hit, object)) = objects.hit(&ray, 0.001, f32::INFINITY) else {
+                sums.beauty += self.background_radiance(ray.direction).to_vec3();
+                continue;
+            };
+            sums.normal += hit.normal;
+            let Some(material) = object.material() else {
+                continue;
+            };
+            sums.albedo += material.albedo_at(&hit).to_vec3();
+
+            let diffuse = Self::is_diffuse(material.as_ref(), &ray, &hit);
+            let casters = Self::shadow_casters(object, objects);
+            let mut reflected = Vec3::ZERO;
+            for (light, light_sum) in lights.iter().zip(&mut sums.lights) {
+                let direct = self.direct_light(&hit, material.as_ref(), diffuse, light.as_ref(), casters).to_vec3();
+                *light_sum += direct;
+                reflected += direct;
+            }
+            if diffuse {
+                reflected += self.sample_environment_light(&hit, material.as_ref(), casters).to_vec3();
+                reflected += self.gather_caustics(&hit, material.as_ref()).to_vec3();
+            }
+            reflected += self.scattered_light(&ray, &hit, material.as_ref(), diffuse, objects, lights, materials, self.config.max_depth.saturating_sub(1), None);
+
+            if diffuse {
+                sums.diffuse += reflected;
+            } else {
+                sums.specular += reflected;
+            }
+            sums.beauty += material.ambient_color().to_vec3() * 0.1 + material.emitted().to_vec3() + reflected;
+        }
+
+        let scale = 1.0 / samples as f32;
+        AovSample {
+            beauty: sums.beauty * (scale * camera.exposure_multiplier()),
+            albedo: sums.albedo * scale,
+            normal: sums.normal * scale,
+            diffuse: sums.diffuse * scale,
+            specular: sums.specular * scale,
+            lights: sums.lights.into_iter().map(|light| light * scale).collect(),
+        }
+    }
+
+    /// Add `samples` more samples per pixel to the persistent accumulation buffer
+    /// and return the resolved image.
+    ///
+    /// Repeated calls refine the same image, so a long render can be split into
+    /// batches and checkpointed in between with [`Raytracer::save_state`].
+    pub fn render_samples(
+        &mut self,
+        samples: u32,
+        objects: &[Arc<dyn SceneObject>],
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) -> Vec<u8> {
+        let width = self.config.width as usize;
+        let height = self.config.height as usize;
+        if self.accumulation.len() != width * height {
+            self.reset_accumulation();
+        }
+        if self.accumulated_samples == 0 && self.config.caustics {
+            self.update_photon_map(objects, lights);
+        }
+
+        if samples > 0 {
+            let bvh = Bvh::new(objects);
+            let lights = &Self::scene_lights(objects, lights);
+            let first_sample = self.accumulated_samples;
+            let mut accumulation = std::mem::take(&mut self.accumulation);
+            accumulation
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(i, sums)| {
+                    let pixel = self.accumulate_pixel(i % width, i / width, first_sample, samples, &bvh, lights, materials, camera);
+                    sums.add(&pixel);
+                });
+            self.accumulation = accumulation;
+            self.accumulated_samples += samples;
+        }
+
+        self.accumulated_image()
+    }
 
+    /// Resolve the accumulation buffer into an RGBA8 image
+    #[must_use]
+    pub fn accumulated_image(&self) -> Vec<u8> {
+        let background_alpha = self.config.background_color.a;
+        let mut pixels = vec![0u8; self.accumulation.len() * 4];
+        pixels
+            .par_chunks_mut(4)
+            .zip(self.accumulation.par_iter())
+            .for_each(|(pixel, sums)| write_pixel(pixel, sums.resolve(background_alpha), &self.config));
         pixels
     }
 
-    /// Calculate color for a ray
+    /// Get the number of samples per pixel accumulated so far
+    #[must_use]
+    pub const fn accumulated_samples(&self) -> u32 {
+        self.accumulated_samples
+    }
+
+    /// Discard all accumulated samples
+    pub fn reset_accumulation(&mut self) {
+        let pixel_count = self.config.width as usize * self.config.height as usize;
+        self.accumulation = vec![PixelAccumulator::default(); pixel_count];
+        self.accumulated_samples = 0;
+    }
+
+    /// Serialize the accumulation buffer and sample count so a render can be resumed later
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing to `writer` fails.
+    pub fn save_state<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(STATE_MAGIC)?;
+        for value in [self.config.width, self.config.height, self.accumulated_samples] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        for sums in &self.accumulation {
+            for value in (sums.hit_mean * sums.hit_count as f32).to_array() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&sums.hit_count.to_le_bytes())?;
+            for value in (sums.background_mean * sums.background_count as f32).to_array() {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&sums.background_count.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Restore an accumulation buffer written by [`Raytracer::save_state`].
+    ///
+    /// The saved resolution must match the current configuration. States saved
+    /// before background sums were stored are assumed to have used the current
+    /// solid `background_color`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the data is not a saved state, was saved at a different
+    /// resolution or cannot be read.
+    pub fn load_state<R: Read>(&mut self, mut reader: R) -> Result<()> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        let legacy = match &magic {
+            STATE_MAGIC => false,
+            STATE_MAGIC_V1 => true,
+            _ => return Err(anyhow!("Not a raytracer state file")),
+        };
+
+        let width = read_u32(&mut reader)?;
+        let height = read_u32(&mut reader)?;
+        if width != self.config.width || height != self.config.height {
+            return Err(anyhow!(
+                "Saved state is {}x{} but the raytracer is configured for {}x{}",
+                width, height, self.config.width, self.config.height
+            ));
+        }
+        let accumulated_samples = read_u32(&mut reader)?;
+
+        let pixel_count = width as usize * height as usize;
+        let mut accumulation = Vec::with_capacity(pixel_count);
+        for _ in 0..pixel_count {
+            let hit_sum = Vec3::new(read_f32(&mut reader)?, read_f32(&mut reader)?, read_f32(&mut reader)?);
+            let hit_count = read_u32(&mut reader)?;
+            let background_sum = if legacy {
+                None
+            } else {
+                Some(Vec3::new(read_f32(&mut reader)?, read_f32(&mut reader)?, read_f32(&mut reader)?))
+            };
+            let background_count = read_u32(&mut reader)?;
+            let background_sum = background_sum
+                .unwrap_or_else(|| self.config.background_color.to_vec3() * background_count as f32);
+            accumulation.push(PixelAccumulator::from_sums(hit_sum, hit_count, background_sum, background_count));
+        }
+
+        self.accumulation = accumulation;
+        self.accumulated_samples = accumulated_samples;
+        Ok(())
+    }
+
+    /// Average `samples_per_pixel` jittered samples for a pixel in linear space.
+    fn sample_pixel(
+        &self,
+        x: usize,
+        y: usize,
+        objects: &Bvh,
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) -> Color {
+        let samples = self.config.samples_per_pixel.max(1);
+        self.accumulate_pixel(x, y, 0, samples, objects, lights, materials, camera)
+            .resolve(self.config.background_color.a)
+    }
+
+    /// Trace `samples` jittered primary rays through a pixel and sum the results,
+    /// scaled by the camera's exposure.
+    ///
+    /// Samples are numbered from `first_sample` for seeding, so a render split
+    /// into batches matches one rendered at once.
+    #[allow(clippy::too_many_arguments)]
+    fn accumulate_pixel(
+        &self,
+        x: usize,
+        y: usize,
+        first_sample: u32,
+        samples: u32,
+        objects: &Bvh,
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) -> PixelAccumulator {
+        let width = self.config.width as f32;
+        let height = self.config.height as f32;
+        let mut sums = PixelAccumulator::default();
+
+        // Multi-sampling for anti-aliasing
+        for sample in first_sample..first_sample + samples {
+            self.seed_sample(x, y, sample);
+            let u = (x as f32 + random::random::<f32>()) / width;
+            let v = (y as f32 + random::random::<f32>()) / height;
+
+            self.add_primary_sample(&mut sums, &camera.generate_ray(u, v), objects, lights, materials, camera);
+        }
+
+        sums
+    }
+
+    /// Trace a primary ray and add its color, scaled by the camera's exposure,
+    /// to the hit or background sums
+    fn add_primary_sample(
+        &self,
+        sums: &mut PixelAccumulator,
+        ray: &Ray,
+        objects: &Bvh,
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        camera: &Camera,
+    ) {
+        let exposure = camera.exposure_multiplier();
+        match self.trace(ray, false, objects, lights, materials, self.config.max_depth, None) {
+            Some(sample_color) => {
+                sums.add_hit(sample_color.to_vec3() * exposure);
+            }
+            None => {
+                sums.add_background(self.background_radiance(ray.direction).to_vec3() * exposure);
+            }
+        }
+    }
+
+    /// Reseed this thread's sampling generator for one sample of a pixel, if
+    /// the configuration has a seed
+    fn seed_sample(&self, x: usize, y: usize, sample: u32) {
+        if let Some(seed) = self.config.seed {
+            random::seed(random::derive_seed(seed, &[x as u64, y as u64, u64::from(sample)]));
+        }
+    }
+
+    /// Calculate color for a ray scattered off a surface.
+    ///
+    /// When an environment map is set, rays scattered off diffuse surfaces that
+    /// escape contribute nothing, because light sampling already counted the
+    /// environment for those surfaces.
+    #[allow(clippy::too_many_arguments)]
     fn ray_color(
         &self,
         ray: &Ray,
-        objects: &[Arc<dyn SceneObject>],
+        from_diffuse: bool,
+        objects: &Bvh,
         lights: &[Arc<dyn Light>],
         materials: &[Arc<dyn Material>],
         depth: u32,
+        channel: Option<usize>,
     ) -> Color {
-        if depth == 0 {
+        self.trace(ray, from_diffuse, objects, lights, materials, depth, channel).unwrap_or_else(|| {
+            if from_diffuse && self.environment.is_some() {
+                Color::BLACK
+            } else {
+                self.background_radiance(ray.direction)
+            }
+        })
+    }
+
+    /// Light arriving along a material's scattered ray, weighted by its albedo.
+    ///
+    /// With dispersion enabled, a dispersive material hit by a full-color ray
+    /// (`channel` is `None`) scatters one ray per color channel and keeps only
+    /// that channel of each; those rays stay single-channel for the rest of
+    /// their path, so later dispersive hits do not split them again.
+    #[allow(clippy::too_many_arguments)]
+    fn scattered_light(
+        &self,
+        ray: &Ray,
+        hit: &HitInfo,
+        material: &dyn Material,
+        diffuse: bool,
+        objects: &Bvh,
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        depth: u32,
+        channel: Option<usize>,
+    ) -> Vec3 {
+        let albedo = material.albedo_at(hit).to_vec3();
+        let dispersive = self.config.dispersion && material.is_dispersive();
+
+        if dispersive && channel.is_none() {
+            let mut light = Vec3::ZERO;
+            for channel in 0..3 {
+                if let Some(scattered_ray) = material.scatter_channel(ray, hit, channel) {
+                    let color = self.ray_color(&scattered_ray, diffuse, objects, lights, materials, depth, Some(channel));
+                    light[channel] = color.to_vec3()[channel];
+                }
+            }
+            return albedo * light;
+        }
+
+        let scattered = match channel {
+            Some(channel) if dispersive => material.scatter_channel(ray, hit, channel),
+            _ => material.scatter(ray, hit),
+        };
+        scattered.map_or(Vec3::ZERO, |scattered_ray| {
+            albedo * self.ray_color(&scattered_ray, diffuse, objects, lights, materials, depth, channel).to_vec3()
+        })
+    }
+
+    /// Radiance seen by a ray that escapes the scene
+    fn background_radiance(&self, direction: Vec3) -> Color {
+        self.environment.as_ref().map_or_else(
+            || self.config.background().sample(direction),
+            |environment| environment.lookup(direction),
+        )
+    }
+
+    /// Importance sample the environment map as a light for a diffuse hit,
+    /// returning `albedo * radiance * cos / (pi * pdf)`
+    fn sample_environment_light(&self, hit: &HitInfo, material: &dyn Material, casters: Option<&Bvh>) -> Color {
+        let Some(environment) = &self.environment else {
             return Color::BLACK;
-        }        // Find closest intersection
-        let mut closest_hit: Option<HitInfo> = None;
-        let mut closest_object: Option<&Arc<dyn SceneObject>> = None;
-        
+        };
+        let Some(sample) = environment.sample(Vec2::new(random::random(), random::random())) else {
+            return Color::BLACK;
+        };
+
+        let cosine = Self::diffuse_cosine(material, hit, sample.direction);
+        if cosine <= 0.0 || sample.pdf <= 0.0 {
+            return Color::BLACK;
+        }
+        if self.is_occluded(hit, sample.direction, f32::INFINITY, casters) {
+            return Color::BLACK;
+        }
+
+        let albedo = material.albedo_at(hit).to_vec3();
+        Color::from(albedo * sample.radiance.to_vec3() * (cosine / (std::f32::consts::PI * sample.pdf)))
+    }
+
+    /// Light focused onto a diffuse hit by specular surfaces, estimated from the
+    /// caustic photon map as `albedo * irradiance`
+    fn gather_caustics(&self, hit: &HitInfo, material: &dyn Material) -> Color {
+        if !self.config.caustics {
+            return Color::BLACK;
+        }
+        let Some(photon_map) = &self.photon_map else {
+            return Color::BLACK;
+        };
+        let irradiance = photon_map.irradiance(hit.point, hit.normal, self.config.photon_radius);
+        Color::from(material.albedo_at(hit).to_vec3() * irradiance)
+    }
+
+    /// Check whether a material scatters diffusely at a hit, i.e. it has a
+    /// non-delta distribution that light sampling can contribute to
+    pub(crate) fn is_diffuse(material: &dyn Material, ray: &Ray, hit: &HitInfo) -> bool {
+        material.scatter_pdf(ray, hit, &Ray::new(hit.point, hit.normal)) > 0.0
+            || material.scatter_pdf(ray, hit, &Ray::new(hit.point, -hit.normal)) > 0.0
+    }
+
+    /// Cosine weight of light arriving from `direction` at a diffuse hit.
+    ///
+    /// Light from behind the surface only reaches the viewer's side of thin
+    /// materials, scaled by their translucency; light from the front is scaled
+    /// by the share they do not transmit.
+    fn diffuse_cosine(material: &dyn Material, hit: &HitInfo, direction: Vec3) -> f32 {
+        let cosine = hit.normal.dot(direction);
+        let translucency = if material.is_thin() { material.translucency() } else { 0.0 };
+        if cosine >= 0.0 {
+            cosine * (1.0 - translucency)
+        } else {
+            -cosine * translucency
+        }
+    }
+
+    /// Next event estimation: sample one light uniformly, test its visibility and
+    /// return `albedo / π * radiance * cos / pdf`.
+    ///
+    /// Point, spot and directional lights are delta lights that scattered rays
+    /// can never hit, so the recursive bounce only carries indirect light and
+    /// nothing is counted twice. Rays scattered off diffuse surfaces skip the
+    /// emission of spheres sampled as [`SphereLight`]s for the same reason.
+    fn sample_direct_light(
+        &self,
+        hit: &HitInfo,
+        material: &dyn Material,
+        casters: Option<&Bvh>,
+        lights: &[Arc<dyn Light>],
+    ) -> Color {
+        if lights.is_empty() {
+            return Color::BLACK;
+        }
+
+        let light = &lights[random::gen_range(0..lights.len())];
+        let light_pdf = 1.0 / lights.len() as f32;
+        Color::from(self.direct_light(hit, material, true, light.as_ref(), casters).to_vec3() / light_pdf)
+    }
+
+    /// Direct lighting from a single light at a hit.
+    ///
+    /// Diffuse surfaces receive `albedo / π * radiance * cos`, the Lambertian
+    /// BRDF times the light's irradiance, from a point on the light picked with
+    /// the per-sample random sequence, so accumulated samples converge to the
+    /// light's penumbra, and are shadow tested against `casters`; other
+    /// surfaces receive the unshadowed light color.
+    fn direct_light(
+        &self,
+        hit: &HitInfo,
+        material: &dyn Material,
+        diffuse: bool,
+        light: &dyn Light,
+        casters: Option<&Bvh>,
+    ) -> Color {
+        if !diffuse {
+            // Specular surfaces already reflect emissive spheres as geometry
+            if light.as_any().is::<SphereLight>() {
+                return Color::BLACK;
+            }
+            let contribution = light.illuminate(hit.point, hit.normal);
+            return contribution.color * contribution.attenuation;
+        }
+        let u = Vec2::new(random::random(), random::random());
+        let contribution = light.illuminate_sampled(hit.point, hit.normal, u);
+
+        let radiance = contribution.color.to_vec3() * contribution.attenuation;
+        let albedo = material.albedo_at(hit).to_vec3();
+
+        // Ambient lights have no direction, so they are neither shadowed nor cosine weighted
+        if contribution.direction == Vec3::ZERO {
+            return Color::from(albedo * radiance);
+        }
+
+        let cosine = material.diffuse_response(Self::diffuse_cosine(material, hit, contribution.direction));
+        if cosine <= 0.0 || contribution.attenuation <= 0.0 {
+            return Color::BLACK;
+        }
+
+        if self.is_occluded(hit, contribution.direction, contribution.distance, casters) {
+            return Color::BLACK;
+        }
+
+        Color::from(albedo * FRAC_1_PI * radiance * cosine)
+    }
+
+    /// Check whether anything among `casters` that casts shadows blocks a
+    /// shadow ray leaving a hit in `direction` before it travels `max_distance`.
+    ///
+    /// The ray starts the shadow epsilon off the surface, on the side it leaves
+    /// towards, and ignores blockers within the epsilon of either end.
+    fn is_occluded(&self, hit: &HitInfo, direction: Vec3, max_distance: f32, casters: Option<&Bvh>) -> bool {
+        let Some(casters) = casters else {
+            return false;
+        };
+        let epsilon = self.config.shadow_epsilon(hit.t);
+        let offset = if hit.normal.dot(direction) < 0.0 { -epsilon } else { epsilon };
+        let ray = Ray::new(hit.point + hit.normal * offset, direction);
+        // The offset origin is already part of the way along the ray
+        let end = hit.normal.dot(direction).mul_add(-offset, max_distance);
+        casters.any_hit(&ray, epsilon, end - epsilon, |object| object.shadow_flags().casts_shadow)
+    }
+
+    /// Find the closest intersection along a ray and the object it hit
+    pub(crate) fn closest_hit<'a>(ray: &Ray, objects: &'a [Arc<dyn SceneObject>]) -> Option<(HitInfo, &'a Arc<dyn SceneObject>)> {
+        let mut closest: Option<(HitInfo, &Arc<dyn SceneObject>)> = None;
         for object in objects {
-            if let Some(hit) = object.intersect(ray, 0.001, f32::INFINITY) {
-                if closest_hit.is_none() || hit.t < closest_hit.as_ref().unwrap().t {
-                    closest_hit = Some(hit);
-                    closest_object = Some(object);
-                }
+            let t_max = closest.as_ref().map_or(f32::INFINITY, |(hit, _)| hit.t);
+            if let Some(hit) = object.intersect(ray, 0.001, t_max) {
+                closest = Some((hit, object));
             }
         }
+        closest
+    }
 
-        if let (Some(hit), Some(object_arc)) = (closest_hit, closest_object) {
+    /// Calculate color for a ray, returning `None` if it escapes to the background.
+    ///
+    /// `channel` is set for rays split off by a dispersive material, of which
+    /// only that color channel is used. Rays scattered off diffuse surfaces
+    /// (`from_diffuse`) see no emission from spheres registered as
+    /// [`SphereLight`]s, because light sampling already counted it.
+    #[allow(clippy::too_many_arguments)]
+    fn trace(
+        &self,
+        ray: &Ray,
+        from_diffuse: bool,
+        objects: &Bvh,
+        lights: &[Arc<dyn Light>],
+        materials: &[Arc<dyn Material>],
+        depth: u32,
+        channel: Option<usize>,
+    ) -> Option<Color> {
+        if depth == 0 {
+            return Some(Color::BLACK);
+        }        // Find closest intersection
+        if let Some((hit, object_arc)) = objects.hit(ray, 0.001, f32::INFINITY) {
             // Get material directly from the object
             if let Some(material_arc) = object_arc.material() {
                 let material = material_arc; // material is Arc<dyn Material>
@@ -122,28 +1497,604 @@ impl Raytracer {
             
                 // Ambient lighting
                 color = color + material.ambient_color() * 0.1; // Assuming ambient_color() exists and is suitable
+                // Self-illumination; also reaches other surfaces through their scattered rays
+                if !(from_diffuse && SphereLight::from_emissive(object_arc.as_ref()).is_some()) {
+                    color = color + material.emitted();
+                }
                 // Direct lighting from light sources
-                for light in lights {
-                    let light_contribution = light.illuminate(hit.point, hit.normal);
-                    color = color + light_contribution.color * light_contribution.attenuation;
+                let diffuse = Self::is_diffuse(material.as_ref(), ray, &hit);
+                let casters = Self::shadow_casters(object_arc, objects);
+                if diffuse {
+                    color = color + self.sample_direct_light(&hit, material.as_ref(), casters, lights);
+                    color = color + self.sample_environment_light(&hit, material.as_ref(), casters);
+                    color = color + self.gather_caustics(&hit, material.as_ref());
+                } else {
+                    for light in lights {
+                        color = color + self.direct_light(&hit, material.as_ref(), false, light.as_ref(), casters);
+                    }
                 }
             
                 // Recursive reflection/refraction
-                if let Some(scattered_ray) = material.scatter(ray, &hit) {
-                    let attenuation = material.albedo();
-                    let scattered_color = self.ray_color(&scattered_ray, objects, lights, materials, depth - 1);
-                    color = color + Color::from(attenuation.to_vec3() * scattered_color.to_vec3());
-                }
+                let scattered = self.scattered_light(ray, &hit, material.as_ref(), diffuse, objects, lights, materials, depth - 1, channel);
+                color = color + Color::from(scattered);
             
-                return color; // Return the calculated color
+                return Some(color); // Return the calculated color
             } else {
                 // Object hit but has no material. This should ideally be handled.
                 // For now, return black to make it visually distinct if this path is taken.
-                return Color::BLACK; 
+                return Some(Color::BLACK);
             }
         } else {
-            // Background color
-            self.config.background_color
+            // Ray escaped to the background
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AreaLight, CameraExposure, Cube, DielectricMaterial, EmissiveMaterial, LambertianMaterial, Plane, PointLight, Sphere};
+
+    fn config(width: u32, height: u32, samples: u32) -> RaytracerConfig {
+        RaytracerConfig {
+            width,
+            height,
+            samples_per_pixel: samples,
+            background_color: Color::BLACK,
+            seed: Some(7),
+            ..RaytracerConfig::default()
+        }
+    }
+
+    fn camera_at(position: Vec3, target: Vec3, up: Vec3, fov: f32) -> Camera {
+        let mut camera = Camera::new_perspective(fov, 1.0, 0.1, 100.0);
+        camera.transform.position = position;
+        camera.look_at(target, up);
+        camera
+    }
+
+    #[test]
+    fn partially_covered_pixel_weights_background_by_coverage() {
+        let mut sums = PixelAccumulator::default();
+        sums.add_hit(Vec3::ONE);
+        for _ in 0..3 {
+            sums.add_background(Vec3::new(0.2, 0.4, 0.0));
+        }
+
+        let color = sums.resolve(0.0);
+        assert!((color.r - 0.4).abs() < 1e-6);
+        assert!((color.g - 0.55).abs() < 1e-6);
+        assert!((color.b - 0.25).abs() < 1e-6);
+        assert!((color.a - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)] // The background must come back bit-exact
+    fn background_pixel_equals_background_for_any_sample_count() {
+        let background = Color::new(0.1, 0.3, 0.7, 1.0);
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 1.0);
+        for samples in [1, 3, 7, 10] {
+            let raytracer = Raytracer::new(RaytracerConfig {
+                background_color: background,
+                ..config(4, 4, samples)
+            });
+            for pixel in raytracer.render_hdr(&[], &[], &[], &camera) {
+                assert_eq!(pixel, [background.r, background.g, background.b, background.a], "{samples} samples");
+            }
         }
     }
+
+    #[test]
+    fn point_light_on_diffuse_surface_matches_lambertian_radiance() {
+        let (albedo, intensity, height) = (0.5, 8.0, 2.0);
+        let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(Plane::with_material(
+            Vec3::ZERO,
+            Vec3::Y,
+            LambertianMaterial::new(Color::new(albedo, albedo, albedo, 1.0)),
+        ))];
+        // Pure quadratic falloff, so the irradiance below the light is I / (1 + h²)
+        let light: Arc<dyn Light> = Arc::new(PointLight::with_attenuation(
+            Vec3::new(0.0, height, 0.0),
+            Color::WHITE,
+            intensity,
+            100.0,
+            0.0,
+            1.0,
+        ));
+        let camera = camera_at(Vec3::new(0.0, 1.0, 0.0), Vec3::ZERO, Vec3::Z, 0.01);
+        let raytracer = Raytracer::new(config(1, 1, 16));
+
+        let lit = raytracer.render_hdr(&objects, &[light], &[], &camera)[0];
+        let unlit = raytracer.render_hdr(&objects, &[], &[], &camera)[0];
+
+        let expected = albedo * FRAC_1_PI * intensity / height.mul_add(height, 1.0);
+        for channel in 0..3 {
+            let radiance = lit[channel] - unlit[channel];
+            assert!((radiance - expected).abs() < 1e-3, "channel {channel}: {radiance} != {expected}");
+        }
+    }
+
+    #[test]
+    fn two_batches_of_five_samples_equal_one_of_ten() {
+        let mut sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), 1.0);
+        sphere.set_material(LambertianMaterial::new(Color::new(0.8, 0.4, 0.2, 1.0)));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sphere)];
+        let lights: Vec<Arc<dyn Light>> =
+            vec![Arc::new(PointLight::new(Vec3::new(2.0, 2.0, 0.0), Color::WHITE, 5.0))];
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 1.0);
+
+        let mut single = Raytracer::new(config(8, 8, 1));
+        let expected = single.render_samples(10, &objects, &lights, &[], &camera);
+
+        let mut first = Raytracer::new(config(8, 8, 1));
+        first.render_samples(5, &objects, &lights, &[], &camera);
+        let mut state = Vec::new();
+        first.save_state(&mut state).unwrap();
+
+        // Resume in a fresh raytracer, as a new process would
+        let mut resumed = Raytracer::new(config(8, 8, 1));
+        resumed.load_state(state.as_slice()).unwrap();
+        assert_eq!(resumed.accumulated_samples(), 5);
+        let image = resumed.render_samples(5, &objects, &lights, &[], &camera);
+
+        assert_eq!(resumed.accumulated_samples(), 10);
+        assert_eq!(image, expected);
+    }
+
+    #[test]
+    fn state_saved_at_another_resolution_is_rejected() {
+        let mut state = Vec::new();
+        Raytracer::new(config(4, 4, 1)).save_state(&mut state).unwrap();
+        assert!(Raytracer::new(config(8, 8, 1)).load_state(state.as_slice()).is_err());
+        assert!(Raytracer::new(config(4, 4, 1)).load_state(&b"not a state"[..]).is_err());
+    }
+
+    #[test]
+    fn rendered_pixels_are_srgb_encoded() {
+        let raytracer = Raytracer::new(RaytracerConfig {
+            background_color: Color::gray(0.5),
+            ..config(2, 2, 1)
+        });
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 1.0);
+        for pixel in raytracer.render(&[], &[], &[], &camera).chunks_exact(4) {
+            assert_eq!(pixel, [188, 188, 188, 255]);
+        }
+    }
+
+    #[test]
+    fn albedo_aov_is_unlit_and_light_aovs_sum_to_the_lighting() {
+        let red = Color::new(0.8, 0.1, 0.1, 1.0);
+        let mut sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), 1.0);
+        sphere.set_material(LambertianMaterial::new(red));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sphere)];
+        let lights: Vec<Arc<dyn Light>> = vec![
+            Arc::new(PointLight::new(Vec3::new(2.0, 2.0, 0.0), Color::WHITE, 5.0)),
+            Arc::new(PointLight::new(Vec3::new(-2.0, 0.0, 0.0), Color::new(0.2, 0.4, 1.0, 1.0), 3.0)),
+        ];
+        // A narrow view keeps every pixel on the sphere; one bounce into a black
+        // background leaves only the direct light and the ambient term
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 0.3);
+        let raytracer = Raytracer::new(RaytracerConfig {
+            max_depth: 1,
+            ..config(4, 4, 2)
+        });
+        let aovs = raytracer.render_aovs(&objects, &lights, &[], &camera);
+
+        let beauty = &aovs[&AovKind::Beauty];
+        let albedo = &aovs[&AovKind::Albedo];
+        for (i, value) in albedo.iter().enumerate() {
+            let expected = red.to_vec3()[i % 3];
+            assert!((value - expected).abs() < 1e-5, "albedo {i}: {value} != {expected}");
+
+            let lit = aovs[&AovKind::Light(0)][i] + aovs[&AovKind::Light(1)][i];
+            assert!(lit > 0.0);
+            let combined = lit + value * 0.01;
+            assert!((beauty[i] - combined).abs() < 1e-4, "beauty {i}: {} != {combined}", beauty[i]);
+        }
+    }
+
+    #[test]
+    fn brighter_hdr_emission_renders_brighter() {
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 0.3);
+        let raytracer = Raytracer::new(config(4, 4, 2));
+        let red_channel = |emission: Color| {
+            let mut sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), 1.0);
+            sphere.set_material(EmissiveMaterial::hdr(emission));
+            let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sphere)];
+            let pixels = raytracer.render_hdr(&objects, &[], &[], &camera);
+            assert!(pixels.iter().all(|pixel| pixel[1] == 0.0 && pixel[2] == 0.0));
+            pixels.iter().map(|pixel| pixel[0]).sum::<f32>()
+        };
+
+        let single = red_channel(Color::new(1.0, 0.0, 0.0, 1.0));
+        let double = red_channel(Color::new(2.0, 0.0, 0.0, 1.0));
+        assert!(single > 0.0);
+        assert!((double / single - 2.0).abs() < 1e-4, "{double} != 2 * {single}");
+    }
+
+    #[test]
+    fn photons_focus_a_caustic_under_a_glass_sphere() {
+        let mut glass = Sphere::new(Vec3::new(0.0, 2.0, 0.0), 1.0);
+        glass.set_material(DielectricMaterial::new(1.5));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![
+            Arc::new(glass),
+            Arc::new(Plane::with_material(Vec3::ZERO, Vec3::Y, LambertianMaterial::new(Color::WHITE))),
+        ];
+        let lights: Vec<Arc<dyn Light>> =
+            vec![Arc::new(PointLight::new(Vec3::new(0.0, 8.0, 0.0), Color::WHITE, 20.0))];
+        // A low, narrow view of the ground under the sphere that passes below it
+        let camera = camera_at(Vec3::new(0.0, 0.3, 3.0), Vec3::ZERO, Vec3::Y, 0.05);
+
+        let brightness = |caustics: bool| {
+            let mut raytracer = Raytracer::new(RaytracerConfig {
+                caustics,
+                caustic_photons: 20_000,
+                photon_radius: 0.2,
+                max_depth: 4,
+                ..config(4, 4, 1)
+            });
+            raytracer.update_photon_map(&objects, &lights);
+            assert_eq!(raytracer.photon_map().is_some(), caustics);
+            let pixels = raytracer.render_hdr(&objects, &lights, &[], &camera);
+            pixels.iter().map(|pixel| pixel[0]).sum::<f32>()
+        };
+
+        let without = brightness(false);
+        let with = brightness(true);
+        assert!(with > 2.0 * without, "{with} vs {without}");
+    }
+
+    #[test]
+    fn thin_sheet_lit_from_behind_glows_on_the_front() {
+        let lights: Vec<Arc<dyn Light>> =
+            vec![Arc::new(PointLight::new(Vec3::new(0.0, 0.0, -5.0), Color::WHITE, 10.0))];
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 0.5);
+        let raytracer = Raytracer::new(RaytracerConfig {
+            max_depth: 2,
+            ..config(4, 4, 8)
+        });
+        let front_brightness = |material: Arc<dyn Material>| {
+            let sheet = Plane::with_material(Vec3::new(0.0, 0.0, -3.0), Vec3::Z, material);
+            let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sheet)];
+            let pixels = raytracer.render_hdr(&objects, &lights, &[], &camera);
+            pixels.iter().map(|pixel| pixel[1]).sum::<f32>()
+        };
+
+        let green = Color::new(0.2, 0.8, 0.2, 1.0);
+        let opaque = front_brightness(LambertianMaterial::new(green));
+        let thin = front_brightness(LambertianMaterial::thin(green, 0.5));
+        assert!(thin > 10.0 * opaque, "{thin} vs {opaque}");
+    }
+
+    #[test]
+    fn same_seed_renders_area_lights_and_glass_identically() {
+        let mut glass = Sphere::new(Vec3::new(0.5, 0.0, -3.0), 0.7);
+        glass.set_material(DielectricMaterial::new(1.5));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![
+            Arc::new(glass),
+            Arc::new(Plane::with_material(Vec3::new(0.0, -1.0, 0.0), Vec3::Y, LambertianMaterial::new(Color::WHITE))),
+        ];
+        let panel = AreaLight::new(Vec3::new(-1.0, 3.0, -4.0), Vec3::X * 2.0, Vec3::Z * 2.0, Color::WHITE, 5.0);
+        let lights: Vec<Arc<dyn Light>> = vec![Arc::new(panel)];
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 1.0);
+        let render = |seed: u64| {
+            let raytracer = Raytracer::new(RaytracerConfig {
+                seed: Some(seed),
+                ..config(8, 8, 4)
+            });
+            raytracer.render(&objects, &lights, &[], &camera)
+        };
+
+        assert_eq!(render(5), render(5));
+        assert_ne!(render(5), render(6));
+    }
+
+    #[test]
+    fn render_region_only_rewrites_pixels_inside_the_rect() {
+        let lights: Vec<Arc<dyn Light>> = vec![Arc::new(PointLight::new(Vec3::new(0.0, 3.0, 0.0), Color::WHITE, 20.0))];
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 1.0);
+        let raytracer = Raytracer::new(config(32, 32, 1));
+        let sphere_at = |x: f32| -> Vec<Arc<dyn SceneObject>> {
+            vec![Arc::new(Sphere::with_material(
+                Vec3::new(x, 0.0, -3.0),
+                1.0,
+                LambertianMaterial::new(Color::WHITE),
+            ))]
+        };
+
+        let before = raytracer.render(&sphere_at(0.0), &lights, &[], &camera);
+        let moved = sphere_at(0.6);
+        let after = raytracer.render(&moved, &lights, &[], &camera);
+
+        let rect = ViewportRect::new(8, 11, 10, 10);
+        let mut pixels = before.clone();
+        raytracer.render_region(rect, &mut pixels, &moved, &lights, &[], &camera);
+
+        for (index, (pixel, (old, new))) in pixels
+            .chunks_exact(4)
+            .zip(before.chunks_exact(4).zip(after.chunks_exact(4)))
+            .enumerate()
+        {
+            let (x, y) = (index % 32, index / 32);
+            let inside = (8..18).contains(&x) && (11..21).contains(&y);
+            let expected = if inside { new } else { old };
+            assert_eq!(pixel, expected, "pixel ({x}, {y})");
+        }
+        assert_ne!(pixels, before, "moving the sphere changes the region");
+    }
+
+    #[test]
+    fn doubling_the_iso_doubles_the_rendered_color() {
+        let mut sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), 1.0);
+        sphere.set_material(EmissiveMaterial::hdr(Color::new(0.5, 0.25, 0.1, 1.0)));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sphere)];
+        let raytracer = Raytracer::new(config(4, 4, 2));
+        let brightness = |iso: f32| {
+            let mut camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 0.3);
+            camera.exposure = Some(CameraExposure::new(2.8, 1.0 / 60.0, iso));
+            let pixels = raytracer.render_hdr(&objects, &[], &[], &camera);
+            pixels.iter().map(|pixel| pixel[0] + pixel[1] + pixel[2]).sum::<f32>()
+        };
+
+        let single = brightness(100.0);
+        let double = brightness(200.0);
+        assert!(single > 0.0);
+        assert!((double / single - 2.0).abs() < 1e-4, "{double} != 2 * {single}");
+    }
+
+    #[test]
+    fn relative_shadow_bias_works_for_tiny_and_huge_scenes() {
+        use crate::DirectionalLight;
+
+        // A sphere resting on a plane, lit from the upper left so its shadow
+        // starts at the contact point; `scale` is the sphere's radius
+        let check = |scale: f32| {
+            let sphere = Sphere::with_material(Vec3::Y * scale, scale, LambertianMaterial::new(Color::WHITE));
+            let ground = Plane::with_material(Vec3::ZERO, Vec3::Y, LambertianMaterial::new(Color::WHITE));
+            let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sphere.clone()), Arc::new(ground)];
+            let to_light = Vec3::new(-1.0, 1.0, 0.0).normalize();
+            let lights: Vec<Arc<dyn Light>> = vec![Arc::new(DirectionalLight::new(-to_light, Color::WHITE, 3.0))];
+            let camera = camera_at(Vec3::new(1.0, 4.0, 5.0) * scale, Vec3::X * scale, Vec3::Y, 0.8);
+            let raytracer = Raytracer::new(RaytracerConfig {
+                max_depth: 1,
+                shadow_bias: 1e-7,
+                shadow_bias_relative: 1e-4,
+                ..config(64, 64, 1)
+            });
+            let pixels = raytracer.render_hdr(&objects, &lights, &[], &camera);
+
+            // Expected light at a screen position, `None` near a boundary
+            let classify = |u: f32, v: f32| -> Option<bool> {
+                let ray = camera.generate_ray(u, v);
+                let (hit, object) = Raytracer::closest_hit(&ray, &objects)?;
+                if Arc::ptr_eq(object, &objects[0]) {
+                    let facing = hit.normal.dot(to_light);
+                    return (facing.abs() > 0.3).then_some(facing > 0.0);
+                }
+                // Distance from the sphere's center to the ray towards the light
+                let offset = sphere.center - hit.point;
+                let distance = (offset - to_light * offset.dot(to_light)).length() / scale;
+                (!(0.8..=1.2).contains(&distance)).then_some(distance > 1.0)
+            };
+
+            let (mut lit, mut shadowed) = (0, 0);
+            for (index, pixel) in pixels.iter().enumerate() {
+                let (x, y) = ((index % 64) as f32, (index / 64) as f32);
+                let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0), (0.5, 0.5)]
+                    .map(|(dx, dy)| classify((x + dx) / 64.0, (y + dy) / 64.0));
+                let Some(expected) = corners[0].filter(|_| corners.iter().all(|c| *c == corners[0])) else {
+                    continue;
+                };
+                let brightness = pixel[0];
+                if expected {
+                    assert!(brightness > 0.1, "acne at ({x}, {y}), scale {scale}: {brightness}");
+                    lit += 1;
+                } else {
+                    assert!(brightness < 0.05, "light leak at ({x}, {y}), scale {scale}: {brightness}");
+                    shadowed += 1;
+                }
+            }
+            assert!(lit > 500 && shadowed > 50, "scale {scale}: {lit} lit, {shadowed} shadowed");
+        };
+
+        check(0.01);
+        check(1000.0);
+    }
+
+    #[test]
+    fn blocker_that_casts_no_shadow_lets_the_light_through() {
+        use crate::ShadowFlags;
+
+        let lights: Vec<Arc<dyn Light>> = vec![Arc::new(PointLight::new(Vec3::new(0.0, 10.0, 0.0), Color::WHITE, 50.0))];
+        // Looks at the origin, below the blocker, which is out of view
+        let camera = camera_at(Vec3::new(0.0, 2.0, 6.0), Vec3::ZERO, Vec3::Y, 0.5);
+        let raytracer = Raytracer::new(RaytracerConfig { max_depth: 1, ..config(9, 9, 1) });
+        let center_brightness = |blocker_flags: ShadowFlags, ground_flags: ShadowFlags| {
+            let mut blocker = Sphere::new(Vec3::new(0.0, 5.0, 0.0), 1.0);
+            blocker.set_shadow_flags(blocker_flags);
+            let mut ground = Plane::with_material(Vec3::ZERO, Vec3::Y, LambertianMaterial::new(Color::WHITE));
+            ground.set_shadow_flags(ground_flags);
+            let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(blocker), Arc::new(ground)];
+            raytracer.render_hdr(&objects, &lights, &[], &camera)[4 * 9 + 4][0]
+        };
+
+        let default = ShadowFlags::default();
+        let shadowed = center_brightness(default, default);
+        let no_cast = center_brightness(ShadowFlags { casts_shadow: false, ..default }, default);
+        let no_receive = center_brightness(default, ShadowFlags { receives_shadow: false, ..default });
+        assert!(shadowed < 0.05, "{shadowed}");
+        assert!(no_cast > 0.1, "{no_cast}");
+        assert!((no_receive - no_cast).abs() < 1e-5, "{no_receive} != {no_cast}");
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)] // Interior pixels must come back bit-exact
+    fn edge_pixels_blend_sphere_and_background_while_interior_pixels_are_untouched() {
+        let mut sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), 1.0);
+        sphere.set_material(EmissiveMaterial::hdr(Color::WHITE));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sphere)];
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 1.0);
+        let render = |edge_samples: u32| {
+            let raytracer = Raytracer::new(RaytracerConfig { edge_samples, ..config(16, 16, 1) });
+            raytracer.render_hdr(&objects, &[], &[], &camera)
+        };
+        let plain = render(0);
+        let refined = render(16);
+        let sphere_red = plain.iter().map(|pixel| pixel[0]).fold(0.0, f32::max);
+
+        // Whether the ray through a pixel's center hits the sphere
+        let covered = |x: usize, y: usize| {
+            let ray = camera.generate_ray((x as f32 + 0.5) / 16.0, (y as f32 + 0.5) / 16.0);
+            objects[0].intersect(&ray, 0.001, f32::INFINITY).is_some()
+        };
+        let mut blended = 0;
+        for (index, (before, after)) in plain.iter().zip(&refined).enumerate() {
+            let (x, y) = (index % 16, index / 16);
+            let neighbors = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+            let interior = neighbors
+                .iter()
+                .filter(|(nx, ny)| *nx < 16 && *ny < 16)
+                .all(|&(nx, ny)| covered(nx, ny) == covered(x, y));
+            if interior {
+                assert_eq!(before, after, "interior pixel ({x}, {y})");
+            } else if after[0] > 0.05 * sphere_red && after[0] < 0.95 * sphere_red {
+                blended += 1;
+            }
+        }
+        assert!(blended > 8, "only {blended} silhouette pixels are blended");
+    }
+
+    #[test]
+    fn accumulated_area_light_penumbra_brightens_monotonically_across_the_edge() {
+        // A slab over x < 0 at height 1 half-covers a 2x2 panel at height 3, so the
+        // ground sees a penumbra from x = -0.5 (fully shadowed) to x = 0.5 (fully lit)
+        let slab = Cube::with_material(Vec3::new(-3.0, 1.0, 0.0), Vec3::new(6.0, 0.1, 8.0), LambertianMaterial::new(Color::WHITE));
+        let ground = Plane::with_material(Vec3::ZERO, Vec3::Y, LambertianMaterial::new(Color::WHITE));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(slab), Arc::new(ground)];
+        let panel = AreaLight::new(Vec3::new(-1.0, 3.0, -1.0), Vec3::X * 2.0, Vec3::Z * 2.0, Color::WHITE, 5.0);
+        let lights: Vec<Arc<dyn Light>> = vec![Arc::new(panel)];
+        // Below the slab, looking down at the ground across the shadow edge
+        let camera = camera_at(Vec3::new(0.0, 0.5, 2.0), Vec3::ZERO, Vec3::Y, 1.0);
+        let mut raytracer = Raytracer::new(RaytracerConfig { max_depth: 1, ..config(16, 16, 1) });
+
+        let image = raytracer.render_samples(1024, &objects, &lights, &[], &camera);
+        let row: Vec<u8> = (0..16).map(|x| image[(8 * 16 + x) * 4]).collect();
+
+        // Only the ambient term reaches the umbra
+        assert!(row[0] < 30, "{row:?}");
+        assert!(row[15] > 100, "{row:?}");
+        let penumbra = row.iter().filter(|&&v| v > row[0] + 20 && v + 20 < row[15]).count();
+        assert!(penumbra >= 2, "no penumbra: {row:?}");
+        assert!(row.windows(2).all(|pair| pair[1] + 2 >= pair[0]), "{row:?}");
+    }
+
+    #[test]
+    fn builder_matches_the_struct_literal_and_rejects_invalid_settings() {
+        let built = RaytracerConfig::builder().width(800).height(600).samples(10).seed(3).shadow_bias(0.01).exposure(2.0).build().unwrap();
+        let literal = RaytracerConfig {
+            width: 800,
+            height: 600,
+            samples_per_pixel: 10,
+            seed: Some(3),
+            shadow_bias: 0.01,
+            exposure: 2.0,
+            ..RaytracerConfig::default()
+        };
+        assert_eq!(built, literal);
+
+        assert!(RaytracerConfig::builder().width(0).build().is_err());
+        assert!(RaytracerConfig::builder().samples(0).build().is_err());
+        assert!(RaytracerConfig::builder().caustics(true).caustic_photons(0).build().is_err());
+        assert!(RaytracerConfig::builder().exposure(-1.0).build().is_err());
+        assert!(RaytracerConfig::builder().exposure(f32::NAN).build().is_err());
+    }
+
+    #[test]
+    fn half_transparent_white_overlay_turns_black_gray_and_clear_overlay_changes_nothing() {
+        let black = [0, 0, 0, 255].repeat(4);
+        let mut frame = black.clone();
+        Raytracer::composite_overlay(&mut frame, &[255, 255, 255, 128].repeat(4)).unwrap();
+        // Half of linear white, encoded as sRGB
+        assert_eq!(frame, [188, 188, 188, 255].repeat(4));
+
+        let rendered: Vec<u8> = (0..16).map(|i| i * 15).collect();
+        let mut frame = rendered.clone();
+        Raytracer::composite_overlay(&mut frame, &[200, 10, 90, 0].repeat(4)).unwrap();
+        assert_eq!(frame, rendered);
+
+        assert!(Raytracer::composite_overlay(&mut frame, &black[..8]).is_err());
+    }
+
+    #[test]
+    fn beauty_aov_matches_the_exposed_hdr_render() {
+        let mut sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), 1.0);
+        sphere.set_material(LambertianMaterial::new(Color::new(0.8, 0.4, 0.2, 1.0)));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sphere)];
+        let lights: Vec<Arc<dyn Light>> = vec![Arc::new(PointLight::new(Vec3::new(2.0, 2.0, 0.0), Color::WHITE, 5.0))];
+        let mut camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 0.3);
+        camera.exposure = Some(CameraExposure::new(2.8, 1.0 / 60.0, 400.0));
+        let raytracer = Raytracer::new(RaytracerConfig { max_depth: 1, ..config(4, 4, 2) });
+
+        let hdr = raytracer.render_hdr(&objects, &lights, &[], &camera);
+        let beauty = &raytracer.render_aovs(&objects, &lights, &[], &camera)[&AovKind::Beauty];
+        for (i, pixel) in hdr.iter().enumerate() {
+            for channel in 0..3 {
+                let (expected, actual) = (pixel[channel], beauty[i * 3 + channel]);
+                assert!((actual - expected).abs() <= 1e-4 * expected.max(1.0), "pixel {i}: {actual} != {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn emissive_sphere_alone_lights_a_diffuse_surface_through_light_sampling() {
+        let (albedo, radiance, radius, height) = (0.5, 4.0, 0.5, 4.0);
+        let plane: Arc<dyn SceneObject> = Arc::new(Plane::with_material(
+            Vec3::ZERO,
+            Vec3::Y,
+            LambertianMaterial::new(Color::new(albedo, albedo, albedo, 1.0)),
+        ));
+        let mut lamp = Sphere::new(Vec3::new(0.0, height, 0.0), radius);
+        lamp.set_material(EmissiveMaterial::new(Color::WHITE, radiance));
+        let camera = camera_at(Vec3::new(0.0, 1.0, 0.0), Vec3::ZERO, Vec3::Z, 0.01);
+        // Direct light only, from a few samples that would almost never hit the lamp by chance
+        let raytracer = Raytracer::new(RaytracerConfig {
+            max_depth: 1,
+            ..config(1, 1, 4)
+        });
+
+        let lit = raytracer.render_hdr(&[plane.clone(), Arc::new(lamp)], &[], &[], &camera)[0];
+        let unlit = raytracer.render_hdr(&[plane], &[], &[], &camera)[0];
+
+        // A sphere straight overhead delivers an irradiance of pi * L * (r / h)²
+        let expected = albedo * radiance * (radius / height).powi(2);
+        for channel in 0..3 {
+            let reflected = lit[channel] - unlit[channel];
+            assert!((reflected - expected).abs() < 0.01 * expected, "channel {channel}: {reflected} != {expected}");
+        }
+    }
+
+    #[test]
+    fn config_exposure_scales_the_8_bit_output_on_top_of_the_camera_exposure() {
+        let mut sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), 1.0);
+        sphere.set_material(EmissiveMaterial::hdr(Color::new(0.2, 0.1, 0.05, 1.0)));
+        let objects: Vec<Arc<dyn SceneObject>> = vec![Arc::new(sphere)];
+        let camera = camera_at(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y, 0.3);
+        let render = |exposure: f32, camera: &Camera| {
+            Raytracer::new(RaytracerConfig { exposure, ..config(2, 2, 1) }).render(&objects, &[], &[], camera)
+        };
+
+        // Doubling in the config undoes halving in the camera
+        let mut halved = camera.clone();
+        halved.exposure = Some(CameraExposure::from_multiplier(0.5));
+        assert_eq!(render(2.0, &halved), render(1.0, &camera));
+
+        // On its own it brightens every channel, and the HDR output ignores it
+        let plain = render(1.0, &camera);
+        let doubled = render(2.0, &camera);
+        assert!(plain.iter().zip(&doubled).all(|(plain, doubled)| doubled >= plain));
+        assert!(doubled[0] > plain[0]);
+        let hdr = |exposure: f32| Raytracer::new(RaytracerConfig { exposure, ..config(2, 2, 1) }).render_hdr(&objects, &[], &[], &camera);
+        assert_eq!(hdr(2.0), hdr(1.0));
+    }
 }
@@ -1,2 +1,666 @@
-@compute @workgroup_size(1)
-fn cs_main() {}
+// Path tracing compute shader.
+//
+// Each invocation traces one primary ray per pixel and follows it for up to
+// `params.max_depth` bounces, accumulating throughput like the CPU raytracer's
+// recursion. Layouts mirror the `*Gpu` structs in gpu_renderer.rs.
+//
+// Code between `// #if <kind>` and `// #endif` lines is removed by
+// `GpuPrimitives::shader_source` when that kind of object is not bound.
+// Blocks may nest; kinds other than boxes, meshes, cylinders and cones are
+// rejected.
+
+struct Camera {
+    position: vec4<f32>,
+    view_projection: mat4x4<f32>,
+    inv_projection: mat4x4<f32>,
+    inv_view: mat4x4<f32>,
+};
+
+struct Sphere {
+    center: vec4<f32>,
+    radius: f32,
+    material_index: u32,
+    shadow_flags: u32,
+    _padding: u32,
+};
+
+// #if boxes
+// Oriented box: intersected in local space after `inv_transform`
+struct Box {
+    center: vec4<f32>,
+    half_extents: vec4<f32>,
+    inv_transform: mat4x4<f32>,
+    material_index: u32,
+    shadow_flags: u32,
+    _padding: array<u32, 2>,
+};
+// #endif
+
+// #if meshes
+struct Vertex {
+    position: vec4<f32>,
+    normal: vec4<f32>,
+};
+
+// Indices into `vertices` and the triangle's material
+struct Triangle {
+    indices: vec3<u32>,
+    material_index: u32,
+};
+
+// A range of `triangles` intersected in local space after `inv_transform`
+struct Mesh {
+    inv_transform: mat4x4<f32>,
+    triangle_offset: u32,
+    triangle_count: u32,
+    shadow_flags: u32,
+    _padding: u32,
+};
+// #endif
+
+// #if cylinders
+// Open cylinder around the local y axis, intersected after `inv_transform`
+struct Cylinder {
+    center: vec4<f32>,
+    inv_transform: mat4x4<f32>,
+    radius: f32,
+    height: f32,
+    material_index: u32,
+    shadow_flags: u32,
+};
+// #endif
+
+// #if cones
+// Open cone around the local y axis with its apex at the top
+struct Cone {
+    center: vec4<f32>,
+    inv_transform: mat4x4<f32>,
+    radius: f32,
+    height: f32,
+    material_index: u32,
+    shadow_flags: u32,
+};
+// #endif
+
+struct Material {
+    color: vec4<f32>,
+    material_type: u32,
+    smoothness: f32,
+    _padding: vec2<u32>,
+};
+
+struct PointLight {
+    position: vec4<f32>,
+    color: vec4<f32>,
+    intensity: f32,
+    range: f32,
+    _padding: vec2<u32>,
+};
+
+struct RenderParams {
+    max_depth: u32,
+    frame_index: u32,
+    exposure: f32,
+    accumulated_samples: u32,
+    background_bottom: vec4<f32>,
+    background_top: vec4<f32>,
+    tonemap: u32,
+};
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(0) @binding(1) var<storage, read> spheres: array<Sphere>;
+@group(0) @binding(2) var<storage, read> materials: array<Material>;
+@group(0) @binding(3) var<storage, read> lights: array<PointLight>;
+@group(0) @binding(4) var output_texture: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(5) var<uniform> params: RenderParams;
+// Sum of the radiance of the frames accumulated so far, and where this frame's sum is stored
+@group(0) @binding(12) var previous_accumulation: texture_2d<f32>;
+@group(0) @binding(13) var accumulation: texture_storage_2d<rgba32float, write>;
+// #if boxes
+@group(0) @binding(6) var<storage, read> boxes: array<Box>;
+// #endif
+// #if meshes
+@group(0) @binding(7) var<storage, read> vertices: array<Vertex>;
+@group(0) @binding(8) var<storage, read> triangles: array<Triangle>;
+@group(0) @binding(9) var<storage, read> meshes: array<Mesh>;
+// #endif
+// #if cylinders
+@group(0) @binding(10) var<storage, read> cylinders: array<Cylinder>;
+// #endif
+// #if cones
+@group(0) @binding(11) var<storage, read> cones: array<Cone>;
+// #endif
+
+const MATERIAL_LAMBERTIAN: u32 = 0u;
+const MATERIAL_METAL: u32 = 1u;
+const MATERIAL_DIELECTRIC: u32 = 2u;
+const MATERIAL_EMISSIVE: u32 = 3u;
+
+// Bits of the objects' `shadow_flags`, as in ShadowFlags::bits
+const CASTS_SHADOW: u32 = 1u;
+const RECEIVES_SHADOW: u32 = 2u;
+
+const T_MIN: f32 = 0.001;
+// Offset of shadow ray origins along the surface normal, against shadow acne;
+// the CPU raytracer's default `shadow_bias`
+const SHADOW_BIAS: f32 = 0.001;
+const T_MAX: f32 = 1.0e30;
+const PI: f32 = 3.14159265;
+
+// Same defaults as PointLight::new
+const LINEAR_ATTENUATION: f32 = 0.09;
+const QUADRATIC_ATTENUATION: f32 = 0.032;
+
+struct Hit {
+    hit: bool,
+    t: f32,
+    point: vec3<f32>,
+    normal: vec3<f32>,
+    front_face: bool,
+    material_index: u32,
+    shadow_flags: u32,
+};
+
+// Vertical gradient matching Background::sample on the CPU
+fn background(direction: vec3<f32>) -> vec3<f32> {
+    let t = 0.5 * (direction.y + 1.0);
+    return mix(params.background_bottom.rgb, params.background_top.rgb, t);
+}
+
+// Curve selected by params.tonemap, matching ToneMapper::apply on the CPU
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    let x = max(color, vec3<f32>(0.0));
+    if (params.tonemap == 1u) {
+        return x / (vec3<f32>(1.0) + x);
+    }
+    if (params.tonemap == 2u) {
+        return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+    }
+    return color;
+}
+
+var<private> rng_state: u32;
+
+fn pcg_hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random_f32() -> f32 {
+    rng_state = pcg_hash(rng_state);
+    return f32(rng_state) / 4294967295.0;
+}
+
+fn random_unit_vector() -> vec3<f32> {
+    let z = random_f32() * 2.0 - 1.0;
+    let angle = random_f32() * 2.0 * PI;
+    let r = sqrt(max(0.0, 1.0 - z * z));
+    return vec3<f32>(r * cos(angle), r * sin(angle), z);
+}
+
+fn random_in_unit_sphere() -> vec3<f32> {
+    return random_unit_vector() * pow(random_f32(), 1.0 / 3.0);
+}
+
+// Returns the hit distance, or -1 on a miss
+fn intersect_sphere(sphere: Sphere, origin: vec3<f32>, direction: vec3<f32>, t_max: f32) -> f32 {
+    if (sphere.radius <= 0.0) {
+        return -1.0;
+    }
+    let oc = origin - sphere.center.xyz;
+    let a = dot(direction, direction);
+    let half_b = dot(oc, direction);
+    let c = dot(oc, oc) - sphere.radius * sphere.radius;
+    let discriminant = half_b * half_b - a * c;
+    if (discriminant < 0.0) {
+        return -1.0;
+    }
+    let sqrt_d = sqrt(discriminant);
+    var root = (-half_b - sqrt_d) / a;
+    if (root < T_MIN || root > t_max) {
+        root = (-half_b + sqrt_d) / a;
+        if (root < T_MIN || root > t_max) {
+            return -1.0;
+        }
+    }
+    return root;
+}
+
+// Nearest root of a*t^2 + 2*half_b*t + c = 0 in [T_MIN, t_max] whose point lies
+// within `half_height` of the center along y, or -1
+fn nearest_clamped_root(a: f32, half_b: f32, c: f32, origin_y: f32, direction_y: f32, half_height: f32, t_max: f32) -> f32 {
+    if (abs(a) < 1.0e-8) {
+        return -1.0; // Ray runs along the surface
+    }
+    let discriminant = half_b * half_b - a * c;
+    if (discriminant < 0.0) {
+        return -1.0;
+    }
+    let sqrt_d = sqrt(discriminant);
+    let r0 = (-half_b - sqrt_d) / a;
+    let r1 = (-half_b + sqrt_d) / a;
+    // `a` is negative for steep rays through a cone, which swaps the roots
+    var roots = array<f32, 2>(min(r0, r1), max(r0, r1));
+    for (var i = 0; i < 2; i = i + 1) {
+        let t = roots[i];
+        if (t >= T_MIN && t <= t_max && abs(origin_y + t * direction_y) <= half_height) {
+            return t;
+        }
+    }
+    return -1.0;
+}
+
+// #if cylinders
+// Side of the cylinder in its local space; returns the hit distance, or -1 on a miss
+fn intersect_cylinder(cylinder: Cylinder, origin: vec3<f32>, direction: vec3<f32>, t_max: f32) -> f32 {
+    if (cylinder.radius <= 0.0 || cylinder.height <= 0.0) {
+        return -1.0;
+    }
+    // The local direction is left unnormalized so distances stay in world units
+    let o = (cylinder.inv_transform * vec4<f32>(origin, 1.0)).xyz - cylinder.center.xyz;
+    let d = (cylinder.inv_transform * vec4<f32>(direction, 0.0)).xyz;
+    let a = d.x * d.x + d.z * d.z;
+    let half_b = o.x * d.x + o.z * d.z;
+    let c = o.x * o.x + o.z * o.z - cylinder.radius * cylinder.radius;
+    return nearest_clamped_root(a, half_b, c, o.y, d.y, 0.5 * cylinder.height, t_max);
+}
+
+// World-space outward normal of the cylinder side at `point`
+fn cylinder_normal(cylinder: Cylinder, point: vec3<f32>) -> vec3<f32> {
+    let local_point = (cylinder.inv_transform * vec4<f32>(point, 1.0)).xyz - cylinder.center.xyz;
+    let local_normal = vec3<f32>(local_point.x, 0.0, local_point.z);
+    // Normals transform by the inverse transpose: n * M == transpose(M) * n
+    return normalize((vec4<f32>(local_normal, 0.0) * cylinder.inv_transform).xyz);
+}
+// #endif
+
+// #if cones
+// Side of the cone in its local space; returns the hit distance, or -1 on a miss
+fn intersect_cone(cone: Cone, origin: vec3<f32>, direction: vec3<f32>, t_max: f32) -> f32 {
+    if (cone.radius <= 0.0 || cone.height <= 0.0) {
+        return -1.0;
+    }
+    let half_height = 0.5 * cone.height;
+    let k2 = (cone.radius / cone.height) * (cone.radius / cone.height);
+    // The local direction is left unnormalized so distances stay in world units
+    let o = (cone.inv_transform * vec4<f32>(origin, 1.0)).xyz - cone.center.xyz;
+    let d = (cone.inv_transform * vec4<f32>(direction, 0.0)).xyz;
+    // x^2 + z^2 = k^2 (h/2 - y)^2
+    let apex_y = o.y - half_height;
+    let a = d.x * d.x + d.z * d.z - k2 * d.y * d.y;
+    let half_b = o.x * d.x + o.z * d.z - k2 * apex_y * d.y;
+    let c = o.x * o.x + o.z * o.z - k2 * apex_y * apex_y;
+    return nearest_clamped_root(a, half_b, c, o.y, d.y, half_height, t_max);
+}
+
+// World-space outward normal of the cone side at `point`
+fn cone_normal(cone: Cone, point: vec3<f32>) -> vec3<f32> {
+    let local_point = (cone.inv_transform * vec4<f32>(point, 1.0)).xyz - cone.center.xyz;
+    // (x / r, k, z / r) scaled by the distance r from the axis
+    let r = length(local_point.xz);
+    let local_normal = vec3<f32>(local_point.x, r * cone.radius / cone.height, local_point.z);
+    // Normals transform by the inverse transpose: n * M == transpose(M) * n
+    return normalize((vec4<f32>(local_normal, 0.0) * cone.inv_transform).xyz);
+}
+// #endif
+
+// #if boxes
+// Slab test in the box's local space; returns the hit distance, or -1 on a miss
+fn intersect_box(box: Box, origin: vec3<f32>, direction: vec3<f32>, t_max: f32) -> f32 {
+    if (any(box.half_extents.xyz <= vec3<f32>(0.0))) {
+        return -1.0;
+    }
+    // The local direction is left unnormalized so distances stay in world units
+    let local_origin = (box.inv_transform * vec4<f32>(origin, 1.0)).xyz - box.center.xyz;
+    let local_direction = (box.inv_transform * vec4<f32>(direction, 0.0)).xyz;
+    let inv_direction = 1.0 / local_direction;
+    let t0 = (-box.half_extents.xyz - local_origin) * inv_direction;
+    let t1 = (box.half_extents.xyz - local_origin) * inv_direction;
+    let t_near_axes = min(t0, t1);
+    let t_far_axes = max(t0, t1);
+    let t_near = max(max(t_near_axes.x, t_near_axes.y), t_near_axes.z);
+    let t_far = min(min(t_far_axes.x, t_far_axes.y), t_far_axes.z);
+    if (t_near > t_far) {
+        return -1.0;
+    }
+    if (t_near >= T_MIN && t_near <= t_max) {
+        return t_near;
+    }
+    if (t_far >= T_MIN && t_far <= t_max) {
+        return t_far;
+    }
+    return -1.0;
+}
+
+// World-space outward normal of the box face nearest to `point`
+fn box_normal(box: Box, point: vec3<f32>) -> vec3<f32> {
+    let local_point = (box.inv_transform * vec4<f32>(point, 1.0)).xyz - box.center.xyz;
+    let scaled = abs(local_point / box.half_extents.xyz);
+    var local_normal = vec3<f32>(0.0, 0.0, sign(local_point.z));
+    if (scaled.x >= scaled.y && scaled.x >= scaled.z) {
+        local_normal = vec3<f32>(sign(local_point.x), 0.0, 0.0);
+    } else if (scaled.y >= scaled.z) {
+        local_normal = vec3<f32>(0.0, sign(local_point.y), 0.0);
+    }
+    // Normals transform by the inverse transpose: n * M == transpose(M) * n
+    return normalize((vec4<f32>(local_normal, 0.0) * box.inv_transform).xyz);
+}
+// #endif
+
+// #if meshes
+// Möller-Trumbore intersection; returns (t, u, v) with t = -1 on a miss
+fn intersect_triangle(triangle: Triangle, origin: vec3<f32>, direction: vec3<f32>, t_max: f32) -> vec3<f32> {
+    let miss = vec3<f32>(-1.0, 0.0, 0.0);
+    let v0 = vertices[triangle.indices.x].position.xyz;
+    let edge1 = vertices[triangle.indices.y].position.xyz - v0;
+    let edge2 = vertices[triangle.indices.z].position.xyz - v0;
+    let h = cross(direction, edge2);
+    let a = dot(edge1, h);
+    if (abs(a) < 1.0e-8) {
+        return miss; // Ray is parallel to the triangle
+    }
+    let f = 1.0 / a;
+    let s = origin - v0;
+    let u = f * dot(s, h);
+    if (u < 0.0 || u > 1.0) {
+        return miss;
+    }
+    let q = cross(s, edge1);
+    let v = f * dot(direction, q);
+    if (v < 0.0 || u + v > 1.0) {
+        return miss;
+    }
+    let t = f * dot(edge2, q);
+    if (t < T_MIN || t > t_max) {
+        return miss;
+    }
+    return vec3<f32>(t, u, v);
+}
+// #endif
+
+fn trace_closest(origin: vec3<f32>, direction: vec3<f32>) -> Hit {
+    var result: Hit;
+    result.hit = false;
+    result.t = T_MAX;
+    let sphere_count = arrayLength(&spheres);
+    for (var i = 0u; i < sphere_count; i = i + 1u) {
+        let t = intersect_sphere(spheres[i], origin, direction, result.t);
+        if (t > 0.0) {
+            result.hit = true;
+            result.t = t;
+            result.material_index = spheres[i].material_index;
+            result.shadow_flags = spheres[i].shadow_flags;
+            result.point = origin + direction * t;
+            let outward_normal = (result.point - spheres[i].center.xyz) / spheres[i].radius;
+            result.front_face = dot(direction, outward_normal) < 0.0;
+            result.normal = select(-outward_normal, outward_normal, result.front_face);
+        }
+    }
+    // #if boxes
+    let box_count = arrayLength(&boxes);
+    for (var i = 0u; i < box_count; i = i + 1u) {
+        let t = intersect_box(boxes[i], origin, direction, result.t);
+        if (t > 0.0) {
+            result.hit = true;
+            result.t = t;
+            result.material_index = boxes[i].material_index;
+            result.shadow_flags = boxes[i].shadow_flags;
+            result.point = origin + direction * t;
+            let outward_normal = box_normal(boxes[i], result.point);
+            result.front_face = dot(direction, outward_normal) < 0.0;
+            result.normal = select(-outward_normal, outward_normal, result.front_face);
+        }
+    }
+    // #endif
+    // #if meshes
+    let mesh_count = arrayLength(&meshes);
+    for (var m = 0u; m < mesh_count; m = m + 1u) {
+        let mesh = meshes[m];
+        // The local direction is left unnormalized so distances stay in world units
+        let local_origin = (mesh.inv_transform * vec4<f32>(origin, 1.0)).xyz;
+        let local_direction = (mesh.inv_transform * vec4<f32>(direction, 0.0)).xyz;
+        for (var i = mesh.triangle_offset; i < mesh.triangle_offset + mesh.triangle_count; i = i + 1u) {
+            let triangle = triangles[i];
+            let tuv = intersect_triangle(triangle, local_origin, local_direction, result.t);
+            if (tuv.x > 0.0) {
+                result.hit = true;
+                result.t = tuv.x;
+                result.material_index = triangle.material_index;
+                result.shadow_flags = mesh.shadow_flags;
+                result.point = origin + direction * tuv.x;
+
+                // Faces are judged by winding; shading uses the interpolated vertex normals
+                let v0 = vertices[triangle.indices.x];
+                let v1 = vertices[triangle.indices.y];
+                let v2 = vertices[triangle.indices.z];
+                let face_normal = cross(v1.position.xyz - v0.position.xyz, v2.position.xyz - v0.position.xyz);
+                let w = 1.0 - tuv.y - tuv.z;
+                let local_normal = w * v0.normal.xyz + tuv.y * v1.normal.xyz + tuv.z * v2.normal.xyz;
+                // Normals transform by the inverse transpose: n * M == transpose(M) * n
+                let geometric_normal = normalize((vec4<f32>(face_normal, 0.0) * mesh.inv_transform).xyz);
+                let shading_normal = normalize((vec4<f32>(local_normal, 0.0) * mesh.inv_transform).xyz);
+                result.front_face = dot(direction, geometric_normal) < 0.0;
+                result.normal = select(-shading_normal, shading_normal, result.front_face);
+            }
+        }
+    }
+    // #endif
+    // #if cylinders
+    let cylinder_count = arrayLength(&cylinders);
+    for (var i = 0u; i < cylinder_count; i = i + 1u) {
+        let t = intersect_cylinder(cylinders[i], origin, direction, result.t);
+        if (t > 0.0) {
+            result.hit = true;
+            result.t = t;
+            result.material_index = cylinders[i].material_index;
+            result.shadow_flags = cylinders[i].shadow_flags;
+            result.point = origin + direction * t;
+            let outward_normal = cylinder_normal(cylinders[i], result.point);
+            result.front_face = dot(direction, outward_normal) < 0.0;
+            result.normal = select(-outward_normal, outward_normal, result.front_face);
+        }
+    }
+    // #endif
+    // #if cones
+    let cone_count = arrayLength(&cones);
+    for (var i = 0u; i < cone_count; i = i + 1u) {
+        let t = intersect_cone(cones[i], origin, direction, result.t);
+        if (t > 0.0) {
+            result.hit = true;
+            result.t = t;
+            result.material_index = cones[i].material_index;
+            result.shadow_flags = cones[i].shadow_flags;
+            result.point = origin + direction * t;
+            let outward_normal = cone_normal(cones[i], result.point);
+            result.front_face = dot(direction, outward_normal) < 0.0;
+            result.normal = select(-outward_normal, outward_normal, result.front_face);
+        }
+    }
+    // #endif
+    return result;
+}
+
+// Only objects that cast shadows are tested
+fn is_occluded(origin: vec3<f32>, direction: vec3<f32>, max_distance: f32) -> bool {
+    let sphere_count = arrayLength(&spheres);
+    for (var i = 0u; i < sphere_count; i = i + 1u) {
+        if ((spheres[i].shadow_flags & CASTS_SHADOW) != 0u && intersect_sphere(spheres[i], origin, direction, max_distance) > 0.0) {
+            return true;
+        }
+    }
+    // #if boxes
+    let box_count = arrayLength(&boxes);
+    for (var i = 0u; i < box_count; i = i + 1u) {
+        if ((boxes[i].shadow_flags & CASTS_SHADOW) != 0u && intersect_box(boxes[i], origin, direction, max_distance) > 0.0) {
+            return true;
+        }
+    }
+    // #endif
+    // #if meshes
+    let mesh_count = arrayLength(&meshes);
+    for (var m = 0u; m < mesh_count; m = m + 1u) {
+        let mesh = meshes[m];
+        if ((mesh.shadow_flags & CASTS_SHADOW) == 0u) {
+            continue;
+        }
+        let local_origin = (mesh.inv_transform * vec4<f32>(origin, 1.0)).xyz;
+        let local_direction = (mesh.inv_transform * vec4<f32>(direction, 0.0)).xyz;
+        for (var i = mesh.triangle_offset; i < mesh.triangle_offset + mesh.triangle_count; i = i + 1u) {
+            if (intersect_triangle(triangles[i], local_origin, local_direction, max_distance).x > 0.0) {
+                return true;
+            }
+        }
+    }
+    // #endif
+    // #if cylinders
+    let cylinder_count = arrayLength(&cylinders);
+    for (var i = 0u; i < cylinder_count; i = i + 1u) {
+        if ((cylinders[i].shadow_flags & CASTS_SHADOW) != 0u && intersect_cylinder(cylinders[i], origin, direction, max_distance) > 0.0) {
+            return true;
+        }
+    }
+    // #endif
+    // #if cones
+    let cone_count = arrayLength(&cones);
+    for (var i = 0u; i < cone_count; i = i + 1u) {
+        if ((cones[i].shadow_flags & CASTS_SHADOW) != 0u && intersect_cone(cones[i], origin, direction, max_distance) > 0.0) {
+            return true;
+        }
+    }
+    // #endif
+    return false;
+}
+
+fn light_attenuation(light: PointLight, distance: f32) -> f32 {
+    if (distance > light.range) {
+        return 0.0;
+    }
+    return 1.0 / (1.0 + LINEAR_ATTENUATION * distance + QUADRATIC_ATTENUATION * distance * distance);
+}
+
+// Shadowed, cosine-weighted light for diffuse surfaces, with the Lambertian BRDF albedo / PI
+fn diffuse_light(hit: Hit, albedo: vec3<f32>) -> vec3<f32> {
+    var total = vec3<f32>(0.0);
+    let light_count = arrayLength(&lights);
+    for (var i = 0u; i < light_count; i = i + 1u) {
+        let light = lights[i];
+        let to_light = light.position.xyz - hit.point;
+        let distance = length(to_light);
+        let attenuation = light_attenuation(light, distance);
+        if (light.intensity <= 0.0 || attenuation <= 0.0) {
+            continue;
+        }
+        let direction = to_light / distance;
+        let cosine = dot(hit.normal, direction);
+        let receives_shadow = (hit.shadow_flags & RECEIVES_SHADOW) != 0u;
+        let shadow_origin = hit.point + hit.normal * SHADOW_BIAS;
+        if (cosine <= 0.0 || (receives_shadow && is_occluded(shadow_origin, direction, distance - SHADOW_BIAS))) {
+            continue;
+        }
+        total = total + albedo / PI * light.color.rgb * light.intensity * attenuation * cosine;
+    }
+    return total;
+}
+
+// Unshadowed light for specular surfaces, as the CPU raytracer does
+fn specular_light(hit: Hit) -> vec3<f32> {
+    var total = vec3<f32>(0.0);
+    let light_count = arrayLength(&lights);
+    for (var i = 0u; i < light_count; i = i + 1u) {
+        let light = lights[i];
+        let distance = length(light.position.xyz - hit.point);
+        total = total + light.color.rgb * light.intensity * light_attenuation(light, distance);
+    }
+    return total;
+}
+
+fn reflectance(cosine: f32, ref_idx: f32) -> f32 {
+    // Schlick's approximation
+    var r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
+    r0 = r0 * r0;
+    return r0 + (1.0 - r0) * pow(1.0 - cosine, 5.0);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output_texture);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    rng_state = pcg_hash(id.x + id.y * size.x + pcg_hash(params.frame_index));
+
+    // Primary ray through the pixel center
+    let ndc = vec2<f32>(
+        (f32(id.x) + 0.5) / f32(size.x) * 2.0 - 1.0,
+        1.0 - (f32(id.y) + 0.5) / f32(size.y) * 2.0,
+    );
+    let view_target = camera.inv_projection * vec4<f32>(ndc, 1.0, 1.0);
+    var origin = camera.position.xyz;
+    var direction = normalize((camera.inv_view * vec4<f32>(view_target.xyz / view_target.w, 0.0)).xyz);
+
+    var throughput = vec3<f32>(1.0);
+    var radiance = vec3<f32>(0.0);
+
+    for (var depth = 0u; depth < params.max_depth; depth = depth + 1u) {
+        let hit = trace_closest(origin, direction);
+        if (!hit.hit) {
+            radiance = radiance + throughput * background(direction);
+            break;
+        }
+
+        let material = materials[hit.material_index];
+        let albedo = material.color.rgb;
+
+        // Emitters carry HDR emission in `color` and end the path
+        if (material.material_type == MATERIAL_EMISSIVE) {
+            radiance = radiance + throughput * albedo;
+            break;
+        }
+
+        // Ambient term: Material::ambient_color() * 0.1
+        radiance = radiance + throughput * albedo * 0.01;
+
+        if (material.material_type == MATERIAL_METAL) {
+            radiance = radiance + throughput * specular_light(hit);
+            let reflected = reflect(direction, hit.normal);
+            let scattered = reflected + material.smoothness * random_in_unit_sphere();
+            if (dot(scattered, hit.normal) <= 0.0) {
+                break;
+            }
+            direction = normalize(scattered);
+        } else if (material.material_type == MATERIAL_DIELECTRIC) {
+            radiance = radiance + throughput * specular_light(hit);
+            // Dielectrics carry their index of refraction in `smoothness`
+            let ior = max(material.smoothness, 1.0);
+            let ratio = select(ior, 1.0 / ior, hit.front_face);
+            let cos_theta = min(dot(-direction, hit.normal), 1.0);
+            let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+            if (ratio * sin_theta > 1.0 || reflectance(cos_theta, ratio) > random_f32()) {
+                direction = reflect(direction, hit.normal);
+            } else {
+                direction = refract(direction, hit.normal, ratio);
+            }
+        } else {
+            radiance = radiance + throughput * diffuse_light(hit, albedo);
+            let scattered = hit.normal + random_unit_vector();
+            direction = select(normalize(scattered), hit.normal, dot(scattered, scattered) < 1.0e-8);
+        }
+
+        throughput = throughput * albedo;
+        origin = hit.point;
+    }
+
+    // Add this frame's sample to the running sum and output the average
+    var sum = radiance;
+    if (params.accumulated_samples > 0u) {
+        sum = sum + textureLoad(previous_accumulation, vec2<i32>(id.xy), 0).rgb;
+    }
+    textureStore(accumulation, vec2<i32>(id.xy), vec4<f32>(sum, 1.0));
+    let average = sum / f32(params.accumulated_samples + 1u);
+
+    // Scaled by the camera exposure and tone mapped as the CPU raytracer does,
+    // then stored linear; the sRGB swap chain encodes gamma on blit
+    let exposed = tonemap(average * params.exposure);
+    textureStore(output_texture, vec2<i32>(id.xy), vec4<f32>(clamp(exposed, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0));
+}
@@ -0,0 +1,194 @@
+// Pixel coordinates, sizes and sample counts stay far below 2^24, so
+// converting them to f32 is exact
+#![allow(clippy::cast_precision_loss)]
+
+use crate::camera::Camera;
+use crate::viewport::ViewportRect;
+use crate::SceneObject;
+use rrte_math::Vec2;
+use std::sync::Arc;
+
+/// How [`plan_tiles`] divides an image into tiles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileSettings {
+    /// Side length of the initial grid of tiles, in pixels
+    pub base_size: u32,
+    /// Tiles are not split below this side length
+    pub min_size: u32,
+    /// Estimated cost of a pixel covered by one object, relative to a pixel that
+    /// only sees the background
+    pub object_cost: f32,
+}
+
+impl Default for TileSettings {
+    fn default() -> Self {
+        Self {
+            base_size: 64,
+            min_size: 8,
+            object_cost: 4.0,
+        }
+    }
+}
+
+/// Pixel rectangle `[min_x, min_y, max_x, max_y]` an object may cover
+type Footprint = [f32; 4];
+
+/// Split a `width` x `height` image into tiles of roughly equal rendering cost.
+///
+/// The image starts as a grid of `base_size` tiles. A pixel is estimated to
+/// cost 1 plus `object_cost` for every object whose projected bounding box
+/// covers it, and tiles costing more than an average-cost `base_size` tile are
+/// split into quarters until they fit or reach `min_size`. Dense regions end
+/// up with many small tiles that spread across threads, while empty regions
+/// keep a few large ones.
+pub fn plan_tiles(
+    width: u32,
+    height: u32,
+    objects: &[Arc<dyn SceneObject>],
+    camera: &Camera,
+    settings: &TileSettings,
+) -> Vec<ViewportRect> {
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let footprints: Vec<Footprint> = objects
+        .iter()
+        .filter_map(|object| object_footprint(object.as_ref(), camera, width, height))
+        .collect();
+
+    let base_size = settings.base_size.max(1);
+    let min_size = settings.min_size.clamp(1, base_size);
+    let image = ViewportRect::new(0, 0, width, height);
+    let cost_per_pixel = tile_cost(&image, &footprints, settings.object_cost) / (width as f32 * height as f32);
+    let target_cost = cost_per_pixel * (base_size * base_size) as f32;
+
+    let mut pending: Vec<ViewportRect> = (0..height)
+        .step_by(base_size as usize)
+        .flat_map(|y| {
+            (0..width)
+                .step_by(base_size as usize)
+                .map(move |x| ViewportRect::new(x, y, base_size, base_size))
+        })
+        .filter_map(|tile| tile.clipped(width, height))
+        .collect();
+
+    let mut tiles = Vec::with_capacity(pending.len());
+    while let Some(tile) = pending.pop() {
+        let splittable = tile.width > min_size || tile.height > min_size;
+        if splittable && tile_cost(&tile, &footprints, settings.object_cost) > target_cost {
+            pending.extend(split_tile(&tile, min_size));
+        } else {
+            tiles.push(tile);
+        }
+    }
+    tiles.sort_by_key(|tile| (tile.y, tile.x));
+    tiles
+}
+
+/// Estimated cost of rendering a tile: its area plus `object_cost` times the
+/// area each object's footprint covers in it
+fn tile_cost(tile: &ViewportRect, footprints: &[Footprint], object_cost: f32) -> f32 {
+    let tile_rect = [
+        tile.x as f32,
+        tile.y as f32,
+        (tile.x + tile.width) as f32,
+        (tile.y + tile.height) as f32,
+    ];
+    let covered: f32 = footprints
+        .iter()
+        .map(|footprint| {
+            let overlap_x = footprint[2].min(tile_rect[2]) - footprint[0].max(tile_rect[0]);
+            let overlap_y = footprint[3].min(tile_rect[3]) - footprint[1].max(tile_rect[1]);
+            overlap_x.max(0.0) * overlap_y.max(0.0)
+        })
+        .sum();
+    object_cost.mul_add(covered, (tile.width * tile.height) as f32)
+}
+
+/// Halve a tile along each axis longer than `min_size`
+fn split_tile(tile: &ViewportRect, min_size: u32) -> Vec<ViewportRect> {
+    let halves = |start: u32, size: u32| {
+        if size > min_size {
+            let first = size / 2;
+            vec![(start, first), (start + first, size - first)]
+        } else {
+            vec![(start, size)]
+        }
+    };
+    let columns = halves(tile.x, tile.width);
+    halves(tile.y, tile.height)
+        .into_iter()
+        .flat_map(|(y, height)| columns.iter().map(move |&(x, width)| ViewportRect::new(x, y, width, height)))
+        .collect()
+}
+
+/// Pixel rectangle covered by an object's projected bounding box, or `None` if
+/// it is entirely off screen. Unbounded objects and boxes reaching behind the
+/// camera conservatively cover the whole image.
+fn object_footprint(object: &dyn SceneObject, camera: &Camera, width: u32, height: u32) -> Option<Footprint> {
+    let full = [0.0, 0.0, width as f32, height as f32];
+    let bounds = object.bounding_box();
+    if bounds.is_unbounded() {
+        return Some(full);
+    }
+
+    let mut min = Vec2::splat(f32::INFINITY);
+    let mut max = Vec2::splat(f32::NEG_INFINITY);
+    for corner in bounds.corners() {
+        let Some(screen) = camera.project_to_screen(corner) else {
+            return Some(full);
+        };
+        min = min.min(screen);
+        max = max.max(screen);
+    }
+
+    let footprint = [
+        (min.x * width as f32).max(0.0),
+        (min.y * height as f32).max(0.0),
+        (max.x * width as f32).min(width as f32),
+        (max.y * height as f32).min(height as f32),
+    ];
+    (footprint[0] < footprint[2] && footprint[1] < footprint[3]).then_some(footprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sphere;
+    use rrte_math::Vec3;
+
+    #[test]
+    fn geometry_in_one_corner_gets_more_tiles_than_the_empty_corner() {
+        let mut camera = Camera::new_perspective(1.0, 1.0, 0.1, 100.0);
+        camera.look_at(Vec3::NEG_Z, Vec3::Y);
+        let cluster = Vec3::new(-1.5, 1.5, -5.0);
+        let objects: Vec<Arc<dyn SceneObject>> = (0..4)
+            .map(|i| {
+                let offset = Vec3::new(f32::from(i % 2u8), f32::from(i / 2u8), 0.0) * 0.3;
+                Arc::new(Sphere::new(cluster + offset, 0.2)) as Arc<dyn SceneObject>
+            })
+            .collect();
+        let settings = TileSettings { base_size: 64, min_size: 8, object_cost: 4.0 };
+        let tiles = plan_tiles(128, 128, &objects, &camera, &settings);
+
+        // The tiles cover the image exactly once
+        let area: u32 = tiles.iter().map(|tile| tile.width * tile.height).sum();
+        assert_eq!(area, 128 * 128);
+
+        let in_quadrant = |quadrant: Vec2| {
+            tiles
+                .iter()
+                .filter(|tile| {
+                    let left = 2 * tile.x + tile.width < 128;
+                    let top = 2 * tile.y + tile.height < 128;
+                    left == (quadrant.x < 0.5) && top == (quadrant.y < 0.5)
+                })
+                .count()
+        };
+        let busy = camera.project_to_screen(cluster).unwrap();
+        let empty = Vec2::ONE - busy;
+        // The empty quadrant keeps its single base tile
+        assert_eq!(in_quadrant(empty), 1);
+        assert!(in_quadrant(busy) > 1, "{} tiles", in_quadrant(busy));
+    }
+}
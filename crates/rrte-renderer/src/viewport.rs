@@ -0,0 +1,86 @@
+use crate::camera::Camera;
+
+/// Pixel rectangle of a render target, with the origin at the top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewportRect {
+    /// Left edge in pixels
+    pub x: u32,
+    /// Top edge in pixels
+    pub y: u32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+impl ViewportRect {
+    /// Create a new viewport rectangle
+    #[must_use]
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Get the width-to-height ratio of the rectangle
+    #[must_use]
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.height == 0 {
+            return 1.0;
+        }
+        self.width as f32 / self.height as f32
+    }
+
+    /// Clip the rectangle to a `width` x `height` target, or `None` if nothing remains
+    #[must_use]
+    pub fn clipped(&self, width: u32, height: u32) -> Option<Self> {
+        let right = self.x.saturating_add(self.width).min(width);
+        let bottom = self.y.saturating_add(self.height).min(height);
+        if self.x >= right || self.y >= bottom {
+            return None;
+        }
+        Some(Self::new(self.x, self.y, right - self.x, bottom - self.y))
+    }
+}
+
+/// A camera rendering into a sub-rectangle of the frame, e.g. one editor panel
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    /// Camera used to render this viewport
+    pub camera: Camera,
+    /// Target rectangle within the frame
+    pub rect: ViewportRect,
+}
+
+impl Viewport {
+    /// Create a new viewport.
+    ///
+    /// A perspective camera has its aspect ratio matched to the rectangle.
+    #[must_use]
+    pub fn new(mut camera: Camera, rect: ViewportRect) -> Self {
+        camera.set_aspect_ratio(rect.aspect_ratio());
+        Self { camera, rect }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::ProjectionType;
+
+    #[test]
+    fn rect_is_clipped_to_the_target() {
+        let rect = ViewportRect::new(10, 20, 50, 50);
+        assert_eq!(rect.clipped(40, 100), Some(ViewportRect::new(10, 20, 30, 50)));
+        assert_eq!(rect.clipped(10, 100), None);
+        assert_eq!(ViewportRect::new(0, 0, 0, 5).clipped(10, 10), None);
+    }
+
+    #[test]
+    fn viewport_camera_takes_the_rect_aspect_ratio() {
+        let camera = Camera::new_perspective(1.0, 1.0, 0.1, 100.0);
+        let viewport = Viewport::new(camera, ViewportRect::new(0, 0, 300, 100));
+        let ProjectionType::Perspective { aspect_ratio, .. } = viewport.camera.projection else {
+            panic!("{:?}", viewport.camera.projection);
+        };
+        assert!((aspect_ratio - 3.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,194 @@
+//! Differences between scene snapshots, for replicating edits to another process.
+
+use crate::{LightRecord, ObjectRecord, Scene, StableId};
+use rrte_ecs::World;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// An object or light that was added or modified, with its new contents.
+///
+/// `record` is `None` for types that [`ObjectRecord`] or [`LightRecord`] cannot
+/// describe; the change is still listed so the receiver knows the id changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemChange<T> {
+    /// Stable id of the item in both scenes
+    pub id: StableId,
+    /// New contents of the item
+    pub record: Option<T>,
+}
+
+/// Objects and lights that differ between two scenes, by [`StableId`].
+///
+/// Produced by [`Scene::diff`]. An item counts as modified when it was edited
+/// through the scene since the other scene's copy, or the scene holds a
+/// different `Arc` for the same id.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SceneDiff {
+    /// Objects only in the newer scene
+    pub added_objects: Vec<ItemChange<ObjectRecord>>,
+    /// Objects only in the older scene
+    pub removed_objects: Vec<StableId>,
+    /// Objects in both scenes that were replaced
+    pub modified_objects: Vec<ItemChange<ObjectRecord>>,
+    /// Lights only in the newer scene
+    pub added_lights: Vec<ItemChange<LightRecord>>,
+    /// Lights only in the older scene
+    pub removed_lights: Vec<StableId>,
+    /// Lights in both scenes that were replaced
+    pub modified_lights: Vec<ItemChange<LightRecord>>,
+}
+
+impl SceneDiff {
+    /// Check whether the scenes had the same objects and lights
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.change_count() == 0
+    }
+
+    /// Get the total number of added, removed and modified items
+    #[must_use]
+    pub const fn change_count(&self) -> usize {
+        self.added_objects.len()
+            + self.removed_objects.len()
+            + self.modified_objects.len()
+            + self.added_lights.len()
+            + self.removed_lights.len()
+            + self.modified_lights.len()
+    }
+}
+
+/// Changed items between `old` and `new` lists of items keyed by stable id
+struct ListDiff<T> {
+    added: Vec<ItemChange<T>>,
+    removed: Vec<StableId>,
+    modified: Vec<ItemChange<T>>,
+}
+
+/// One side of a diff: item ids, the items, and the revisions of edited items
+type DiffSide<'a, I> = (&'a [StableId], &'a [Arc<I>], &'a HashMap<StableId, u64>);
+
+fn diff_items<I: ?Sized, T>(
+    (old_ids, old, old_revisions): DiffSide<'_, I>,
+    (new_ids, new, new_revisions): DiffSide<'_, I>,
+    describe: impl Fn(&Arc<I>) -> Option<T>,
+) -> ListDiff<T> {
+    let old_items: HashMap<StableId, &Arc<I>> = old_ids.iter().copied().zip(old).collect();
+    let kept: HashSet<StableId> = new_ids.iter().copied().collect();
+
+    let mut diff = ListDiff {
+        added: Vec::new(),
+        removed: old_ids.iter().copied().filter(|id| !kept.contains(id)).collect(),
+        modified: Vec::new(),
+    };
+    for (&id, item) in new_ids.iter().zip(new) {
+        let change = || ItemChange {
+            id,
+            record: describe(item),
+        };
+        match old_items.get(&id) {
+            None => diff.added.push(change()),
+            Some(old_item)
+                if old_revisions.get(&id) != new_revisions.get(&id) || !Arc::ptr_eq(old_item, item) =>
+            {
+                diff.modified.push(change());
+            }
+            Some(_) => {}
+        }
+    }
+    diff
+}
+
+impl Scene {
+    /// Copy the scene's objects, lights, materials and animations, keeping
+    /// their stable ids, to diff against later.
+    ///
+    /// Objects and lights are shared with this scene rather than cloned; edits
+    /// made through the scene copy an object first, so the snapshot keeps its
+    /// old contents. ECS entities are not copied.
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            world: World::new(),
+            objects: self.objects.clone(),
+            materials: self.materials.clone(),
+            material_registry: self.material_registry.clone(),
+            lights: self.lights.clone(),
+            object_ids: self.object_ids.clone(),
+            light_ids: self.light_ids.clone(),
+            next_stable_id: self.next_stable_id,
+            revisions: self.revisions.clone(),
+            dynamic_objects: self.dynamic_objects.clone(),
+            bvh: self.bvh.clone(),
+            previous_transforms: self.previous_transforms.clone(),
+            current_transforms: self.current_transforms.clone(),
+            legacy_spheres: self.legacy_spheres.clone(),
+            legacy_lights: self.legacy_lights.clone(),
+            instanced_lights: self.instanced_lights.clone(),
+            animations: self.animations.clone(),
+            time: self.time,
+            lod_camera: self.lod_camera,
+            dirty: self.dirty,
+            version: self.version,
+        }
+    }
+
+    /// List the objects and lights that changed going from `self` to `other`.
+    ///
+    /// Items are matched by [`StableId`], so `other` should be a
+    /// [`Scene::snapshot`] of this scene or a scene derived from one.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> SceneDiff {
+        let object_diff = diff_items(
+            (&self.object_ids, &self.objects, &self.revisions),
+            (&other.object_ids, &other.objects, &other.revisions),
+            |object| ObjectRecord::from_object(object.as_ref()),
+        );
+        let light_diff = diff_items(
+            (&self.light_ids, &self.lights, &self.revisions),
+            (&other.light_ids, &other.lights, &other.revisions),
+            |light| LightRecord::from_light(light.as_ref()),
+        );
+        SceneDiff {
+            added_objects: object_diff.added,
+            removed_objects: object_diff.removed,
+            modified_objects: object_diff.modified,
+            added_lights: light_diff.added,
+            removed_lights: light_diff.removed,
+            modified_lights: light_diff.modified,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rrte_math::Vec3;
+    use rrte_renderer::primitives::Sphere;
+
+    #[test]
+    fn one_added_and_one_removed_object_are_the_only_changes() {
+        let mut scene = Scene::new();
+        for x in [-2.0, 0.0, 2.0] {
+            scene.add_object(Arc::new(Sphere::new(Vec3::new(x, 0.0, 0.0), 0.5)));
+        }
+        let snapshot = scene.snapshot();
+        assert!(snapshot.diff(&scene).is_empty());
+
+        let removed = scene.object_stable_id(1).unwrap();
+        scene.remove_object(1);
+        scene.add_object(Arc::new(Sphere::new(Vec3::new(0.0, 3.0, 0.0), 1.0)));
+        let added = scene.object_stable_id(2).unwrap();
+
+        let diff = snapshot.diff(&scene);
+        assert_eq!(diff.change_count(), 2);
+        assert_eq!(diff.removed_objects, [removed]);
+        assert_eq!(diff.added_objects.len(), 1);
+        assert_eq!(diff.added_objects[0].id, added);
+        assert!(matches!(
+            diff.added_objects[0].record,
+            Some(ObjectRecord::Sphere { radius, .. }) if (radius - 1.0).abs() < 1e-6
+        ));
+    }
+}
@@ -3,13 +3,26 @@
 //! This crate defines scene data structures used by the renderer
 //! and gameplay systems.
 
-use rrte_math::{Transform, Vec3, Color};
-use rrte_renderer::{SceneObject, Material, Light, primitives::Sphere, light::PointLight};
+use rrte_math::{AnimationTrack, GeoSphere, Transform, Vec3, Color};
+use rrte_renderer::{make_object_mut, SceneObject, Material, MaterialId, MaterialRegistry, Light, Camera, GpuSceneData, primitives::Sphere, light::{InstancedLight, PointLight}};
 use rrte_ecs::{Entity, World, Component};
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
+pub mod persistence;
+pub mod lod;
+pub mod diff;
+pub mod partition;
+mod motion;
+mod shadows;
+pub use persistence::*;
+pub use lod::*;
+pub use diff::*;
+pub use partition::*;
+
 /// Scene configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SceneConfig {
@@ -23,6 +36,10 @@ pub struct SceneConfig {
     pub fog_density: f32,
     /// Gravity vector applied to physics objects
     pub gravity: Vec3,
+    /// Keep the objects and the GPU sphere and light lists sorted by content instead of insertion
+    /// order, so identical scenes upload identical buffers
+    #[serde(default)]
+    pub stable_ordering: bool,
 }
 
 impl Default for SceneConfig {
@@ -33,6 +50,7 @@ impl Default for SceneConfig {
             fog_color: Color::new(0.5, 0.5, 0.5, 1.0),
             fog_density: 0.0,
             gravity: Vec3::new(0.0, -9.81, 0.0),
+            stable_ordering: false,
         }
     }
 }
@@ -58,16 +76,61 @@ impl Default for SceneComponent {
     }
 }
 
+/// Identifier of an object in a [`Scene`], as its index into [`Scene::get_objects`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ObjectId(usize);
+
+impl ObjectId {
+    /// Create an object id from an index into the scene's object list
+    #[must_use]
+    pub const fn new(index: usize) -> Self {
+        Self(index)
+    }
+
+    /// Get the index into the scene's object list
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// Identifier of an object or light that stays the same while the scene
+/// changes around it, unlike [`ObjectId`] indices. Never reused within a scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StableId(u64);
+
+impl StableId {
+    /// Get the raw id
+    #[must_use]
+    pub const fn value(&self) -> u64 {
+        self.0
+    }
+}
+
 /// Scene management system
 pub struct Scene {
     config: SceneConfig,
     world: World,
     objects: Vec<Arc<dyn SceneObject>>,
     materials: Vec<Arc<dyn Material>>,
+    material_registry: MaterialRegistry,
     lights: Vec<Arc<dyn Light>>,
+    object_ids: Vec<StableId>,
+    light_ids: Vec<StableId>,
+    next_stable_id: u64,
+    revisions: HashMap<StableId, u64>, // Scene version of each item's last in-place edit
+    dynamic_objects: HashSet<StableId>,
+    bvh: SceneBvh,
+    previous_transforms: HashMap<StableId, Transform>,
+    current_transforms: HashMap<StableId, Transform>,
     legacy_spheres: Vec<Arc<Sphere>>, // Stored separately for GPU renderer compatibility
     legacy_lights: Vec<Arc<PointLight>>, // Stored separately for GPU renderer compatibility
+    instanced_lights: Vec<InstancedLight>, // Expanded by the GPU renderer on upload
+    animations: Vec<(StableId, AnimationTrack)>,
+    time: f32,
+    lod_camera: Option<Vec3>,
     dirty: bool,
+    version: u64,
 }
 
 impl Scene {    /// Create a new empty scene
@@ -77,10 +140,24 @@ impl Scene {    /// Create a new empty scene
             world: World::new(),
             objects: Vec::new(),
             materials: Vec::new(),
+            material_registry: MaterialRegistry::new(),
             lights: Vec::new(),
+            object_ids: Vec::new(),
+            light_ids: Vec::new(),
+            next_stable_id: 0,
+            revisions: HashMap::new(),
+            dynamic_objects: HashSet::new(),
+            bvh: SceneBvh::default(),
+            previous_transforms: HashMap::new(),
+            current_transforms: HashMap::new(),
             legacy_spheres: Vec::new(),
             legacy_lights: Vec::new(),
+            instanced_lights: Vec::new(),
+            animations: Vec::new(),
+            time: 0.0,
+            lod_camera: None,
             dirty: true,
+            version: 0,
         }
     }
 
@@ -91,41 +168,162 @@ impl Scene {    /// Create a new empty scene
             world: World::new(),
             objects: Vec::new(),
             materials: Vec::new(),
+            material_registry: MaterialRegistry::new(),
             lights: Vec::new(),
+            object_ids: Vec::new(),
+            light_ids: Vec::new(),
+            next_stable_id: 0,
+            revisions: HashMap::new(),
+            dynamic_objects: HashSet::new(),
+            bvh: SceneBvh::default(),
+            previous_transforms: HashMap::new(),
+            current_transforms: HashMap::new(),
             legacy_spheres: Vec::new(),
             legacy_lights: Vec::new(),
+            instanced_lights: Vec::new(),
+            animations: Vec::new(),
+            time: 0.0,
+            lod_camera: None,
             dirty: true,
+            version: 0,
         }
     }
 
-    /// Update the scene
-    pub fn update(&mut self, _delta_time: f32) {
+    /// Update the scene, advancing the scene clock, applying object animations
+    /// and recording object transforms for [`Scene::object_velocity`]
+    pub fn update(&mut self, delta_time: f32) {
         // Update entity systems
         // self.entity_manager_mut().update(delta_time); // FIXME: World has no update method
         
-        // Mark as clean after update
+        self.time += delta_time;
+        self.apply_animations();
+        self.record_transforms();
+
+        // Mark as clean after update, including the animations just applied
         self.dirty = false;
     }
 
+    /// Apply each animation track's sample at the current scene time to its
+    /// object. Objects already at their sample are left alone, so a finished
+    /// animation stops advancing [`Scene::scene_version`].
+    fn apply_animations(&mut self) {
+        self.sync_stable_ids();
+        let samples: Vec<(usize, Transform)> = self
+            .animations
+            .iter()
+            .filter_map(|(id, track)| Some((self.object_index(*id)?, track.sample(self.time)?)))
+            .filter(|(index, transform)| self.objects[*index].transform() != transform)
+            .collect();
+        for (index, transform) in samples {
+            self.set_object_transform(ObjectId::new(index), transform);
+        }
+    }
+
+    /// Animate an object's transform with a keyframe track, replacing any existing track.
+    ///
+    /// The track follows the object when other objects are removed, and is
+    /// dropped with it. Does nothing if there is no such object.
+    pub fn set_object_animation(&mut self, id: ObjectId, track: AnimationTrack) {
+        self.sync_stable_ids();
+        let Some(stable_id) = self.object_stable_id(id.index()) else {
+            return;
+        };
+        self.remove_object_animation(id);
+        self.animations.push((stable_id, track));
+    }
+
+    /// Stop animating an object, returning its track
+    pub fn remove_object_animation(&mut self, id: ObjectId) -> Option<AnimationTrack> {
+        let stable_id = self.object_stable_id(id.index())?;
+        let index = self.animations.iter().position(|(animated, _)| *animated == stable_id)?;
+        Some(self.animations.remove(index).1)
+    }
+
+    /// Get the animation track of an object
+    #[must_use]
+    pub fn object_animation(&self, id: ObjectId) -> Option<&AnimationTrack> {
+        let stable_id = self.object_stable_id(id.index())?;
+        self.animations
+            .iter()
+            .find(|(animated, _)| *animated == stable_id)
+            .map(|(_, track)| track)
+    }
+
+    /// Get the scene clock in seconds, advanced by [`Scene::update`]
+    #[must_use]
+    pub const fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Set the scene clock and apply animations at that time
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time;
+        self.apply_animations();
+    }
+
+    /// Set the camera position used to pick the level of every [`LodObject`].
+    ///
+    /// Call this once per frame before rendering; LOD objects added later pick
+    /// their level from the same position.
+    pub fn set_lod_camera(&mut self, camera_position: Vec3) {
+        self.lod_camera = Some(camera_position);
+        for object in &self.objects {
+            if let Some(lod) = (**object).as_any().downcast_ref::<LodObject>() {
+                lod.select_level(camera_position);
+            }
+        }
+    }
+
+    /// Get the camera position last used for level-of-detail selection
+    #[must_use]
+    pub const fn lod_camera(&self) -> Option<Vec3> {
+        self.lod_camera
+    }
+
     /// Add an object implementing [`SceneObject`]
     pub fn add_object(&mut self, object: Arc<dyn SceneObject>) {
+        if let (Some(camera_position), Some(lod)) = (self.lod_camera, (*object).as_any().downcast_ref::<LodObject>()) {
+            lod.select_level(camera_position);
+        }
+        self.sync_stable_ids();
         self.objects.push(object);
-        self.dirty = true;
+        let id = self.allocate_stable_id();
+        self.object_ids.push(id);
+        self.mark_dirty();
+        self.apply_stable_ordering();
     }
 
     /// Convenience method to add a [`Sphere`]. This stores the sphere in the
     /// legacy list used by the GPU renderer.
     pub fn add_sphere(&mut self, sphere: Arc<Sphere>) {
+        self.sync_stable_ids();
         self.legacy_spheres.push(Arc::clone(&sphere));
         self.objects.push(sphere);
-        self.dirty = true;
+        let id = self.allocate_stable_id();
+        self.object_ids.push(id);
+        self.mark_dirty();
+        self.apply_stable_ordering();
     }
 
-    /// Remove an object from the scene by index
+    /// Remove an object from the scene by index, including a sphere's entry in
+    /// the GPU list.
+    ///
+    /// Later objects shift down by one, so their [`ObjectId`]s change; their
+    /// [`StableId`]s, animations and other per-object state move with them.
     pub fn remove_object(&mut self, index: usize) -> Option<Arc<dyn SceneObject>> {
+        self.sync_stable_ids();
         if index < self.objects.len() {
-            self.dirty = true;
-            Some(self.objects.remove(index))
+            self.mark_dirty();
+            let id = self.object_ids.remove(index);
+            self.revisions.remove(&id);
+            self.dynamic_objects.remove(&id);
+            self.previous_transforms.remove(&id);
+            self.current_transforms.remove(&id);
+            self.animations.retain(|(animated, _)| *animated != id);
+            let object = self.objects.remove(index);
+            self.legacy_spheres
+                .retain(|sphere| !std::ptr::addr_eq(Arc::as_ptr(sphere), Arc::as_ptr(&object)));
+            Some(object)
         } else {
             None
         }
@@ -134,33 +332,172 @@ impl Scene {    /// Create a new empty scene
     /// Add a material to the scene
     pub fn add_material(&mut self, material: Arc<dyn Material>) {
         self.materials.push(material);
-        self.dirty = true;
+        self.mark_dirty();
+    }
+
+    /// Register a shared material that objects can reference by id
+    pub fn register_material(&mut self, material: Arc<dyn Material>) -> MaterialId {
+        self.material_registry.register(material)
+    }
+
+    /// Get a material for assigning to objects that always resolves to the
+    /// material registered under `id`, or `None` if `id` is not registered
+    #[must_use]
+    pub fn material_ref(&self, id: MaterialId) -> Option<Arc<dyn Material>> {
+        self.material_registry.reference(id)
+    }
+
+    /// Replace a registered material, changing every object that references it.
+    /// Returns false if `id` is not registered.
+    pub fn set_registered_material(&mut self, id: MaterialId, material: Arc<dyn Material>) -> bool {
+        if !self.material_registry.set(id, material) {
+            return false;
+        }
+        let registry = self.material_registry.clone();
+        for index in 0..self.objects.len() {
+            if self.objects[index].has_stale_materials(&registry) {
+                self.modify_object(index, |object| {
+                    object.refresh_materials(&registry);
+                });
+            }
+        }
+        self.mark_dirty();
+        true
+    }
+
+    /// Get the scene's material registry
+    #[must_use]
+    pub const fn material_registry(&self) -> &MaterialRegistry {
+        &self.material_registry
     }
 
     /// Add a light implementing [`Light`]
     pub fn add_light(&mut self, light: Arc<dyn Light>) {
+        self.sync_stable_ids();
         self.lights.push(light);
-        self.dirty = true;
+        let id = self.allocate_stable_id();
+        self.light_ids.push(id);
+        self.mark_dirty();
     }
 
     /// Convenience method to add a [`PointLight`]. This stores the light in the
     /// legacy list used by the GPU renderer.
     pub fn add_point_light(&mut self, light: Arc<PointLight>) {
+        self.sync_stable_ids();
         self.legacy_lights.push(Arc::clone(&light));
         self.lights.push(light);
-        self.dirty = true;
+        let id = self.allocate_stable_id();
+        self.light_ids.push(id);
+        self.mark_dirty();
+        self.apply_stable_ordering();
+    }
+
+    /// Add a light definition repeated at several positions.
+    ///
+    /// Every instance is added to [`Scene::get_lights`] as its own light. The
+    /// GPU renderer instead receives the shared definition through
+    /// [`Scene::instanced_lights`] and expands it when uploading lights.
+    pub fn add_instanced_light(&mut self, light: InstancedLight) {
+        for instance in light.instances() {
+            self.add_light(Arc::new(instance));
+        }
+        self.instanced_lights.push(light);
+    }
+
+    /// Get the instanced lights added with [`Scene::add_instanced_light`]
+    #[must_use]
+    pub fn instanced_lights(&self) -> &[InstancedLight] {
+        &self.instanced_lights
+    }
+
+    /// Sort the objects and the GPU sphere and light lists by content if
+    /// [`SceneConfig::stable_ordering`] is enabled.
+    ///
+    /// Runs automatically when objects or point lights are added and when the
+    /// config is replaced. The sort is stable. Objects are ordered by kind, then
+    /// by content, and keep their [`StableId`]s; an [`ObjectId`] indexes the
+    /// sorted list, so it may refer to another object after an addition.
+    pub fn apply_stable_ordering(&mut self) {
+        if !self.config.stable_ordering {
+            return;
+        }
+        self.sync_stable_ids();
+        let keys: Vec<(TypeId, Vec<f32>)> = self
+            .objects
+            .iter()
+            .map(|object| ((**object).as_any().type_id(), object_key(object.as_ref())))
+            .collect();
+        let mut order: Vec<usize> = (0..self.objects.len()).collect();
+        order.sort_by(|&a, &b| keys[a].0.cmp(&keys[b].0).then_with(|| compare_keys(&keys[a].1, &keys[b].1)));
+        self.objects = order.iter().map(|&index| Arc::clone(&self.objects[index])).collect();
+        self.object_ids = order.iter().map(|&index| self.object_ids[index]).collect();
+
+        self.legacy_spheres
+            .sort_by(|a, b| compare_keys(&sphere_key(a), &sphere_key(b)));
+        self.legacy_lights
+            .sort_by(|a, b| compare_keys(&point_light_key(a), &point_light_key(b)));
     }
 
     /// Remove a light from the scene by index
     pub fn remove_light(&mut self, index: usize) -> Option<Arc<dyn Light>> {
+        self.sync_stable_ids();
         if index < self.lights.len() {
-            self.dirty = true;
+            self.mark_dirty();
+            let id = self.light_ids.remove(index);
+            self.revisions.remove(&id);
             Some(self.lights.remove(index))
         } else {
             None
         }
     }
 
+    /// Get the stable id of the object at `index`
+    #[must_use]
+    pub fn object_stable_id(&self, index: usize) -> Option<StableId> {
+        self.object_ids.get(index).copied()
+    }
+
+    /// Get the index of the object with a stable id
+    #[must_use]
+    pub fn object_index(&self, id: StableId) -> Option<usize> {
+        self.object_ids.iter().position(|&object_id| object_id == id)
+    }
+
+    /// Get the stable id of the light at `index`
+    #[must_use]
+    pub fn light_stable_id(&self, index: usize) -> Option<StableId> {
+        self.light_ids.get(index).copied()
+    }
+
+    /// Get the index of the light with a stable id
+    #[must_use]
+    pub fn light_index(&self, id: StableId) -> Option<usize> {
+        self.light_ids.iter().position(|&light_id| light_id == id)
+    }
+
+    /// Hand out the next unused stable id
+    const fn allocate_stable_id(&mut self) -> StableId {
+        let id = StableId(self.next_stable_id);
+        self.next_stable_id += 1;
+        id
+    }
+
+    /// Match the id lists to objects and lights added or removed through
+    /// [`Scene::get_objects_mut`] or [`Scene::get_lights_mut`]: extra entries
+    /// get fresh ids and ids past the end are dropped
+    fn sync_stable_ids(&mut self) {
+        self.object_ids.truncate(self.objects.len());
+        while self.object_ids.len() < self.objects.len() {
+            let id = self.allocate_stable_id();
+            self.object_ids.push(id);
+        }
+        self.light_ids.truncate(self.lights.len());
+        while self.light_ids.len() < self.lights.len() {
+            let id = self.allocate_stable_id();
+            self.light_ids.push(id);
+        }
+    }
+
     /// Get all objects in the scene
     pub fn get_objects(&self) -> &[Arc<dyn SceneObject>] {
         &self.objects
@@ -196,45 +533,159 @@ impl Scene {    /// Create a new empty scene
         &self.legacy_lights
     }
 
-    /// Get mutable reference to objects
+    /// Pack the GPU spheres, the other objects the GPU renderer supports with
+    /// their shadow flags, their materials and every point light, including
+    /// instanced ones, into the layout uploaded by the GPU renderer
+    #[must_use]
+    pub fn gpu_snapshot(&self) -> GpuSceneData {
+        GpuSceneData::pack(&self.legacy_spheres, &self.objects, &self.legacy_lights, &self.instanced_lights)
+    }
+
+    /// Find all objects overlapping a sphere.
+    ///
+    /// This is a geometric query for triggers and gameplay logic, not a physics
+    /// simulation. Spheres, cubes and planes are tested exactly; other
+    /// primitives use their bounding boxes.
+    #[must_use]
+    pub fn overlap_sphere(&self, center: Vec3, radius: f32) -> Vec<ObjectId> {
+        self.objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| object.overlaps_sphere(center, radius))
+            .map(|(index, _)| ObjectId::new(index))
+            .collect()
+    }
+
+    /// Get a sphere enclosing the bounding boxes of every bounded object, as
+    /// `(center, radius)`.
+    ///
+    /// Objects with [unbounded](rrte_math::AABB::is_unbounded) boxes, such as
+    /// planes, are ignored; returns `None` if every object is unbounded.
+    #[must_use]
+    pub fn bounding_sphere(&self) -> Option<(Vec3, f32)> {
+        let corners: Vec<Vec3> = self
+            .objects
+            .iter()
+            .map(|object| object.bounding_box())
+            .filter(|bounds| !bounds.is_unbounded())
+            .flat_map(|bounds| bounds.corners())
+            .collect();
+        GeoSphere::enclosing_points(&corners).map(|sphere| (sphere.center, sphere.radius))
+    }
+
+    /// Get an object by id
+    #[must_use]
+    pub fn get_object(&self, id: ObjectId) -> Option<&Arc<dyn SceneObject>> {
+        self.objects.get(id.index())
+    }
+
+    /// Replace an object's transform.
+    ///
+    /// Objects whose `Arc` is shared, for example with a [`Scene::snapshot`],
+    /// are copied first, so the other holders keep the old transform. Returns
+    /// false if there is no such object or it is shared and cannot be copied.
+    pub fn set_object_transform(&mut self, id: ObjectId, transform: Transform) -> bool {
+        self.modify_object(id.index(), |object| object.set_transform(transform))
+    }
+
+    /// Change an object through `modify`, copying it first if its `Arc` is
+    /// shared, and advance its revision for [`Scene::diff`]. A sphere in the GPU
+    /// list is replaced by the same copy.
+    fn modify_object(&mut self, index: usize, modify: impl FnOnce(&mut dyn SceneObject)) -> bool {
+        self.sync_stable_ids();
+        let Some(object) = self.objects.get_mut(index) else {
+            return false;
+        };
+        let legacy_index = self
+            .legacy_spheres
+            .iter()
+            .position(|sphere| std::ptr::addr_eq(Arc::as_ptr(sphere), Arc::as_ptr(object)));
+        if let Some(legacy_index) = legacy_index {
+            let mut sphere = Sphere::clone(&self.legacy_spheres[legacy_index]);
+            modify(&mut sphere);
+            let sphere = Arc::new(sphere);
+            self.legacy_spheres[legacy_index] = Arc::clone(&sphere);
+            *object = sphere;
+        } else {
+            let Some(object) = make_object_mut(object) else {
+                return false;
+            };
+            modify(object);
+        }
+        self.mark_dirty();
+        self.revisions.insert(self.object_ids[index], self.version);
+        true
+    }
+
+    /// Get the point lights whose influence sphere (`position`, `range`) intersects
+    /// the camera frustum. Used to skip uploading lights that cannot affect the view.
+    #[must_use]
+    pub fn visible_point_lights(&self, camera: &Camera) -> Vec<Arc<PointLight>> {
+        let frustum = camera.frustum();
+        self.legacy_lights
+            .iter()
+            .filter(|light| frustum.intersects_sphere(light.position, light.range))
+            .cloned()
+            .collect()
+    }
+
+    /// Get mutable reference to objects.
+    ///
+    /// Objects pushed or removed through the vector are not tracked by stable id;
+    /// ids are matched up by index the next time the scene adds or removes one.
     pub fn get_objects_mut(&mut self) -> &mut Vec<Arc<dyn SceneObject>> {
-        self.dirty = true;
+        self.mark_dirty();
         &mut self.objects
     }
 
-    /// Get mutable reference to lights
+    /// Get mutable reference to lights; see [`Scene::get_objects_mut`] for stable ids
     pub fn get_lights_mut(&mut self) -> &mut Vec<Arc<dyn Light>> {
-        self.dirty = true;
+        self.mark_dirty();
         &mut self.lights
     }
 
     /// Clear all objects from the scene
     pub fn clear_objects(&mut self) {
         self.objects.clear();
+        self.revisions.retain(|id, _| self.light_ids.contains(id));
+        self.object_ids.clear();
+        self.dynamic_objects.clear();
+        self.previous_transforms.clear();
+        self.current_transforms.clear();
         self.legacy_spheres.clear();
-        self.dirty = true;
+        self.animations.clear();
+        self.mark_dirty();
     }
 
     /// Clear all lights from the scene
     pub fn clear_lights(&mut self) {
+        self.revisions.retain(|id, _| self.object_ids.contains(id));
         self.lights.clear();
+        self.light_ids.clear();
         self.legacy_lights.clear();
-        self.dirty = true;
+        self.instanced_lights.clear();
+        self.mark_dirty();
     }
 
     /// Clear all materials from the scene
     pub fn clear_materials(&mut self) {
         self.materials.clear();
-        self.dirty = true;
+        self.mark_dirty();
     }
 
-    /// Clear the entire scene
-    pub fn clear(&mut self) {
+    /// Clear all objects, lights and materials, keeping the ECS world and its entities
+    pub fn clear_renderables(&mut self) {
         self.clear_objects();
         self.clear_lights();
         self.clear_materials();
+    }
+
+    /// Clear the entire scene, including every ECS entity; see
+    /// [`Scene::clear_renderables`] to keep them
+    pub fn clear(&mut self) {
+        self.clear_renderables();
         self.world = World::new();
-        self.dirty = true;
+        self.mark_dirty();
     }
 
     /// Create a new entity in the scene
@@ -244,20 +695,20 @@ impl Scene {    /// Create a new empty scene
         // Add default scene component
         self.entity_manager_mut().add_component(entity, SceneComponent::default());
         
-        self.dirty = true;
+        self.mark_dirty();
         entity
     }
 
     /// Remove an entity from the scene
     pub fn remove_entity(&mut self, entity: Entity) {
         self.entity_manager_mut().destroy_entity(entity);
-        self.dirty = true;
+        self.mark_dirty();
     }
 
     /// Add a component to an entity
     pub fn add_component<T: Component>(&mut self, entity: Entity, component: T) {
         self.entity_manager_mut().add_component(entity, component);
-        self.dirty = true;
+        self.mark_dirty();
     }
 
     /// Get a component from an entity
@@ -279,7 +730,7 @@ impl Scene {    /// Create a new empty scene
     pub fn remove_component<T: Component>(&mut self, entity: Entity) -> bool {
         let removed = self.entity_manager_mut().remove_component::<T>(entity);
         if removed {
-            self.dirty = true;
+            self.mark_dirty();
         }
         removed
     }
@@ -296,14 +747,15 @@ impl Scene {    /// Create a new empty scene
 
     /// Get mutable scene configuration
     pub fn config_mut(&mut self) -> &mut SceneConfig {
-        self.dirty = true;
+        self.mark_dirty();
         &mut self.config
     }
 
     /// Set scene configuration
     pub fn set_config(&mut self, config: SceneConfig) {
         self.config = config;
-        self.dirty = true;
+        self.mark_dirty();
+        self.apply_stable_ordering();
     }
 
     /// Check if the scene has been modified
@@ -316,9 +768,19 @@ impl Scene {    /// Create a new empty scene
         self.dirty = false;
     }
 
-    /// Mark the scene as dirty
+    /// Mark the scene as dirty, advancing [`Scene::scene_version`]
     pub fn mark_dirty(&mut self) {
         self.dirty = true;
+        self.version = self.version.wrapping_add(1);
+    }
+
+    /// Get a counter that changes whenever the scene is modified.
+    ///
+    /// Unlike [`Scene::is_dirty`] it is not reset by [`Scene::update`], so
+    /// renderers can compare it against the version they last uploaded.
+    #[must_use]
+    pub const fn scene_version(&self) -> u64 {
+        self.version
     }
 
     /// Get the number of objects in the scene
@@ -352,6 +814,61 @@ impl Scene {    /// Create a new empty scene
     }
 }
 
+/// Content key ordering spheres by geometry, then by what the GPU uploads of their material
+fn sphere_key(sphere: &Sphere) -> Vec<f32> {
+    let center = sphere.world_center();
+    let mut key = vec![center.x, center.y, center.z, sphere.world_radius()];
+    if let Some(material) = &sphere.material {
+        key.extend(material_key(material.as_ref()));
+    }
+    key
+}
+
+/// Content key ordering objects of the same kind by their bounds, transform and material
+fn object_key(object: &dyn SceneObject) -> Vec<f32> {
+    let bounds = object.bounding_box();
+    let transform = object.transform();
+    let mut key = vec![bounds.min.x, bounds.min.y, bounds.min.z, bounds.max.x, bounds.max.y, bounds.max.z];
+    key.extend(transform.position.to_array());
+    key.extend(transform.rotation.to_array());
+    key.extend(transform.scale.to_array());
+    if let Some(material) = object.material() {
+        key.extend(material_key(material.as_ref()));
+    }
+    key
+}
+
+/// Content key of the material fields uploaded to the GPU
+fn material_key(material: &dyn Material) -> [f32; 7] {
+    let albedo = material.albedo();
+    let properties = material.get_properties();
+    [albedo.r, albedo.g, albedo.b, albedo.a, properties.roughness, properties.metallic, properties.ior]
+}
+
+/// Content key ordering point lights by every uploaded field
+fn point_light_key(light: &PointLight) -> Vec<f32> {
+    vec![
+        light.position.x,
+        light.position.y,
+        light.position.z,
+        light.color.r,
+        light.color.g,
+        light.color.b,
+        light.color.a,
+        light.intensity,
+        light.range,
+    ]
+}
+
+/// Compare content keys lexicographically with a total order on floats
+fn compare_keys(a: &[f32], b: &[f32]) -> std::cmp::Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| x.total_cmp(y))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
 impl Default for Scene {
     fn default() -> Self {
         Self::new()
@@ -370,3 +887,247 @@ impl std::fmt::Debug for Scene {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rrte_renderer::{Cube, Cylinder, EmissiveMaterial, Raytracer, RaytracerConfig};
+
+    fn render(scene: &Scene) -> Vec<[f32; 4]> {
+        let mut camera = Camera::new_perspective(2.0 * 0.25_f32.atan(), 2.0, 0.1, 100.0);
+        camera.transform.position = Vec3::new(0.0, 0.0, 4.0);
+        camera.look_at(Vec3::ZERO, Vec3::Y);
+        let raytracer = Raytracer::new(RaytracerConfig {
+            width: 2,
+            height: 1,
+            samples_per_pixel: 4,
+            background_color: Color::BLACK,
+            seed: Some(7),
+            ..RaytracerConfig::default()
+        });
+        raytracer.render_hdr(scene.get_objects(), scene.get_lights(), &[], &camera)
+    }
+
+    #[test]
+    fn replacing_registered_material_recolors_every_referencing_object() {
+        let mut scene = Scene::new();
+        let id = scene.register_material(EmissiveMaterial::new(Color::RED, 1.0));
+        for x in [-1.5, 1.5] {
+            let mut sphere = Sphere::new(Vec3::new(x, 0.0, 0.0), 1.4);
+            sphere.set_material(scene.material_ref(id).unwrap());
+            scene.add_object(Arc::new(sphere));
+        }
+        // A live snapshot shares the objects and must keep the old material
+        let snapshot = scene.snapshot();
+
+        assert!(render(&scene).iter().all(|pixel| pixel[0] > 0.9 && pixel[1] < 0.1));
+        assert!(scene.set_registered_material(id, EmissiveMaterial::new(Color::GREEN, 1.0)));
+        assert!(render(&scene).iter().all(|pixel| pixel[1] > 0.9 && pixel[0] < 0.1));
+        assert_eq!(snapshot.diff(&scene).modified_objects.len(), 2);
+    }
+
+    #[test]
+    fn lights_outside_the_frustum_are_not_uploaded() {
+        let mut scene = Scene::new();
+        let mut camera = Camera::new_perspective(60_f32.to_radians(), 1.0, 0.1, 100.0);
+        camera.transform.position = Vec3::new(0.0, 0.0, 5.0);
+        camera.look_at(Vec3::ZERO, Vec3::Y);
+
+        let in_view = PointLight::with_attenuation(Vec3::ZERO, Color::WHITE, 1.0, 2.0, 0.0, 0.0);
+        let far_away =
+            PointLight::with_attenuation(Vec3::new(500.0, 0.0, 0.0), Color::WHITE, 1.0, 2.0, 0.0, 0.0);
+        scene.add_point_light(Arc::new(in_view));
+        scene.add_point_light(Arc::new(far_away));
+
+        let visible = scene.visible_point_lights(&camera);
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].position, Vec3::ZERO);
+    }
+
+    #[test]
+    fn overlap_query_returns_only_the_touched_sphere() {
+        let mut scene = Scene::new();
+        scene.add_object(Arc::new(Sphere::new(Vec3::new(-3.0, 0.0, 0.0), 1.0)));
+        scene.add_object(Arc::new(Sphere::new(Vec3::new(3.0, 0.0, 0.0), 1.0)));
+
+        assert_eq!(scene.overlap_sphere(Vec3::new(4.5, 0.0, 0.0), 0.75), vec![ObjectId::new(1)]);
+        assert!(scene.overlap_sphere(Vec3::new(0.0, 0.0, 0.0), 1.5).is_empty());
+    }
+
+    #[test]
+    fn overlap_query_is_exact_for_rotated_cubes() {
+        let mut cube = rrte_renderer::Cube::new(Vec3::ZERO, Vec3::splat(2.0));
+        cube.transform.rotation = rrte_math::Quat::from_rotation_y(std::f32::consts::FRAC_PI_4);
+        let mut scene = Scene::new();
+        scene.add_object(Arc::new(cube));
+
+        // Inside the cube's bounding box, but off its rotated corner
+        let query = Vec3::new(1.3, 0.0, 1.3);
+        assert!(scene.get_objects()[0].bounding_box().contains_point(query));
+        assert!(scene.overlap_sphere(query, 0.1).is_empty());
+        assert_eq!(scene.overlap_sphere(Vec3::new(1.3, 0.0, 0.0), 0.1), vec![ObjectId::new(0)]);
+    }
+
+    #[test]
+    fn update_applies_the_animation_sample_at_the_scene_time() {
+        let mut scene = Scene::new();
+        scene.add_object(Arc::new(Sphere::new(Vec3::ZERO, 1.0)));
+        let mut rotated = Transform::from_position(Vec3::new(0.0, 2.0, 0.0));
+        rotated.rotation = rrte_math::Quat::from_rotation_y(1.0);
+        let track = AnimationTrack::new()
+            .with_keyframe(0.0, Transform::identity())
+            .with_keyframe(1.0, rotated);
+        scene.set_object_animation(ObjectId::new(0), track.clone());
+
+        scene.update(0.25);
+        scene.update(0.25);
+
+        let expected = track.sample(0.5).unwrap();
+        let actual = scene.get_objects()[0].transform();
+        assert!(actual.position.abs_diff_eq(expected.position, 1e-6));
+        assert!(actual.rotation.abs_diff_eq(expected.rotation, 1e-6));
+    }
+
+    #[test]
+    fn animated_scene_ends_each_update_clean_and_stops_changing_when_the_track_ends() {
+        let mut scene = Scene::new();
+        scene.add_object(Arc::new(Sphere::new(Vec3::ZERO, 1.0)));
+        let track = AnimationTrack::new()
+            .with_keyframe(0.0, Transform::identity())
+            .with_keyframe(1.0, Transform::from_position(Vec3::new(0.0, 2.0, 0.0)));
+        scene.set_object_animation(ObjectId::new(0), track);
+
+        let version = scene.scene_version();
+        scene.update(0.5);
+        assert!(!scene.is_dirty());
+        assert_ne!(scene.scene_version(), version, "the moved object must be uploaded again");
+
+        // Past the last keyframe the object holds still
+        scene.update(1.0);
+        let version = scene.scene_version();
+        scene.update(0.5);
+        assert!(!scene.is_dirty());
+        assert_eq!(scene.scene_version(), version);
+    }
+
+    #[test]
+    fn insertion_order_does_not_change_uploaded_buffers_with_stable_ordering() {
+        let build = |reversed: bool, stable_ordering: bool| {
+            let mut scene = Scene::with_config(SceneConfig { stable_ordering, ..SceneConfig::default() });
+            let mut spheres: Vec<Sphere> = [(-2.0, Color::RED), (0.0, Color::GREEN), (2.0, Color::BLUE)]
+                .into_iter()
+                .map(|(x, color)| {
+                    let mut sphere = Sphere::new(Vec3::new(x, 0.0, 0.0), 0.5);
+                    sphere.set_material(EmissiveMaterial::new(color, 1.0));
+                    sphere
+                })
+                .collect();
+            let mut lights = vec![
+                PointLight::new(Vec3::new(0.0, 4.0, 0.0), Color::WHITE, 2.0),
+                PointLight::new(Vec3::new(3.0, 1.0, 2.0), Color::RED, 5.0),
+            ];
+            let mut others: Vec<Arc<dyn SceneObject>> = vec![
+                Arc::new(Cube::with_material(Vec3::new(0.0, -2.0, 0.0), Vec3::ONE, EmissiveMaterial::new(Color::RED, 1.0))),
+                Arc::new(Cylinder::new(Vec3::new(3.0, 0.0, 0.0), 0.5, 1.0)),
+                Arc::new(Cube::with_material(Vec3::new(0.0, 2.0, 0.0), Vec3::ONE, EmissiveMaterial::new(Color::BLUE, 1.0))),
+            ];
+            if reversed {
+                spheres.reverse();
+                lights.reverse();
+                others.reverse();
+            }
+            // Interleave the kinds so their relative insertion order differs too
+            for (sphere, other) in spheres.into_iter().zip(others) {
+                scene.add_object(other);
+                scene.add_sphere(Arc::new(sphere));
+            }
+            for light in lights {
+                scene.add_point_light(Arc::new(light));
+            }
+            scene.gpu_snapshot()
+        };
+
+        assert_eq!(build(false, true), build(true, true));
+        assert_ne!(build(false, false), build(true, false));
+    }
+
+    #[test]
+    fn bounding_sphere_encloses_both_spheres_and_ignores_planes() {
+        let mut scene = Scene::new();
+        assert!(scene.bounding_sphere().is_none());
+        scene.add_object(Arc::new(rrte_renderer::primitives::Plane::new(Vec3::ZERO, Vec3::Y)));
+        assert!(scene.bounding_sphere().is_none());
+
+        for x in [-2.0, 2.0] {
+            scene.add_sphere(Arc::new(Sphere::new(Vec3::new(x, 1.0, 0.0), 1.0)));
+        }
+        let (center, radius) = scene.bounding_sphere().unwrap();
+        assert!(radius >= 3.0, "{radius}");
+        for far_side in [Vec3::new(-3.0, 1.0, 0.0), Vec3::new(3.0, 1.0, 0.0)] {
+            assert!(center.distance(far_side) <= radius + 1e-4);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)] // Positions and colors are copied, so they come back bit-exact
+    fn instanced_light_uploads_one_entry_per_position() {
+        let mut scene = Scene::new();
+        scene.add_point_light(Arc::new(PointLight::new(Vec3::Y, Color::WHITE, 1.0)));
+        let positions: Vec<Vec3> = (0..5u8).map(|i| Vec3::new(f32::from(i), 3.0, 0.0)).collect();
+        let template = PointLight::new(Vec3::ZERO, Color::new(1.0, 0.5, 0.2, 1.0), 7.0);
+        scene.add_instanced_light(InstancedLight::new(template, positions.clone()));
+
+        let lights = scene.gpu_snapshot().lights;
+        assert_eq!(lights.len(), 6);
+        for (light, position) in lights[1..].iter().zip(&positions) {
+            assert_eq!(light.position[..3], position.to_array());
+            assert_eq!(light.color, [1.0, 0.5, 0.2, 1.0]);
+            assert!((light.intensity - 7.0).abs() < 1e-6);
+        }
+        // The CPU renderer sees every instance as a light too
+        assert_eq!(scene.get_lights().len(), 6);
+    }
+
+    #[test]
+    fn gpu_snapshot_of_two_spheres_dedupes_their_shared_material() {
+        use rrte_renderer::{LambertianMaterial, MetalMaterial};
+
+        let shared = LambertianMaterial::new(Color::RED);
+        let mut scene = Scene::new();
+        for x in [-1.0, 1.0] {
+            scene.add_sphere(Arc::new(Sphere::with_material(Vec3::new(x, 0.0, 0.0), 0.5, Arc::clone(&shared))));
+        }
+        let snapshot = scene.gpu_snapshot();
+        assert_eq!(snapshot.spheres.len(), 2);
+        // The default material plus the one both spheres share
+        assert_eq!(snapshot.materials.len(), 2);
+        assert_eq!(snapshot.spheres[0].material_index, snapshot.spheres[1].material_index);
+        assert!(snapshot.boxes.is_empty() && snapshot.lights.is_empty());
+
+        // A sphere with its own material adds exactly one more
+        let metal = MetalMaterial::new(Color::WHITE, 0.1);
+        scene.add_sphere(Arc::new(Sphere::with_material(Vec3::new(0.0, 2.0, 0.0), 0.5, metal)));
+        let snapshot = scene.gpu_snapshot();
+        assert_eq!(snapshot.spheres.len(), 3);
+        assert_eq!(snapshot.materials.len(), 3);
+    }
+
+    #[test]
+    fn clear_renderables_keeps_entities_but_clear_drops_them() {
+        let mut scene = Scene::new();
+        let entity = scene.create_entity();
+        scene.add_sphere(Arc::new(Sphere::new(Vec3::ZERO, 1.0)));
+        scene.add_object(Arc::new(rrte_renderer::primitives::Plane::new(Vec3::ZERO, Vec3::Y)));
+        scene.add_point_light(Arc::new(PointLight::new(Vec3::Y * 3.0, Color::WHITE, 5.0)));
+
+        scene.clear_renderables();
+        assert_eq!(scene.object_count(), 0);
+        assert!(scene.objects().is_empty());
+        assert!(scene.get_lights().is_empty() && scene.lights().is_empty());
+        assert!(scene.entity_manager().contains_entity(entity));
+        assert_eq!(scene.entity_manager().get_entities(), [entity]);
+
+        scene.clear();
+        assert!(!scene.entity_manager().contains_entity(entity));
+    }
+}
@@ -0,0 +1,196 @@
+//! Distance-based level-of-detail selection for scene objects.
+
+use rrte_math::{HitInfo, Ray, Transform, Vec3, AABB};
+use rrte_renderer::{make_object_mut, Material, MaterialRegistry, SceneObject, ShadowFlags};
+use std::any::Any;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// An object with several representations, picked by distance to the camera.
+///
+/// Levels are `(max_distance, object)` pairs ordered from most to least detailed.
+/// The active level is the first whose `max_distance` is at least the camera
+/// distance; cameras beyond every level use the last one. The distance is
+/// measured to the center of the most detailed level's bounding box.
+///
+/// The level is chosen by [`LodObject::select_level`], which
+/// [`crate::Scene::set_lod_camera`] calls for every LOD object in the scene.
+#[derive(Debug)]
+pub struct LodObject {
+    levels: Vec<(f32, Arc<dyn SceneObject>)>,
+    selected: AtomicUsize,
+}
+
+impl LodObject {
+    /// Create a LOD object, starting at the most detailed level.
+    ///
+    /// Levels are sorted by `max_distance`; returns `None` if `levels` is empty.
+    #[must_use]
+    pub fn new(mut levels: Vec<(f32, Arc<dyn SceneObject>)>) -> Option<Self> {
+        if levels.is_empty() {
+            return None;
+        }
+        levels.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Some(Self {
+            levels,
+            selected: AtomicUsize::new(0),
+        })
+    }
+
+    /// Get the levels, ordered from most to least detailed
+    pub fn levels(&self) -> &[(f32, Arc<dyn SceneObject>)] {
+        &self.levels
+    }
+
+    /// Get the index of the active level
+    pub fn selected_level(&self) -> usize {
+        self.selected.load(Ordering::Relaxed)
+    }
+
+    /// Get the active level's object
+    pub fn current(&self) -> &Arc<dyn SceneObject> {
+        &self.levels[self.selected_level()].1
+    }
+
+    /// Get the point distances are measured to
+    pub fn center(&self) -> Vec3 {
+        let detailed = &self.levels[0].1;
+        let bounds = detailed.bounding_box();
+        if bounds.is_unbounded() {
+            detailed.transform().position
+        } else {
+            bounds.center()
+        }
+    }
+
+    /// Choose the active level for a camera at `camera_position` and return its index
+    pub fn select_level(&self, camera_position: Vec3) -> usize {
+        let distance = camera_position.distance(self.center());
+        let level = self
+            .levels
+            .iter()
+            .position(|(max_distance, _)| distance <= *max_distance)
+            .unwrap_or(self.levels.len() - 1);
+        self.selected.store(level, Ordering::Relaxed);
+        level
+    }
+}
+
+impl SceneObject for LodObject {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitInfo> {
+        self.current().intersect(ray, t_min, t_max)
+    }
+
+    fn material(&self) -> Option<Arc<dyn Material>> {
+        self.current().material()
+    }
+
+    fn transform(&self) -> &Transform {
+        self.current().transform()
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        // Levels that cannot be copied when shared keep their transform
+        for (_, level) in &mut self.levels {
+            if let Some(level) = make_object_mut(level) {
+                level.set_transform(transform.clone());
+            }
+        }
+    }
+
+    fn clone_object(&self) -> Option<Arc<dyn SceneObject>> {
+        Some(Arc::new(Self {
+            levels: self.levels.clone(),
+            selected: AtomicUsize::new(self.selected_level()),
+        }))
+    }
+
+    fn shadow_flags(&self) -> ShadowFlags {
+        self.current().shadow_flags()
+    }
+
+    fn set_shadow_flags(&mut self, flags: ShadowFlags) {
+        for (_, level) in &mut self.levels {
+            if let Some(level) = make_object_mut(level) {
+                level.set_shadow_flags(flags);
+            }
+        }
+    }
+
+    fn has_stale_materials(&self, registry: &MaterialRegistry) -> bool {
+        self.levels.iter().any(|(_, level)| level.has_stale_materials(registry))
+    }
+
+    fn refresh_materials(&mut self, registry: &MaterialRegistry) -> bool {
+        let mut changed = false;
+        for (_, level) in &mut self.levels {
+            if level.has_stale_materials(registry) {
+                if let Some(level) = make_object_mut(level) {
+                    changed |= level.refresh_materials(registry);
+                }
+            }
+        }
+        changed
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.current().bounding_box()
+    }
+
+    fn overlaps_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.current().overlaps_sphere(center, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scene;
+    use rrte_renderer::primitives::{Cube, Sphere};
+
+    fn lod() -> LodObject {
+        let detailed: Arc<dyn SceneObject> = Arc::new(Sphere::new(Vec3::ZERO, 1.0));
+        let coarse: Arc<dyn SceneObject> = Arc::new(Cube::new(Vec3::ZERO, Vec3::splat(2.0)));
+        LodObject::new(vec![(50.0, coarse), (10.0, detailed)]).unwrap()
+    }
+
+    #[test]
+    fn near_camera_selects_detail_and_far_camera_the_coarse_level() {
+        let lod = lod();
+        assert_eq!(lod.select_level(Vec3::new(0.0, 0.0, 5.0)), 0);
+        assert!(lod.current().as_any().is::<Sphere>());
+
+        assert_eq!(lod.select_level(Vec3::new(0.0, 0.0, 30.0)), 1);
+        assert!(lod.current().as_any().is::<Cube>());
+
+        // Beyond every level the coarsest one stays active
+        assert_eq!(lod.select_level(Vec3::new(0.0, 0.0, 500.0)), 1);
+    }
+
+    #[test]
+    fn scene_lod_camera_updates_its_lod_objects() {
+        let mut scene = Scene::new();
+        scene.add_object(Arc::new(lod()));
+        let selected = |scene: &Scene, index: usize| {
+            scene.get_objects()[index]
+                .as_any()
+                .downcast_ref::<LodObject>()
+                .unwrap()
+                .selected_level()
+        };
+
+        scene.set_lod_camera(Vec3::new(0.0, 0.0, 40.0));
+        assert_eq!(selected(&scene, 0), 1);
+        scene.set_lod_camera(Vec3::new(0.0, 0.0, 2.0));
+        assert_eq!(selected(&scene, 0), 0);
+
+        // Objects added later pick their level from the last camera position
+        scene.set_lod_camera(Vec3::new(0.0, 0.0, 40.0));
+        scene.add_object(Arc::new(lod()));
+        assert_eq!(selected(&scene, 1), 1);
+    }
+}
@@ -0,0 +1,74 @@
+//! Per-object transforms from the last two scene updates, for velocities and
+//! motion blur.
+
+use crate::{ObjectId, Scene};
+use rrte_math::{Transform, Vec3};
+
+impl Scene {
+    /// Get an object's world-space velocity in units per second, from how far
+    /// its transform moved during the last [`Scene::update`], which advanced
+    /// the clock by `delta_time`.
+    ///
+    /// Zero until the object has been through two updates, and for a
+    /// non-positive `delta_time`.
+    #[must_use]
+    pub fn object_velocity(&self, id: ObjectId, delta_time: f32) -> Vec3 {
+        if delta_time <= 0.0 {
+            return Vec3::ZERO;
+        }
+        let Some(stable_id) = self.object_stable_id(id.index()) else {
+            return Vec3::ZERO;
+        };
+        match (self.previous_transforms.get(&stable_id), self.current_transforms.get(&stable_id)) {
+            (Some(previous), Some(current)) => (current.position - previous.position) / delta_time,
+            _ => Vec3::ZERO,
+        }
+    }
+
+    /// Get an object's transform as of the update before the last one, the
+    /// start of the motion blur shutter interval
+    #[must_use]
+    pub fn object_previous_transform(&self, id: ObjectId) -> Option<&Transform> {
+        let stable_id = self.object_stable_id(id.index())?;
+        self.previous_transforms.get(&stable_id)
+    }
+
+    /// Shift the recorded transforms back by one update and record every
+    /// object's current transform
+    pub(crate) fn record_transforms(&mut self) {
+        self.sync_stable_ids();
+        self.previous_transforms = std::mem::take(&mut self.current_transforms);
+        self.current_transforms = self
+            .object_ids
+            .iter()
+            .zip(&self.objects)
+            .map(|(&id, object)| (id, object.transform().clone()))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rrte_renderer::Sphere;
+    use std::sync::Arc;
+
+    #[test]
+    fn moving_two_units_in_half_a_second_is_four_units_per_second() {
+        let mut scene = Scene::new();
+        scene.add_object(Arc::new(Sphere::new(Vec3::ZERO, 1.0)));
+        let id = ObjectId::new(0);
+        scene.update(0.5);
+        assert_eq!(scene.object_velocity(id, 0.5), Vec3::ZERO, "one update has no motion yet");
+
+        scene.set_object_transform(id, Transform::from_position(Vec3::new(2.0, 0.0, 0.0)));
+        scene.update(0.5);
+        assert!(scene.object_velocity(id, 0.5).abs_diff_eq(Vec3::new(4.0, 0.0, 0.0), 1e-5));
+        assert_eq!(scene.object_previous_transform(id).unwrap().position, Vec3::ZERO);
+        assert_eq!(scene.object_velocity(id, 0.0), Vec3::ZERO);
+
+        // Standing still for the next update brings it to rest
+        scene.update(0.5);
+        assert_eq!(scene.object_velocity(id, 0.5), Vec3::ZERO);
+    }
+}
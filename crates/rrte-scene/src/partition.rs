@@ -0,0 +1,214 @@
+//! Static/dynamic partitioning of scene objects into two bounding volume
+//! hierarchies, so only the moving part is rebuilt every frame.
+
+use crate::{Scene, StableId};
+use rrte_math::{HitInfo, Ray};
+use rrte_renderer::{Bvh, SceneObject};
+use std::sync::Arc;
+
+/// A persistent hierarchy over a scene's static objects and a per-frame one
+/// over its dynamic objects, [layered](Bvh::layered) on it so both are queried
+/// together.
+///
+/// Kept up to date by [`Scene::update_bvh`]; the engine's CPU renderer renders
+/// through [`SceneBvh::hierarchy`].
+#[derive(Debug, Clone, Default)]
+pub struct SceneBvh {
+    static_bvh: Arc<Bvh>,
+    /// Dynamic objects layered on `static_bvh`
+    hierarchy: Bvh,
+    /// Static objects the static hierarchy was built from
+    static_objects: Vec<(StableId, Arc<dyn SceneObject>)>,
+    static_builds: u32,
+    dynamic_builds: u32,
+}
+
+impl SceneBvh {
+    /// Get the hierarchy over static objects
+    #[must_use]
+    pub const fn static_bvh(&self) -> &Arc<Bvh> {
+        &self.static_bvh
+    }
+
+    /// Get the hierarchy over every object: the dynamic objects layered on
+    /// the static hierarchy
+    #[must_use]
+    pub const fn hierarchy(&self) -> &Bvh {
+        &self.hierarchy
+    }
+
+    /// Get the number of times the static hierarchy was built
+    #[must_use]
+    pub const fn static_builds(&self) -> u32 {
+        self.static_builds
+    }
+
+    /// Get the number of times the dynamic hierarchy was built
+    #[must_use]
+    pub const fn dynamic_builds(&self) -> u32 {
+        self.dynamic_builds
+    }
+
+    /// Find the closest hit in either hierarchy and the object it hit
+    #[must_use]
+    pub fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<(HitInfo, &Arc<dyn SceneObject>)> {
+        self.hierarchy.hit(ray, t_min, t_max)
+    }
+
+    /// Rebuild the dynamic hierarchy, and the static one only if the static
+    /// objects were added, removed or replaced since it was built
+    fn update(&mut self, static_objects: Vec<(StableId, Arc<dyn SceneObject>)>, dynamic_objects: &[Arc<dyn SceneObject>]) {
+        let unchanged = static_objects.len() == self.static_objects.len()
+            && static_objects
+                .iter()
+                .zip(&self.static_objects)
+                .all(|((id, object), (built_id, built_object))| id == built_id && Arc::ptr_eq(object, built_object));
+        if !unchanged || self.static_builds == 0 {
+            let objects: Vec<Arc<dyn SceneObject>> = static_objects.iter().map(|(_, object)| Arc::clone(object)).collect();
+            self.static_bvh = Arc::new(Bvh::new(&objects));
+            self.static_objects = static_objects;
+            self.static_builds += 1;
+        }
+
+        self.hierarchy = Bvh::layered(Arc::clone(&self.static_bvh), dynamic_objects);
+        self.dynamic_builds += 1;
+    }
+}
+
+impl Scene {
+    /// Mark an object as dynamic (moving) or static.
+    ///
+    /// Objects are static by default. Static objects must not change in place;
+    /// replacing one in the object list, as [`Scene::set_object_transform`] does
+    /// while the hierarchy shares it, is detected and rebuilds the static
+    /// hierarchy. Animated objects are always treated as dynamic.
+    pub fn set_object_dynamic(&mut self, index: usize, dynamic: bool) {
+        self.sync_stable_ids();
+        let Some(id) = self.object_stable_id(index) else {
+            return;
+        };
+        if dynamic {
+            self.dynamic_objects.insert(id);
+        } else {
+            self.dynamic_objects.remove(&id);
+        }
+    }
+
+    /// Add an object that moves, see [`Scene::set_object_dynamic`]
+    pub fn add_dynamic_object(&mut self, object: Arc<dyn SceneObject>) {
+        self.add_object(object);
+        self.set_object_dynamic(self.objects.len() - 1, true);
+    }
+
+    /// Check whether an object is rebuilt into the dynamic hierarchy every frame
+    #[must_use]
+    pub fn is_object_dynamic(&self, index: usize) -> bool {
+        self.object_stable_id(index).is_some_and(|id| {
+            self.dynamic_objects.contains(&id) || self.animations.iter().any(|(animated, _)| *animated == id)
+        })
+    }
+
+    /// Rebuild the dynamic object hierarchy, reusing the static one unless the
+    /// static objects changed. Call once per frame before querying [`Scene::bvh`].
+    pub fn update_bvh(&mut self) -> &SceneBvh {
+        self.sync_stable_ids();
+        let mut static_objects = Vec::new();
+        let mut dynamic_objects = Vec::new();
+        for (index, object) in self.objects.iter().enumerate() {
+            if self.is_object_dynamic(index) {
+                dynamic_objects.push(Arc::clone(object));
+            } else {
+                static_objects.push((self.object_ids[index], Arc::clone(object)));
+            }
+        }
+        self.bvh.update(static_objects, &dynamic_objects);
+        &self.bvh
+    }
+
+    /// Get the hierarchies last built by [`Scene::update_bvh`]
+    #[must_use]
+    pub const fn bvh(&self) -> &SceneBvh {
+        &self.bvh
+    }
+
+    /// Build a single hierarchy over all of the scene's objects, for use
+    /// outside the renderer.
+    ///
+    /// Unlike [`Scene::update_bvh`] nothing is cached; the hierarchy shares the
+    /// objects and does not follow later changes to the scene.
+    #[must_use]
+    pub fn build_bvh(&self) -> Bvh {
+        Bvh::new(&self.objects)
+    }
+
+    /// Find the closest hit along a ray between `t_min` and `t_max` by testing
+    /// every object, and the object it hit
+    #[must_use]
+    pub fn intersect(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<(HitInfo, &Arc<dyn SceneObject>)> {
+        let mut closest: Option<(HitInfo, &Arc<dyn SceneObject>)> = None;
+        for object in &self.objects {
+            let closest_t = closest.as_ref().map_or(t_max, |(hit, _)| hit.t);
+            if let Some(hit) = object.intersect(ray, t_min, closest_t) {
+                closest = Some((hit, object));
+            }
+        }
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rrte_math::Vec3;
+    use rrte_renderer::{Plane, Sphere};
+
+    fn sphere_at(x: f32) -> Arc<dyn SceneObject> {
+        Arc::new(Sphere::new(Vec3::new(x, 0.0, -5.0), 0.5))
+    }
+
+    #[test]
+    fn adding_a_dynamic_object_rebuilds_only_the_dynamic_hierarchy() {
+        let mut scene = Scene::new();
+        scene.add_object(sphere_at(-2.0));
+        scene.add_object(sphere_at(0.0));
+        scene.update_bvh();
+        assert_eq!((scene.bvh().static_builds(), scene.bvh().dynamic_builds()), (1, 1));
+        let static_bounds = scene.bvh().static_bvh().bounds();
+
+        scene.add_dynamic_object(sphere_at(2.0));
+        let bvh = scene.update_bvh();
+        assert_eq!(bvh.static_builds(), 1, "static hierarchy is reused");
+        assert_eq!(bvh.dynamic_builds(), 2);
+        assert_eq!(bvh.static_bvh().len(), 2);
+        assert_eq!(bvh.hierarchy().len(), 3);
+        assert!(Arc::ptr_eq(bvh.hierarchy().base().unwrap(), bvh.static_bvh()));
+        assert_eq!(bvh.static_bvh().bounds(), static_bounds);
+
+        // Both hierarchies are queried together
+        let ray = Ray::new(Vec3::new(2.0, 0.0, 0.0), Vec3::NEG_Z);
+        assert!(bvh.hit(&ray, 0.001, f32::INFINITY).is_some());
+
+        // A new static object does rebuild the static hierarchy
+        scene.add_object(sphere_at(4.0));
+        assert_eq!(scene.update_bvh().static_builds(), 2);
+    }
+
+    #[test]
+    fn exported_bvh_raycast_matches_scene_intersect() {
+        let mut scene = Scene::new();
+        for x in [-2.0, 0.0, 2.0] {
+            scene.add_object(sphere_at(x));
+        }
+        scene.add_object(Arc::new(Sphere::new(Vec3::new(0.0, 0.0, -8.0), 2.0)));
+        scene.add_object(Arc::new(Plane::new(Vec3::new(0.0, -1.0, 0.0), Vec3::Y)));
+        let bvh = scene.build_bvh();
+
+        let origin = Vec3::new(0.0, 0.5, 2.0);
+        for direction in [Vec3::NEG_Z, Vec3::new(-0.3, -0.05, -1.0), Vec3::new(0.3, -0.05, -1.0), Vec3::new(0.1, -1.0, -0.2), Vec3::Y] {
+            let ray = Ray::new(origin, direction.normalize());
+            let expected = scene.intersect(&ray, 0.001, f32::INFINITY).map(|(hit, _)| (hit.t, hit.point));
+            let exported = bvh.raycast(&ray, 0.001, f32::INFINITY).map(|hit| (hit.t, hit.point));
+            assert_eq!(exported, expected, "along {direction:?}");
+        }
+    }
+}
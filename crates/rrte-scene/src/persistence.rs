@@ -0,0 +1,622 @@
+//! RON save/load support for [`Scene`].
+//!
+//! Objects, materials and lights are stored as plain records that mirror the
+//! renderer's concrete types. Anything that cannot be represented is listed in
+//! [`SceneFile::warnings`] instead of being dropped silently.
+
+use crate::{Scene, SceneConfig};
+use anyhow::{Context, Result};
+use rrte_math::{Color, Transform, Vec3};
+use rrte_renderer::{
+    AmbientLight, AreaLight, Camera, Capsule, CoatedMaterial, Cone, Cube, Cylinder, DielectricMaterial,
+    DirectionalLight, EmissiveMaterial, LambertianMaterial, Light, Material, MetalMaterial, Plane,
+    PointLight, RegisteredMaterial, SceneObject, ShadowFlags, Sphere, SpotLight, Triangle,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Serialized form of a material
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MaterialRecord {
+    /// [`LambertianMaterial`]
+    Lambertian {
+        /// Diffuse color
+        albedo: Color,
+        /// Whether the surface is a thin translucent sheet
+        #[serde(default)]
+        thin: bool,
+        /// Share of light scattered through a thin surface
+        #[serde(default)]
+        translucency: f32,
+    },
+    /// [`MetalMaterial`]
+    Metal {
+        /// Reflective tint
+        albedo: Color,
+        /// Reflection blur in `[0, 1]`
+        roughness: f32,
+    },
+    /// [`DielectricMaterial`]
+    Dielectric {
+        /// Index of refraction
+        ior: f32,
+        /// Transmission tint
+        color: Color,
+        /// Per-channel indices of refraction of a dispersive dielectric
+        #[serde(default)]
+        channel_iors: Option<[f32; 3]>,
+    },
+    /// [`CoatedMaterial`]
+    Coated {
+        /// Material under the clearcoat
+        base: Box<Self>,
+        /// Index of refraction of the coat
+        coat_ior: f32,
+        /// Reflection blur of the coat in `[0, 1]`
+        coat_roughness: f32,
+    },
+    /// [`EmissiveMaterial`]
+    Emissive {
+        /// Emitted color
+        color: Color,
+        /// Emission strength
+        intensity: f32,
+    },
+}
+
+impl MaterialRecord {
+    /// Describe a material, or `None` if its type is not supported.
+    ///
+    /// Registered materials are saved as the material they currently resolve to.
+    pub fn from_material(material: &dyn Material) -> Option<Self> {
+        let any = material.as_any();
+        if let Some(m) = any.downcast_ref::<RegisteredMaterial>() {
+            Self::from_material(m.resolve().as_ref())
+        } else if let Some(m) = any.downcast_ref::<LambertianMaterial>() {
+            Some(Self::Lambertian { albedo: m.albedo, thin: m.thin, translucency: m.translucency })
+        } else if let Some(m) = any.downcast_ref::<MetalMaterial>() {
+            Some(Self::Metal { albedo: m.albedo, roughness: m.roughness })
+        } else if let Some(m) = any.downcast_ref::<DielectricMaterial>() {
+            Some(Self::Dielectric { ior: m.ior, color: m.color, channel_iors: m.channel_iors })
+        } else if let Some(m) = any.downcast_ref::<CoatedMaterial>() {
+            Some(Self::Coated {
+                base: Box::new(Self::from_material(m.base.as_ref())?),
+                coat_ior: m.coat_ior,
+                coat_roughness: m.coat_roughness,
+            })
+        } else {
+            any.downcast_ref::<EmissiveMaterial>()
+                .map(|m| Self::Emissive { color: m.color, intensity: m.intensity })
+        }
+    }
+
+    /// Build the material described by this record
+    #[must_use]
+    pub fn to_material(&self) -> Arc<dyn Material> {
+        match self {
+            Self::Lambertian { albedo, thin: false, .. } => LambertianMaterial::new(*albedo),
+            Self::Lambertian { albedo, thin: true, translucency } => LambertianMaterial::thin(*albedo, *translucency),
+            Self::Metal { albedo, roughness } => MetalMaterial::new(*albedo, *roughness),
+            Self::Dielectric { ior, color, channel_iors: None } => DielectricMaterial::with_color(*ior, *color),
+            Self::Dielectric { color, channel_iors: Some(iors), .. } => DielectricMaterial::dispersive(*iors, *color),
+            Self::Coated { base, coat_ior, coat_roughness } => {
+                CoatedMaterial::new(base.to_material(), *coat_ior, *coat_roughness)
+            }
+            Self::Emissive { color, intensity } => EmissiveMaterial::new(*color, *intensity),
+        }
+    }
+}
+
+/// Serialized form of a scene object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ObjectRecord {
+    /// [`Sphere`]
+    Sphere {
+        /// Sphere center
+        center: Vec3,
+        /// Sphere radius
+        radius: f32,
+        /// Surface material
+        material: Option<MaterialRecord>,
+        /// Object transform
+        transform: Transform,
+        /// Whether the object casts and receives shadows
+        #[serde(default)]
+        shadow_flags: ShadowFlags,
+    },
+    /// [`Plane`]
+    Plane {
+        /// A point on the plane
+        point: Vec3,
+        /// Plane normal
+        normal: Vec3,
+        /// Surface material
+        material: Option<MaterialRecord>,
+        /// Object transform
+        transform: Transform,
+        /// Whether the object casts and receives shadows
+        #[serde(default)]
+        shadow_flags: ShadowFlags,
+    },
+    /// [`Triangle`]
+    Triangle {
+        /// Corner positions
+        vertices: [Vec3; 3],
+        /// Per-vertex normals
+        normals: [Vec3; 3],
+        /// Per-vertex texture coordinates
+        uvs: [Vec3; 3],
+        /// Surface material
+        material: Option<MaterialRecord>,
+        /// Object transform
+        transform: Transform,
+        /// Whether the object casts and receives shadows
+        #[serde(default)]
+        shadow_flags: ShadowFlags,
+    },
+    /// [`Cube`]
+    Cube {
+        /// Box center
+        center: Vec3,
+        /// Box size along each axis
+        size: Vec3,
+        /// Surface material
+        material: Option<MaterialRecord>,
+        /// Object transform
+        transform: Transform,
+        /// Whether the object casts and receives shadows
+        #[serde(default)]
+        shadow_flags: ShadowFlags,
+    },
+    /// [`Cylinder`]
+    Cylinder {
+        /// Cylinder center
+        center: Vec3,
+        /// Cylinder radius
+        radius: f32,
+        /// Cylinder height
+        height: f32,
+        /// Surface material
+        material: Option<MaterialRecord>,
+        /// Object transform
+        transform: Transform,
+        /// Whether the object casts and receives shadows
+        #[serde(default)]
+        shadow_flags: ShadowFlags,
+    },
+    /// [`Cone`]
+    Cone {
+        /// Cone center
+        center: Vec3,
+        /// Base radius
+        radius: f32,
+        /// Cone height
+        height: f32,
+        /// Surface material
+        material: Option<MaterialRecord>,
+        /// Object transform
+        transform: Transform,
+        /// Whether the object casts and receives shadows
+        #[serde(default)]
+        shadow_flags: ShadowFlags,
+    },
+    /// [`Capsule`]
+    Capsule {
+        /// Capsule center
+        center: Vec3,
+        /// Capsule radius
+        radius: f32,
+        /// Height of the cylindrical section
+        height: f32,
+        /// Surface material
+        material: Option<MaterialRecord>,
+        /// Object transform
+        transform: Transform,
+        /// Whether the object casts and receives shadows
+        #[serde(default)]
+        shadow_flags: ShadowFlags,
+    },
+}
+
+/// Serialized form of a light
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LightRecord {
+    /// [`DirectionalLight`]
+    Directional(DirectionalLight),
+    /// [`PointLight`]
+    Point(PointLight),
+    /// [`SpotLight`] (cookie textures are not stored)
+    Spot(SpotLight),
+    /// [`AmbientLight`]
+    Ambient(AmbientLight),
+    /// [`AreaLight`]
+    Area(AreaLight),
+}
+
+impl ObjectRecord {
+    /// Describe an object, or `None` if its type is not supported
+    pub fn from_object(object: &dyn SceneObject) -> Option<Self> {
+        Self::describe(object, "object", &mut Vec::new())
+    }
+
+    /// Describe an object, noting unsupported materials in `warnings`
+    fn describe(object: &dyn SceneObject, owner: &str, warnings: &mut Vec<String>) -> Option<Self> {
+        let any = object.as_any();
+        let record = if let Some(o) = any.downcast_ref::<Sphere>() {
+            Self::Sphere {
+                center: o.center,
+                radius: o.radius,
+                material: material_record(o.material.as_ref(), owner, warnings),
+                transform: o.transform.clone(),
+                shadow_flags: o.shadow_flags,
+            }
+        } else if let Some(o) = any.downcast_ref::<Plane>() {
+            Self::Plane {
+                point: o.point,
+                normal: o.normal,
+                material: material_record(o.material.as_ref(), owner, warnings),
+                transform: o.transform.clone(),
+                shadow_flags: o.shadow_flags,
+            }
+        } else if let Some(o) = any.downcast_ref::<Triangle>() {
+            Self::Triangle {
+                vertices: o.vertices,
+                normals: o.normals,
+                uvs: o.uvs,
+                material: material_record(o.material.as_ref(), owner, warnings),
+                transform: o.transform.clone(),
+                shadow_flags: o.shadow_flags,
+            }
+        } else if let Some(o) = any.downcast_ref::<Cube>() {
+            Self::Cube {
+                center: o.center,
+                size: o.size,
+                material: material_record(o.material.as_ref(), owner, warnings),
+                transform: o.transform.clone(),
+                shadow_flags: o.shadow_flags,
+            }
+        } else if let Some(o) = any.downcast_ref::<Cylinder>() {
+            Self::Cylinder {
+                center: o.center,
+                radius: o.radius,
+                height: o.height,
+                material: material_record(o.material.as_ref(), owner, warnings),
+                transform: o.transform.clone(),
+                shadow_flags: o.shadow_flags,
+            }
+        } else if let Some(o) = any.downcast_ref::<Cone>() {
+            Self::Cone {
+                center: o.center,
+                radius: o.radius,
+                height: o.height,
+                material: material_record(o.material.as_ref(), owner, warnings),
+                transform: o.transform.clone(),
+                shadow_flags: o.shadow_flags,
+            }
+        } else {
+            let o = any.downcast_ref::<Capsule>()?;
+            Self::Capsule {
+                center: o.center,
+                radius: o.radius,
+                height: o.height,
+                material: material_record(o.material.as_ref(), owner, warnings),
+                transform: o.transform.clone(),
+                shadow_flags: o.shadow_flags,
+            }
+        };
+        Some(record)
+    }
+}
+
+impl LightRecord {
+    /// Describe a light, or `None` if its type is not supported
+    pub fn from_light(light: &dyn Light) -> Option<Self> {
+        let any = light.as_any();
+        if let Some(l) = any.downcast_ref::<DirectionalLight>() {
+            Some(Self::Directional(l.clone()))
+        } else if let Some(l) = any.downcast_ref::<PointLight>() {
+            Some(Self::Point(l.clone()))
+        } else if let Some(l) = any.downcast_ref::<SpotLight>() {
+            Some(Self::Spot(l.clone()))
+        } else if let Some(l) = any.downcast_ref::<AmbientLight>() {
+            Some(Self::Ambient(l.clone()))
+        } else {
+            any.downcast_ref::<AreaLight>().map(|l| Self::Area(l.clone()))
+        }
+    }
+}
+
+/// On-disk representation of a [`Scene`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneFile {
+    /// Scene configuration
+    pub config: SceneConfig,
+    /// Scene objects
+    pub objects: Vec<ObjectRecord>,
+    /// Scene-level materials
+    pub materials: Vec<MaterialRecord>,
+    /// Scene lights
+    pub lights: Vec<LightRecord>,
+    /// Camera saved alongside the scene, if any
+    pub camera: Option<Camera>,
+    /// Content that could not be saved, one entry per skipped or degraded item
+    pub warnings: Vec<String>,
+}
+
+/// Describe an object's material, noting unsupported materials in `warnings`
+fn material_record(
+    material: Option<&Arc<dyn Material>>,
+    owner: &str,
+    warnings: &mut Vec<String>,
+) -> Option<MaterialRecord> {
+    let material = material?;
+    let record = MaterialRecord::from_material(material.as_ref());
+    if record.is_none() {
+        warnings.push(format!("{owner}: unsupported material {material:?} was not saved"));
+    }
+    record
+}
+
+impl Scene {
+    /// Describe the scene as a [`SceneFile`].
+    ///
+    /// ECS entities, animation tracks and objects, materials or lights of
+    /// unsupported types are not stored; the latter are noted in `warnings`.
+    #[must_use]
+    pub fn to_scene_file(&self, camera: Option<&Camera>) -> SceneFile {
+        let mut warnings = Vec::new();
+
+        let mut objects = Vec::with_capacity(self.objects.len());
+        for (index, object) in self.objects.iter().enumerate() {
+            let owner = format!("object {index}");
+            match ObjectRecord::describe(object.as_ref(), &owner, &mut warnings) {
+                Some(record) => objects.push(record),
+                None => warnings.push(format!("{owner}: unsupported object {object:?} was not saved")),
+            }
+        }
+
+        let materials = self
+            .materials
+            .iter()
+            .enumerate()
+            .filter_map(|(index, material)| {
+                material_record(Some(material), &format!("material {index}"), &mut warnings)
+            })
+            .collect();
+
+        let mut lights = Vec::with_capacity(self.lights.len());
+        for (index, light) in self.lights.iter().enumerate() {
+            if light.as_any().downcast_ref::<SpotLight>().is_some_and(|l| l.cookie.is_some()) {
+                warnings.push(format!("light {index}: spot light cookie texture was not saved"));
+            }
+            match LightRecord::from_light(light.as_ref()) {
+                Some(record) => lights.push(record),
+                None => warnings.push(format!("light {index}: unsupported light {light:?} was not saved")),
+            }
+        }
+
+        SceneFile {
+            config: self.config.clone(),
+            objects,
+            materials,
+            lights,
+            camera: camera.cloned(),
+            warnings,
+        }
+    }
+
+    /// Build a scene from a [`SceneFile`]
+    #[must_use]
+    pub fn from_scene_file(file: &SceneFile) -> Self {
+        let mut scene = Self::with_config(file.config.clone());
+        let material = |record: &Option<MaterialRecord>| record.as_ref().map(MaterialRecord::to_material);
+
+        for record in &file.objects {
+            let object: Arc<dyn SceneObject> = match record {
+                ObjectRecord::Sphere { center, radius, material: m, transform, shadow_flags } => {
+                    scene.add_sphere(Arc::new(Sphere {
+                        center: *center,
+                        radius: *radius,
+                        material: material(m),
+                        transform: transform.clone(),
+                        shadow_flags: *shadow_flags,
+                    }));
+                    continue;
+                }
+                ObjectRecord::Plane { point, normal, material: m, transform, shadow_flags } => Arc::new(Plane {
+                    point: *point,
+                    normal: *normal,
+                    material: material(m),
+                    transform: transform.clone(),
+                    shadow_flags: *shadow_flags,
+                }),
+                ObjectRecord::Triangle { vertices, normals, uvs, material: m, transform, shadow_flags } => {
+                    Arc::new(Triangle {
+                        vertices: *vertices,
+                        normals: *normals,
+                        uvs: *uvs,
+                        material: material(m),
+                        transform: transform.clone(),
+                        shadow_flags: *shadow_flags,
+                    })
+                }
+                ObjectRecord::Cube { center, size, material: m, transform, shadow_flags } => Arc::new(Cube {
+                    center: *center,
+                    size: *size,
+                    material: material(m),
+                    transform: transform.clone(),
+                    shadow_flags: *shadow_flags,
+                }),
+                ObjectRecord::Cylinder { center, radius, height, material: m, transform, shadow_flags } => {
+                    Arc::new(Cylinder {
+                        center: *center,
+                        radius: *radius,
+                        height: *height,
+                        material: material(m),
+                        transform: transform.clone(),
+                        shadow_flags: *shadow_flags,
+                    })
+                }
+                ObjectRecord::Cone { center, radius, height, material: m, transform, shadow_flags } => {
+                    Arc::new(Cone {
+                        center: *center,
+                        radius: *radius,
+                        height: *height,
+                        material: material(m),
+                        transform: transform.clone(),
+                        shadow_flags: *shadow_flags,
+                    })
+                }
+                ObjectRecord::Capsule { center, radius, height, material: m, transform, shadow_flags } => {
+                    Arc::new(Capsule {
+                        center: *center,
+                        radius: *radius,
+                        height: *height,
+                        material: material(m),
+                        transform: transform.clone(),
+                        shadow_flags: *shadow_flags,
+                    })
+                }
+            };
+            scene.add_object(object);
+        }
+
+        for record in &file.materials {
+            scene.add_material(record.to_material());
+        }
+
+        for record in &file.lights {
+            let light: Arc<dyn Light> = match record {
+                LightRecord::Point(light) => {
+                    scene.add_point_light(Arc::new(light.clone()));
+                    continue;
+                }
+                LightRecord::Directional(light) => Arc::new(light.clone()),
+                LightRecord::Spot(light) => Arc::new(light.clone()),
+                LightRecord::Ambient(light) => Arc::new(light.clone()),
+                LightRecord::Area(light) => Arc::new(light.clone()),
+            };
+            scene.add_light(light);
+        }
+
+        scene
+    }
+
+    /// Save the scene to a RON file
+    ///
+    /// # Errors
+    ///
+    /// Fails if the scene cannot be serialized or the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.save_with_camera(path, None)
+    }
+
+    /// Save the scene and a camera to a RON file
+    ///
+    /// # Errors
+    ///
+    /// Fails if the scene cannot be serialized or the file cannot be written.
+    pub fn save_with_camera(&self, path: impl AsRef<Path>, camera: Option<&Camera>) -> Result<()> {
+        let path = path.as_ref();
+        let file = self.to_scene_file(camera);
+        let text = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .context("Failed to serialize scene")?;
+        std::fs::write(path, text)
+            .with_context(|| format!("Failed to write scene to {}", path.display()))
+    }
+
+    /// Load a scene from a RON file
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::load_with_camera(path)?.0)
+    }
+
+    /// Load a scene and its saved camera, if any, from a RON file
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or parsed.
+    pub fn load_with_camera(path: impl AsRef<Path>) -> Result<(Self, Option<Camera>)> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scene from {}", path.display()))?;
+        let file: SceneFile = ron::from_str(&text)
+            .with_context(|| format!("Failed to parse scene file {}", path.display()))?;
+        Ok((Self::from_scene_file(&file), file.camera))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_scene_loads_back_with_the_same_contents() {
+        let mut scene = Scene::new();
+        let mut sphere = Sphere::new(Vec3::new(1.0, 2.0, 3.0), 0.5);
+        sphere.set_material(MetalMaterial::new(Color::RED, 0.25));
+        scene.add_sphere(Arc::new(sphere));
+        let mut cube = Cube::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 3.0));
+        cube.set_material(LambertianMaterial::new(Color::BLUE));
+        scene.add_object(Arc::new(cube));
+        let light = PointLight::new(Vec3::new(0.0, 5.0, 0.0), Color::WHITE, 7.0);
+        scene.add_point_light(Arc::new(light));
+        let mut camera = Camera::new_perspective(1.0, 1.5, 0.1, 100.0);
+        camera.transform.position = Vec3::new(0.0, 1.0, 8.0);
+
+        let name = format!("rrte-scene-roundtrip-{}.ron", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        scene.save_with_camera(&path, Some(&camera)).unwrap();
+        let loaded = Scene::load_with_camera(&path);
+        std::fs::remove_file(&path).unwrap();
+        let (loaded, loaded_camera) = loaded.unwrap();
+
+        assert_eq!(loaded.object_count(), 2);
+        assert_eq!(loaded.light_count(), 1);
+        let objects = loaded.get_objects();
+        let sphere = objects[0].as_any().downcast_ref::<Sphere>().unwrap();
+        assert_eq!((sphere.center, sphere.radius), (Vec3::new(1.0, 2.0, 3.0), 0.5));
+        let material = sphere.material.as_ref().unwrap();
+        let metal = material.as_any().downcast_ref::<MetalMaterial>().unwrap();
+        assert_eq!((metal.albedo, metal.roughness), (Color::RED, 0.25));
+        let cube = objects[1].as_any().downcast_ref::<Cube>().unwrap();
+        assert_eq!(cube.center, Vec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(cube.size, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(cube.material.as_ref().unwrap().albedo(), Color::BLUE);
+        let light = &loaded.lights()[0];
+        assert_eq!((light.position, light.intensity), (Vec3::new(0.0, 5.0, 0.0), 7.0));
+
+        let loaded_camera = loaded_camera.unwrap();
+        assert_eq!(loaded_camera.transform, camera.transform);
+        assert_eq!(loaded_camera.projection, camera.projection);
+    }
+
+    #[test]
+    fn shadow_flags_survive_a_save_and_load() {
+        let mut scene = Scene::new();
+        let mut sphere = Sphere::new(Vec3::ZERO, 1.0);
+        sphere.shadow_flags = ShadowFlags { casts_shadow: false, receives_shadow: true };
+        scene.add_sphere(Arc::new(sphere));
+        let mut plane = Plane::new(Vec3::ZERO, Vec3::Y);
+        plane.shadow_flags = ShadowFlags { casts_shadow: true, receives_shadow: false };
+        scene.add_object(Arc::new(plane));
+
+        let text = ron::to_string(&scene.to_scene_file(None)).unwrap();
+        let loaded = Scene::from_scene_file(&ron::from_str(&text).unwrap());
+        let flags: Vec<ShadowFlags> = loaded.get_objects().iter().map(|object| object.shadow_flags()).collect();
+        let saved: Vec<ShadowFlags> = scene.get_objects().iter().map(|object| object.shadow_flags()).collect();
+        assert_eq!(flags, saved);
+        assert!(scene.to_scene_file(None).warnings.is_empty());
+
+        // Files written before the flags were saved load with the defaults
+        let record: ObjectRecord = ron::from_str(
+            "Sphere(center: (0.0, 0.0, 0.0), radius: 1.0, material: None, transform: (position: (0.0, 0.0, 0.0), rotation: (0.0, 0.0, 0.0, 1.0), scale: (1.0, 1.0, 1.0)))",
+        )
+        .unwrap();
+        assert!(matches!(record, ObjectRecord::Sphere { shadow_flags, .. } if shadow_flags == ShadowFlags::default()));
+    }
+}
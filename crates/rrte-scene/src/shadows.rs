@@ -0,0 +1,62 @@
+//! Per-object flags excluding objects from casting or receiving shadows.
+
+use crate::Scene;
+use rrte_renderer::ShadowFlags;
+
+impl Scene {
+    /// Set whether an object casts and receives shadows.
+    ///
+    /// The flags are stored on the object, copying it first if its `Arc` is
+    /// shared, as for [`Scene::set_object_transform`].
+    pub fn set_object_shadow_flags(&mut self, index: usize, flags: ShadowFlags) {
+        self.modify_object(index, |object| object.set_shadow_flags(flags));
+    }
+
+    /// Set whether an object blocks light from reaching other surfaces
+    pub fn set_object_casts_shadow(&mut self, index: usize, casts_shadow: bool) {
+        let flags = ShadowFlags { casts_shadow, ..self.object_shadow_flags(index) };
+        self.set_object_shadow_flags(index, flags);
+    }
+
+    /// Set whether light reaching an object is tested for blockers
+    pub fn set_object_receives_shadow(&mut self, index: usize, receives_shadow: bool) {
+        let flags = ShadowFlags { receives_shadow, ..self.object_shadow_flags(index) };
+        self.set_object_shadow_flags(index, flags);
+    }
+
+    /// Get whether an object casts and receives shadows; both by default
+    #[must_use]
+    pub fn object_shadow_flags(&self, index: usize) -> ShadowFlags {
+        self.objects
+            .get(index)
+            .map(|object| object.shadow_flags())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rrte_math::Vec3;
+    use rrte_renderer::{SceneObject, Sphere};
+    use std::sync::Arc;
+
+    #[test]
+    fn shadow_flags_are_set_per_object_and_uploaded() {
+        let mut scene = Scene::new();
+        let shared = Arc::new(Sphere::new(Vec3::ZERO, 1.0));
+        scene.add_sphere(Arc::clone(&shared));
+        scene.add_sphere(Arc::new(Sphere::new(Vec3::X * 3.0, 1.0)));
+
+        scene.set_object_casts_shadow(0, false);
+        scene.set_object_receives_shadow(1, false);
+        assert_eq!(scene.object_shadow_flags(0), ShadowFlags { casts_shadow: false, receives_shadow: true });
+        assert_eq!(scene.object_shadow_flags(1), ShadowFlags { casts_shadow: true, receives_shadow: false });
+        // The caller's copy of the shared sphere is left alone
+        assert_eq!(shared.shadow_flags(), ShadowFlags::default());
+
+        let spheres = scene.gpu_snapshot().spheres;
+        assert_eq!(spheres[0].shadow_flags, ShadowFlags::RECEIVES_SHADOW);
+        assert_eq!(spheres[1].shadow_flags, ShadowFlags::CASTS_SHADOW);
+    }
+}
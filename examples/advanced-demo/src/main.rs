@@ -80,13 +80,13 @@ async fn main() -> Result<()> {
           rrte_engine::features::DEFORMATION_SUPPORT);
     
     // Configure the engine for the advanced demo
-    let raytracer_config = RaytracerConfig {
-        max_depth: 50,
-        samples_per_pixel: 10,
-        width: 1200,
-        height: 800,
-        background_color: Color::new(0.05, 0.05, 0.1, 1.0), // Dark background
-    };
+    let raytracer_config = RaytracerConfig::builder()
+        .max_depth(50)
+        .samples(10)
+        .width(1200)
+        .height(800)
+        .background_color(Color::new(0.05, 0.05, 0.1, 1.0)) // Dark background
+        .build()?;
 
     let gpu_renderer_config = GpuRendererConfig {
         width: raytracer_config.width,
@@ -95,14 +95,11 @@ async fn main() -> Result<()> {
         ..Default::default()
     };
 
-    let engine_config = EngineConfig {
-        renderer_mode: RendererMode::Gpu,
-        renderer_config: raytracer_config,
-        gpu_renderer_config,
-        target_fps: 60.0,
-        enable_vsync: true,
-        log_level: log::LevelFilter::Info,
-    };
+    let engine_config = EngineConfig::builder()
+        .renderer_mode(RendererMode::Gpu)
+        .renderer_config(raytracer_config)
+        .gpu_renderer_config(gpu_renderer_config)
+        .build()?;
 
     // Create and initialize the engine
     let mut engine = Engine::new(engine_config)?;
@@ -196,7 +193,7 @@ async fn main() -> Result<()> {
                 // Update engine systems
                 let dt = engine.time().delta_time();
                 engine.time_mut().update();
-                engine.input_mut().update();
+                engine.update_input();
                 engine.scene_mut().update(dt);
 
                 // Update scene animation (orbiting camera and cycling lights)
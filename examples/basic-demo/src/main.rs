@@ -22,13 +22,13 @@ async fn main() -> Result<()> {
     info!("Engine: {} v{}", rrte_engine::ENGINE_NAME, rrte_engine::VERSION);
     
     // Configure the engine
-    let raytracer_config = RaytracerConfig {
-        max_depth: 50,
-        samples_per_pixel: 10,
-        width: 800,
-        height: 600,
-        background_color: Color::new(0.5, 0.7, 1.0, 1.0),
-    };
+    let raytracer_config = RaytracerConfig::builder()
+        .max_depth(50)
+        .samples(10)
+        .width(800)
+        .height(600)
+        .background_color(Color::new(0.5, 0.7, 1.0, 1.0))
+        .build()?;
 
     let gpu_renderer_config = GpuRendererConfig {
         width: raytracer_config.width,
@@ -37,14 +37,11 @@ async fn main() -> Result<()> {
         ..Default::default()
     };
 
-    let engine_config = EngineConfig {
-        renderer_mode: RendererMode::Gpu,
-        renderer_config: raytracer_config,
-        gpu_renderer_config,
-        target_fps: 60.0,
-        enable_vsync: true,
-        log_level: log::LevelFilter::Info,
-    };
+    let engine_config = EngineConfig::builder()
+        .renderer_mode(RendererMode::Gpu)
+        .renderer_config(raytracer_config)
+        .gpu_renderer_config(gpu_renderer_config)
+        .build()?;
 
     // Create and initialize the engine
     let mut engine = Engine::new(engine_config)?;
@@ -138,7 +135,7 @@ async fn main() -> Result<()> {
                 // Update engine systems
                 let dt = engine.time().delta_time();
                 engine.time_mut().update();
-                engine.input_mut().update();
+                engine.update_input();
                 engine.scene_mut().update(dt);
 
                 // Render frame
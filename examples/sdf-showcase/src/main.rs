@@ -23,13 +23,13 @@ async fn main() -> Result<()> {
     info!("Starting RRTE Engine Primitive Showcase");
     
     // Configure the engine
-    let raytracer_config = RaytracerConfig {
-        max_depth: 50,
-        samples_per_pixel: 4,
-        width: 1200,
-        height: 800,
-        background_color: Color::new(0.05, 0.05, 0.08, 1.0), // Much darker background
-    };
+    let raytracer_config = RaytracerConfig::builder()
+        .max_depth(50)
+        .samples(4)
+        .width(1200)
+        .height(800)
+        .background_color(Color::new(0.05, 0.05, 0.08, 1.0)) // Much darker background
+        .build()?;
     
     let gpu_renderer_config = GpuRendererConfig {
         width: raytracer_config.width,
@@ -38,14 +38,11 @@ async fn main() -> Result<()> {
         ..Default::default()
     };
     
-    let engine_config = EngineConfig {
-        renderer_mode: RendererMode::Gpu, // Use GPU for better performance
-        renderer_config: raytracer_config,
-        gpu_renderer_config,
-        target_fps: 60.0,
-        enable_vsync: true,
-        log_level: log::LevelFilter::Info,
-    };
+    let engine_config = EngineConfig::builder()
+        .renderer_mode(RendererMode::Gpu) // Use GPU for better performance
+        .renderer_config(raytracer_config)
+        .gpu_renderer_config(gpu_renderer_config)
+        .build()?;
     
     // Create and initialize engine
     let mut engine = Engine::new(engine_config)?;
@@ -132,7 +129,7 @@ async fn main() -> Result<()> {
                 // Update engine systems
                 let dt = engine.time().delta_time();
                 engine.time_mut().update();
-                engine.input_mut().update();
+                engine.update_input();
                 engine.scene_mut().update(dt);
 
                 // Render frame
@@ -24,13 +24,13 @@ async fn main() -> Result<()> {
     info!("Starting Simple RRTE Demo");
     
     // Configure the engine
-    let raytracer_config = RaytracerConfig {
-        max_depth: 10,
-        samples_per_pixel: 2,
-        width: 800,
-        height: 600,
-        background_color: Color::new(0.2, 0.3, 0.4, 1.0), // Nice blue-gray background
-    };
+    let raytracer_config = RaytracerConfig::builder()
+        .max_depth(10)
+        .samples(2)
+        .width(800)
+        .height(600)
+        .background_color(Color::new(0.2, 0.3, 0.4, 1.0)) // Nice blue-gray background
+        .build()?;
     
     let gpu_renderer_config = GpuRendererConfig {
         width: raytracer_config.width,
@@ -39,14 +39,11 @@ async fn main() -> Result<()> {
         ..Default::default()
     };
     
-    let engine_config = EngineConfig {
-        renderer_mode: RendererMode::Gpu,
-        renderer_config: raytracer_config,
-        gpu_renderer_config,
-        target_fps: 60.0,
-        enable_vsync: true,
-        log_level: log::LevelFilter::Info,
-    };
+    let engine_config = EngineConfig::builder()
+        .renderer_mode(RendererMode::Gpu)
+        .renderer_config(raytracer_config)
+        .gpu_renderer_config(gpu_renderer_config)
+        .build()?;
     
     // Create and initialize engine
     let mut engine = Engine::new(engine_config)?;
@@ -133,7 +130,7 @@ async fn main() -> Result<()> {
                 // Update engine systems
                 let dt = engine.time().delta_time();
                 engine.time_mut().update();
-                engine.input_mut().update();
+                engine.update_input();
                 engine.scene_mut().update(dt);
 
                 // Render frame